@@ -9,7 +9,7 @@ import {
 } from '@modelcontextprotocol/sdk/types.js';
 import fs from 'fs/promises';
 import path from 'path';
-import { existsSync } from 'fs';
+import { existsSync, lstatSync, realpathSync } from 'fs';
 
 // Get vault path from environment or use current directory
 const VAULT_PATH = process.env.VAULT_PATH || process.cwd();
@@ -36,11 +36,34 @@ server.onerror = (error) => {
 };
 
 // Helper function to ensure path is within vault
+function isInside(parent, child) {
+  const relative = path.relative(parent, child);
+  return relative === '' || (!relative.startsWith('..') && !path.isAbsolute(relative));
+}
+
+function lstatExists(target) {
+  try {
+    lstatSync(target);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
 function ensureInVault(targetPath) {
   const resolvedPath = path.resolve(VAULT_PATH, targetPath);
   const resolvedVault = path.resolve(VAULT_PATH);
   
-  if (!resolvedPath.startsWith(resolvedVault)) {
+  if (!isInside(resolvedVault, resolvedPath)) {
+    throw new Error('Path is outside vault directory');
+  }
+  
+  // Symlinks inside the vault may not lead out of it
+  let existing = resolvedPath;
+  while (!lstatExists(existing) && existing !== resolvedVault) {
+    existing = path.dirname(existing);
+  }
+  if (!isInside(realpathSync(resolvedVault), realpathSync(existing))) {
     throw new Error('Path is outside vault directory');
   }
   
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::vault::is_inside;
+
+/// Archive entry describing the backup; read before anything is restored
+pub const MANIFEST_NAME: &str = "manifest.json";
+/// Vault files are stored under this folder of the archive
+const VAULT_DIR: &str = "vault/";
+/// Optional dump of the vault's graph nodes, relationships and embeddings
+const GRAPH_DUMP_NAME: &str = "graph.json";
+/// Bumped when archives change in a way older versions cannot restore
+const FORMAT_VERSION: u32 = 1;
+/// Backups made on a schedule are named `<vault> backup <timestamp>.zip`
+const SCHEDULED_NAME_MARKER: &str = " backup ";
+const BACKUP_EXTENSION: &str = "zip";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackupEntry {
+    /// Vault-relative path with `/` separators
+    pub path: String,
+    pub size: u64,
+    /// Hex SHA-256 of the file's bytes
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub vault_name: String,
+    pub created: DateTime<Utc>,
+    pub files: Vec<BackupEntry>,
+    /// Present when the graph was dumped with the vault
+    #[serde(default)]
+    pub graph: Option<BackupEntry>,
+}
+
+/// What a restore recreated
+#[derive(Debug)]
+pub struct RestoredBackup {
+    pub manifest: BackupManifest,
+    pub vault_path: PathBuf,
+    pub graph_dump: Option<Vec<u8>>,
+}
+
+/// Writes every file of the vault, including its `.gaimplan` settings, into
+/// one compressed archive. The vault's git repository is left out; it has
+/// its own remotes.
+pub fn create_archive(vault_path: &Path, archive_path: &Path, graph_dump: Option<&[u8]>) -> Result<BackupManifest, String> {
+    if !vault_path.is_dir() {
+        return Err(format!("Vault not found: {}", vault_path.display()));
+    }
+    if is_inside(archive_path, vault_path) {
+        return Err("Choose a backup location outside the vault".to_string());
+    }
+    let parent = archive_path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+
+    // Written next to the destination and renamed once complete, so an
+    // interrupted backup never looks like a finished one
+    let partial_path = archive_path.with_extension("zip.partial");
+    let result = write_archive(vault_path, &partial_path, graph_dump)
+        .and_then(|manifest| {
+            fs::rename(&partial_path, archive_path)
+                .map_err(|e| format!("Failed to write {}: {}", archive_path.display(), e))?;
+            Ok(manifest)
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&partial_path);
+    }
+    result
+}
+
+fn write_archive(vault_path: &Path, partial_path: &Path, graph_dump: Option<&[u8]>) -> Result<BackupManifest, String> {
+    let file = fs::File::create(partial_path)
+        .map_err(|e| format!("Failed to create {}: {}", partial_path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+    let write_error = |e: &dyn std::fmt::Display| format!("Failed to write backup: {}", e);
+
+    let mut files = Vec::new();
+    for entry in WalkDir::new(vault_path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() != 1 || e.file_name() != ".git")
+    {
+        let entry = entry.map_err(|e| write_error(&e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_path = match crate::index::resolver::relative_path(vault_path, entry.path()) {
+            Some(rel_path) => rel_path,
+            None => continue,
+        };
+        zip.start_file(format!("{}{}", VAULT_DIR, rel_path), options).map_err(|e| write_error(&e))?;
+        let mut source = fs::File::open(entry.path())
+            .map_err(|e| format!("Failed to read {}: {}", rel_path, e))?;
+        let (size, sha256) = copy_hashed(&mut source, &mut zip)
+            .map_err(|e| format!("Failed to back up {}: {}", rel_path, e))?;
+        files.push(BackupEntry { path: rel_path, size, sha256 });
+    }
+
+    let graph = match graph_dump {
+        Some(dump) => {
+            zip.start_file(GRAPH_DUMP_NAME, options).map_err(|e| write_error(&e))?;
+            zip.write_all(dump).map_err(|e| write_error(&e))?;
+            Some(BackupEntry {
+                path: GRAPH_DUMP_NAME.to_string(),
+                size: dump.len() as u64,
+                sha256: format!("{:x}", Sha256::digest(dump)),
+            })
+        }
+        None => None,
+    };
+
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        vault_name: vault_name(vault_path),
+        created: Utc::now(),
+        files,
+        graph,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|e| write_error(&e))?;
+    zip.start_file(MANIFEST_NAME, options).map_err(|e| write_error(&e))?;
+    zip.write_all(&manifest_json).map_err(|e| write_error(&e))?;
+    zip.finish().map_err(|e| write_error(&e))?;
+    Ok(manifest)
+}
+
+/// Reads the manifest of an archive without extracting anything
+pub fn read_manifest(archive_path: &Path) -> Result<BackupManifest, String> {
+    let mut zip = open_archive(archive_path)?;
+    manifest_of(&mut zip)
+}
+
+/// Recreates a vault from an archive at `target`, which must not exist yet or
+/// be an empty folder. Every file is checked against the manifest's
+/// checksums before the vault appears at `target`.
+pub fn restore_archive(archive_path: &Path, target: &Path) -> Result<RestoredBackup, String> {
+    if target.exists() && !is_empty_dir(target) {
+        return Err(format!("{} already exists and is not empty", target.display()));
+    }
+    let mut zip = open_archive(archive_path)?;
+    let manifest = manifest_of(&mut zip)?;
+
+    // Extracted beside the target and moved into place once verified
+    let parent = target.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    let staging = parent.join(format!(".{}.restoring-{}", vault_name(target), uuid::Uuid::new_v4().simple()));
+    let result = extract_verified(&mut zip, &manifest, &staging).and_then(|graph_dump| {
+        if target.exists() {
+            fs::remove_dir(target).map_err(|e| format!("Failed to replace {}: {}", target.display(), e))?;
+        }
+        fs::rename(&staging, target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        Ok(graph_dump)
+    });
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+
+    Ok(RestoredBackup {
+        manifest,
+        vault_path: target.to_path_buf(),
+        graph_dump: result?,
+    })
+}
+
+fn extract_verified(zip: &mut ZipArchive<fs::File>, manifest: &BackupManifest, into: &Path) -> Result<Option<Vec<u8>>, String> {
+    let expected: HashMap<&str, &BackupEntry> = manifest.files.iter().map(|f| (f.path.as_str(), f)).collect();
+    let mut restored = 0;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| format!("Invalid backup archive: {}", e))?;
+        let rel_path = match entry.name().strip_prefix(VAULT_DIR) {
+            Some(rel_path) if !entry.is_dir() => rel_path.to_string(),
+            _ => continue,
+        };
+        let listed = expected.get(rel_path.as_str())
+            .ok_or_else(|| format!("{} is not listed in the backup manifest", rel_path))?;
+        // Entries with absolute paths or `..` are never written outside the vault
+        let path = entry.enclosed_name()
+            .and_then(|p| p.strip_prefix(VAULT_DIR).ok().map(|p| into.join(p)))
+            .ok_or_else(|| format!("Unsafe path in backup: {}", rel_path))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to restore {}: {}", rel_path, e))?;
+        }
+        let mut out = fs::File::create(&path).map_err(|e| format!("Failed to restore {}: {}", rel_path, e))?;
+        let (size, sha256) = copy_hashed(&mut entry, &mut out)
+            .map_err(|e| format!("Failed to restore {}: {}", rel_path, e))?;
+        if size != listed.size || sha256 != listed.sha256 {
+            return Err(format!("Checksum mismatch for {}; the backup is damaged", rel_path));
+        }
+        restored += 1;
+    }
+    if restored != manifest.files.len() {
+        return Err(format!(
+            "The backup is incomplete: {} of {} files found",
+            restored,
+            manifest.files.len()
+        ));
+    }
+    // Empty vaults still restore as a folder
+    fs::create_dir_all(into).map_err(|e| format!("Failed to create {}: {}", into.display(), e))?;
+
+    let listed = match &manifest.graph {
+        Some(listed) => listed,
+        None => return Ok(None),
+    };
+    let mut entry = zip.by_name(GRAPH_DUMP_NAME)
+        .map_err(|_| "The backup is incomplete: graph dump missing".to_string())?;
+    let mut dump = Vec::new();
+    entry.read_to_end(&mut dump).map_err(|e| format!("Failed to read graph dump: {}", e))?;
+    if dump.len() as u64 != listed.size || format!("{:x}", Sha256::digest(&dump)) != listed.sha256 {
+        return Err("Checksum mismatch for the graph dump; the backup is damaged".to_string());
+    }
+    Ok(Some(dump))
+}
+
+fn open_archive(archive_path: &Path) -> Result<ZipArchive<fs::File>, String> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| format!("Failed to open {}: {}", archive_path.display(), e))?;
+    ZipArchive::new(file).map_err(|e| format!("Invalid backup archive: {}", e))
+}
+
+fn manifest_of(zip: &mut ZipArchive<fs::File>) -> Result<BackupManifest, String> {
+    let entry = zip.by_name(MANIFEST_NAME)
+        .map_err(|_| "Not a vault backup: manifest missing".to_string())?;
+    let manifest: BackupManifest = serde_json::from_reader(entry)
+        .map_err(|e| format!("Invalid backup manifest: {}", e))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "This backup was made by a newer version of Gaimplan ({}); update to restore it",
+            manifest.app_version
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Copies all of `reader` into `writer`, returning the byte count and the hex
+/// SHA-256 of what was copied
+fn copy_hashed(reader: &mut impl Read, writer: &mut impl Write) -> std::io::Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        size += read as u64;
+    }
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
+
+/// File name of a scheduled backup of a vault
+pub fn backup_file_name(vault_name: &str, time: DateTime<Local>) -> String {
+    format!("{}{}{}.{}", vault_name, SCHEDULED_NAME_MARKER, time.format("%Y-%m-%d %H%M%S"), BACKUP_EXTENSION)
+}
+
+/// Scheduled backups of a vault in a folder, oldest first
+pub fn scheduled_backups(backup_dir: &Path, vault_name: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}{}", vault_name, SCHEDULED_NAME_MARKER);
+    let mut backups: Vec<PathBuf> = fs::read_dir(backup_dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    backups.retain(|path| {
+        path.is_file()
+            && path.extension().is_some_and(|ext| ext == BACKUP_EXTENSION)
+            && path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(&prefix))
+    });
+    // Timestamps in the names sort chronologically
+    backups.sort();
+    backups
+}
+
+/// When the newest scheduled backup of a vault was written
+pub fn last_backup_time(backup_dir: &Path, vault_name: &str) -> Option<SystemTime> {
+    scheduled_backups(backup_dir, vault_name)
+        .last()
+        .and_then(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+}
+
+/// Deletes the oldest scheduled backups of a vault beyond `keep`; returns the
+/// deleted archives
+pub fn rotate_backups(backup_dir: &Path, vault_name: &str, keep: usize) -> Result<Vec<PathBuf>, String> {
+    let backups = scheduled_backups(backup_dir, vault_name);
+    let excess = backups.len().saturating_sub(keep);
+    let mut removed = Vec::new();
+    for path in backups.into_iter().take(excess) {
+        fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+pub fn vault_name(vault_path: &Path) -> String {
+    vault_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "vault".to_string())
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_backup_and_restore() {
+        let root = std::env::temp_dir().join(format!("gaimplan-backup-{}", uuid::Uuid::new_v4()));
+        let vault = root.join("Notes");
+        fs::create_dir_all(vault.join("files")).unwrap();
+        fs::create_dir_all(vault.join(".gaimplan")).unwrap();
+        fs::create_dir_all(vault.join(".git")).unwrap();
+        fs::write(vault.join("a.md"), "# A").unwrap();
+        fs::write(vault.join("files/pic.png"), [1u8, 2, 3]).unwrap();
+        fs::write(vault.join(".gaimplan/settings.json"), "{}").unwrap();
+        fs::write(vault.join(".git/HEAD"), "ref").unwrap();
+
+        assert!(create_archive(&vault, &vault.join("b.zip"), None).is_err());
+        let archive = root.join("backups/notes.zip");
+        let manifest = create_archive(&vault, &archive, Some(b"{\"nodes\":[]}")).unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec![".gaimplan/settings.json", "a.md", "files/pic.png"]);
+        assert_eq!(read_manifest(&archive).unwrap().vault_name, "Notes");
+
+        let restored = restore_archive(&archive, &root.join("Restored")).unwrap();
+        assert_eq!(fs::read_to_string(restored.vault_path.join("a.md")).unwrap(), "# A");
+        assert_eq!(fs::read(restored.vault_path.join("files/pic.png")).unwrap(), vec![1, 2, 3]);
+        assert!(!restored.vault_path.join(".git").exists());
+        assert_eq!(restored.graph_dump.as_deref(), Some(&b"{\"nodes\":[]}"[..]));
+        assert!(restore_archive(&archive, &root.join("Restored")).is_err());
+
+        // A manifest that disagrees with the files is refused and nothing is left behind
+        let mut tampered = manifest.clone();
+        tampered.files[1].sha256 = "0".repeat(64);
+        let damaged = root.join("damaged.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&damaged).unwrap());
+        zip.start_file("vault/.gaimplan/settings.json", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"{}").unwrap();
+        zip.start_file("vault/a.md", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"# A").unwrap();
+        zip.start_file(MANIFEST_NAME, SimpleFileOptions::default()).unwrap();
+        zip.write_all(&serde_json::to_vec(&tampered).unwrap()).unwrap();
+        zip.finish().unwrap();
+        assert!(restore_archive(&damaged, &root.join("Damaged")).unwrap_err().contains("a.md"));
+        assert!(!root.join("Damaged").exists());
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 4);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_keeps_newest_backups() {
+        let dir = std::env::temp_dir().join(format!("gaimplan-rotate-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let time = |day: u32| Local.with_ymd_and_hms(2025, 7, day, 9, 30, 0).unwrap();
+        for day in [3, 1, 2] {
+            fs::write(dir.join(backup_file_name("Notes", time(day))), "").unwrap();
+        }
+        fs::write(dir.join(backup_file_name("Other", time(1))), "").unwrap();
+        assert_eq!(backup_file_name("Notes", time(1)), "Notes backup 2025-07-01 093000.zip");
+
+        let removed = rotate_backups(&dir, "Notes", 2).unwrap();
+        assert_eq!(removed, vec![dir.join("Notes backup 2025-07-01 093000.zip")]);
+        assert_eq!(scheduled_backups(&dir, "Notes").len(), 2);
+        assert_eq!(scheduled_backups(&dir, "Other").len(), 1);
+        assert!(last_backup_time(&dir, "Notes").is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,444 @@
+//! Headless access to a vault for scripts and cron jobs:
+//!
+//! ```text
+//! gaimplan-cli [--vault PATH] [--json] <command> [arguments]
+//! ```
+//!
+//! The vault defaults to `$GAIMPLAN_VAULT`, then the current directory. With
+//! `--json` the result is printed to stdout as JSON and log output goes to stderr.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use serde_json::json;
+use tokio::sync::Mutex;
+use gaimplan_dev::backup;
+use gaimplan_dev::docker::SharedDockerManager;
+use gaimplan_dev::encryption;
+use gaimplan_dev::graph::sync::GraphSyncService;
+use gaimplan_dev::graph::{GraphConfig, GraphManagerImpl, GraphManagerTrait};
+use gaimplan_dev::index::resolver::is_markdown;
+use gaimplan_dev::index::VaultIndex;
+use gaimplan_dev::pdf_export::{self, ExportOptions, PdfExporter};
+use gaimplan_dev::publish::{publish_site, PublishOptions};
+use gaimplan_dev::search::types::{SearchFilters, SearchMode, SearchOptions};
+use gaimplan_dev::search::{HybridSearchManager, SearchQuery};
+use gaimplan_dev::templates::{TemplateContext, Templates, NEW_NOTE_TEMPLATE};
+use gaimplan_dev::vault::Vault;
+
+const USAGE: &str = "Usage: gaimplan-cli [--vault PATH] [--json] <command> [arguments]
+
+Commands:
+  search <query> [--mode keyword|graph|hybrid] [--limit N]
+                            Search notes; graph and hybrid modes use the graph databases
+  sync                      Sync every note to the graph databases
+  export <note> [--format pdf|html] [--output PATH] [--theme light|dark]
+                            Export a note to PDF or HTML
+  publish <output> [--folder DIR] [--theme light|dark] [--title TEXT]
+                            Render the vault, or a folder of it, as a static website
+  backup <folder> [--keep N] [--graph]
+                            Write an archive of the vault into a folder, deleting all but the
+                            newest N backups; --graph adds a dump of the graph databases
+  check-links               List wiki-links and attachment references that point at nothing;
+                            exits with status 1 when there are any
+  tags [<tag>]              List tags with note counts, or the notes with a tag
+  new <path> [--template NAME] [--templates-folder DIR] [--content TEXT|-] [--var NAME=VALUE]...
+                            Create a note, from a template and/or content (`-` reads stdin)
+
+Options:
+  --vault PATH              Vault folder (default: $GAIMPLAN_VAULT or the current directory)
+  --json                    Print the result as JSON
+  -h, --help                Show this help";
+
+/// Options that never take a value
+const FLAGS: &[&str] = &["json", "help", "graph"];
+
+struct Args {
+    vault: Option<PathBuf>,
+    json: bool,
+    command: Option<String>,
+    positional: Vec<String>,
+    options: HashMap<String, Vec<String>>,
+}
+
+impl Args {
+    fn parse(mut raw: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut args = Args { vault: None, json: false, command: None, positional: Vec::new(), options: HashMap::new() };
+        while let Some(arg) = raw.next() {
+            let name = match arg.strip_prefix("--") {
+                Some(name) => name.to_string(),
+                None if arg == "-h" => "help".to_string(),
+                None if args.command.is_none() => {
+                    args.command = Some(arg);
+                    continue;
+                }
+                None => {
+                    args.positional.push(arg);
+                    continue;
+                }
+            };
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None if FLAGS.contains(&name.as_str()) => (name, None),
+                None => {
+                    let value = raw.next().ok_or_else(|| format!("--{} needs a value", name))?;
+                    (name, Some(value))
+                }
+            };
+            match (name.as_str(), value) {
+                ("json", _) => args.json = true,
+                ("help", _) => args.command = Some("help".to_string()),
+                ("vault", Some(value)) => args.vault = Some(PathBuf::from(value)),
+                (_, Some(value)) => args.options.entry(name).or_default().push(value),
+                (_, None) => {
+                    args.options.entry(name).or_default();
+                }
+            }
+        }
+        Ok(args)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).and_then(|values| values.last()).map(String::as_str)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    fn positional(&self, index: usize, what: &str) -> Result<&str, String> {
+        self.positional.get(index).map(String::as_str).ok_or_else(|| format!("Missing {}\n\n{}", what, USAGE))
+    }
+}
+
+/// What a command produced: JSON for `--json`, text otherwise
+struct Output {
+    json: serde_json::Value,
+    text: String,
+    /// Whether the exit status is 0; checks report problems through it
+    ok: bool,
+}
+
+impl Output {
+    fn new(json: serde_json::Value, text: String) -> Self {
+        Self { json, text, ok: true }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let command = match args.command.as_deref() {
+        None | Some("help") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => command.to_string(),
+    };
+
+    // Library code logs to stderr, so stdout carries nothing but the result
+    let mut out = std::io::stdout();
+    let result = match open_vault(&args) {
+        Ok(vault) => run(&command, &args, vault).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(output) => {
+            let printed = if args.json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&output.json).unwrap_or_default())
+            } else {
+                writeln!(out, "{}", output.text.trim_end())
+            };
+            if printed.is_err() {
+                return ExitCode::FAILURE;
+            }
+            if output.ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+        }
+        Err(e) => {
+            if args.json {
+                let _ = writeln!(out, "{}", json!({ "error": e }));
+            }
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn open_vault(args: &Args) -> Result<Vault, String> {
+    let path = match &args.vault {
+        Some(path) => path.clone(),
+        None => match std::env::var_os("GAIMPLAN_VAULT") {
+            Some(path) => PathBuf::from(path),
+            None => std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?,
+        },
+    };
+    let path = path.canonicalize().map_err(|e| format!("Vault not found at {}: {}", path.display(), e))?;
+    Vault::new(path).map_err(|e| format!("Failed to open vault: {}", e))
+}
+
+async fn run(command: &str, args: &Args, vault: Vault) -> Result<Output, String> {
+    match command {
+        "search" => search(args, vault).await,
+        "sync" => sync(vault).await,
+        "export" => export(args, vault).await,
+        "publish" => publish(args, vault),
+        "backup" => back_up(args, vault).await,
+        "check-links" => check_links(vault).await,
+        "tags" => tags(args, vault).await,
+        "new" => new_note(args, vault),
+        _ => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+/// Starts the graph databases if needed and connects to this vault's graph
+async fn connect_graph(vault: &Vault) -> Result<(Arc<dyn GraphManagerTrait>, String), String> {
+    let vault_name = vault.path()
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("default")
+        .to_string();
+
+    let docker_manager = SharedDockerManager::new();
+    docker_manager.ensure_started().await?;
+    let conn_info = docker_manager.get_connection_info(&vault_name).await?;
+
+    let graph_manager: Arc<dyn GraphManagerTrait> = Arc::new(GraphManagerImpl::new(conn_info.vault_id.clone()));
+    let config = GraphConfig {
+        vault_id: conn_info.vault_id.clone(),
+        vault_path: vault.path().to_string_lossy().to_string(),
+        neo4j_uri: conn_info.neo4j.uri.clone(),
+        neo4j_user: conn_info.neo4j.username.clone(),
+        neo4j_password: conn_info.neo4j.password.clone(),
+        qdrant_url: conn_info.qdrant.rest_url.clone(),
+    };
+    graph_manager.connect(&config).await?;
+    Ok((graph_manager, conn_info.vault_id))
+}
+
+async fn search(args: &Args, vault: Vault) -> Result<Output, String> {
+    let query = args.positional.join(" ");
+    if query.trim().is_empty() {
+        return Err(format!("Missing search query\n\n{}", USAGE));
+    }
+    let mode = match args.option("mode").unwrap_or("keyword") {
+        "keyword" => SearchMode::Keyword,
+        "graph" => SearchMode::Graph,
+        "hybrid" => SearchMode::Hybrid,
+        other => return Err(format!("Unknown search mode: {}", other)),
+    };
+    let max_results = match args.option("limit") {
+        Some(limit) => limit.parse().map_err(|_| format!("Invalid limit: {}", limit))?,
+        None => SearchOptions::default().max_results,
+    };
+
+    let (graph_manager, vault_id) = match mode {
+        SearchMode::Keyword => (None, String::new()),
+        _ => {
+            let (graph_manager, vault_id) = connect_graph(&vault).await?;
+            (Some(graph_manager), vault_id)
+        }
+    };
+    let manager = HybridSearchManager::new(Arc::new(Mutex::new(graph_manager)), vault.path().to_path_buf(), vault_id, vault.ignore());
+    let results = manager.search(SearchQuery {
+        query,
+        mode,
+        filters: SearchFilters::default(),
+        options: SearchOptions { max_results, ..Default::default() },
+    }).await?;
+
+    let text = results.iter()
+        .map(|r| format!("{}\t{}", r.file_path, r.preview.split_whitespace().collect::<Vec<_>>().join(" ")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(Output::new(json!(results), text))
+}
+
+async fn sync(vault: Vault) -> Result<Output, String> {
+    let (graph_manager, vault_id) = connect_graph(&vault).await?;
+    let start = std::time::Instant::now();
+    GraphSyncService::new(graph_manager, Arc::new(vault)).initial_sync().await?;
+    let seconds = start.elapsed().as_secs_f64();
+    Ok(Output::new(
+        json!({ "vault_id": vault_id, "seconds": seconds }),
+        format!("✅ Synced vault in {:.2}s", seconds),
+    ))
+}
+
+async fn export(args: &Args, vault: Vault) -> Result<Output, String> {
+    let note = args.positional(0, "note to export")?;
+    let content = vault.read_file(Path::new(note)).map_err(|e| format!("Failed to read {}: {}", note, e))?;
+    let format = args.option("format").unwrap_or("pdf");
+    let output = match args.option("output") {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let stem = Path::new(note).file_stem().and_then(|s| s.to_str()).unwrap_or("export");
+            PathBuf::from(format!("{}.{}", stem, format))
+        }
+    };
+    let options = ExportOptions {
+        theme: args.option("theme").unwrap_or("light").to_string(),
+        source_path: Some(note.to_string()),
+        ..Default::default()
+    };
+
+    match format {
+        "pdf" => PdfExporter::new(vault.path().to_path_buf()).export_to_pdf(&content, &output, options).await?,
+        "html" => pdf_export::export_to_html(&content, &output, vault.path(), options).await?,
+        other => return Err(format!("Unknown export format: {}", other)),
+    }
+    Ok(Output::new(
+        json!({ "note": note, "format": format, "output": output }),
+        format!("✅ Exported {} to {}", note, output.display()),
+    ))
+}
+
+fn publish(args: &Args, vault: Vault) -> Result<Output, String> {
+    let output = PathBuf::from(args.positional(0, "output folder")?);
+    let output = std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?.join(output);
+    let options = PublishOptions {
+        folder: args.option("folder").unwrap_or_default().to_string(),
+        theme: args.option("theme").unwrap_or("light").to_string(),
+        site_title: args.option("title").map(str::to_string),
+    };
+
+    let report = publish_site(&vault, &output, &options)?;
+    let mut text = format!("✅ Published {} pages to {}", report.pages, output.display());
+    if !report.skipped.is_empty() {
+        text.push_str(&format!(" ({} notes not published)", report.skipped.len()));
+    }
+    Ok(Output::new(json!(report), text))
+}
+
+async fn back_up(args: &Args, vault: Vault) -> Result<Output, String> {
+    let backup_dir = PathBuf::from(args.positional(0, "backup folder")?);
+    let backup_dir = std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?.join(backup_dir);
+    let keep = match args.option("keep") {
+        Some(keep) => Some(keep.parse::<usize>().map_err(|_| format!("Invalid number of backups to keep: {}", keep))?),
+        None => None,
+    };
+
+    let graph_dump = if args.flag("graph") {
+        let (graph_manager, _) = connect_graph(&vault).await?;
+        let graph_impl = graph_manager.as_any().downcast_ref::<GraphManagerImpl>()
+            .ok_or_else(|| "Graph manager cannot be dumped".to_string())?;
+        let dump = graph_impl.dump_vault().await?;
+        Some(serde_json::to_vec(&dump).map_err(|e| format!("Failed to serialize graph dump: {}", e))?)
+    } else {
+        None
+    };
+
+    let vault_name = backup::vault_name(vault.path());
+    let archive = backup_dir.join(backup::backup_file_name(&vault_name, chrono::Local::now()));
+    let manifest = backup::create_archive(vault.path(), &archive, graph_dump.as_deref())?;
+    let removed = match keep {
+        Some(keep) => backup::rotate_backups(&backup_dir, &vault_name, keep.max(1))?,
+        None => Vec::new(),
+    };
+
+    let mut text = format!("✅ Backed up {} files to {}", manifest.files.len(), archive.display());
+    if !removed.is_empty() {
+        text.push_str(&format!(" (deleted {} old backups)", removed.len()));
+    }
+    Ok(Output::new(json!({ "archive": archive, "manifest": manifest, "deleted": removed }), text))
+}
+
+async fn check_links(vault: Vault) -> Result<Output, String> {
+    let index = VaultIndex::build(vault.path(), vault.ignore_rules())?;
+    let links = index.links.lock().await;
+    let attachments = index.attachments.lock().await;
+
+    let mut notes: Vec<&String> = links.resolver().files().filter(|p| is_markdown(p)).collect();
+    notes.sort();
+    let mut broken = Vec::new();
+    for note in notes {
+        for link in links.unresolved_links(note).into_iter().filter(|l| !l.link.target.is_empty()) {
+            broken.push(json!({ "source_path": note, "target": link.link.target, "line": link.link.line, "kind": "link" }));
+        }
+    }
+    // Wiki-style attachment embeds were reported as links above
+    for reference in attachments.broken_references(links.resolver()).into_iter().filter(|r| !r.reference.is_wiki_link) {
+        broken.push(json!({
+            "source_path": reference.source_path,
+            "target": reference.reference.target,
+            "line": reference.reference.line,
+            "kind": "attachment",
+        }));
+    }
+
+    let text = if broken.is_empty() {
+        "✅ No broken links".to_string()
+    } else {
+        broken.iter()
+            .map(|b| format!("{}:{}\t{}", b["source_path"].as_str().unwrap_or(""), b["line"], b["target"].as_str().unwrap_or("")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let ok = broken.is_empty();
+    Ok(Output { json: json!(broken), text, ok })
+}
+
+async fn tags(args: &Args, vault: Vault) -> Result<Output, String> {
+    let index = VaultIndex::build(vault.path(), vault.ignore_rules())?;
+    let tags = index.tags.lock().await;
+
+    match args.positional.first() {
+        Some(tag) => {
+            let notes = tags.notes_with_tag(tag);
+            let text = notes.join("\n");
+            Ok(Output::new(json!(notes), text))
+        }
+        None => {
+            let all = tags.all_tags();
+            let text = all.iter().map(|t| format!("{}\t{}", t.count, t.tag)).collect::<Vec<_>>().join("\n");
+            Ok(Output::new(json!(all), text))
+        }
+    }
+}
+
+fn new_note(args: &Args, vault: Vault) -> Result<Output, String> {
+    let path = args.positional(0, "path of the new note")?;
+    let path = if is_markdown(path) { path.to_string() } else { format!("{}.md", path) };
+    let full_path = vault.resolve_path(Path::new(&path)).map_err(|e| e.to_string())?;
+    if full_path.exists() {
+        return Err(format!("{} already exists", path));
+    }
+
+    let title = Path::new(&path).file_stem().and_then(|s| s.to_str()).unwrap_or("Untitled");
+    let mut context = TemplateContext::new(title);
+    for variable in args.options.get("var").into_iter().flatten() {
+        let (name, value) = variable.split_once('=').ok_or_else(|| format!("Invalid variable (expected NAME=VALUE): {}", variable))?;
+        context = context.with_variable(name.trim(), value);
+    }
+    let templates = Templates::new(vault.path(), args.option("templates-folder").unwrap_or("Templates"));
+
+    let content = match args.option("content") {
+        Some("-") => {
+            let mut stdin = String::new();
+            std::io::stdin().read_to_string(&mut stdin).map_err(|e| format!("Failed to read stdin: {}", e))?;
+            Some(stdin)
+        }
+        Some(content) => Some(content.to_string()),
+        None => None,
+    };
+    let note = match (args.option("template"), content) {
+        (Some(template), None) => templates.render(template, &context)?.content,
+        (Some(template), Some(content)) => format!("{}\n{}", templates.render(template, &context)?.content.trim_end(), content),
+        (None, Some(content)) => content,
+        (None, None) => templates.render_source(NEW_NOTE_TEMPLATE, &context)?.content,
+    };
+
+    // There is no passphrase here, so notes in encrypted folders are refused
+    encryption::write_note(&vault, Path::new(&path), &note, None).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    Ok(Output::new(json!({ "path": path }), format!("📝 Created {}", path)))
+}
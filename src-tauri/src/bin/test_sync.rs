@@ -13,9 +13,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     println!("=== Graph Sync Test Harness ===\n");
     
-    // Get vault path from environment or use default
+    // Get vault path from environment
     let vault_path = env::var("VAULT_PATH")
-        .unwrap_or_else(|_| "/Users/ksnyder/neo4j/data".to_string());
+        .map_err(|_| "Set VAULT_PATH to the vault to sync")?;
     
     println!("📁 Vault path: {}", vault_path);
     
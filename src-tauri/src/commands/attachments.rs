@@ -0,0 +1,109 @@
+use tauri::{State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::index::{AttachmentUsage, BrokenReference};
+use crate::trash::{Trash, TrashEntry};
+
+/// Every image and PDF in the vault with the notes referencing it
+#[tauri::command]
+pub async fn list_attachments(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<Vec<AttachmentUsage>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+
+    let unlocked = refactored_state.keyring.get(index.vault_path()).await;
+
+    let links = index.links.lock().await;
+    let attachments = index.readable_attachments(unlocked.as_deref()).await?;
+    Ok(attachments.usages(links.resolver()))
+}
+
+/// Notes referencing an attachment
+#[tauri::command]
+pub async fn get_attachment_references(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<Vec<String>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let path = index.relative_key(&file_path);
+
+    let unlocked = refactored_state.keyring.get(index.vault_path()).await;
+
+    let links = index.links.lock().await;
+    let attachments = index.readable_attachments(unlocked.as_deref()).await?;
+    Ok(attachments
+        .usages(links.resolver())
+        .into_iter()
+        .find(|usage| usage.path == path)
+        .map(|usage| usage.referenced_by)
+        .unwrap_or_default())
+}
+
+/// Attachments no note references
+#[tauri::command]
+pub async fn find_orphaned_attachments(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<Vec<String>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+
+    let unlocked = refactored_state.keyring.get(index.vault_path()).await;
+
+    let links = index.links.lock().await;
+    let attachments = index.readable_attachments(unlocked.as_deref()).await?;
+    Ok(attachments.orphans(links.resolver()))
+}
+
+/// Image and PDF references pointing at files that do not exist
+#[tauri::command]
+pub async fn find_broken_attachment_references(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<Vec<BrokenReference>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+
+    let unlocked = refactored_state.keyring.get(index.vault_path()).await;
+
+    let links = index.links.lock().await;
+    let attachments = index.readable_attachments(unlocked.as_deref()).await?;
+    Ok(attachments.broken_references(links.resolver()))
+}
+
+/// Moves the orphaned attachments the user confirmed to the trash. Orphans are
+/// only found through markdown and wiki-links (not HTML, frontmatter or
+/// canvases), so nothing is trashed without an explicit list. Paths that gained
+/// a reference since being listed are skipped, so a stale selection never
+/// deletes an attachment in use. Refused while encrypted notes cannot be read.
+#[tauri::command]
+pub async fn delete_orphaned_attachments(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    paths: Vec<String>,
+) -> Result<Vec<TrashEntry>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let unlocked = refactored_state.keyring.get(index.vault_path()).await;
+    let orphans = {
+        let links = index.links.lock().await;
+        let attachments = index.readable_attachments(unlocked.as_deref()).await?;
+        attachments.orphans(links.resolver())
+    };
+
+    let selected: Vec<String> = paths
+        .iter()
+        .map(|p| index.relative_key(p))
+        .filter(|p| orphans.contains(p))
+        .collect();
+
+    let trash = Trash::new(index.vault_path());
+    let mut trashed = Vec::new();
+    for path in &selected {
+        match trash.move_to_trash(path) {
+            Ok(entry) => trashed.push(entry),
+            Err(e) => eprintln!("⚠️ Failed to trash orphaned attachment {}: {}", path, e),
+        }
+    }
+
+    println!("🧹 Moved {} orphaned attachments to trash", trashed.len());
+    Ok(trashed)
+}
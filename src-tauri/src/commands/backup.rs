@@ -0,0 +1,194 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, Window};
+use crate::backup::{self, BackupManifest};
+use crate::graph::simple_sync::sync_vault_simple;
+use crate::graph::{GraphDump, GraphManagerTrait};
+use crate::refactored_app_state::RefactoredAppState;
+
+#[derive(Debug, Serialize)]
+pub struct CreatedBackup {
+    pub archive_path: String,
+    pub files: usize,
+    pub graph_included: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreReport {
+    pub vault_path: String,
+    pub files: usize,
+    /// Notes synced to the graph; `None` when the graph could not be rebuilt
+    pub graph_synced_notes: Option<usize>,
+    pub graph_error: Option<String>,
+    pub embeddings_restored: usize,
+}
+
+/// Writes an archive of the window's vault into `output_dir`, optionally with
+/// a dump of its graph and embeddings
+#[tauri::command]
+pub async fn create_backup(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    output_dir: String,
+    include_graph: Option<bool>,
+) -> Result<CreatedBackup, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let graph_dump = if include_graph.unwrap_or(false) {
+        Some(dump_graph(&app, &vault_path).await?)
+    } else {
+        None
+    };
+    back_up(vault_path, PathBuf::from(output_dir), graph_dump).await
+}
+
+/// Manifest of a backup archive, to show what a restore would recreate
+#[tauri::command]
+pub async fn read_backup_manifest(archive_path: String) -> Result<BackupManifest, String> {
+    tokio::task::spawn_blocking(move || backup::read_manifest(Path::new(&archive_path)))
+        .await
+        .map_err(|e| format!("Reading backup failed: {}", e))?
+}
+
+/// Recreates a vault from a backup as `parent_dir/<vault name>` and rebuilds
+/// its graph. A vault that restored but could not be synced is reported, not
+/// refused.
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    archive_path: String,
+    parent_dir: String,
+    vault_name: Option<String>,
+) -> Result<RestoreReport, String> {
+    let archive_path = PathBuf::from(archive_path);
+    let manifest = tokio::task::spawn_blocking({
+        let archive_path = archive_path.clone();
+        move || backup::read_manifest(&archive_path)
+    })
+    .await
+    .map_err(|e| format!("Restore failed: {}", e))??;
+
+    let name = vault_name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or(manifest.vault_name);
+    if name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(format!("Invalid vault name: {}", name));
+    }
+    let target = PathBuf::from(parent_dir).join(name);
+    let restored = tokio::task::spawn_blocking(move || backup::restore_archive(&archive_path, &target))
+        .await
+        .map_err(|e| format!("Restore failed: {}", e))??;
+    println!("📦 Restored {} files to {}", restored.manifest.files.len(), restored.vault_path.display());
+
+    let graph_dump = restored.graph_dump.as_deref()
+        .map(|dump| serde_json::from_slice::<GraphDump>(dump).map_err(|e| format!("Invalid graph dump: {}", e)))
+        .transpose()?;
+    let (graph_synced_notes, graph_error, embeddings_restored) = match resync_graph(&app, &restored.vault_path, graph_dump).await {
+        Ok((notes, embeddings)) => (Some(notes), None, embeddings),
+        Err(e) => {
+            eprintln!("⚠️ Restored vault was not synced to the graph: {}", e);
+            (None, Some(e), 0)
+        }
+    };
+
+    Ok(RestoreReport {
+        vault_path: restored.vault_path.to_string_lossy().to_string(),
+        files: restored.manifest.files.len(),
+        graph_synced_notes,
+        graph_error,
+        embeddings_restored,
+    })
+}
+
+async fn back_up(vault_path: PathBuf, backup_dir: PathBuf, graph_dump: Option<GraphDump>) -> Result<CreatedBackup, String> {
+    let graph_dump = graph_dump
+        .map(|dump| serde_json::to_vec(&dump).map_err(|e| format!("Failed to serialize graph dump: {}", e)))
+        .transpose()?;
+    let archive_path = backup_dir.join(backup::backup_file_name(&backup::vault_name(&vault_path), chrono::Local::now()));
+
+    let manifest = tokio::task::spawn_blocking({
+        let archive_path = archive_path.clone();
+        move || backup::create_archive(&vault_path, &archive_path, graph_dump.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Backup failed: {}", e))??;
+    println!("📦 Backed up {} files to {}", manifest.files.len(), archive_path.display());
+
+    Ok(CreatedBackup {
+        archive_path: archive_path.to_string_lossy().to_string(),
+        files: manifest.files.len(),
+        graph_included: manifest.graph.is_some(),
+    })
+}
+
+async fn dump_graph(app: &AppHandle, vault_path: &Path) -> Result<GraphDump, String> {
+    let (graph_manager, _) = crate::commands::graph::connect_vault_graph(app.clone(), vault_path).await?;
+    let dump = graph_manager.dump_vault().await;
+    let _ = graph_manager.disconnect().await;
+    dump
+}
+
+/// Syncs a restored vault's notes to the graph and writes back the embeddings
+/// of its dump; returns the notes synced and the embeddings restored
+async fn resync_graph(app: &AppHandle, vault_path: &Path, graph_dump: Option<GraphDump>) -> Result<(usize, usize), String> {
+    let (graph_manager, vault_id) = crate::commands::graph::connect_vault_graph(app.clone(), vault_path).await?;
+    let graph: Arc<dyn GraphManagerTrait> = graph_manager.clone();
+    let result: Result<(usize, usize), String> = async {
+        let ignore = app.state::<RefactoredAppState>().ignore_rules(vault_path).current();
+        let (notes, _) = sync_vault_simple(vault_path, &graph, &vault_id, false, &ignore).await?;
+        let embeddings = match graph_dump {
+            Some(dump) => graph_manager.restore_embeddings(dump).await?,
+            None => 0,
+        };
+        Ok((notes, embeddings))
+    }
+    .await;
+    let _ = graph.disconnect().await;
+    result
+}
+
+/// Backs up each vault whose scheduled backup is due, then deletes its
+/// oldest backups beyond the number kept
+pub async fn back_up_due_vaults(app: &AppHandle, vault_paths: Vec<PathBuf>) {
+    for vault_path in vault_paths {
+        let settings = match crate::vault_settings::get_vault_settings(app.clone(), vault_path.to_string_lossy().to_string()).await {
+            Ok(settings) if !settings.files.backup_directory.trim().is_empty() => settings.files,
+            _ => continue,
+        };
+        let backup_dir = PathBuf::from(settings.backup_directory.trim());
+        let vault_name = backup::vault_name(&vault_path);
+        let interval = Duration::from_secs(u64::from(settings.backup_interval_hours.max(1)) * 3600);
+        let elapsed = backup::last_backup_time(&backup_dir, &vault_name)
+            .and_then(|last| SystemTime::now().duration_since(last).ok());
+        if elapsed.is_some_and(|elapsed| elapsed < interval) {
+            continue;
+        }
+
+        // Scheduled backups still run when the graph is unreachable
+        let graph_dump = if settings.backup_include_graph {
+            match dump_graph(app, &vault_path).await {
+                Ok(dump) => Some(dump),
+                Err(e) => {
+                    eprintln!("⚠️ Backing up {} without its graph: {}", vault_name, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        if let Err(e) = back_up(vault_path.clone(), backup_dir.clone(), graph_dump).await {
+            eprintln!("⚠️ Scheduled backup of {} failed: {}", vault_name, e);
+            continue;
+        }
+        if settings.backup_keep > 0 {
+            match backup::rotate_backups(&backup_dir, &vault_name, settings.backup_keep as usize) {
+                Ok(removed) if !removed.is_empty() => println!("🗑️ Deleted {} old backups of {}", removed.len(), vault_name),
+                Ok(_) => {}
+                Err(e) => eprintln!("⚠️ Failed to rotate backups of {}: {}", vault_name, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::path::Path;
+use std::sync::Arc;
+use serde::Serialize;
+use tauri::{AppHandle, State, Window};
+use walkdir::WalkDir;
+use zeroize::Zeroizing;
+use crate::{AppState, refactored_app_state::RefactoredAppState};
+use crate::encryption::{is_encrypted, EncryptionConfig, UnlockedVault};
+use crate::graph::sync::GraphSyncService;
+use crate::history::NoteHistory;
+use crate::vault::{resolve_in_vault, write_atomic, Vault, INTERNAL_DIR};
+
+#[derive(Debug, Serialize)]
+pub struct EncryptionStatus {
+    /// Whether a passphrase was ever set for this vault
+    pub set_up: bool,
+    /// Whether the passphrase was entered this session
+    pub unlocked: bool,
+    pub folders: Vec<String>,
+}
+
+async fn unlocked_vault(vault_path: &Path, refactored_state: &RefactoredAppState) -> Result<Arc<UnlockedVault>, String> {
+    refactored_state.keyring.get(vault_path).await
+        .ok_or_else(|| "Unlock the vault first".to_string())
+}
+
+#[tauri::command]
+pub async fn get_encryption_status(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<EncryptionStatus, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let config = EncryptionConfig::load(&vault_path)?;
+    Ok(EncryptionStatus {
+        set_up: config.is_some(),
+        unlocked: refactored_state.keyring.get(&vault_path).await.is_some(),
+        folders: config.map(|c| c.folders).unwrap_or_default(),
+    })
+}
+
+/// Sets the vault's passphrase. It cannot be recovered: notes encrypted with
+/// it are lost if it is forgotten.
+#[tauri::command]
+pub async fn set_up_encryption(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    passphrase: String,
+) -> Result<(), String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    refactored_state.keyring.set_up(&vault_path, Zeroizing::new(passphrase)).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unlock_vault(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    passphrase: String,
+) -> Result<(), String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    refactored_state.keyring.unlock(&vault_path, Zeroizing::new(passphrase)).await?;
+    Ok(())
+}
+
+/// Forgets the passphrase for every window of the vault
+#[tauri::command]
+pub async fn lock_vault(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<(), String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    refactored_state.keyring.lock(&vault_path).await;
+    Ok(())
+}
+
+/// Encrypts a note in place. Its plaintext versions are dropped from history
+/// and the note is removed from the graph. Copies made before are not touched:
+/// git commits, the trash and backups still hold the plaintext.
+#[tauri::command]
+pub async fn encrypt_note(
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<(), String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let unlocked = unlocked_vault(&vault_path, &refactored_state).await?;
+    let rel_path = relative_note_path(&vault_path, &file_path)?;
+
+    encrypt_file(&vault_path, &rel_path, &unlocked)?;
+    forget_plaintext(&state, &vault_path, &[rel_path]).await;
+    Ok(())
+}
+
+/// Stores an encrypted note as plaintext again
+#[tauri::command]
+pub async fn decrypt_note(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<(), String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let vault_path = vault.path().to_path_buf();
+    let unlocked = unlocked_vault(&vault_path, &refactored_state).await?;
+    let rel_path = relative_note_path(&vault_path, &file_path)?;
+    if unlocked.config().covers(&rel_path) {
+        return Err(format!("{} is in an encrypted folder; decrypt the folder instead", rel_path));
+    }
+
+    if let Some(content) = decrypt_file(&vault_path, &rel_path, &unlocked)? {
+        record_plaintext(&app, &state, &vault, &rel_path, &content).await;
+    }
+    Ok(())
+}
+
+/// Encrypts every note in a folder and every note saved into it from now on;
+/// returns the number of notes encrypted. As with `encrypt_note`, git commits,
+/// the trash and backups keep the plaintext they already have.
+#[tauri::command]
+pub async fn encrypt_folder(
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    folder_path: String,
+) -> Result<usize, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let unlocked = unlocked_vault(&vault_path, &refactored_state).await?;
+    let folder = relative_folder_path(&vault_path, &folder_path)?;
+
+    unlocked.set_folder(&vault_path, &folder, true)?;
+    let mut encrypted = Vec::new();
+    for rel_path in folder_notes(&vault_path, &folder) {
+        if encrypt_file(&vault_path, &rel_path, &unlocked)? {
+            encrypted.push(rel_path);
+        }
+    }
+    println!("🔒 Encrypted {} notes in {}", encrypted.len(), folder);
+
+    forget_plaintext(&state, &vault_path, &encrypted).await;
+    Ok(encrypted.len())
+}
+
+/// Stops encrypting a folder and decrypts the notes in it; returns the number
+/// of notes decrypted
+#[tauri::command]
+pub async fn decrypt_folder(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    folder_path: String,
+) -> Result<usize, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let vault_path = vault.path().to_path_buf();
+    let unlocked = unlocked_vault(&vault_path, &refactored_state).await?;
+    let folder = relative_folder_path(&vault_path, &folder_path)?;
+
+    unlocked.set_folder(&vault_path, &folder, false)?;
+    let mut count = 0;
+    for rel_path in folder_notes(&vault_path, &folder) {
+        if let Some(content) = decrypt_file(&vault_path, &rel_path, &unlocked)? {
+            record_plaintext(&app, &state, &vault, &rel_path, &content).await;
+            count += 1;
+        }
+    }
+    println!("🔓 Decrypted {} notes in {}", count, folder);
+    Ok(count)
+}
+
+/// Reads a note to hand to the AI chat or the embedding sync. Encrypted notes,
+/// and notes in encrypted folders, are refused even while the vault is unlocked.
+#[tauri::command]
+pub async fn read_note_for_ai(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<String, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let full_path = resolve_in_vault(&vault_path, Path::new(&file_path))?;
+    let content = std::fs::read_to_string(&full_path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let in_encrypted_folder = crate::index::resolver::relative_path(&vault_path, &full_path)
+        .is_some_and(|rel| EncryptionConfig::load(&vault_path).ok().flatten().is_some_and(|c| c.covers(&rel)));
+    if is_encrypted(&content) || in_encrypted_folder {
+        return Err(format!("{} is encrypted and is not shared with AI", file_path));
+    }
+    Ok(content)
+}
+
+fn relative_note_path(vault_path: &Path, file_path: &str) -> Result<String, String> {
+    let full_path = resolve_in_vault(vault_path, Path::new(file_path))?;
+    if !full_path.is_file() {
+        return Err(format!("Note not found: {}", file_path));
+    }
+    crate::index::resolver::relative_path(vault_path, &full_path)
+        .ok_or_else(|| format!("Not a file in the vault: {}", file_path))
+}
+
+fn relative_folder_path(vault_path: &Path, folder_path: &str) -> Result<String, String> {
+    let full_path = resolve_in_vault(vault_path, Path::new(folder_path))?;
+    if !full_path.is_dir() || full_path == vault_path {
+        return Err(format!("Not a folder in the vault: {}", folder_path));
+    }
+    crate::index::resolver::relative_path(vault_path, &full_path)
+        .ok_or_else(|| format!("Not a folder in the vault: {}", folder_path))
+}
+
+/// Vault-relative paths of the markdown notes under a folder
+fn folder_notes(vault_path: &Path, folder: &str) -> Vec<String> {
+    WalkDir::new(vault_path.join(folder))
+        .into_iter()
+        .filter_entry(|e| e.file_name() != INTERNAL_DIR)
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|e| crate::index::resolver::relative_path(vault_path, e.path()))
+        .collect()
+}
+
+/// Encrypts a note on disk; false if it already was
+fn encrypt_file(vault_path: &Path, rel_path: &str, unlocked: &UnlockedVault) -> Result<bool, String> {
+    let full_path = vault_path.join(rel_path);
+    let content = std::fs::read_to_string(&full_path)
+        .map_err(|e| format!("Failed to read {}: {}", rel_path, e))?;
+    if is_encrypted(&content) {
+        return Ok(false);
+    }
+    let sealed = unlocked.encrypt(&content)?;
+    write_atomic(&full_path, sealed.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", rel_path, e))?;
+    Ok(true)
+}
+
+/// Decrypts a note on disk and returns its content; `None` if it was not encrypted
+fn decrypt_file(vault_path: &Path, rel_path: &str, unlocked: &UnlockedVault) -> Result<Option<String>, String> {
+    let full_path = vault_path.join(rel_path);
+    let content = std::fs::read_to_string(&full_path)
+        .map_err(|e| format!("Failed to read {}: {}", rel_path, e))?;
+    if !is_encrypted(&content) {
+        return Ok(None);
+    }
+    let plaintext = unlocked.decrypt(&content)?;
+    write_atomic(&full_path, plaintext.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", rel_path, e))?;
+    Ok(Some(plaintext))
+}
+
+/// Drops what the app itself kept of newly encrypted notes in plaintext: their
+/// version history and their graph nodes and embeddings
+async fn forget_plaintext(state: &AppState, vault_path: &Path, notes: &[String]) {
+    let history = NoteHistory::new(vault_path);
+    for note in notes {
+        if let Err(e) = history.forget(note) {
+            eprintln!("⚠️ Failed to drop history of {}: {}", note, e);
+        }
+    }
+
+    let graph_manager = match state.graph_manager.lock().await.clone() {
+        Some(graph_manager) => graph_manager,
+        None => return,
+    };
+    let vault_id = crate::vault_id::generate_vault_id(vault_path);
+    for note in notes {
+        let note_id = GraphSyncService::generate_note_id(&vault_path.join(note), &vault_id);
+        if let Err(e) = graph_manager.delete_note(&note_id).await {
+            eprintln!("⚠️ Failed to delete encrypted note {} from graph: {}", note, e);
+        }
+    }
+}
+
+/// Records a decrypted note in history and queues it for graph sync, like a save
+async fn record_plaintext(app: &AppHandle, state: &AppState, vault: &Vault, rel_path: &str, content: &str) {
+    crate::commands::history::record_save(app, vault, rel_path, content).await;
+    if let Some(queue) = state.update_queue.lock().await.clone() {
+        let vault_path = vault.path();
+        if let Err(e) = queue.add_update(vault_path.join(rel_path), vault_path.to_path_buf(), content, &vault.ignore()).await {
+            eprintln!("⚠️ Failed to queue graph update for {}: {}", rel_path, e);
+        }
+    }
+}
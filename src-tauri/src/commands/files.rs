@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+use tauri::{State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::file_tree::{DirectoryPage, FileInfo, DEFAULT_PAGE_SIZE};
+
+/// Lists the direct children of a folder ("" or omitted for the vault root)
+/// from the cached tree, a page at a time, so large folders load lazily
+#[tauri::command]
+pub async fn list_directory(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    path: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<DirectoryPage, String> {
+    let tree = refactored_state.get_file_tree(window.label()).await?;
+    let mut page = tree.lock().await.list(
+        path.as_deref().unwrap_or(""),
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_PAGE_SIZE),
+    )?;
+
+    if let Some(vault_path) = refactored_state.get_window_vault_path(window.label()).await {
+        mark_git_status(vault_path, &mut page.entries).await;
+    }
+    Ok(page)
+}
+
+/// Marks uncommitted changes when the vault is a git repository
+pub(crate) async fn mark_git_status(vault_path: PathBuf, files: &mut [FileInfo]) {
+    let statuses = crate::commands::git::file_statuses(vault_path).await;
+    for file in files {
+        file.git_status = statuses.get(&file.path).copied();
+    }
+}
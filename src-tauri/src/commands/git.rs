@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::encryption::reveal;
+use crate::git::{AutoCommit, ConflictNote, GitCommit, GitFileStatus, GitRepo, RepoStatus};
+use crate::history::{diff_lines, DiffLine};
+use crate::vault_settings::FileSettings;
+
+/// Commits in the log when the caller does not ask for a number
+const DEFAULT_LOG_LIMIT: usize = 100;
+
+/// One git command at a time, so a scheduled commit never races a pull
+static GIT_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+async fn file_settings(app: &AppHandle, vault_path: &Path) -> Result<FileSettings, String> {
+    crate::vault_settings::get_vault_settings(app.clone(), vault_path.to_string_lossy().to_string()).await
+        .map(|settings| settings.files)
+}
+
+/// Runs git commands against the vault's repository off the async runtime
+async fn with_repo<T, F>(vault_path: PathBuf, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&GitRepo) -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let _guard = GIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let repo = GitRepo::open(&vault_path)
+            .ok_or_else(|| "This vault is not a git repository".to_string())?;
+        f(&repo)
+    })
+    .await
+    .map_err(|e| format!("git task failed: {}", e))?
+}
+
+/// Branch and uncommitted changes, or `None` when the vault is not a repository
+#[tauri::command]
+pub async fn git_status(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<Option<RepoStatus>, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    tokio::task::spawn_blocking(move || {
+        let _guard = GIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        GitRepo::open(&vault_path).map(|repo| repo.repo_status()).transpose()
+    })
+    .await
+    .map_err(|e| format!("git task failed: {}", e))?
+}
+
+/// Uncommitted changes by vault-relative path for the file tree; empty when
+/// the vault is not a repository
+pub(crate) async fn file_statuses(vault_path: PathBuf) -> HashMap<String, GitFileStatus> {
+    tokio::task::spawn_blocking(move || {
+        let _guard = GIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        GitRepo::open(&vault_path)?.status().ok()
+    })
+    .await
+    .ok()
+    .flatten()
+    .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn git_init(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<(), String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    tokio::task::spawn_blocking(move || {
+        let _guard = GIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        if GitRepo::open(&vault_path).is_some() {
+            return Err("This vault is already a git repository".to_string());
+        }
+        GitRepo::init(&vault_path)?;
+        println!("📦 Created git repository in {}", vault_path.display());
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("git task failed: {}", e))?
+}
+
+/// Commits all changes; without a message one is generated from the changes
+#[tauri::command]
+pub async fn git_commit(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    message: Option<String>,
+) -> Result<Option<GitCommit>, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let commit = with_repo(vault_path.clone(), move |repo| repo.commit_all(message.as_deref())).await?;
+    refactored_state.auto_commit.committed(&vault_path).await;
+    Ok(commit)
+}
+
+/// Commits that changed a note, newest first
+#[tauri::command]
+pub async fn git_note_log(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+    limit: Option<usize>,
+) -> Result<Vec<GitCommit>, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let rel_path = relative_note_path(&vault_path, &file_path)?;
+    with_repo(vault_path, move |repo| repo.log(Some(&rel_path), limit.unwrap_or(DEFAULT_LOG_LIMIT))).await
+}
+
+/// Line diff of a note between two commits; without `to_commit` the note as
+/// it is on disk now is compared
+#[tauri::command]
+pub async fn git_note_diff(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+    from_commit: String,
+    to_commit: Option<String>,
+) -> Result<Vec<DiffLine>, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let rel_path = relative_note_path(&vault_path, &file_path)?;
+
+    let repo_path = rel_path.clone();
+    let disk_path = vault_path.join(&rel_path);
+    let (old, new) = with_repo(vault_path.clone(), move |repo| {
+        let old = repo.read_at(&from_commit, &repo_path)?;
+        let new = match to_commit {
+            Some(to_commit) => repo.read_at(&to_commit, &repo_path)?,
+            None => std::fs::read_to_string(disk_path)
+                .map_err(|e| format!("Failed to read file: {}", e))?,
+        };
+        Ok((old, new))
+    }).await?;
+
+    // Encrypted notes are committed encrypted; compare their plaintext
+    let unlocked = refactored_state.keyring.get(&vault_path).await;
+    let old = reveal(&rel_path, old, unlocked.as_deref())?;
+    let new = reveal(&rel_path, new, unlocked.as_deref())?;
+    Ok(diff_lines(&old, &new))
+}
+
+/// Points the vault's configured remote at a URL or a local path
+#[tauri::command]
+pub async fn git_set_remote(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    url: String,
+) -> Result<(), String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let remote = file_settings(&app, &vault_path).await?.git_remote;
+    with_repo(vault_path, move |repo| repo.set_remote(&remote, &url)).await
+}
+
+/// Commits local changes and merges the remote's. Notes changed on both
+/// sides are returned with the conflict notes holding the remote's content.
+#[tauri::command]
+pub async fn git_pull(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<Vec<ConflictNote>, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let remote = file_settings(&app, &vault_path).await?.git_remote;
+    let conflicts = with_repo(vault_path.clone(), move |repo| {
+        repo.commit_all(None)?;
+        repo.pull(&remote)
+    }).await?;
+    refactored_state.auto_commit.committed(&vault_path).await;
+    Ok(conflicts)
+}
+
+#[tauri::command]
+pub async fn git_push(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<(), String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let remote = file_settings(&app, &vault_path).await?.git_remote;
+    with_repo(vault_path, move |repo| repo.push(&remote)).await
+}
+
+/// Settles a conflict note from a pull, keeping either the note as it is or
+/// the conflict note's content; returns the note's path
+#[tauri::command]
+pub async fn resolve_git_conflict(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    conflict_path: String,
+    keep_conflict_copy: bool,
+) -> Result<String, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let conflict_path = relative_note_path(&vault_path, &conflict_path)?;
+    with_repo(vault_path, move |repo| repo.resolve_conflict(&conflict_path, keep_conflict_copy)).await
+}
+
+fn relative_note_path(vault_path: &Path, file_path: &str) -> Result<String, String> {
+    let full_path = crate::vault::resolve_in_vault(vault_path, Path::new(file_path))?;
+    crate::index::resolver::relative_path(vault_path, &full_path)
+        .ok_or_else(|| format!("Not a file in the vault: {}", file_path))
+}
+
+/// Counts a save toward the vault's auto-commit batch and commits in the
+/// background once the batch is full
+pub async fn note_saved(app: &AppHandle, auto_commit: &Arc<AutoCommit>, vault_path: &Path) {
+    let settings = match file_settings(app, vault_path).await {
+        Ok(settings) if settings.git_auto_commit => settings,
+        _ => return,
+    };
+    if auto_commit.note_saved(vault_path, settings.git_commit_after_saves).await {
+        let auto_commit = auto_commit.clone();
+        let vault_path = vault_path.to_path_buf();
+        tauri::async_runtime::spawn(async move {
+            commit_automatically(&auto_commit, vault_path, &settings).await;
+        });
+    }
+}
+
+/// Commits each vault with automatic commits whose interval has passed
+pub async fn commit_due_vaults(app: &AppHandle, auto_commit: &AutoCommit, vault_paths: Vec<PathBuf>) {
+    for vault_path in vault_paths {
+        let settings = match file_settings(app, &vault_path).await {
+            Ok(settings) if settings.git_auto_commit && settings.git_commit_interval_minutes > 0 => settings,
+            _ => continue,
+        };
+        let interval = Duration::from_secs(u64::from(settings.git_commit_interval_minutes) * 60);
+        if auto_commit.is_due(&vault_path, interval).await {
+            commit_automatically(auto_commit, vault_path, &settings).await;
+        }
+    }
+}
+
+async fn commit_automatically(auto_commit: &AutoCommit, vault_path: PathBuf, settings: &FileSettings) {
+    auto_commit.committed(&vault_path).await;
+    let remote = settings.git_remote.clone();
+    let push = settings.git_auto_push;
+
+    let display_path = vault_path.display().to_string();
+    let result = tokio::task::spawn_blocking(move || {
+        let _guard = GIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let repo = match GitRepo::open(&vault_path) {
+            Some(repo) => repo,
+            None => return Ok(None),
+        };
+        let commit = repo.commit_all(None)?;
+        if push && commit.is_some() {
+            repo.push(&remote)?;
+        }
+        Ok::<_, String>(commit)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(Some(commit))) => println!("📦 Auto-committed {}: {}", display_path, commit.message.lines().next().unwrap_or_default()),
+        Ok(Ok(None)) => {}
+        Ok(Err(e)) => eprintln!("⚠️ Auto-commit of {} failed: {}", display_path, e),
+        Err(e) => eprintln!("⚠️ Auto-commit of {} failed: {}", display_path, e),
+    }
+}
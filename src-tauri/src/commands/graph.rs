@@ -96,6 +96,48 @@ pub async fn sync_vault_to_graph(
     let vault = crate::vault::Vault::new(vault_path.clone())
         .map_err(|e| format!("Failed to create vault: {}", e))?;
     let vault_arc = Arc::new(vault);
+    
+    let (graph_manager, vault_id) = connect_vault_graph(app_handle, vault_arc.path()).await?;
+    let graph_manager: Arc<dyn crate::graph::GraphManagerTrait> = graph_manager;
+    
+    // Clone for statistics later
+    let graph_manager_stats = graph_manager.clone();
+    
+    // Use simple sync instead
+    println!("Running simple sync...");
+    use crate::graph::simple_sync::sync_vault_simple;
+    
+    let skip_rels = skip_relationships.unwrap_or(false);
+    if skip_rels {
+        println!("Skipping relationship building for this sync");
+    }
+    
+    let (file_count, relationship_count) = sync_vault_simple(
+        vault_arc.path(),
+        &graph_manager,
+        &vault_id,
+        skip_rels,
+        &refactored_state.ignore_rules(&vault_path).current(),
+    ).await?;
+    
+    println!("Simple sync completed: {} files, {} relationships", file_count, relationship_count);
+    
+    let result = format!(
+        "Vault synced successfully! Processed {} files and created {} relationships in knowledge graph.",
+        file_count, relationship_count
+    );
+    
+    println!("Sync completed: {}", result);
+    
+    Ok(result)
+}
+
+/// Connects a graph manager with embedding support to a vault's databases;
+/// returns it with the vault's id in the graph
+pub(crate) async fn connect_vault_graph(
+    app_handle: tauri::AppHandle,
+    vault_path: &std::path::Path,
+) -> Result<(Arc<GraphManagerImpl>, String), String> {
     let vault_name = vault_path.file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("default")
@@ -103,7 +145,6 @@ pub async fn sync_vault_to_graph(
     
     // Get Docker connection info first
     println!("Getting Docker connection info...");
-    use crate::docker::shared::SharedDockerManager;
     let docker_manager = SharedDockerManager::new();
     let conn_info = docker_manager.get_connection_info(&vault_name).await
         .map_err(|e| {
@@ -111,16 +152,15 @@ pub async fn sync_vault_to_graph(
             format!("Failed to get connection info: {}", e)
         })?;
     
-    // Create a new graph manager for this sync operation with app handle for embeddings
+    // Create a new graph manager for this operation with app handle for embeddings
     println!("Creating graph manager with embedding support...");
-    use crate::graph::GraphManagerImpl;
-    let graph_manager: Arc<dyn crate::graph::GraphManagerTrait> = Arc::new(
+    let graph_manager = Arc::new(
         GraphManagerImpl::with_vault_and_app_handle(conn_info.vault_id.clone(), app_handle)
     );
     
     let config = crate::graph::GraphConfig {
         vault_id: conn_info.vault_id.clone(),
-        vault_path: vault_arc.path().to_string_lossy().to_string(),
+        vault_path: vault_path.to_string_lossy().to_string(),
         neo4j_uri: conn_info.neo4j.uri.clone(),
         neo4j_user: conn_info.neo4j.username.clone(),
         neo4j_password: conn_info.neo4j.password.clone(),
@@ -128,41 +168,13 @@ pub async fn sync_vault_to_graph(
     };
     
     println!("Connecting to graph databases...");
-    graph_manager.connect(&config).await
+    crate::graph::GraphManagerTrait::connect(graph_manager.as_ref(), &config).await
         .map_err(|e| {
             println!("Failed to connect to graph databases: {}", e);
             format!("Failed to connect to graph databases: {}", e)
         })?;
     
-    // Clone for statistics later
-    let graph_manager_stats = graph_manager.clone();
-    
-    // Use simple sync instead
-    println!("Running simple sync...");
-    use crate::graph::simple_sync::sync_vault_simple;
-    
-    let skip_rels = skip_relationships.unwrap_or(false);
-    if skip_rels {
-        println!("Skipping relationship building for this sync");
-    }
-    
-    let (file_count, relationship_count) = sync_vault_simple(
-        vault_arc.path(),
-        &graph_manager,
-        &conn_info.vault_id,
-        skip_rels
-    ).await?;
-    
-    println!("Simple sync completed: {} files, {} relationships", file_count, relationship_count);
-    
-    let result = format!(
-        "Vault synced successfully! Processed {} files and created {} relationships in knowledge graph.",
-        file_count, relationship_count
-    );
-    
-    println!("Sync completed: {}", result);
-    
-    Ok(result)
+    Ok((graph_manager, conn_info.vault_id))
 }
 
 #[tauri::command]
@@ -230,7 +242,7 @@ pub async fn graph_update_node(
     // Get graph manager
     let graph_lock = state.graph_manager.lock().await;
     if let Some(ref manager) = *graph_lock {
-        let full_path = vault_path.join(&file_path);
+        let full_path = crate::vault::resolve_in_vault(&vault_path, std::path::Path::new(&file_path))?;
         
         // Use the existing sync_single_file function
         crate::graph::sync::sync_single_file(
@@ -0,0 +1,123 @@
+use std::path::Path;
+use tauri::{AppHandle, State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::encryption::{conceal, reveal};
+use crate::history::{diff_lines, DiffLine, HistoryRetention, NoteHistory, NoteVersion};
+use crate::index::resolver::relative_path;
+use crate::vault::{FileVersion, Vault, WriteError};
+
+/// Vault-relative key a note's history is stored under
+fn history_key(vault: &Vault, file_path: &str) -> Result<String, String> {
+    let full_path = vault.resolve_path(Path::new(file_path))?;
+    relative_path(vault.path(), &full_path).ok_or_else(|| format!("Not a file in the vault: {}", file_path))
+}
+
+/// Records a saved note in its version history using the vault's retention settings.
+/// Failures are logged rather than returned so a save never fails because of history.
+pub async fn record_save(app: &AppHandle, vault: &Vault, file_path: &str, content: &str) {
+    let retention = match crate::vault_settings::get_vault_settings(app.clone(), vault.path().to_string_lossy().to_string()).await {
+        Ok(settings) => HistoryRetention {
+            max_versions: settings.files.history_max_versions,
+            retention_days: settings.files.history_retention_days,
+        },
+        Err(e) => {
+            eprintln!("⚠️ Failed to load vault settings for history: {}", e);
+            return;
+        }
+    };
+
+    let result = history_key(vault, file_path)
+        .and_then(|key| NoteHistory::new(vault.path()).record(&key, content, retention));
+    if let Err(e) = result {
+        eprintln!("⚠️ Failed to record version of {}: {}", file_path, e);
+    }
+}
+
+/// Lists the saved versions of a note, newest first. Opening the history also
+/// deletes the stored contents of versions pruned since it was last opened.
+#[tauri::command]
+pub async fn list_note_versions(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<Vec<NoteVersion>, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let versions = NoteHistory::new(vault.path()).list(&history_key(&vault, &file_path)?)?;
+
+    let vault_path = vault.path().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = NoteHistory::new(&vault_path).collect_garbage() {
+            eprintln!("⚠️ Failed to clean up history: {}", e);
+        }
+    });
+    Ok(versions)
+}
+
+#[tauri::command]
+pub async fn get_note_version(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+    version_id: String,
+) -> Result<String, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let key = history_key(&vault, &file_path)?;
+    let content = NoteHistory::new(vault.path()).read_version(&key, &version_id)?;
+    let unlocked = refactored_state.keyring.get(vault.path()).await;
+    reveal(&key, content, unlocked.as_deref())
+}
+
+/// Line diff between two versions of a note; without `to_version` the note
+/// as it is on disk now is compared
+#[tauri::command]
+pub async fn diff_note_versions(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+    from_version: String,
+    to_version: Option<String>,
+) -> Result<Vec<DiffLine>, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let key = history_key(&vault, &file_path)?;
+    let history = NoteHistory::new(vault.path());
+    let unlocked = refactored_state.keyring.get(vault.path()).await;
+
+    let old = history.read_version(&key, &from_version)?;
+    let new = match to_version {
+        Some(to_version) => history.read_version(&key, &to_version)?,
+        None => vault.read_file(Path::new(&key))
+            .map_err(|e| format!("Failed to read file: {}", e))?,
+    };
+    let old = reveal(&key, old, unlocked.as_deref())?;
+    let new = reveal(&key, new, unlocked.as_deref())?;
+    Ok(diff_lines(&old, &new))
+}
+
+/// Writes an old version back as a new save, so the current content stays in
+/// the history too. Returns the version the editor should continue from.
+#[tauri::command]
+pub async fn restore_note_version(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+    version_id: String,
+) -> Result<FileVersion, WriteError> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let key = history_key(&vault, &file_path)?;
+    let content = NoteHistory::new(vault.path()).read_version(&key, &version_id)?;
+    // A version is restored encrypted whenever the note is encrypted now
+    let unlocked = refactored_state.keyring.get(vault.path()).await;
+    let content = reveal(&key, content, unlocked.as_deref())?;
+    let content = conceal(vault.path(), &key, &content, unlocked.as_deref())?;
+
+    // Whatever is on disk now becomes a version before it is replaced
+    if let Ok(current) = vault.read_file(Path::new(&key)) {
+        record_save(&app, &vault, &key, &current).await;
+    }
+
+    let version = vault.write_file_checked(Path::new(&key), &content, None, None)?;
+    record_save(&app, &vault, &key, &content).await;
+    println!("⏪ Restored {} to version {}", key, version_id);
+    Ok(version)
+}
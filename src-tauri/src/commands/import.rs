@@ -0,0 +1,90 @@
+use serde::Serialize;
+use tauri::{AppHandle, State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::import::{Import, ImportOptions, ImportReport, ImportSource, ImportedSettings};
+use crate::vault_settings::{FileSettings, VaultSettingsInput};
+
+/// What an export looks like before importing it
+#[derive(Debug, Serialize)]
+pub struct ImportPreview {
+    pub source: ImportSource,
+    pub settings: ImportedSettings,
+}
+
+/// Detects the kind of export at `options.path` and the settings it carries
+#[tauri::command]
+pub async fn preview_import(options: ImportOptions) -> Result<ImportPreview, String> {
+    tokio::task::spawn_blocking(move || {
+        let import = Import::prepare(&options)?;
+        Ok(ImportPreview { source: import.source(), settings: import.settings().clone() })
+    })
+    .await
+    .map_err(|e| format!("Import failed: {}", e))?
+}
+
+/// Imports an Obsidian vault, a Notion/markdown export, Evernote `.enex`
+/// files or HTML files into the window's vault. With `apply_settings`, the
+/// export's attachment folder, daily notes and templates settings replace the
+/// vault's.
+#[tauri::command]
+pub async fn import_into_vault(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    options: ImportOptions,
+    apply_settings: Option<bool>,
+) -> Result<ImportReport, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let vault_path = vault.path().to_string_lossy().to_string();
+    let image_location = crate::vault_settings::get_vault_settings(app.clone(), vault_path.clone()).await?
+        .files
+        .image_location;
+    let apply_settings = apply_settings.unwrap_or(false);
+    let unlocked = refactored_state.keyring.get(vault.path()).await;
+
+    let (mut report, imported) = tokio::task::spawn_blocking(move || {
+        let import = Import::prepare(&options)?;
+        let imported = import.settings().clone();
+        // Attachments go where the vault will look for them after the import
+        let image_location = match (&imported.attachment_folder, apply_settings) {
+            (Some(folder), true) => folder.clone(),
+            _ => image_location,
+        };
+        import.run(&vault, &image_location, unlocked.as_deref()).map(|report| (report, imported))
+    })
+    .await
+    .map_err(|e| format!("Import failed: {}", e))??;
+
+    if apply_settings && !imported.is_empty() {
+        let mut settings = crate::vault_settings::get_vault_settings(app.clone(), vault_path.clone()).await?;
+        apply_imported_settings(&mut settings.files, &imported);
+        crate::vault_settings::save_vault_settings(app, VaultSettingsInput {
+            vault_path,
+            editor: settings.editor,
+            files: settings.files,
+        })
+        .await?;
+        report.settings = Some(imported);
+    }
+
+    Ok(report)
+}
+
+/// Maps settings read from an export onto the vault's file settings
+fn apply_imported_settings(files: &mut FileSettings, imported: &ImportedSettings) {
+    if let Some(folder) = &imported.attachment_folder {
+        files.image_location = format!("{}/", folder);
+    }
+    if let Some(folder) = &imported.daily_notes_folder {
+        files.daily_notes_folder = folder.clone();
+    }
+    if let Some(format) = &imported.daily_note_format {
+        files.daily_note_format = format.clone();
+    }
+    if let Some(template) = &imported.daily_note_template {
+        files.daily_note_template = template.clone();
+    }
+    if let Some(folder) = &imported.templates_folder {
+        files.templates_folder = folder.clone();
+    }
+}
@@ -0,0 +1,64 @@
+use tauri::{State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::index::{Backlink, ResolvedLink};
+
+#[tauri::command]
+pub async fn get_outgoing_links(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<Vec<ResolvedLink>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let source = index.relative_key(&file_path);
+
+    let links = index.links.lock().await;
+    Ok(links.outgoing_links(&source))
+}
+
+#[tauri::command]
+pub async fn get_backlinks(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<Vec<Backlink>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let target = index.relative_key(&file_path);
+
+    let links = index.links.lock().await;
+    Ok(links.backlinks(&target))
+}
+
+#[tauri::command]
+pub async fn get_unresolved_links(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<Vec<ResolvedLink>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let source = index.relative_key(&file_path);
+
+    let links = index.links.lock().await;
+    Ok(links.unresolved_links(&source))
+}
+
+/// Resolves the text inside `[[...]]` to a vault-relative path
+#[tauri::command]
+pub async fn resolve_wiki_link(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    link_text: String,
+    source_path: Option<String>,
+) -> Result<Option<String>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let source = source_path
+        .map(|p| index.relative_key(&p))
+        .unwrap_or_default();
+
+    let link = match crate::index::links::parse_link_inner(&link_text) {
+        Some(link) => link,
+        None => return Ok(None),
+    };
+
+    let links = index.links.lock().await;
+    Ok(links.resolve(&link.target, &source))
+}
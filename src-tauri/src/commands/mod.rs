@@ -1,3 +1,21 @@
+pub mod attachments;
+pub mod backup;
+pub mod encryption;
+pub mod files;
+pub mod git;
 pub mod graph;
+pub mod history;
+pub mod import;
+pub mod links;
+pub mod periodic;
+pub mod properties;
+pub mod publish;
+pub mod query;
+pub mod refactor;
+pub mod replace;
 pub mod search;
-pub mod sync;
\ No newline at end of file
+pub mod sync;
+pub mod tags;
+pub mod tasks;
+pub mod templates;
+pub mod trash;
\ No newline at end of file
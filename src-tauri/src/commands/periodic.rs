@@ -0,0 +1,63 @@
+use chrono::NaiveDate;
+use tauri::{AppHandle, State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::periodic::{Direction, Period, PeriodicNote, PeriodicNoteSettings};
+use crate::templates::Templates;
+use crate::vault::Vault;
+
+async fn period_settings(app: &AppHandle, vault: &Vault, period: Period) -> Result<(PeriodicNoteSettings, Templates), String> {
+    let settings = crate::vault_settings::get_vault_settings(app.clone(), vault.path().to_string_lossy().to_string()).await?;
+    let templates = Templates::new(vault.path(), &settings.files.templates_folder);
+    Ok((settings.files.periodic_notes(period), templates))
+}
+
+/// Opens the daily, weekly or monthly note for `date` (today if omitted),
+/// creating it from the period's template when it does not exist yet
+#[tauri::command]
+pub async fn open_periodic_note(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    period: Period,
+    date: Option<NaiveDate>,
+) -> Result<PeriodicNote, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let (settings, templates) = period_settings(&app, &vault, period).await?;
+    let date = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+    let unlocked = refactored_state.keyring.get(vault.path()).await;
+    settings.open_or_create(&vault, &templates, period, date, unlocked.as_deref())
+}
+
+/// Nearest existing periodic note before or after the one for `date`
+#[tauri::command]
+pub async fn adjacent_periodic_note(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    period: Period,
+    date: NaiveDate,
+    direction: Direction,
+) -> Result<Option<PeriodicNote>, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let (settings, _) = period_settings(&app, &vault, period).await?;
+    Ok(settings.adjacent(&vault, period, date, direction))
+}
+
+/// Existing periodic notes between `from` and `to` (inclusive), oldest first
+#[tauri::command]
+pub async fn list_periodic_notes(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    period: Period,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<PeriodicNote>, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let (settings, _) = period_settings(&app, &vault, period).await?;
+    Ok(settings.existing_notes(&vault, period)
+        .into_iter()
+        .filter(|n| from.is_none_or(|from| n.date >= period.start(from)))
+        .filter(|n| to.is_none_or(|to| n.date <= to))
+        .collect())
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+use tauri::{AppHandle, State, Window};
+use crate::{AppState, refactored_app_state::RefactoredAppState};
+use crate::index::frontmatter::{remove_property, set_property};
+use crate::index::resolver::is_markdown;
+use crate::index::NoteProperties;
+use crate::vault::{content_hash, Vault};
+
+fn note_path(file_path: &str) -> Result<&Path, String> {
+    if !is_markdown(file_path) {
+        return Err(format!("Not a markdown note: {}", file_path));
+    }
+    Ok(Path::new(file_path))
+}
+
+/// Reads a note's frontmatter as typed properties
+#[tauri::command]
+pub async fn get_note_properties(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<NoteProperties, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let content = vault.read_file(note_path(&file_path)?)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(NoteProperties::from_content(&content))
+}
+
+/// Sets one frontmatter property (any JSON value) and returns the updated properties
+#[tauri::command]
+pub async fn set_note_property(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+    key: String,
+    value: serde_json::Value,
+) -> Result<NoteProperties, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    update_note(&app, &state, &vault, &file_path, |content| set_property(content, &key, &value)).await
+}
+
+#[tauri::command]
+pub async fn remove_note_property(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+    key: String,
+) -> Result<NoteProperties, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    update_note(&app, &state, &vault, &file_path, |content| remove_property(content, &key)).await
+}
+
+/// Rewrites a note's frontmatter unless the note changed since it was read,
+/// then records the save and queues the graph update like an editor save
+async fn update_note(
+    app: &AppHandle,
+    state: &AppState,
+    vault: &Vault,
+    file_path: &str,
+    edit: impl FnOnce(&str) -> Result<String, String>,
+) -> Result<NoteProperties, String> {
+    let path = note_path(file_path)?;
+    let content = vault.read_file(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    if crate::encryption::is_encrypted(&content) {
+        return Err("Properties of encrypted notes are edited in the note itself".to_string());
+    }
+
+    let updated = edit(&content)?;
+    if updated != content {
+        vault.write_file_checked(path, &updated, Some(&content_hash(&content)), None)
+            .map_err(|e| e.to_string())?;
+
+        crate::commands::history::record_save(app, vault, file_path, &updated).await;
+        if let Some(queue) = state.update_queue.lock().await.clone() {
+            let full_path = vault.resolve_path(path).map_err(|e| format!("Invalid path: {}", e))?;
+            if let Err(e) = queue.add_update(full_path, vault.path().to_path_buf(), &updated, &vault.ignore()).await {
+                eprintln!("⚠️ Failed to queue graph update for {}: {}", file_path, e);
+            }
+        }
+    }
+    Ok(NoteProperties::from_content(&updated))
+}
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+use tauri::{State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::publish::{PublishOptions, PublishReport};
+
+/// Renders the vault, or `options.folder`, as a static website in `output_dir`
+#[tauri::command]
+pub async fn publish_site(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    output_dir: String,
+    options: PublishOptions,
+) -> Result<PublishReport, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let output_dir = PathBuf::from(output_dir);
+
+    tokio::task::spawn_blocking(move || crate::publish::publish_site(&vault, &output_dir, &options))
+        .await
+        .map_err(|e| format!("Publishing failed: {}", e))?
+}
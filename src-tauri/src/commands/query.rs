@@ -0,0 +1,34 @@
+use tauri::{State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::index::query::{Query, QueryNote, QueryResult};
+use crate::index::resolver::is_markdown;
+
+/// Runs a `TABLE`/`LIST` query against the vault's cached file tree and
+/// indexed frontmatter
+#[tauri::command]
+pub async fn run_query(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    query: String,
+) -> Result<QueryResult, String> {
+    let query = Query::parse(&query)?;
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let tree = refactored_state.get_file_tree(window.label()).await?;
+
+    let files = tree.lock().await.all();
+    let notes: Vec<QueryNote> = {
+        let properties = index.properties.lock().await;
+        let tags = index.tags.lock().await;
+        files
+            .into_iter()
+            .filter(|file| !file.is_dir && is_markdown(&file.path))
+            .map(|file| QueryNote {
+                properties: properties.properties_for_file(&file.path),
+                tags: tags.tags_for_file(&file.path),
+                file,
+            })
+            .collect()
+    };
+
+    Ok(query.run(&notes, chrono::Local::now().date_naive()))
+}
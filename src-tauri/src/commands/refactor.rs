@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{State, Window};
+use crate::{AppState, refactored_app_state::RefactoredAppState};
+use crate::encryption::{conceal, is_encrypted, reveal, EncryptionConfig, UnlockedVault};
+use crate::graph::GraphManagerTrait;
+use crate::graph::sync::GraphSyncService;
+use crate::index::VaultIndex;
+use crate::index::refactor::{plan_rename, RenamePlan};
+use crate::index::resolver::is_markdown;
+use crate::vault::{content_hash, resolve_in_vault, write_atomic, write_lock};
+
+/// Computes what renaming/moving a note or folder would change without touching the disk
+#[tauri::command]
+pub async fn preview_rename(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    old_path: String,
+    new_path: String,
+    with_attachments: Option<bool>,
+) -> Result<RenamePlan, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let unlocked = refactored_state.keyring.get(index.vault_path()).await;
+    build_plan(&index, unlocked.as_deref(), &old_path, &new_path, with_attachments.unwrap_or(false)).await
+}
+
+/// Plans a rename; with `with_attachments`, attachments in the note's folder
+/// that only this note references move along with it. Encrypted notes are
+/// planned from their plaintext, so the vault must be unlocked when it has any.
+async fn build_plan(
+    index: &VaultIndex,
+    unlocked: Option<&UnlockedVault>,
+    old_path: &str,
+    new_path: &str,
+    with_attachments: bool,
+) -> Result<RenamePlan, String> {
+    let vault_path = index.vault_path().to_path_buf();
+    let from = resolve_in_vault(&vault_path, Path::new(old_path))?;
+    let to = resolve_in_vault(&vault_path, Path::new(new_path))?;
+    let from = index.relative_key(&from.to_string_lossy());
+    let to = index.relative_key(&to.to_string_lossy());
+
+    if unlocked.is_none() && !index.encrypted.lock().await.is_empty() {
+        return Err(format!("Encrypted notes may link to {}; unlock the vault to rename it", from));
+    }
+    let read_note = move |rel: &str| std::fs::read_to_string(vault_path.join(rel))
+        .ok()
+        .and_then(|content| reveal(rel, content, unlocked).ok());
+
+    let links = index.links.lock().await;
+    let companions = if with_attachments && is_markdown(&from) {
+        index.readable_attachments(unlocked).await?.companions(links.resolver(), &from, &to)
+    } else {
+        Vec::new()
+    };
+    plan_rename(links.resolver(), &from, &to, &companions, &read_note)
+}
+
+/// Renames or moves a note or folder, rewriting every link that points at it
+/// and re-keying the moved notes in the graph. Notes moved into an encrypted
+/// folder are encrypted, which needs the vault unlocked. Returns the applied plan.
+pub async fn rename_with_links(
+    index: &VaultIndex,
+    graph_manager: Option<Arc<dyn GraphManagerTrait>>,
+    unlocked: Option<&UnlockedVault>,
+    old_path: &str,
+    new_path: &str,
+    with_attachments: bool,
+) -> Result<RenamePlan, String> {
+    let plan = build_plan(index, unlocked, old_path, new_path, with_attachments).await?;
+    let vault_path = index.vault_path();
+
+    let old_full_path = vault_path.join(&plan.from);
+    let new_full_path = vault_path.join(&plan.to);
+    if new_full_path.exists() {
+        return Err(format!("A file already exists at {}", plan.to));
+    }
+
+    // Plaintext notes landing in an encrypted folder
+    let encryption = EncryptionConfig::load(vault_path)?;
+    let sealed: Vec<&str> = plan.moved_files.iter()
+        .filter(|m| is_markdown(&m.to) && encryption.as_ref().is_some_and(|c| c.covers(&m.to)))
+        .filter(|m| std::fs::read_to_string(vault_path.join(&m.from)).is_ok_and(|c| !is_encrypted(&c)))
+        .map(|m| m.to.as_str())
+        .collect();
+    if !sealed.is_empty() && unlocked.is_none() {
+        return Err(format!("{} is in an encrypted folder; unlock the vault to move notes there", plan.to));
+    }
+    move_path(&old_full_path, &new_full_path)
+        .map_err(|e| format!("Failed to move {}: {}", plan.from, e))?;
+    let mut moves = vec![(old_full_path, new_full_path)];
+
+    // Companion files (a note's attachments) live outside the moved path and move one by one
+    let inner = format!("{}/", plan.from);
+    for moved in plan.moved_files.iter().filter(|m| m.from != plan.from && !m.from.starts_with(&inner)) {
+        let (from, to) = (vault_path.join(&moved.from), vault_path.join(&moved.to));
+        let result = if to.exists() {
+            Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, "destination exists"))
+        } else {
+            move_path(&from, &to)
+        };
+        if let Err(e) = result {
+            undo_moves(&moves);
+            return Err(format!("Failed to move attachment {}: {}", moved.from, e));
+        }
+        moves.push((from, to));
+    }
+
+    // Links are rewritten and moved notes encrypted once the files are in
+    // place; a note edited since the plan, or a failed write, restores the
+    // notes already written and moves the files back
+    let writes = plan.edited_files.iter()
+        .map(|edit| (edit.new_path.as_str(), Some(edit)))
+        .chain(sealed.iter()
+            .filter(|path| !plan.edited_files.iter().any(|e| e.new_path == **path))
+            .map(|path| (*path, None)));
+    let mut written: Vec<(PathBuf, String)> = Vec::new();
+    for (rel_path, edit) in writes {
+        let path = vault_path.join(rel_path);
+        let lock = write_lock(&path);
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let result = std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|original| {
+            // Encrypted notes were planned from their plaintext
+            let plaintext = reveal(rel_path, original.clone(), unlocked)?;
+            let new_content = match edit {
+                Some(edit) if content_hash(&plaintext) != edit.original_hash => {
+                    return Err("it changed since the rename was planned; try again".to_string());
+                }
+                Some(edit) => edit.new_content.as_str(),
+                None => original.as_str(),
+            };
+            let content = conceal(vault_path, rel_path, new_content, unlocked)?;
+            write_atomic(&path, content.as_bytes()).map_err(|e| e.to_string())?;
+            Ok(original)
+        });
+        match result {
+            Ok(original) => written.push((path, original)),
+            Err(e) => {
+                drop(_guard);
+                for (path, original) in written.iter().rev() {
+                    let lock = write_lock(path);
+                    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Err(e) = write_atomic(path, original.as_bytes()) {
+                        eprintln!("⚠️ Failed to restore {}: {}", path.display(), e);
+                    }
+                }
+                undo_moves(&moves);
+                return Err(format!("Failed to update {}: {}", rel_path, e));
+            }
+        }
+    }
+
+    println!("📦 Moved {} -> {} ({} files moved, {} links updated in {} notes)",
+             plan.from, plan.to, plan.moved_files.len(), plan.links_updated(), plan.edited_files.len());
+
+    // Version history follows the notes to their new paths; notes now
+    // encrypted leave their plaintext history and the graph behind
+    let history = crate::history::NoteHistory::new(vault_path);
+    for moved in &plan.moved_files {
+        let result = if sealed.contains(&moved.to.as_str()) {
+            history.forget(&moved.from)
+        } else {
+            history.rename(&moved.from, &moved.to)
+        };
+        if let Err(e) = result {
+            eprintln!("⚠️ Failed to move version history of {}: {}", moved.from, e);
+        }
+    }
+
+    if let Some(graph_manager) = graph_manager {
+        rename_graph_notes(&graph_manager, vault_path, &plan).await;
+        let sealed: Vec<String> = sealed.iter().map(|path| path.to_string()).collect();
+        crate::commands::trash::delete_graph_notes(&graph_manager, vault_path, &sealed).await;
+    }
+
+    Ok(plan)
+}
+
+/// Renames `from` to `to`, creating the destination's parent folders
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(from, to)
+}
+
+/// Moves files back to where they were, newest move first
+fn undo_moves(moves: &[(PathBuf, PathBuf)]) {
+    for (from, to) in moves.iter().rev() {
+        if let Err(e) = std::fs::rename(to, from) {
+            eprintln!("⚠️ Failed to move {} back: {}", to.display(), e);
+        }
+    }
+}
+
+/// Keeps graph nodes (and their relationships) attached to the moved notes
+async fn rename_graph_notes(graph_manager: &Arc<dyn GraphManagerTrait>, vault_path: &Path, plan: &RenamePlan) {
+    let vault_id = crate::vault_id::generate_vault_id(vault_path);
+
+    for moved in plan.moved_files.iter().filter(|m| crate::index::resolver::is_markdown(&m.to)) {
+        let old_id = GraphSyncService::generate_note_id(&vault_path.join(&moved.from), &vault_id);
+        let new_id = GraphSyncService::generate_note_id(&vault_path.join(&moved.to), &vault_id);
+        let title = Path::new(&moved.to)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled");
+
+        if let Err(e) = graph_manager.rename_note(&old_id, &new_id, &moved.to, title).await {
+            eprintln!("⚠️ Failed to update graph note for {}: {}", moved.to, e);
+        }
+    }
+}
+
+/// Current graph manager, if graph services are connected
+pub async fn current_graph_manager(state: &AppState) -> Option<Arc<dyn GraphManagerTrait>> {
+    state.graph_manager.lock().await.clone()
+}
@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State, Window};
+use crate::{AppState, refactored_app_state::RefactoredAppState};
+use crate::index::VaultIndex;
+use crate::index::replace::{apply, FileMatches, ReplaceQuery, Replacer};
+use crate::vault::{content_hash, resolve_in_vault, write_atomic, write_lock, Vault};
+
+/// The matches chosen for replacing in one note
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplaceSelection {
+    pub path: String,
+    /// `hash` from the preview; the note must not have changed since
+    pub hash: String,
+    /// `start` offsets of the selected matches
+    pub matches: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplaceResult {
+    pub files: Vec<String>,
+    pub replacements: usize,
+}
+
+/// Notes the query applies to, from the index so ignore rules hold
+async fn scoped_notes(index: &VaultIndex, replacer: &Replacer) -> Vec<String> {
+    let files: Vec<String> = index.links.lock().await.resolver().files().cloned().collect();
+    let tags = index.tags.lock().await;
+    let mut notes: Vec<String> = files
+        .into_iter()
+        .filter(|rel| replacer.includes(rel, &tags.tags_for_file(rel)))
+        .collect();
+    notes.sort();
+    notes
+}
+
+/// Lists every match of a vault-wide search with its replacement, per note
+#[tauri::command]
+pub async fn preview_replace(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    query: ReplaceQuery,
+) -> Result<Vec<FileMatches>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let replacer = query.compile()?;
+    let notes = scoped_notes(&index, &replacer).await;
+    let vault_path = index.vault_path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        notes
+            .into_iter()
+            .filter_map(|rel| {
+                let content = std::fs::read_to_string(vault_path.join(&rel)).ok()?;
+                if crate::encryption::is_encrypted(&content) {
+                    return None;
+                }
+                let matches = replacer.find(&content);
+                (!matches.is_empty()).then(|| FileMatches { path: rel, hash: content_hash(&content), matches })
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Search failed: {}", e))
+}
+
+/// Replaces the selected matches. Every note is checked against its preview
+/// first; if any changed, or a write fails, no note is left modified. Edited
+/// notes are recorded in version history and queued for graph sync.
+#[tauri::command]
+pub async fn apply_replace(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    query: ReplaceQuery,
+    selections: Vec<ReplaceSelection>,
+) -> Result<ReplaceResult, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let replacer = query.compile()?;
+    let in_scope = scoped_notes(&index, &replacer).await;
+    let vault_path = index.vault_path().to_path_buf();
+
+    let mut targets = Vec::new();
+    for selection in &selections {
+        let rel = index.relative_key(&selection.path);
+        if !in_scope.contains(&rel) {
+            return Err(format!("{} is not part of this search", rel));
+        }
+        let full_path = resolve_in_vault(&vault_path, Path::new(&rel))?;
+        targets.push((rel, full_path, selection));
+    }
+    let edits = write_edits(&replacer, targets)?;
+
+    let vault = Vault::new(vault_path.clone()).map_err(|e| format!("Failed to open vault: {}", e))?;
+    let update_queue = state.update_queue.lock().await.clone();
+    for (rel, full_path, _, updated, _) in &edits {
+        crate::commands::history::record_save(&app, &vault, rel, updated).await;
+        if let Some(queue) = &update_queue {
+            if let Err(e) = queue.add_update(full_path.clone(), vault_path.clone(), updated, &index.ignore()).await {
+                eprintln!("⚠️ Failed to queue graph update for {}: {}", rel, e);
+            }
+        }
+    }
+
+    let replacements = edits.iter().map(|edit| edit.4).sum();
+    println!("🔁 Replaced {} matches in {} notes", replacements, edits.len());
+    Ok(ReplaceResult {
+        files: edits.into_iter().map(|edit| edit.0).collect(),
+        replacements,
+    })
+}
+
+/// A note rewritten by a replace: path, full path, original, updated, count
+type Edit = (String, PathBuf, String, String, usize);
+
+/// Checks and writes every selected note while holding all their write locks,
+/// so no save lands between a note's hash check, its write, or its rollback
+fn write_edits(
+    replacer: &Replacer,
+    mut targets: Vec<(String, PathBuf, &ReplaceSelection)>,
+) -> Result<Vec<Edit>, String> {
+    // One lock per file, always taken in path order
+    targets.sort_by(|a, b| a.1.cmp(&b.1));
+    if let Some(pair) = targets.windows(2).find(|pair| pair[0].1 == pair[1].1) {
+        return Err(format!("{} is selected more than once", pair[0].0));
+    }
+    let locks: Vec<_> = targets.iter().map(|(_, full_path, _)| write_lock(full_path)).collect();
+    let _guards: Vec<_> = locks.iter().map(|lock| lock.lock().unwrap_or_else(|e| e.into_inner())).collect();
+
+    let mut edits = Vec::new();
+    for (rel, full_path, selection) in targets {
+        let content = std::fs::read_to_string(&full_path)
+            .map_err(|e| format!("Failed to read {}: {}", rel, e))?;
+        if content_hash(&content) != selection.hash {
+            return Err(format!("{} changed since the preview; search again", rel));
+        }
+        let (updated, count) = apply(&content, &replacer.find(&content), &selection.matches);
+        if count > 0 && updated != content {
+            edits.push((rel, full_path, content, updated, count));
+        }
+    }
+
+    for (i, (rel, full_path, _, updated, _)) in edits.iter().enumerate() {
+        if let Err(e) = write_atomic(full_path, updated.as_bytes()) {
+            for (_, written_path, original, _, _) in &edits[..i] {
+                if let Err(e) = write_atomic(written_path, original.as_bytes()) {
+                    eprintln!("⚠️ Failed to restore {}: {}", written_path.display(), e);
+                }
+            }
+            return Err(format!("Failed to write {}: {}", rel, e));
+        }
+    }
+    Ok(edits)
+}
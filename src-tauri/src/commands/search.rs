@@ -27,11 +27,12 @@ pub async fn hybrid_search(
         .map_err(|e| format!("Failed to get connection info: {}", e))?;
 
     // Create hybrid search manager with the correct vault_id
+    let ignore = refactored_state.ignore_rules(&vault_path).current();
     let search_manager = HybridSearchManager::new(
         state.graph_manager.clone(),
-        state.mcp_manager.clone(),
         vault_path,
         conn_info.vault_id,
+        ignore,
     );
 
     // Execute search
@@ -0,0 +1,28 @@
+use tauri::{State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::index::TagCount;
+
+/// Lists every tag in the vault with its note count, including parent tags
+#[tauri::command]
+pub async fn get_all_tags(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<Vec<TagCount>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+
+    let tags = index.tags.lock().await;
+    Ok(tags.all_tags())
+}
+
+#[tauri::command]
+pub async fn get_note_tags(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<Vec<String>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let rel_path = index.relative_key(&file_path);
+
+    let tags = index.tags.lock().await;
+    Ok(tags.tags_for_file(&rel_path))
+}
@@ -0,0 +1,70 @@
+use std::path::Path;
+use tauri::{AppHandle, State, Window};
+use crate::{AppState, refactored_app_state::RefactoredAppState};
+use crate::index::tasks::toggle_task as toggle_task_line;
+use crate::index::{Task, TaskQuery};
+use crate::vault::{content_hash, resolve_in_vault, Vault};
+
+/// Lists the vault's tasks matching a query, soonest due first
+#[tauri::command]
+pub async fn query_tasks(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    query: TaskQuery,
+) -> Result<Vec<Task>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+
+    let tasks = index.tasks.lock().await;
+    Ok(tasks.query(&query, chrono::Local::now().date_naive()))
+}
+
+#[tauri::command]
+pub async fn get_note_tasks(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+) -> Result<Vec<Task>, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let rel_path = index.relative_key(&file_path);
+
+    let tasks = index.tasks.lock().await;
+    Ok(tasks.tasks_for_file(&rel_path))
+}
+
+/// Marks a task done, or open again, by rewriting its line in the note.
+/// `text` is the task's text as listed, so a line edited since is not touched.
+#[tauri::command]
+pub async fn toggle_task(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    file_path: String,
+    line: usize,
+    text: String,
+) -> Result<Task, String> {
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let rel_path = index.relative_key(&file_path);
+    let vault_path = index.vault_path().to_path_buf();
+    let full_path = resolve_in_vault(&vault_path, Path::new(&rel_path))?;
+    let vault = Vault::new(vault_path.clone()).map_err(|e| format!("Failed to open vault: {}", e))?;
+
+    let content = std::fs::read_to_string(&full_path)
+        .map_err(|e| format!("Failed to read {}: {}", rel_path, e))?;
+    let (updated, task) = toggle_task_line(&rel_path, &content, line, &text, chrono::Local::now().date_naive())?;
+    // A save between the read and this write fails the check instead of being lost
+    vault.write_file_checked(Path::new(&rel_path), &updated, Some(&content_hash(&content)), None)
+        .map_err(|e| e.to_string())?;
+
+    // Update the index now so a query right after sees the change
+    index.tasks.lock().await.update_file(&rel_path, Some(&updated));
+
+    crate::commands::history::record_save(&app, &vault, &rel_path, &updated).await;
+    if let Some(queue) = state.update_queue.lock().await.clone() {
+        if let Err(e) = queue.add_update(full_path, vault_path, &updated, &index.ignore()).await {
+            eprintln!("⚠️ Failed to queue graph update for {}: {}", rel_path, e);
+        }
+    }
+
+    Ok(task)
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, State, Window};
+use crate::refactored_app_state::RefactoredAppState;
+use crate::templates::{TemplateContext, TemplateInfo, Templates};
+
+/// The templates of a vault, read from the folder set in its settings
+pub async fn vault_templates(app: &AppHandle, vault_path: &Path) -> Templates {
+    let folder = match crate::vault_settings::get_vault_settings(app.clone(), vault_path.to_string_lossy().to_string()).await {
+        Ok(settings) => settings.files.templates_folder,
+        Err(_) => crate::vault_settings::FileSettings::default().templates_folder,
+    };
+    Templates::new(vault_path, &folder)
+}
+
+/// A note just created from a template
+#[derive(Debug, Serialize)]
+pub struct CreatedNote {
+    /// Vault-relative path
+    pub path: String,
+    /// Where the editor should put the cursor, in UTF-16 code units
+    pub cursor: Option<usize>,
+}
+
+#[tauri::command]
+pub async fn list_templates(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<Vec<TemplateInfo>, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    Ok(vault_templates(&app, vault.path()).await.list())
+}
+
+/// Questions a template asks with `{{prompt:...}}`, to be answered before
+/// creating a note from it
+#[tauri::command]
+pub async fn list_template_prompts(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    template: String,
+) -> Result<Vec<String>, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    vault_templates(&app, vault.path()).await.prompts(&template)
+}
+
+/// Creates a note at `path` from a template. `properties` are merged over the
+/// template's frontmatter, `answers` fill its prompts and the title defaults
+/// to the file name.
+#[tauri::command]
+pub async fn create_note_from_template(
+    app: AppHandle,
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+    template: String,
+    path: String,
+    title: Option<String>,
+    properties: Option<Map<String, Value>>,
+    answers: Option<HashMap<String, String>>,
+) -> Result<CreatedNote, String> {
+    let vault = refactored_state.window_vault(window.label()).await?;
+    let path = if path.ends_with(".md") { path } else { format!("{}.md", path) };
+    let full_path = vault.resolve_path(Path::new(&path))?;
+    if full_path.exists() {
+        return Err(format!("A note already exists at {}", path));
+    }
+
+    let title = title.unwrap_or_else(|| {
+        full_path.file_stem().and_then(|s| s.to_str()).unwrap_or("Untitled").to_string()
+    });
+    let mut context = TemplateContext::new(&title);
+    context.properties = properties.unwrap_or_default();
+    context.answers = answers.unwrap_or_default();
+
+    let rendered = vault_templates(&app, vault.path()).await.render(&template, &context)?;
+    let unlocked = refactored_state.keyring.get(vault.path()).await;
+    crate::encryption::write_note(&vault, Path::new(&path), &rendered.content, unlocked.as_deref())
+        .map_err(|e| format!("Failed to create note: {}", e))?;
+
+    println!("📝 Created {} from template {}", path, template);
+    Ok(CreatedNote {
+        path: crate::index::resolver::relative_path(vault.path(), &full_path).unwrap_or(path),
+        cursor: rendered.cursor,
+    })
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{State, Window};
+use crate::{AppState, refactored_app_state::RefactoredAppState};
+use crate::graph::GraphManagerTrait;
+use crate::graph::sync::GraphSyncService;
+use crate::trash::{Trash, TrashEntry};
+
+#[tauri::command]
+pub async fn list_trash(
+    window: Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<Vec<TrashEntry>, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    Trash::new(&vault_path).list()
+}
+
+/// Restores a trash entry and returns the vault-relative path it was restored to
+#[tauri::command]
+pub async fn restore_from_trash(
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    entry_id: String,
+) -> Result<String, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let trash = Trash::new(&vault_path);
+    let entry = trash.list()?
+        .into_iter()
+        .find(|e| e.id == entry_id)
+        .ok_or_else(|| format!("Trash entry not found: {}", entry_id))?;
+    let notes = trash.note_paths(&entry);
+    let restored = trash.restore(&entry_id)?;
+
+    // Notes back at their old path get their relationships back; notes
+    // restored under a new name are synced as new notes
+    if let Some(graph_manager) = state.graph_manager.lock().await.clone() {
+        if restored == entry.original_path {
+            mark_graph_notes_trashed(&graph_manager, &vault_path, &notes, false).await;
+        } else {
+            delete_graph_notes(&graph_manager, &vault_path, &notes).await;
+        }
+    }
+    Ok(restored)
+}
+
+#[tauri::command]
+pub async fn purge_trash_entry(
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+    entry_id: String,
+) -> Result<(), String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let trash = Trash::new(&vault_path);
+
+    let entry = trash.list()?
+        .into_iter()
+        .find(|e| e.id == entry_id)
+        .ok_or_else(|| format!("Trash entry not found: {}", entry_id))?;
+    let notes = trash.note_paths(&entry);
+    trash.purge(&entry_id)?;
+
+    if let Some(graph_manager) = state.graph_manager.lock().await.clone() {
+        delete_graph_notes(&graph_manager, &vault_path, &notes).await;
+    }
+    Ok(())
+}
+
+/// Permanently deletes everything in the trash; returns the number of entries removed
+#[tauri::command]
+pub async fn empty_trash(
+    window: Window,
+    state: State<'_, AppState>,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<usize, String> {
+    let vault_path = refactored_state.window_vault_path(window.label()).await?;
+    let trash = Trash::new(&vault_path);
+
+    let entries = trash.list()?;
+    let mut notes = Vec::new();
+    for entry in &entries {
+        notes.extend(trash.note_paths(entry));
+        trash.purge(&entry.id)?;
+    }
+
+    if let Some(graph_manager) = state.graph_manager.lock().await.clone() {
+        delete_graph_notes(&graph_manager, &vault_path, &notes).await;
+    }
+    Ok(entries.len())
+}
+
+/// Flags notes as trashed, or no longer trashed, in the graph; trashed notes
+/// keep their relationships until the trash is purged
+pub(crate) async fn mark_graph_notes_trashed(graph_manager: &Arc<dyn GraphManagerTrait>, vault_path: &Path, notes: &[String], trashed: bool) {
+    let vault_id = crate::vault_id::generate_vault_id(vault_path);
+
+    for note in notes {
+        let note_id = GraphSyncService::generate_note_id(&vault_path.join(note), &vault_id);
+        if let Err(e) = graph_manager.set_note_trashed(&note_id, trashed).await {
+            eprintln!("⚠️ Failed to update trashed note {} in graph: {}", note, e);
+        }
+    }
+}
+
+/// Drops purged notes (and their relationships/embeddings) from the graph
+pub(crate) async fn delete_graph_notes(graph_manager: &Arc<dyn GraphManagerTrait>, vault_path: &Path, notes: &[String]) {
+    let vault_id = crate::vault_id::generate_vault_id(vault_path);
+
+    for note in notes {
+        let note_id = GraphSyncService::generate_note_id(&vault_path.join(note), &vault_id);
+        if let Err(e) = graph_manager.delete_note(&note_id).await {
+            eprintln!("⚠️ Failed to delete purged note {} from graph: {}", note, e);
+        }
+    }
+}
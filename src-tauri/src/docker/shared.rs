@@ -195,7 +195,7 @@ impl SharedDockerManager {
             }
         };
         
-        println!("Using Neo4j password from environment/file");
+        eprintln!("Using Neo4j password from environment/file");
         
         // Generate vault_id consistently using the vault name
         let vault_id = vault_name;
@@ -232,57 +232,78 @@ pub async fn get_initial_file(
 }
 
 // Wiki-link and markdown-specific commands
+
+/// Resolves a wiki-link title (`Note`, `folder/Note`, `Note#Heading|alias`) to
+/// the vault-relative path of the note it points at
 #[tauri::command]
 pub async fn open_note(
     title: String,
-    state: State<'_, crate::AppState>,
-) -> Result<(), String> {
-    let _manager = &state.editor;
-    // For now, just log the request
+    source_path: Option<String>,
+    window: tauri::Window,
+    refactored_state: State<'_, crate::refactored_app_state::RefactoredAppState>,
+) -> Result<Option<String>, String> {
     println!("Opening note: {}", title);
     
-    // In a full implementation, this would:
-    // 1. Search for the note file
-    // 2. Create the note if it doesn't exist
-    // 3. Open the note in the editor
-    // 4. Update the navigation history
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let source = source_path
+        .map(|p| index.relative_key(&p))
+        .unwrap_or_default();
     
-    Ok(())
+    let target = match crate::index::links::parse_link_inner(&title) {
+        Some(link) => link.target,
+        None => return Ok(None),
+    };
+    
+    let links = index.links.lock().await;
+    Ok(links.resolve(&target, &source))
 }
 
+/// Lists the notes tagged with `tag` (or a tag nested below it)
 #[tauri::command]
 pub async fn search_by_tag(
     tag: String,
-    state: State<'_, crate::AppState>,
+    window: tauri::Window,
+    refactored_state: State<'_, crate::refactored_app_state::RefactoredAppState>,
 ) -> Result<Vec<String>, String> {
-    let _manager = &state.editor;
-    // For now, just log the request
     println!("Searching by tag: {}", tag);
     
-    // In a full implementation, this would:
-    // 1. Search through all markdown files
-    // 2. Find files containing the tag
-    // 3. Return the list of matching files
-    
-    Ok(vec![])
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let tags = index.tags.lock().await;
+    Ok(tags.notes_with_tag(&tag))
 }
 
+/// Returns the markdown behind an `![[embed]]`: the whole note, a heading
+/// section (`Note#Heading`) or an anchored block (`Note#^id`), with nested
+/// embeds expanded
 #[tauri::command]
 pub async fn get_embedded_block(
     note_title: String,
     block_id: Option<String>,
-    state: State<'_, crate::AppState>,
+    source_path: Option<String>,
+    window: tauri::Window,
+    refactored_state: State<'_, crate::refactored_app_state::RefactoredAppState>,
 ) -> Result<Option<String>, String> {
-    let _manager = &state.editor;
-    // For now, just log the request
     println!("Getting embedded block: {} {:?}", note_title, block_id);
     
-    // In a full implementation, this would:
-    // 1. Find the note file
-    // 2. If block_id is provided, find the specific block
-    // 3. Return the block content
+    let index = refactored_state.get_vault_index(window.label()).await?;
+    let source = source_path
+        .map(|p| index.relative_key(&p))
+        .unwrap_or_default();
+    
+    let link = match crate::index::links::parse_link_inner(&note_title) {
+        Some(link) => link,
+        None => return Ok(None),
+    };
+    let block_id = block_id
+        .map(|id| id.trim_start_matches('^').to_string())
+        .or(link.block_id);
+    
+    let vault_path = index.vault_path().to_path_buf();
+    let read_note = move |rel: &str| std::fs::read_to_string(vault_path.join(rel)).ok();
     
-    Ok(Some("Sample embedded content".to_string()))
+    let links = index.links.lock().await;
+    let transcluder = crate::index::Transcluder::new(links.resolver(), &read_note);
+    Ok(transcluder.fragment(&link.target, link.heading.as_deref(), block_id.as_deref(), &source))
 }
 
 #[tauri::command]
@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine as _, engine::general_purpose};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::vault::{write_atomic, Vault, INTERNAL_DIR};
+
+/// First line of every encrypted note
+pub const ENVELOPE_HEADER: &str = "-----BEGIN GAIMPLAN ENCRYPTED NOTE-----";
+const ENVELOPE_FOOTER: &str = "-----END GAIMPLAN ENCRYPTED NOTE-----";
+const ENVELOPE_VERSION: u32 = 1;
+const CONFIG_FILE: &str = "encryption.json";
+/// Sealed with the vault key so a passphrase can be checked on unlock
+const CHECK_TEXT: &str = "gaimplan";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Argon2id cost parameters, stored with every salt they were used with
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        // OWASP's recommended minimum for Argon2id
+        Self { memory_kib: 19 * 1024, iterations: 2, parallelism: 1 }
+    }
+}
+
+impl KdfParams {
+    /// Refuses costs above our own, so a crafted note or settings file cannot
+    /// make key derivation exhaust memory or stall
+    fn check(self) -> Result<Self, String> {
+        let max = Self::default();
+        if self.memory_kib > max.memory_kib || self.iterations > max.iterations || self.parallelism > max.parallelism {
+            return Err(format!(
+                "Key derivation cost too high: m={}, t={}, p={}",
+                self.memory_kib, self.iterations, self.parallelism
+            ));
+        }
+        Ok(self)
+    }
+}
+
+/// Derives a 256-bit key from a passphrase with Argon2id
+pub fn derive_key(passphrase: &str, salt: &[u8], params: KdfParams) -> Result<Zeroizing<[u8; 32]>, String> {
+    let params = Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+        .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Whether a note's content is an encrypted envelope
+pub fn is_encrypted(content: &str) -> bool {
+    content.trim_start_matches('\u{feff}').trim_start().starts_with(ENVELOPE_HEADER)
+}
+
+/// An encrypted note as stored on disk:
+///
+/// ```text
+/// -----BEGIN GAIMPLAN ENCRYPTED NOTE-----
+/// Version: 1
+/// KDF: argon2id; m=19456; t=2; p=1
+/// Salt: <base64>
+/// Nonce: <base64>
+///
+/// <base64 ciphertext>
+/// -----END GAIMPLAN ENCRYPTED NOTE-----
+/// ```
+///
+/// The salt and parameters travel with the note, so a note sealed with
+/// another key is recognised before decryption. The header is authenticated
+/// along with the ciphertext.
+#[derive(Debug, Clone, PartialEq)]
+struct Envelope {
+    params: KdfParams,
+    salt: Vec<u8>,
+    nonce: [u8; NONCE_LEN],
+    ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    fn header(&self) -> String {
+        format!(
+            "{}\nVersion: {}\nKDF: argon2id; m={}; t={}; p={}\nSalt: {}\nNonce: {}\n",
+            ENVELOPE_HEADER,
+            ENVELOPE_VERSION,
+            self.params.memory_kib,
+            self.params.iterations,
+            self.params.parallelism,
+            general_purpose::STANDARD.encode(&self.salt),
+            general_purpose::STANDARD.encode(self.nonce),
+        )
+    }
+
+    fn render(&self) -> String {
+        let body = general_purpose::STANDARD.encode(&self.ciphertext);
+        let mut text = self.header();
+        text.push('\n');
+        for chunk in body.as_bytes().chunks(64) {
+            text.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+            text.push('\n');
+        }
+        text.push_str(ENVELOPE_FOOTER);
+        text.push('\n');
+        text
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let invalid = |what: &str| format!("Invalid encrypted note: {}", what);
+        let mut lines = content.trim_start_matches('\u{feff}').trim().lines().map(str::trim);
+        if lines.next() != Some(ENVELOPE_HEADER) {
+            return Err(invalid("missing header"));
+        }
+
+        let mut fields = HashMap::new();
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            let (key, value) = line.split_once(':').ok_or_else(|| invalid("malformed header"))?;
+            fields.insert(key.trim().to_lowercase(), value.trim().to_string());
+        }
+        let field = |name: &str| fields.get(name).ok_or_else(|| invalid(&format!("missing {}", name)));
+
+        if field("version")?.parse::<u32>().ok() != Some(ENVELOPE_VERSION) {
+            return Err(format!("Unsupported encrypted note version: {}", field("version")?));
+        }
+        let mut params = KdfParams { memory_kib: 0, iterations: 0, parallelism: 0 };
+        let mut kdf = field("kdf")?.split(';').map(str::trim);
+        if kdf.next() != Some("argon2id") {
+            return Err(invalid("unknown key derivation"));
+        }
+        for part in kdf {
+            let (name, value) = part.split_once('=').ok_or_else(|| invalid("malformed KDF"))?;
+            let value: u32 = value.trim().parse().map_err(|_| invalid("malformed KDF"))?;
+            match name.trim() {
+                "m" => params.memory_kib = value,
+                "t" => params.iterations = value,
+                "p" => params.parallelism = value,
+                _ => return Err(invalid("malformed KDF")),
+            }
+        }
+        let params = params.check()?;
+
+        let decode = |value: &str| general_purpose::STANDARD.decode(value).map_err(|_| invalid("bad base64"));
+        let salt = decode(field("salt")?)?;
+        let nonce: [u8; NONCE_LEN] = decode(field("nonce")?)?
+            .try_into()
+            .map_err(|_| invalid("bad nonce"))?;
+
+        let mut body = String::new();
+        let mut closed = false;
+        for line in lines {
+            if line == ENVELOPE_FOOTER {
+                closed = true;
+                break;
+            }
+            body.push_str(line);
+        }
+        if !closed {
+            return Err(invalid("missing footer"));
+        }
+
+        Ok(Self { params, salt, nonce, ciphertext: decode(&body)? })
+    }
+}
+
+/// Encrypts a note with a key derived from `salt` and `params`
+fn seal(plaintext: &str, key: &[u8; 32], salt: &[u8], params: KdfParams) -> Result<String, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let mut envelope = Envelope { params, salt: salt.to_vec(), nonce, ciphertext: Vec::new() };
+
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| format!("Failed to create cipher: {}", e))?;
+    let header = envelope.header();
+    envelope.ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext.as_bytes(), aad: header.as_bytes() })
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+    Ok(envelope.render())
+}
+
+/// Decrypts an envelope; `key_for` supplies the key for its salt and parameters
+fn open(content: &str, key_for: impl FnOnce(&[u8], KdfParams) -> Result<Zeroizing<[u8; 32]>, String>) -> Result<String, String> {
+    let envelope = Envelope::parse(content)?;
+    let key = key_for(&envelope.salt, envelope.params)?;
+
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref()).map_err(|e| format!("Failed to create cipher: {}", e))?;
+    let header = envelope.header();
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&envelope.nonce),
+            Payload { msg: &envelope.ciphertext, aad: header.as_bytes() },
+        )
+        .map_err(|_| "Wrong passphrase, or the note is damaged".to_string())?;
+    String::from_utf8(plaintext).map_err(|e| format!("UTF-8 decode failed: {}", e))
+}
+
+/// A vault's encryption settings in `.gaimplan/encryption.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    pub kdf: KdfParams,
+    /// Salt of the vault key, base64
+    pub salt: String,
+    /// `CHECK_TEXT` sealed with the vault key
+    pub check: String,
+    /// Folders (vault-relative) whose notes are encrypted when saved
+    #[serde(default)]
+    pub folders: Vec<String>,
+}
+
+impl EncryptionConfig {
+    fn path(vault_path: &Path) -> PathBuf {
+        vault_path.join(INTERNAL_DIR).join(CONFIG_FILE)
+    }
+
+    /// The vault's settings, or `None` if encryption was never set up
+    pub fn load(vault_path: &Path) -> Result<Option<Self>, String> {
+        let path = Self::path(vault_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path).map_err(|e| format!("Failed to read encryption settings: {}", e))?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Invalid encryption settings: {}", e))
+    }
+
+    fn save(&self, vault_path: &Path) -> Result<(), String> {
+        let path = Self::path(vault_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", INTERNAL_DIR, e))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_atomic(&path, json.as_bytes()).map_err(|e| format!("Failed to save encryption settings: {}", e))
+    }
+
+    fn salt_bytes(&self) -> Result<Vec<u8>, String> {
+        general_purpose::STANDARD.decode(&self.salt)
+            .map_err(|_| "Invalid encryption settings: bad salt".to_string())
+    }
+
+    /// Whether notes at `rel_path` belong to an encrypted folder
+    pub fn covers(&self, rel_path: &str) -> bool {
+        self.folders.iter().any(|folder| rel_path.starts_with(&format!("{}/", folder)))
+    }
+}
+
+/// A vault whose passphrase was entered this session. Only the key derived
+/// from it is kept, and it is wiped from memory when the vault is locked.
+pub struct UnlockedVault {
+    key: Zeroizing<[u8; 32]>,
+    salt: Vec<u8>,
+    kdf: KdfParams,
+    config: std::sync::Mutex<EncryptionConfig>,
+}
+
+impl UnlockedVault {
+    /// Checks a key derived from the passphrase against the vault's settings
+    fn new(config: EncryptionConfig, key: Zeroizing<[u8; 32]>) -> Result<Self, String> {
+        let unlocked = Self {
+            key,
+            salt: config.salt_bytes()?,
+            kdf: config.kdf,
+            config: std::sync::Mutex::new(config.clone()),
+        };
+        match unlocked.decrypt(&config.check) {
+            Ok(text) if text == CHECK_TEXT => Ok(unlocked),
+            _ => Err("Wrong passphrase".to_string()),
+        }
+    }
+
+    fn key(&self, salt: &[u8], params: KdfParams) -> Result<Zeroizing<[u8; 32]>, String> {
+        if salt != self.salt || params != self.kdf {
+            return Err("The note was encrypted with another passphrase".to_string());
+        }
+        Ok(self.key.clone())
+    }
+
+    pub fn config(&self) -> EncryptionConfig {
+        self.config.lock().map(|c| c.clone()).unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, String> {
+        seal(plaintext, &self.key, &self.salt, self.kdf)
+    }
+
+    pub fn decrypt(&self, content: &str) -> Result<String, String> {
+        open(content, |salt, params| self.key(salt, params))
+    }
+
+    /// Adds or removes an encrypted folder and saves the settings
+    pub fn set_folder(&self, vault_path: &Path, folder: &str, encrypted: bool) -> Result<(), String> {
+        let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        let folder = folder.replace('\\', "/").trim_matches('/').to_string();
+        config.folders.retain(|f| *f != folder);
+        if encrypted {
+            config.folders.push(folder);
+            config.folders.sort();
+        }
+        config.save(vault_path)
+    }
+}
+
+/// Keys of the vaults unlocked this session, shared by all their windows
+#[derive(Default)]
+pub struct Keyring {
+    vaults: Mutex<HashMap<PathBuf, Arc<UnlockedVault>>>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn get(&self, vault_path: &Path) -> Option<Arc<UnlockedVault>> {
+        self.vaults.lock().await.get(vault_path).cloned()
+    }
+
+    /// Sets up encryption for a vault with a new passphrase and unlocks it
+    pub async fn set_up(&self, vault_path: &Path, passphrase: Zeroizing<String>) -> Result<Arc<UnlockedVault>, String> {
+        if EncryptionConfig::load(vault_path)?.is_some() {
+            return Err("Encryption is already set up for this vault".to_string());
+        }
+        if passphrase.chars().count() < 8 {
+            return Err("Use a passphrase of at least 8 characters".to_string());
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let kdf = KdfParams::default();
+        let key = derive_key_blocking(passphrase, salt.to_vec(), kdf).await?;
+        let config = EncryptionConfig {
+            kdf,
+            salt: general_purpose::STANDARD.encode(salt),
+            check: seal(CHECK_TEXT, &key, &salt, kdf)?,
+            folders: Vec::new(),
+        };
+        config.save(vault_path)?;
+        self.insert(vault_path, UnlockedVault::new(config, key)?).await
+    }
+
+    pub async fn unlock(&self, vault_path: &Path, passphrase: Zeroizing<String>) -> Result<Arc<UnlockedVault>, String> {
+        let config = EncryptionConfig::load(vault_path)?
+            .ok_or_else(|| "Encryption is not set up for this vault".to_string())?;
+        let key = derive_key_blocking(passphrase, config.salt_bytes()?, config.kdf.check()?).await?;
+        self.insert(vault_path, UnlockedVault::new(config, key)?).await
+    }
+
+    async fn insert(&self, vault_path: &Path, unlocked: UnlockedVault) -> Result<Arc<UnlockedVault>, String> {
+        let unlocked = Arc::new(unlocked);
+        self.vaults.lock().await.insert(vault_path.to_path_buf(), unlocked.clone());
+        println!("🔓 Unlocked encrypted notes in {}", vault_path.display());
+        Ok(unlocked)
+    }
+
+    pub async fn lock(&self, vault_path: &Path) {
+        if self.vaults.lock().await.remove(vault_path).is_some() {
+            println!("🔒 Locked encrypted notes in {}", vault_path.display());
+        }
+    }
+}
+
+/// Runs Argon2 off the async runtime; it takes a noticeable fraction of a second
+async fn derive_key_blocking(passphrase: Zeroizing<String>, salt: Vec<u8>, params: KdfParams) -> Result<Zeroizing<[u8; 32]>, String> {
+    tokio::task::spawn_blocking(move || derive_key(&passphrase, &salt, params))
+        .await
+        .map_err(|e| format!("Key derivation task failed: {}", e))?
+}
+
+/// Content to show for a note read from disk: decrypted when the vault is
+/// unlocked, an error while it is locked
+pub fn reveal(rel_path: &str, content: String, unlocked: Option<&UnlockedVault>) -> Result<String, String> {
+    if !is_encrypted(&content) {
+        return Ok(content);
+    }
+    match unlocked {
+        Some(unlocked) => unlocked.decrypt(&content),
+        None => Err(format!("{} is encrypted; unlock the vault to open it", rel_path)),
+    }
+}
+
+/// Content to write for a note: encrypted when the note is encrypted on disk
+/// or lives in an encrypted folder. Refuses to save such a note in plaintext
+/// while the vault is locked.
+pub fn conceal(vault_path: &Path, rel_path: &str, plaintext: &str, unlocked: Option<&UnlockedVault>) -> Result<String, String> {
+    if is_encrypted(plaintext) {
+        return Ok(plaintext.to_string());
+    }
+    let encrypted_on_disk = fs::read_to_string(vault_path.join(rel_path)).is_ok_and(|c| is_encrypted(&c));
+    let in_encrypted_folder = match unlocked {
+        Some(unlocked) => unlocked.config().covers(rel_path),
+        None => EncryptionConfig::load(vault_path)?.is_some_and(|c| c.covers(rel_path)),
+    };
+    if !encrypted_on_disk && !in_encrypted_folder {
+        return Ok(plaintext.to_string());
+    }
+    match unlocked {
+        Some(unlocked) => unlocked.encrypt(plaintext),
+        None => Err(format!("{} is encrypted; unlock the vault to save it", rel_path)),
+    }
+}
+
+/// Writes a note through `conceal`, so a note created in an encrypted folder
+/// never reaches the disk in plaintext
+pub fn write_note(vault: &Vault, relative_path: &Path, content: &str, unlocked: Option<&UnlockedVault>) -> Result<(), String> {
+    let full_path = vault.resolve_path(relative_path)?;
+    let rel_path = crate::index::resolver::relative_path(vault.path(), &full_path)
+        .ok_or_else(|| format!("Not a file in the vault: {}", relative_path.display()))?;
+    let content = conceal(vault.path(), &rel_path, content, unlocked)?;
+    vault.write_file(relative_path, &content)
+        .map_err(|e| format!("Failed to write {}: {}", rel_path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap parameters so the tests run quickly
+    const TEST_KDF: KdfParams = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+
+    #[test]
+    fn test_seal_and_open_round_trip() {
+        let salt = [7u8; SALT_LEN];
+        let key = derive_key("correct horse", &salt, TEST_KDF).unwrap();
+        let note = "---\ntags: [secret]\n---\n# Accounts\n\nPIN is [[1234]] 🔐\n";
+
+        let sealed = seal(note, &key, &salt, TEST_KDF).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("Accounts") && !sealed.contains('#') && !sealed.contains("[["));
+        assert_ne!(sealed, seal(note, &key, &salt, TEST_KDF).unwrap(), "every seal uses a new nonce");
+
+        let opened = open(&sealed.replace('\n', "\r\n"), |s, p| derive_key("correct horse", s, p)).unwrap();
+        assert_eq!(opened, note);
+        assert!(open(&sealed, |s, p| derive_key("wrong horse", s, p)).is_err());
+
+        let costly = sealed.replace("m=64;", "m=4194304;");
+        assert!(Envelope::parse(&costly).unwrap_err().contains("too high"));
+    }
+
+    /// A vault in a temp dir with `Private` encrypted, and its wrong-passphrase key
+    fn test_vault() -> (PathBuf, EncryptionConfig, Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+        let dir = std::env::temp_dir().join(format!("gaimplan-encryption-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("Private")).unwrap();
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key("passphrase", &salt, TEST_KDF).unwrap();
+        let config = EncryptionConfig {
+            kdf: TEST_KDF,
+            salt: general_purpose::STANDARD.encode(salt),
+            check: seal(CHECK_TEXT, &key, &salt, TEST_KDF).unwrap(),
+            folders: vec!["Private".to_string()],
+        };
+        config.save(&dir).unwrap();
+        let wrong = derive_key("not it", &salt, TEST_KDF).unwrap();
+        (dir, config, key, wrong)
+    }
+
+    #[test]
+    fn test_unlock_reveal_and_conceal() {
+        let (dir, config, key, wrong) = test_vault();
+        assert!(UnlockedVault::new(config.clone(), wrong).is_err());
+        let unlocked = UnlockedVault::new(config, key).unwrap();
+
+        assert_eq!(conceal(&dir, "Public.md", "plain", Some(&unlocked)).unwrap(), "plain");
+        let sealed = conceal(&dir, "Private/Keys.md", "secret", Some(&unlocked)).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(conceal(&dir, "Private/Keys.md", "secret", None).is_err());
+
+        assert_eq!(reveal("Private/Keys.md", sealed.clone(), Some(&unlocked)).unwrap(), "secret");
+        assert!(reveal("Private/Keys.md", sealed, None).is_err());
+        assert_eq!(reveal("Public.md", "plain".to_string(), None).unwrap(), "plain");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_index_reads_attachments_of_encrypted_notes() {
+        let (dir, config, key, _) = test_vault();
+        let unlocked = UnlockedVault::new(config, key).unwrap();
+        fs::write(dir.join("Private/scan.png"), b"png").unwrap();
+        fs::write(dir.join("Private/Keys.md"), conceal(&dir, "Private/Keys.md", "![](scan.png)", Some(&unlocked)).unwrap()).unwrap();
+
+        let rules = Arc::new(crate::vault_ignore::IgnoreRules::new(&dir, &[]));
+        let index = crate::index::VaultIndex::build(&dir, rules).unwrap();
+        let links = index.links.lock().await;
+        assert_eq!(index.attachments.lock().await.orphans(links.resolver()), vec!["Private/scan.png"]);
+        assert!(index.readable_attachments(None).await.is_err());
+        assert!(index.readable_attachments(Some(&unlocked)).await.unwrap().orphans(links.resolver()).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
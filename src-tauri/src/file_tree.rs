@@ -0,0 +1,388 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use walkdir::WalkDir;
+
+use crate::git::GitFileStatus;
+use crate::index::resolver::{parent_dir, relative_path};
+use crate::vault::Vault;
+use crate::vault_ignore::IgnoreRules;
+
+/// Entries per page when a directory listing does not ask for a size
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// A folder, note or attachment in the file tree
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileInfo {
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    pub extension: Option<String>,
+    pub depth: usize,
+    pub parent_path: Option<String>,
+    pub created: Option<i64>,  // Unix timestamp
+    pub modified: Option<i64>, // Unix timestamp
+    /// Uncommitted changes when the vault is a git repository; filled in per request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_status: Option<GitFileStatus>,
+}
+
+/// One page of a folder's direct children
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryPage {
+    pub path: String,
+    pub entries: Vec<FileInfo>,
+    pub offset: usize,
+    pub total: usize,
+    pub has_more: bool,
+}
+
+/// A change to a vault's tree, sent to windows with `vault-tree-changed`.
+/// Removing or renaming a folder covers everything inside it.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TreeDelta {
+    Added { entry: FileInfo },
+    Removed { path: String },
+    Renamed { from: String, entry: FileInfo },
+    Modified { entry: FileInfo },
+    /// The tree was rebuilt (ignore rules changed or events were missed); reload it
+    Reset,
+}
+
+/// Folders, notes and attachments of a vault, scanned once and then kept
+/// current from file watcher events
+pub struct FileTreeCache {
+    vault_path: PathBuf,
+    ignore: Arc<IgnoreRules>,
+    entries: HashMap<String, FileInfo>,
+    /// Paths of each folder's children ("" for the vault root), sorted
+    children: HashMap<String, BTreeSet<String>>,
+}
+
+impl FileTreeCache {
+    pub fn build(vault_path: &Path, ignore: Arc<IgnoreRules>) -> Result<Self, String> {
+        let vault = Vault::new(vault_path.to_path_buf())
+            .map_err(|e| format!("Failed to open vault: {}", e))?
+            .with_ignore_rules(ignore.clone());
+        let files = vault.list_markdown_files()
+            .map_err(|e| format!("Failed to list files: {}", e))?;
+
+        let mut tree = Self {
+            vault_path: vault_path.to_path_buf(),
+            ignore,
+            entries: HashMap::new(),
+            children: HashMap::new(),
+        };
+        for path in &files {
+            if let Some(entry) = relative_path(vault_path, path).and_then(|rel| file_info(path, &rel)) {
+                tree.insert(entry);
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Every entry, sorted by path
+    pub fn all(&self) -> Vec<FileInfo> {
+        let mut all: Vec<FileInfo> = self.entries.values().cloned().collect();
+        all.sort_by(|a, b| a.path.cmp(&b.path));
+        all
+    }
+
+    /// Direct children of `dir` ("" for the vault root), `limit` at a time
+    pub fn list(&self, dir: &str, offset: usize, limit: usize) -> Result<DirectoryPage, String> {
+        let dir = dir.replace('\\', "/").trim_matches('/').to_string();
+        if !dir.is_empty() && !self.entries.get(&dir).is_some_and(|e| e.is_dir) {
+            return Err(format!("Not a folder: {}", dir));
+        }
+
+        let children = self.children.get(&dir);
+        let total = children.map(BTreeSet::len).unwrap_or(0);
+        let entries: Vec<FileInfo> = children
+            .into_iter()
+            .flatten()
+            .skip(offset)
+            .take(limit.max(1))
+            .filter_map(|path| self.entries.get(path).cloned())
+            .collect();
+        Ok(DirectoryPage {
+            has_more: offset + entries.len() < total,
+            path: dir,
+            entries,
+            offset,
+            total,
+        })
+    }
+
+    /// Applies a watcher event (with ignored paths already removed) and
+    /// returns what changed
+    pub fn apply_event(&mut self, event: &Event) -> Vec<TreeDelta> {
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                self.rename(&event.paths[0], &event.paths[1])
+            }
+            EventKind::Remove(_) => event.paths.iter().flat_map(|p| self.remove_path(p)).collect(),
+            EventKind::Create(_) | EventKind::Modify(_) => {
+                event.paths.iter().flat_map(|p| self.refresh_path(p)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, entry: FileInfo) {
+        self.children
+            .entry(parent_dir(&entry.path).to_string())
+            .or_default()
+            .insert(entry.path.clone());
+        self.entries.insert(entry.path.clone(), entry);
+    }
+
+    /// Removes an entry and everything below it
+    fn remove(&mut self, rel: &str) -> bool {
+        if let Some(children) = self.children.remove(rel) {
+            for child in children {
+                self.remove(&child);
+            }
+        }
+        if let Some(siblings) = self.children.get_mut(parent_dir(rel)) {
+            siblings.remove(rel);
+        }
+        self.entries.remove(rel).is_some()
+    }
+
+    fn remove_path(&mut self, path: &Path) -> Vec<TreeDelta> {
+        match relative_path(&self.vault_path, path) {
+            Some(rel) if self.remove(&rel) => vec![TreeDelta::Removed { path: rel }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Adds a created path, updates a modified one, or drops one that no
+    /// longer exists (platforms report some renames as plain modifications)
+    fn refresh_path(&mut self, path: &Path) -> Vec<TreeDelta> {
+        let rel = match relative_path(&self.vault_path, path) {
+            Some(rel) => rel,
+            None => return Vec::new(),
+        };
+        let entry = match file_info(path, &rel) {
+            Some(entry) => entry,
+            None => return self.remove_path(path),
+        };
+
+        match self.entries.get(&rel) {
+            Some(existing) if *existing == entry => Vec::new(),
+            Some(_) => {
+                self.entries.insert(rel, entry.clone());
+                vec![TreeDelta::Modified { entry }]
+            }
+            None => {
+                let mut deltas = self.add_parents(&rel);
+                deltas.extend(self.add(path, entry).into_iter().map(|entry| TreeDelta::Added { entry }));
+                deltas
+            }
+        }
+    }
+
+    fn rename(&mut self, from: &Path, to: &Path) -> Vec<TreeDelta> {
+        let (from_rel, to_rel) = match (relative_path(&self.vault_path, from), relative_path(&self.vault_path, to)) {
+            (Some(from_rel), Some(to_rel)) => (from_rel, to_rel),
+            _ => return [self.remove_path(from), self.refresh_path(to)].concat(),
+        };
+        if !self.entries.contains_key(&from_rel) {
+            return self.refresh_path(to);
+        }
+
+        self.remove(&from_rel);
+        match file_info(to, &to_rel) {
+            Some(entry) => {
+                let mut deltas = self.add_parents(&to_rel);
+                self.add(to, entry.clone());
+                deltas.push(TreeDelta::Renamed { from: from_rel, entry });
+                deltas
+            }
+            // Renamed to something the tree does not show, such as a `.txt` file
+            None => vec![TreeDelta::Removed { path: from_rel }],
+        }
+    }
+
+    /// Adds an entry and, for a folder, everything inside it that is not
+    /// ignored. Returns the added entries.
+    fn add(&mut self, path: &Path, entry: FileInfo) -> Vec<FileInfo> {
+        let is_dir = entry.is_dir;
+        self.insert(entry.clone());
+        let mut added = vec![entry];
+        if !is_dir {
+            return added;
+        }
+
+        let ignore = self.ignore.current();
+        let inner = WalkDir::new(path)
+            .min_depth(1)
+            .follow_links(true)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| !ignore.is_ignored(e.path(), e.file_type().is_dir()))
+            .filter_map(|e| e.ok());
+        for e in inner {
+            let child = relative_path(&self.vault_path, e.path()).and_then(|rel| file_info(e.path(), &rel));
+            if let Some(child) = child.filter(|child| !self.entries.contains_key(&child.path)) {
+                self.insert(child.clone());
+                added.push(child);
+            }
+        }
+        added
+    }
+
+    /// Folders leading to `rel` that the tree does not know yet
+    fn add_parents(&mut self, rel: &str) -> Vec<TreeDelta> {
+        let mut missing = Vec::new();
+        let mut parent = parent_dir(rel);
+        while !parent.is_empty() && !self.entries.contains_key(parent) {
+            missing.push(parent.to_string());
+            parent = parent_dir(parent);
+        }
+
+        let mut deltas = Vec::new();
+        for folder in missing.into_iter().rev() {
+            if let Some(entry) = file_info(&self.vault_path.join(&folder), &folder) {
+                self.insert(entry.clone());
+                deltas.push(TreeDelta::Added { entry });
+            }
+        }
+        deltas
+    }
+}
+
+/// Tree entry for a path, if it is a folder, note or attachment
+pub(crate) fn file_info(path: &Path, rel: &str) -> Option<FileInfo> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let extension = path.extension().and_then(|s| s.to_str()).map(|s| s.to_string());
+    let is_dir = metadata.is_dir();
+    if !is_dir && extension.as_deref() != Some("md") && !crate::index::attachments::is_attachment(rel) {
+        return None;
+    }
+
+    let seconds = |time: std::io::Result<std::time::SystemTime>| {
+        time.ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs() as i64)
+    };
+    let parent = parent_dir(rel);
+    Some(FileInfo {
+        path: rel.to_string(),
+        name: rel.rsplit('/').next().unwrap_or(rel).to_string(),
+        is_dir,
+        extension,
+        depth: rel.split('/').count(),
+        parent_path: (!parent.is_empty()).then(|| parent.to_string()),
+        // Note: created() is not available on all platforms
+        created: seconds(metadata.created()),
+        modified: seconds(metadata.modified()),
+        git_status: None,
+    })
+}
+
+/// Shares one cached tree per vault across all windows viewing it
+#[derive(Default)]
+pub struct FileTreeRegistry {
+    trees: Mutex<HashMap<PathBuf, Arc<Mutex<FileTreeCache>>>>,
+}
+
+impl FileTreeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the tree for a vault, scanning the vault on first use. The scan
+    /// runs on a blocking thread without holding the registry, so other vaults'
+    /// trees stay available meanwhile.
+    pub async fn get_or_build(&self, vault_path: &Path, ignore: Arc<IgnoreRules>) -> Result<Arc<Mutex<FileTreeCache>>, String> {
+        if let Some(tree) = self.get(vault_path).await {
+            return Ok(tree);
+        }
+
+        let start = std::time::Instant::now();
+        let tree = tokio::task::spawn_blocking({
+            let vault_path = vault_path.to_path_buf();
+            move || FileTreeCache::build(&vault_path, ignore)
+        })
+        .await
+        .map_err(|e| format!("Failed to build file tree: {}", e))??;
+        eprintln!("🌳 Built file tree for {} in {:.2}s", vault_path.display(), start.elapsed().as_secs_f64());
+
+        // A tree built by another request in the meantime wins
+        let mut trees = self.trees.lock().await;
+        Ok(trees
+            .entry(vault_path.to_path_buf())
+            .or_insert_with(|| Arc::new(Mutex::new(tree)))
+            .clone())
+    }
+
+    /// Returns the tree for a vault only if it has already been built
+    pub async fn get(&self, vault_path: &Path) -> Option<Arc<Mutex<FileTreeCache>>> {
+        self.trees.lock().await.get(vault_path).cloned()
+    }
+
+    /// Drops a vault's tree so the next request scans the vault again
+    pub async fn remove(&self, vault_path: &Path) {
+        self.trees.lock().await.remove(vault_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, RemoveKind};
+
+    fn event(kind: EventKind, paths: &[&Path]) -> Event {
+        let mut event = Event::new(kind);
+        event.paths = paths.iter().map(|p| p.to_path_buf()).collect();
+        event
+    }
+
+    #[test]
+    fn test_tree_follows_watcher_events() {
+        let root = std::env::temp_dir().join(format!("gaimplan-tree-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("Notes")).unwrap();
+        for name in ["a.md", "b.md", "c.md"] {
+            std::fs::write(root.join("Notes").join(name), "x").unwrap();
+        }
+        std::fs::write(root.join("Notes/skip.txt"), "x").unwrap();
+
+        let mut tree = FileTreeCache::build(&root, Arc::new(IgnoreRules::new(&root, &[]))).unwrap();
+        let page = tree.list("Notes", 1, 1).unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.entries[0].path, "Notes/b.md");
+        assert!(page.has_more);
+        assert!(tree.list("Notes/a.md", 0, 10).is_err());
+
+        std::fs::create_dir_all(root.join("New/Deep")).unwrap();
+        std::fs::write(root.join("New/Deep/d.md"), "x").unwrap();
+        let deltas = tree.apply_event(&event(EventKind::Create(CreateKind::File), &[&root.join("New/Deep/d.md")]));
+        let added: Vec<&str> = deltas.iter().map(|d| match d {
+            TreeDelta::Added { entry } => entry.path.as_str(),
+            _ => "",
+        }).collect();
+        assert_eq!(added, vec!["New", "New/Deep", "New/Deep/d.md"]);
+
+        std::fs::rename(root.join("New"), root.join("Moved")).unwrap();
+        let deltas = tree.apply_event(&event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+            &[&root.join("New"), &root.join("Moved")],
+        ));
+        assert!(matches!(&deltas[..], [TreeDelta::Renamed { from, entry }] if from == "New" && entry.path == "Moved"));
+        assert!(tree.entries.contains_key("Moved/Deep/d.md"));
+        assert!(!tree.entries.contains_key("New/Deep/d.md"));
+
+        std::fs::remove_dir_all(root.join("Notes")).unwrap();
+        let deltas = tree.apply_event(&event(EventKind::Remove(RemoveKind::Folder), &[&root.join("Notes")]));
+        assert_eq!(deltas, vec![TreeDelta::Removed { path: "Notes".into() }]);
+        assert_eq!(tree.all().iter().map(|e| e.path.as_str()).collect::<Vec<_>>(), vec!["Moved", "Moved/Deep", "Moved/Deep/d.md"]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,531 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::vault::{write_atomic, INTERNAL_DIR};
+
+/// Files listed by name in a generated commit message
+const MAX_MESSAGE_FILES: usize = 50;
+
+lazy_static::lazy_static! {
+    /// `Note (conflict 2024-05-01 2).md`, as named by `conflict_path`
+    static ref CONFLICT_NOTE: Regex = Regex::new(r"^(.*) \(conflict \d{4}-\d{2}-\d{2}(?: \d+)?\)(\.[^./]+)?$").unwrap();
+}
+
+/// Uncommitted state of a file in the vault's repository
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitFileStatus {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GitCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    /// Unix timestamp
+    pub timestamp: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChange {
+    pub path: String,
+    pub status: GitFileStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoStatus {
+    /// `None` when HEAD is detached
+    pub branch: Option<String>,
+    pub changes: Vec<FileChange>,
+    /// Commits not yet pushed to, and not yet pulled from, the upstream branch
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+/// A note both sides changed during a pull. The note keeps this device's
+/// content; the remote's is saved next to it as a conflict note.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictNote {
+    pub path: String,
+    pub conflict_path: String,
+}
+
+/// The git repository a vault is in, driven through the `git` command line.
+/// Paths taken and returned are vault-relative; the app's own `.gaimplan`
+/// folder is left out of status and commits.
+pub struct GitRepo {
+    vault_path: PathBuf,
+    /// Path of the vault inside the repository, with a trailing slash
+    prefix: String,
+    /// Whether commits need a fallback author because none is configured
+    needs_identity: bool,
+}
+
+impl GitRepo {
+    /// The repository the vault is in, or `None` if it is not in one
+    pub fn open(vault_path: &Path) -> Option<Self> {
+        let mut repo = Self {
+            vault_path: vault_path.to_path_buf(),
+            prefix: String::new(),
+            needs_identity: false,
+        };
+        repo.prefix = repo.run(["rev-parse", "--show-prefix"]).ok()?.trim().to_string();
+        repo.needs_identity = repo.run(["config", "user.email"]).is_err();
+        Some(repo)
+    }
+
+    /// Creates a repository in the vault
+    pub fn init(vault_path: &Path) -> Result<Self, String> {
+        let output = Command::new("git").arg("init").arg(vault_path).output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("git init failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Self::open(vault_path).ok_or_else(|| "git init did not create a repository".to_string())
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(&self.vault_path);
+        // Never wait on a credentials prompt nobody can answer
+        command.env("GIT_TERMINAL_PROMPT", "0");
+        if self.needs_identity {
+            command.args(["-c", "user.name=Gaimplan", "-c", "user.email=gaimplan@localhost"]);
+        }
+        command
+    }
+
+    fn output<I, S>(&self, args: I) -> Result<Vec<u8>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let args: Vec<S> = args.into_iter().collect();
+        let output = self.command().args(&args).output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+        if !output.status.success() {
+            let subcommand = args.first().map(|a| a.as_ref().to_string_lossy().to_string()).unwrap_or_default();
+            return Err(format!("git {} failed: {}", subcommand, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(output.stdout)
+    }
+
+    fn run<I, S>(&self, args: I) -> Result<String, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.output(args).map(|out| String::from_utf8_lossy(&out).to_string())
+    }
+
+    /// Pathspecs covering the vault without the app's own folder
+    fn vault_pathspecs() -> Vec<String> {
+        vec![".".to_string(), format!(":(exclude){}", INTERNAL_DIR)]
+    }
+
+    fn has_commits(&self) -> bool {
+        self.run(["rev-parse", "--verify", "-q", "HEAD"]).is_ok()
+    }
+
+    pub fn current_branch(&self) -> Option<String> {
+        self.run(["symbolic-ref", "--short", "-q", "HEAD"]).ok().map(|b| b.trim().to_string())
+    }
+
+    /// Uncommitted changes in the vault, by vault-relative path
+    pub fn status(&self) -> Result<HashMap<String, GitFileStatus>, String> {
+        let mut args = vec!["status".to_string(), "--porcelain=v1".to_string(), "-z".to_string(), "--untracked-files=all".to_string(), "--".to_string()];
+        args.extend(Self::vault_pathspecs());
+        let out = self.run(args)?;
+        Ok(parse_status(&out)
+            .into_iter()
+            .filter_map(|(path, status)| Some((path.strip_prefix(&self.prefix)?.to_string(), status)))
+            .collect())
+    }
+
+    pub fn repo_status(&self) -> Result<RepoStatus, String> {
+        let mut changes: Vec<FileChange> = self.status()?
+            .into_iter()
+            .map(|(path, status)| FileChange { path, status })
+            .collect();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let (ahead, behind) = self.run(["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])
+            .ok()
+            .and_then(|out| {
+                let (ahead, behind) = out.trim().split_once('\t')?;
+                Some((ahead.parse().ok()?, behind.parse().ok()?))
+            })
+            .unwrap_or((0, 0));
+
+        Ok(RepoStatus { branch: self.current_branch(), changes, ahead, behind })
+    }
+
+    /// Commits every change in the vault, with a generated message unless one
+    /// is given. `None` if there was nothing to commit.
+    pub fn commit_all(&self, message: Option<&str>) -> Result<Option<GitCommit>, String> {
+        let mut changes: Vec<(String, GitFileStatus)> = self.status()?.into_iter().collect();
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        let message = message.map(str::to_string).unwrap_or_else(|| commit_message(&changes));
+        self.commit_pathspecs(&Self::vault_pathspecs(), &message)
+    }
+
+    fn commit_pathspecs(&self, pathspecs: &[String], message: &str) -> Result<Option<GitCommit>, String> {
+        let mut add = vec!["add".to_string(), "-A".to_string(), "--".to_string()];
+        add.extend(pathspecs.iter().cloned());
+        self.run(add)?;
+
+        let mut staged = vec!["diff".to_string(), "--cached".to_string(), "--quiet".to_string(), "--".to_string()];
+        staged.extend(pathspecs.iter().cloned());
+        if self.run(staged).is_ok() {
+            return Ok(None);
+        }
+
+        let mut commit = vec!["commit".to_string(), "-q".to_string(), "-m".to_string(), message.to_string(), "--".to_string()];
+        commit.extend(pathspecs.iter().cloned());
+        self.run(commit)?;
+        Ok(self.log(None, 1)?.into_iter().next())
+    }
+
+    /// Commits touching a note (following renames), or the whole vault, newest first
+    pub fn log(&self, rel_path: Option<&str>, limit: usize) -> Result<Vec<GitCommit>, String> {
+        if !self.has_commits() {
+            return Ok(Vec::new());
+        }
+        let mut args = vec![
+            "log".to_string(),
+            format!("-n{}", limit),
+            "--format=%H%x1f%h%x1f%an%x1f%at%x1f%s%x1e".to_string(),
+        ];
+        match rel_path {
+            Some(rel_path) => args.extend(["--follow".to_string(), "--".to_string(), rel_path.to_string()]),
+            None => {
+                args.push("--".to_string());
+                args.extend(Self::vault_pathspecs());
+            }
+        }
+        Ok(parse_log(&self.run(args)?))
+    }
+
+    /// A note's content as of a commit
+    pub fn read_at(&self, commit: &str, rel_path: &str) -> Result<String, String> {
+        if commit.starts_with('-') {
+            return Err(format!("Invalid commit: {}", commit));
+        }
+        self.run(["show".to_string(), format!("{}:./{}", commit, rel_path)])
+    }
+
+    pub fn set_remote(&self, name: &str, url: &str) -> Result<(), String> {
+        let exists = self.run(["remote", "get-url", name]).is_ok();
+        let action = if exists { "set-url" } else { "add" };
+        self.run(["remote", action, "--", name, url]).map(|_| ())
+    }
+
+    fn remote_has_branch(&self, remote: &str, branch: &str) -> bool {
+        self.run(["ls-remote", "--exit-code", "--heads", remote, branch]).is_ok()
+    }
+
+    /// Merges the remote's copy of the current branch. Notes changed on both
+    /// sides keep this device's content and get a conflict note with the
+    /// remote's, and the merge is committed so the vault stays usable.
+    pub fn pull(&self, remote: &str) -> Result<Vec<ConflictNote>, String> {
+        let branch = self.current_branch().ok_or_else(|| "Not on a branch".to_string())?;
+        if !self.remote_has_branch(remote, &branch) {
+            return Ok(Vec::new());
+        }
+
+        let error = match self.run(["pull", "--no-rebase", "--no-edit", remote, &branch]) {
+            Ok(_) => return Ok(Vec::new()),
+            Err(e) => e,
+        };
+        let conflicted: Vec<String> = self.status()?
+            .into_iter()
+            .filter(|(_, status)| *status == GitFileStatus::Conflicted)
+            .map(|(path, _)| path)
+            .collect();
+        if conflicted.is_empty() {
+            return Err(error);
+        }
+
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let mut conflicts = Vec::new();
+        for rel_path in &conflicted {
+            let ours = self.output(["show".to_string(), format!(":2:./{}", rel_path)]).ok();
+            let theirs = self.output(["show".to_string(), format!(":3:./{}", rel_path)]).ok();
+            let full_path = self.vault_path.join(rel_path);
+
+            let mut paths = vec![rel_path.clone()];
+            match (ours, theirs) {
+                (Some(ours), Some(theirs)) => {
+                    let conflict_path = conflict_copy_path(rel_path, &today, |p| self.vault_path.join(p).exists());
+                    write_atomic(&full_path, &ours).map_err(|e| format!("Failed to write {}: {}", rel_path, e))?;
+                    write_atomic(&self.vault_path.join(&conflict_path), &theirs)
+                        .map_err(|e| format!("Failed to write {}: {}", conflict_path, e))?;
+                    paths.push(conflict_path.clone());
+                    conflicts.push(ConflictNote { path: rel_path.clone(), conflict_path });
+                }
+                // Deleted on one side and changed on the other: keep the changes
+                (Some(content), None) | (None, Some(content)) => {
+                    write_atomic(&full_path, &content).map_err(|e| format!("Failed to write {}: {}", rel_path, e))?;
+                }
+                (None, None) => {
+                    let _ = fs::remove_file(&full_path);
+                }
+            }
+            let mut add = vec!["add".to_string(), "-A".to_string(), "--".to_string()];
+            add.extend(paths);
+            self.run(add)?;
+        }
+        self.run(["commit", "-q", "--no-edit"])?;
+        println!("⚠️ Pulled with {} conflicting notes", conflicts.len());
+        Ok(conflicts)
+    }
+
+    pub fn push(&self, remote: &str) -> Result<(), String> {
+        let branch = self.current_branch().ok_or_else(|| "Not on a branch".to_string())?;
+        self.run(["push", "-q", "-u", remote, &branch]).map(|_| ())
+    }
+
+    /// Settles a conflict note: its content replaces the note when
+    /// `keep_conflict_copy` is set, otherwise it is deleted. The result is committed.
+    pub fn resolve_conflict(&self, conflict_path: &str, keep_conflict_copy: bool) -> Result<String, String> {
+        let original = conflict_original(conflict_path)
+            .ok_or_else(|| format!("Not a conflict note: {}", conflict_path))?;
+        let conflict_full = self.vault_path.join(conflict_path);
+        if keep_conflict_copy {
+            fs::rename(&conflict_full, self.vault_path.join(&original))
+                .map_err(|e| format!("Failed to replace {}: {}", original, e))?;
+        } else {
+            fs::remove_file(&conflict_full).map_err(|e| format!("Failed to delete {}: {}", conflict_path, e))?;
+        }
+        self.commit_pathspecs(&[original.clone(), conflict_path.to_string()], &format!("Resolve conflict in {}", original))?;
+        Ok(original)
+    }
+}
+
+/// Parses `git status --porcelain=v1 -z` into repository-relative paths
+fn parse_status(out: &str) -> Vec<(String, GitFileStatus)> {
+    let mut entries = out.split('\0').filter(|e| !e.is_empty());
+    let mut statuses = Vec::new();
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = entry.split_at(3);
+        let mut chars = code.chars();
+        let (x, y) = (chars.next().unwrap_or(' '), chars.next().unwrap_or(' '));
+        // Renames and copies are followed by their original path
+        if x == 'R' || x == 'C' {
+            entries.next();
+        }
+        let status = match (x, y) {
+            ('!', '!') => continue,
+            ('?', '?') => GitFileStatus::Untracked,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => GitFileStatus::Conflicted,
+            ('R', _) | (_, 'R') => GitFileStatus::Renamed,
+            ('A', _) => GitFileStatus::Added,
+            ('D', _) | (_, 'D') => GitFileStatus::Deleted,
+            _ => GitFileStatus::Modified,
+        };
+        statuses.push((path.to_string(), status));
+    }
+    statuses
+}
+
+fn parse_log(out: &str) -> Vec<GitCommit> {
+    out.split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim().split('\x1f');
+            Some(GitCommit {
+                hash: fields.next().filter(|h| !h.is_empty())?.to_string(),
+                short_hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                timestamp: fields.next()?.parse().ok()?,
+                message: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Commit message describing a set of changes, e.g. "Update notes/a.md" or
+/// "Update 3 files: 2 modified, 1 added" followed by the file list
+pub fn commit_message(changes: &[(String, GitFileStatus)]) -> String {
+    let verb = |status: GitFileStatus| match status {
+        GitFileStatus::Added | GitFileStatus::Untracked => "Add",
+        GitFileStatus::Deleted => "Delete",
+        GitFileStatus::Renamed => "Rename",
+        GitFileStatus::Modified | GitFileStatus::Conflicted => "Update",
+    };
+    if let [(path, status)] = changes {
+        return format!("{} {}", verb(*status), path);
+    }
+
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for (_, status) in changes {
+        let label = match verb(*status) {
+            "Add" => "added",
+            "Delete" => "deleted",
+            "Rename" => "renamed",
+            _ => "modified",
+        };
+        match counts.iter_mut().find(|(l, _)| *l == label) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((label, 1)),
+        }
+    }
+    let summary: Vec<String> = counts.iter().map(|(label, n)| format!("{} {}", n, label)).collect();
+    let mut message = format!("Update {} files: {}\n", changes.len(), summary.join(", "));
+    for (path, status) in changes.iter().take(MAX_MESSAGE_FILES) {
+        message.push_str(&format!("\n{} {}", verb(*status), path));
+    }
+    if changes.len() > MAX_MESSAGE_FILES {
+        message.push_str(&format!("\n…and {} more", changes.len() - MAX_MESSAGE_FILES));
+    }
+    message
+}
+
+/// Free path for the remote's side of a conflicting note, e.g.
+/// `notes/a (conflict 2025-07-03).md`
+pub fn conflict_copy_path(rel_path: &str, date: &str, exists: impl Fn(&str) -> bool) -> String {
+    let (dir, name) = match rel_path.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), rel_path),
+    };
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| match n {
+            1 => format!("{}{} (conflict {}){}", dir, stem, date, ext),
+            n => format!("{}{} (conflict {} {}){}", dir, stem, date, n, ext),
+        })
+        .find(|path| !exists(path))
+        .unwrap_or_default()
+}
+
+/// The note a conflict note was made for
+pub fn conflict_original(conflict_path: &str) -> Option<String> {
+    let caps = CONFLICT_NOTE.captures(conflict_path)?;
+    Some(format!("{}{}", &caps[1], caps.get(2).map_or("", |m| m.as_str())))
+}
+
+/// When each open vault was last committed automatically, and the saves since
+#[derive(Default)]
+pub struct AutoCommit {
+    vaults: Mutex<HashMap<PathBuf, (Instant, u32)>>,
+}
+
+impl AutoCommit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a save; true once `batch` saves were made since the last commit
+    pub async fn note_saved(&self, vault_path: &Path, batch: u32) -> bool {
+        let mut vaults = self.vaults.lock().await;
+        let (_, saves) = vaults.entry(vault_path.to_path_buf()).or_insert_with(|| (Instant::now(), 0));
+        *saves += 1;
+        batch > 0 && *saves >= batch
+    }
+
+    /// Whether `interval` passed since the vault was last committed (or first seen)
+    pub async fn is_due(&self, vault_path: &Path, interval: Duration) -> bool {
+        let mut vaults = self.vaults.lock().await;
+        let (since, _) = vaults.entry(vault_path.to_path_buf()).or_insert_with(|| (Instant::now(), 0));
+        since.elapsed() >= interval
+    }
+
+    pub async fn committed(&self, vault_path: &Path) {
+        self.vaults.lock().await.insert(vault_path.to_path_buf(), (Instant::now(), 0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_messages_and_conflict_names() {
+        let out = " M notes/a.md\0?? new.md\0R  b.md\0old b.md\0UU c.md\0 D gone.md\0";
+        let statuses = parse_status(out);
+        assert_eq!(statuses, vec![
+            ("notes/a.md".to_string(), GitFileStatus::Modified),
+            ("new.md".to_string(), GitFileStatus::Untracked),
+            ("b.md".to_string(), GitFileStatus::Renamed),
+            ("c.md".to_string(), GitFileStatus::Conflicted),
+            ("gone.md".to_string(), GitFileStatus::Deleted),
+        ]);
+
+        assert_eq!(commit_message(&statuses[..1]), "Update notes/a.md");
+        let message = commit_message(&statuses);
+        assert!(message.starts_with("Update 5 files: 2 modified, 1 added, 1 renamed, 1 deleted\n\n"));
+        assert!(message.contains("\nAdd new.md"));
+
+        let taken = |p: &str| p == "notes/a (conflict 2025-07-03).md";
+        let copy = conflict_copy_path("notes/a.md", "2025-07-03", taken);
+        assert_eq!(copy, "notes/a (conflict 2025-07-03 2).md");
+        assert_eq!(conflict_original(&copy).as_deref(), Some("notes/a.md"));
+        assert_eq!(conflict_original("notes/a.md"), None);
+    }
+
+    #[test]
+    fn test_pull_turns_conflicts_into_conflict_notes() {
+        if Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let root = std::env::temp_dir().join(format!("gaimplan-git-{}", uuid::Uuid::new_v4()));
+        let (remote, a, b) = (root.join("remote.git"), root.join("a"), root.join("b"));
+        fs::create_dir_all(&remote).unwrap();
+        Command::new("git").args(["init", "-q", "--bare"]).arg(&remote).output().unwrap();
+
+        let repo_a = GitRepo::init(&a).unwrap();
+        repo_a.set_remote("origin", remote.to_str().unwrap()).unwrap();
+        fs::write(a.join("note.md"), "one").unwrap();
+        fs::create_dir_all(a.join(INTERNAL_DIR)).unwrap();
+        fs::write(a.join(INTERNAL_DIR).join("state.json"), "{}").unwrap();
+        let first = repo_a.commit_all(None).unwrap().unwrap();
+        assert_eq!(first.message, "Add note.md");
+        assert!(repo_a.commit_all(None).unwrap().is_none());
+        repo_a.push("origin").unwrap();
+
+        Command::new("git").args(["clone", "-q"]).arg(&remote).arg(&b).output().unwrap();
+        let repo_b = GitRepo::open(&b).unwrap();
+        assert!(!b.join(INTERNAL_DIR).exists());
+        fs::write(b.join("note.md"), "two from b").unwrap();
+        repo_b.commit_all(None).unwrap();
+        repo_b.push("origin").unwrap();
+
+        fs::write(a.join("note.md"), "two from a").unwrap();
+        assert_eq!(repo_a.status().unwrap().get("note.md"), Some(&GitFileStatus::Modified));
+        repo_a.commit_all(None).unwrap();
+        let conflicts = repo_a.pull("origin").unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(fs::read_to_string(a.join("note.md")).unwrap(), "two from a");
+        assert_eq!(fs::read_to_string(a.join(&conflicts[0].conflict_path)).unwrap(), "two from b");
+        assert!(repo_a.status().unwrap().is_empty());
+
+        assert_eq!(repo_a.resolve_conflict(&conflicts[0].conflict_path, true).unwrap(), "note.md");
+        assert_eq!(fs::read_to_string(a.join("note.md")).unwrap(), "two from b");
+        assert_eq!(repo_a.read_at(&first.hash, "note.md").unwrap(), "one");
+        assert_eq!(repo_a.log(Some("note.md"), 10).unwrap().len(), 4);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -22,7 +22,7 @@ pub fn init_debug_log(vault_path: &str) -> Result<(), String> {
         let mut debug_file = DEBUG_FILE.lock().unwrap();
         *debug_file = Some(log_file.clone());
         
-        println!("📄 Debug log will be saved to: {}", log_file.display());
+        eprintln!("📄 Debug log will be saved to: {}", log_file.display());
         return Ok(());
     }
     
@@ -38,7 +38,7 @@ pub fn init_debug_log(vault_path: &str) -> Result<(), String> {
     debug_log(&format!("Log file: {}", log_file.display()));
     debug_log(&format!("===========================\n"));
     
-    println!("📄 Debug log will be saved to: {}", log_file.display());
+    eprintln!("📄 Debug log will be saved to: {}", log_file.display());
     
     Ok(())
 }
@@ -59,7 +59,7 @@ pub fn debug_log(message: &str) {
     
     // Also print to console but only important messages
     if message.contains("✅") || message.contains("❌") || message.contains("SUMMARY") {
-        println!("{}", message);
+        eprintln!("{}", message);
     }
 }
 
@@ -69,7 +69,7 @@ pub fn close_debug_log() {
     
     if let Ok(mut debug_file) = DEBUG_FILE.lock() {
         if let Some(ref path) = *debug_file {
-            println!("📄 Debug log saved to: {}", path.display());
+            eprintln!("📄 Debug log saved to: {}", path.display());
         }
         *debug_file = None;
     }
@@ -53,6 +53,9 @@ pub struct Note {
     pub created: chrono::DateTime<chrono::Utc>,
     pub modified: chrono::DateTime<chrono::Utc>,
     pub vault_id: String,
+    /// Parsed YAML frontmatter
+    #[serde(default)]
+    pub properties: crate::index::NoteProperties,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,6 +94,16 @@ pub struct Relationship {
     pub properties: serde_json::Value,
 }
 
+/// A vault's graph and embeddings, as stored in backups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphDump {
+    pub vault_id: String,
+    pub nodes: Vec<serde_json::Value>,
+    pub relationships: Vec<Relationship>,
+    #[serde(default)]
+    pub embeddings: Vec<qdrant_http::PointStruct>,
+}
+
 #[async_trait]
 pub trait GraphManagerTrait: Send + Sync {
     async fn connect(&self, config: &GraphConfig) -> Result<(), String>;
@@ -104,11 +117,14 @@ pub trait GraphManagerTrait: Send + Sync {
     async fn create_note(&self, note: &Note) -> Result<String, String>;
     async fn update_note(&self, note: &Note) -> Result<(), String>;
     async fn delete_note(&self, note_id: &str) -> Result<(), String>;
+    async fn rename_note(&self, old_id: &str, new_id: &str, new_path: &str, new_title: &str) -> Result<(), String>;
+    async fn set_note_trashed(&self, note_id: &str, trashed: bool) -> Result<(), String>;
     async fn get_note(&self, note_id: &str) -> Result<Option<Note>, String>;
     
     // Relationship operations
     async fn create_relationship(&self, rel: &Relationship) -> Result<String, String>;
     async fn delete_relationship(&self, from_id: &str, to_id: &str, rel_type: &str) -> Result<(), String>;
+    async fn delete_outgoing_relationships(&self, from_id: &str, rel_type: &str) -> Result<(), String>;
     async fn get_related_notes(&self, note_id: &str, rel_type: Option<&str>, depth: i32) -> Result<Vec<Note>, String>;
     async fn relationship_exists(&self, from_id: &str, to_id: &str, rel_type: &str) -> Result<bool, String>;
     
@@ -145,6 +161,40 @@ impl GraphManagerImpl {
             current_vault_id: tokio::sync::Mutex::new(Some(vault_id)),
         }
     }
+    
+    /// Dumps the connected vault's nodes, relationships and, when Qdrant is
+    /// connected, its embeddings
+    pub async fn dump_vault(&self) -> Result<GraphDump, String> {
+        let vault_id = self.current_vault_id.lock().await
+            .as_ref()
+            .ok_or_else(|| "No vault connected".to_string())?
+            .clone();
+        
+        let (nodes, relationships) = self.neo4j.export_vault(&vault_id).await?;
+        let embeddings = if self.qdrant.is_connected().await {
+            self.qdrant.export_embeddings().await.unwrap_or_else(|e| {
+                eprintln!("Warning: Failed to export embeddings: {}", e);
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+        
+        Ok(GraphDump { vault_id, nodes, relationships, embeddings })
+    }
+    
+    /// Writes back the embeddings of a dump; notes and relationships are
+    /// rebuilt by syncing the restored notes instead
+    pub async fn restore_embeddings(&self, dump: GraphDump) -> Result<usize, String> {
+        let current = self.current_vault_id.lock().await.clone();
+        // Note ids include the vault id, so embeddings only match their own vault
+        if current.as_deref() != Some(dump.vault_id.as_str()) || dump.embeddings.is_empty() {
+            return Ok(0);
+        }
+        let count = dump.embeddings.len();
+        self.qdrant.import_embeddings(dump.embeddings).await?;
+        Ok(count)
+    }
 }
 
 #[async_trait]
@@ -217,6 +267,24 @@ impl GraphManagerTrait for GraphManagerImpl {
         Ok(())
     }
     
+    async fn rename_note(&self, old_id: &str, new_id: &str, new_path: &str, new_title: &str) -> Result<(), String> {
+        let vault_id = self.current_vault_id.lock().await
+            .as_ref()
+            .ok_or_else(|| "No vault connected".to_string())?
+            .clone();
+            
+        self.neo4j.rename_note(old_id, new_id, new_path, new_title, &vault_id).await
+    }
+    
+    async fn set_note_trashed(&self, note_id: &str, trashed: bool) -> Result<(), String> {
+        let vault_id = self.current_vault_id.lock().await
+            .as_ref()
+            .ok_or_else(|| "No vault connected".to_string())?
+            .clone();
+            
+        self.neo4j.set_note_trashed(note_id, trashed, &vault_id).await
+    }
+    
     async fn get_note(&self, note_id: &str) -> Result<Option<Note>, String> {
         let vault_id = self.current_vault_id.lock().await
             .as_ref()
@@ -239,6 +307,15 @@ impl GraphManagerTrait for GraphManagerImpl {
         Err("Delete relationship not implemented".to_string())
     }
     
+    async fn delete_outgoing_relationships(&self, from_id: &str, rel_type: &str) -> Result<(), String> {
+        let vault_id = self.current_vault_id.lock().await
+            .as_ref()
+            .ok_or_else(|| "No vault connected".to_string())?
+            .clone();
+            
+        self.neo4j.delete_outgoing_relationships(from_id, rel_type, &vault_id).await
+    }
+    
     async fn get_related_notes(&self, note_id: &str, rel_type: Option<&str>, depth: i32) -> Result<Vec<Note>, String> {
         let vault_id = self.current_vault_id.lock().await
             .as_ref()
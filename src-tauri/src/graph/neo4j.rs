@@ -1,7 +1,9 @@
-use neo4rs::{Graph, Node, query};
+use std::collections::HashMap;
+use neo4rs::{BoltNull, BoltType, Graph, Node, query};
 use tokio::sync::Mutex;
 use chrono::{DateTime, Utc};
 use super::{GraphConfig, Note, Pattern, PatternType, Relationship};
+use crate::index::NoteProperties;
 
 pub struct Neo4jManager {
     vault_id: String,
@@ -17,18 +19,18 @@ impl Neo4jManager {
     }
     
     pub async fn connect(&self, uri: &str, user: &str, password: &str) -> Result<(), String> {
-        println!("Neo4j connection attempt:");
-        println!("  URI: {}", uri);
-        println!("  User: {}", user);
-        println!("  Password: {}", password); // Show full password for debugging
+        eprintln!("Neo4j connection attempt:");
+        eprintln!("  URI: {}", uri);
+        eprintln!("  User: {}", user);
+        eprintln!("  Password: {}", password); // Show full password for debugging
         
         // Add a small delay to ensure Neo4j is ready after restart
-        println!("Waiting for Neo4j to be ready...");
+        eprintln!("Waiting for Neo4j to be ready...");
         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
         
         // Try using ConfigBuilder instead
         use neo4rs::ConfigBuilder;
-        println!("Building Neo4j config...");
+        eprintln!("Building Neo4j config...");
         let config = ConfigBuilder::default()
             .uri(uri)
             .user(user)
@@ -36,28 +38,28 @@ impl Neo4jManager {
             .build()
             .map_err(|e| format!("Failed to build Neo4j config: {}", e))?;
         
-        println!("Attempting to connect to Neo4j...");
+        eprintln!("Attempting to connect to Neo4j...");
         // Add timeout to prevent hanging
         let connect_future = Graph::connect(config);
         let timeout_duration = tokio::time::Duration::from_secs(10);
         
         let graph = match tokio::time::timeout(timeout_duration, connect_future).await {
             Ok(Ok(graph)) => {
-                println!("Successfully connected to Neo4j");
+                eprintln!("Successfully connected to Neo4j");
                 graph
             }
             Ok(Err(e)) => {
-                println!("Failed to connect to Neo4j: {}", e);
+                eprintln!("Failed to connect to Neo4j: {}", e);
                 return Err(format!("Failed to connect to Neo4j: {}", e));
             }
             Err(_) => {
-                println!("Neo4j connection timed out after 10 seconds");
+                eprintln!("Neo4j connection timed out after 10 seconds");
                 return Err("Neo4j connection timed out after 10 seconds".to_string());
             }
         };
         
         // Run connection test
-        println!("Testing Neo4j connection...");
+        eprintln!("Testing Neo4j connection...");
         let test_future = graph.execute(query("RETURN 1 as n"));
         let mut result = match tokio::time::timeout(tokio::time::Duration::from_secs(5), test_future).await {
             Ok(Ok(result)) => result,
@@ -67,17 +69,17 @@ impl Neo4jManager {
         
         // Consume the result to ensure connection works
         while let Ok(Some(_)) = result.next().await {}
-        println!("Neo4j connection test successful");
+        eprintln!("Neo4j connection test successful");
         
         *self.graph.lock().await = Some(graph);
         
         // Initialize schema - but don't fail if it errors
-        println!("Initializing Neo4j schema...");
+        eprintln!("Initializing Neo4j schema...");
         match self.initialize_schema().await {
-            Ok(_) => println!("Neo4j schema initialization successful"),
-            Err(e) => println!("Warning: Schema initialization had issues: {} (continuing anyway)", e),
+            Ok(_) => eprintln!("Neo4j schema initialization successful"),
+            Err(e) => eprintln!("Warning: Schema initialization had issues: {} (continuing anyway)", e),
         }
-        println!("Neo4j connection fully established");
+        eprintln!("Neo4j connection fully established");
         
         Ok(())
     }
@@ -124,10 +126,10 @@ impl Neo4jManager {
         ];
         
         // Execute constraints with timeout
-        println!("Creating constraints...");
+        eprintln!("Creating constraints...");
         for (i, constraint) in constraints.iter().enumerate() {
             let constraint_name = constraint.split(" ").nth(2).unwrap_or("");
-            println!("  Creating constraint {}/{}: {}", i + 1, constraints.len(), constraint_name);
+            eprintln!("  Creating constraint {}/{}: {}", i + 1, constraints.len(), constraint_name);
             
             // First try to drop the constraint if it exists (to handle corrupted state)
             let drop_query = format!("DROP CONSTRAINT {} IF EXISTS", constraint_name);
@@ -140,61 +142,62 @@ impl Neo4jManager {
                 Ok(Ok(mut result)) => {
                     // Consume the result to ensure the query completes
                     while let Ok(Some(_)) = result.next().await {}
-                    println!("    Constraint created successfully");
+                    eprintln!("    Constraint created successfully");
                 },
                 Ok(Err(e)) => {
                     // Check if it's an already exists error
                     let err_str = e.to_string();
                     if err_str.contains("already exists") || err_str.contains("ConstraintAlreadyExists") || err_str.contains("Equivalent constraint already exists") {
-                        println!("    Constraint already exists, continuing...");
+                        eprintln!("    Constraint already exists, continuing...");
                     } else {
-                        println!("    Error creating constraint: {}", e);
+                        eprintln!("    Error creating constraint: {}", e);
                         // Don't fail on constraint errors, they might already exist
                     }
                 },
                 Err(_) => {
-                    println!("    Constraint creation timed out after 10 seconds, continuing...");
+                    eprintln!("    Constraint creation timed out after 10 seconds, continuing...");
                     // Don't fail on timeout, constraint might already exist
                 }
             }
         }
         
         // Execute indexes with timeout
-        println!("Creating indexes...");
+        eprintln!("Creating indexes...");
         for (i, index) in indexes.iter().enumerate() {
             let index_name = index.split(" ").nth(2).unwrap_or("");
-            println!("  Creating index {}/{}: {}", i + 1, indexes.len(), index_name);
+            eprintln!("  Creating index {}/{}: {}", i + 1, indexes.len(), index_name);
             
             let future = graph.execute(query(index));
             match tokio::time::timeout(tokio::time::Duration::from_secs(10), future).await {
                 Ok(Ok(mut result)) => {
                     // Consume the result to ensure the query completes
                     while let Ok(Some(_)) = result.next().await {}
-                    println!("    Index created successfully");
+                    eprintln!("    Index created successfully");
                 },
                 Ok(Err(e)) => {
                     // Check if it's an already exists error
                     let err_str = e.to_string();
                     if err_str.contains("already exists") || err_str.contains("IndexAlreadyExists") || err_str.contains("Equivalent index already exists") {
-                        println!("    Index already exists, continuing...");
+                        eprintln!("    Index already exists, continuing...");
                     } else {
-                        println!("    Error creating index: {}", e);
+                        eprintln!("    Error creating index: {}", e);
                         // Don't fail on index errors, they might already exist
                     }
                 },
                 Err(_) => {
-                    println!("    Index creation timed out after 10 seconds, continuing...");
+                    eprintln!("    Index creation timed out after 10 seconds, continuing...");
                     // Don't fail on timeout, index might already exist
                 }
             }
         }
         
-        println!("Schema initialization complete");
+        eprintln!("Schema initialization complete");
         Ok(())
     }
     
     pub async fn create_note(&self, note: &Note) -> Result<String, String> {
         let graph = self.get_graph().await?;
+        let (properties, property_keys) = custom_properties(note, &self.property_keys(&graph, note).await);
         
         let query_str = r#"
             MERGE (n:Note {id: $id, vault_id: $vault_id})
@@ -208,7 +211,13 @@ impl Neo4jManager {
                 n.path = $path,
                 n.title = $title,
                 n.content = $content,
+                n.created = coalesce($frontmatter_created, n.created),
                 n.modified = $modified
+            SET n.aliases = $aliases,
+                n.tags = $tags,
+                n.property_keys = $property_keys,
+                n += $properties
+            REMOVE n.properties
             RETURN n.id as id
         "#;
         
@@ -220,7 +229,12 @@ impl Neo4jManager {
                     .param("title", note.title.clone())
                     .param("content", note.content.clone())
                     .param("created", note.created.timestamp())
+                    .param("frontmatter_created", note.properties.created.map(|c| c.timestamp()))
                     .param("modified", note.modified.timestamp())
+                    .param("aliases", note.properties.aliases.clone())
+                    .param("tags", note.properties.tags.clone())
+                    .param("properties", properties)
+                    .param("property_keys", property_keys)
                     .param("vault_id", note.vault_id.clone())
             )
             .await
@@ -236,13 +250,20 @@ impl Neo4jManager {
     
     pub async fn update_note(&self, note: &Note) -> Result<(), String> {
         let graph = self.get_graph().await?;
+        let (properties, property_keys) = custom_properties(note, &self.property_keys(&graph, note).await);
         
         let query_str = r#"
             MATCH (n:Note {id: $id, vault_id: $vault_id})
             SET n.path = $path,
                 n.title = $title,
                 n.content = $content,
-                n.modified = $modified
+                n.modified = $modified,
+                n.aliases = $aliases,
+                n.tags = $tags,
+                n.property_keys = $property_keys,
+                n += $properties,
+                n.trashed = false
+            REMOVE n.properties
             RETURN n.id as id
         "#;
         
@@ -255,6 +276,10 @@ impl Neo4jManager {
                     .param("title", note.title.clone())
                     .param("content", note.content.clone())
                     .param("modified", note.modified.timestamp())
+                    .param("aliases", note.properties.aliases.clone())
+                    .param("tags", note.properties.tags.clone())
+                    .param("properties", properties)
+                    .param("property_keys", property_keys)
             )
             .await
             .map_err(|e| format!("Failed to update note: {}", e))?;
@@ -266,6 +291,21 @@ impl Neo4jManager {
         }
     }
     
+    /// Node properties the note's custom frontmatter keys were last stored as
+    async fn property_keys(&self, graph: &Graph, note: &Note) -> Vec<String> {
+        let query_str = "MATCH (n:Note {id: $id, vault_id: $vault_id}) RETURN n.property_keys AS keys";
+        let result = graph
+            .execute(query(query_str).param("id", note.id.clone()).param("vault_id", note.vault_id.clone()))
+            .await;
+        match result {
+            Ok(mut rows) => match rows.next().await {
+                Ok(Some(row)) => row.get::<Vec<String>>("keys").unwrap_or_default(),
+                _ => Vec::new(),
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
     pub async fn delete_note(&self, note_id: &str, vault_id: &str) -> Result<(), String> {
         let graph = self.get_graph().await?;
         
@@ -286,6 +326,53 @@ impl Neo4jManager {
         Ok(())
     }
     
+    /// Flags a note whose file sits in the vault trash; its relationships stay until it is purged
+    pub async fn set_note_trashed(&self, note_id: &str, trashed: bool, vault_id: &str) -> Result<(), String> {
+        let graph = self.get_graph().await?;
+        
+        let query_str = r#"
+            MATCH (n:Note {id: $id, vault_id: $vault_id})
+            SET n.trashed = $trashed,
+                n.trashed_at = CASE WHEN $trashed THEN timestamp() ELSE null END
+        "#;
+        
+        graph
+            .run(
+                query(query_str)
+                    .param("id", note_id.to_string())
+                    .param("trashed", trashed)
+                    .param("vault_id", vault_id.to_string())
+            )
+            .await
+            .map_err(|e| format!("Failed to update trashed flag: {}", e))?;
+        
+        Ok(())
+    }
+    
+    /// Re-keys a note after its file was renamed or moved, keeping its relationships
+    pub async fn rename_note(&self, old_id: &str, new_id: &str, new_path: &str, new_title: &str, vault_id: &str) -> Result<(), String> {
+        let graph = self.get_graph().await?;
+        
+        let query_str = r#"
+            MATCH (n:Note {id: $old_id, vault_id: $vault_id})
+            SET n.id = $new_id, n.path = $path, n.title = $title
+        "#;
+        
+        graph
+            .run(
+                query(query_str)
+                    .param("old_id", old_id.to_string())
+                    .param("new_id", new_id.to_string())
+                    .param("path", new_path.to_string())
+                    .param("title", new_title.to_string())
+                    .param("vault_id", vault_id.to_string())
+            )
+            .await
+            .map_err(|e| format!("Failed to rename note: {}", e))?;
+        
+        Ok(())
+    }
+    
     pub async fn get_note(&self, note_id: &str, vault_id: &str) -> Result<Option<Note>, String> {
         let graph = self.get_graph().await?;
         
@@ -321,6 +408,8 @@ impl Neo4jManager {
         let modified: i64 = node.get("modified").map_err(|e| format!("Failed to get modified: {}", e))?;
         let vault_id: String = node.get("vault_id").map_err(|e| format!("Failed to get vault_id: {}", e))?;
         
+        let properties = NoteProperties::from_content(&content);
+        
         Ok(Note {
             id,
             path,
@@ -333,6 +422,7 @@ impl Neo4jManager {
                 .ok_or_else(|| "Invalid modified timestamp".to_string())?
                 .with_timezone(&Utc),
             vault_id,
+            properties,
         })
     }
     
@@ -357,12 +447,13 @@ impl Neo4jManager {
                 }
             },
             "LINKS_TO" => {
-                // For links, we'll handle this differently once we resolve note IDs
+                // Wiki-links are resolved to real notes before they reach the graph
                 format!(
                     r#"
                     MATCH (from:Note {{id: $from_id, vault_id: $vault_id}})
-                    MERGE (to:LinkedNote {{id: $to_id, vault_id: $vault_id}})
+                    MATCH (to:Note {{id: $to_id, vault_id: $vault_id}})
                     MERGE (from)-[r:LINKS_TO]->(to)
+                    SET r.link_text = $link_text
                     RETURN id(r) as rel_id
                     "#
                 )
@@ -405,6 +496,11 @@ impl Neo4jManager {
             if let Some(tag_name) = rel.properties.get("tag_name").and_then(|v| v.as_str()) {
                 q = q.param("tag_name", tag_name);
             }
+        } else if rel.rel_type == "LINKS_TO" {
+            let link_text = rel.properties.get("link_text")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            q = q.param("link_text", link_text);
         } else {
             // For semantic relationships, extract properties
             let confidence = rel.properties.get("confidence")
@@ -447,6 +543,30 @@ impl Neo4jManager {
         }
     }
     
+    /// Removes every outgoing relationship of one type from a note, so it can be rebuilt
+    pub async fn delete_outgoing_relationships(&self, from_id: &str, rel_type: &str, vault_id: &str) -> Result<(), String> {
+        let graph = self.get_graph().await?;
+        
+        let query_str = format!(
+            r#"
+            MATCH (from:Note {{id: $from_id, vault_id: $vault_id}})-[r:{}]->()
+            DELETE r
+            "#,
+            rel_type
+        );
+        
+        graph
+            .run(
+                query(&query_str)
+                    .param("from_id", from_id.to_string())
+                    .param("vault_id", vault_id.to_string())
+            )
+            .await
+            .map_err(|e| format!("Failed to delete relationships: {}", e))?;
+        
+        Ok(())
+    }
+    
     pub async fn relationship_exists(&self, from_id: &str, to_id: &str, rel_type: &str, vault_id: &str) -> Result<bool, String> {
         let graph = self.get_graph().await?;
         
@@ -563,7 +683,7 @@ impl Neo4jManager {
     pub async fn clear_vault_data(&self, vault_id: &str) -> Result<(), String> {
         let graph = self.get_graph().await?;
         
-        println!("Clearing all data for vault: {}", vault_id);
+        eprintln!("Clearing all data for vault: {}", vault_id);
         
         // Delete all relationships first (Neo4j requires this)
         let delete_relationships = r#"
@@ -580,7 +700,7 @@ impl Neo4jManager {
             .await
             .map_err(|e| format!("Failed to delete relationships: {}", e))?;
             
-        println!("Deleted all relationships for vault: {}", vault_id);
+        eprintln!("Deleted all relationships for vault: {}", vault_id);
         
         // Delete all nodes
         let delete_nodes = r#"
@@ -597,11 +717,61 @@ impl Neo4jManager {
             .await
             .map_err(|e| format!("Failed to delete nodes: {}", e))?;
             
-        println!("Deleted all nodes for vault: {}", vault_id);
+        eprintln!("Deleted all nodes for vault: {}", vault_id);
         
         Ok(())
     }
     
+    /// Every node of a vault with its labels and properties, and every
+    /// relationship between them, as JSON
+    pub async fn export_vault(&self, vault_id: &str) -> Result<(Vec<serde_json::Value>, Vec<Relationship>), String> {
+        let graph = self.get_graph().await?;
+
+        let mut result = graph
+            .execute(query("MATCH (n {vault_id: $vault_id}) RETURN n").param("vault_id", vault_id.to_string()))
+            .await
+            .map_err(|e| format!("Failed to export nodes: {}", e))?;
+        let mut nodes = Vec::new();
+        while let Ok(Some(row)) = result.next().await {
+            let node: Node = row.get("n").map_err(|e| format!("Failed to get node: {}", e))?;
+            let mut properties = serde_json::Map::new();
+            for key in node.keys() {
+                if let Ok(value) = node.get::<neo4rs::BoltType>(key) {
+                    properties.insert(key.to_string(), bolt_type_to_json(value));
+                }
+            }
+            nodes.push(serde_json::json!({
+                "labels": node.labels(),
+                "properties": properties,
+            }));
+        }
+
+        let relationships_query = r#"
+            MATCH (a {vault_id: $vault_id})-[r]->(b {vault_id: $vault_id})
+            RETURN a.id AS from_id, type(r) AS rel_type, b.id AS to_id, properties(r) AS properties
+        "#;
+        let mut result = graph
+            .execute(query(relationships_query).param("vault_id", vault_id.to_string()))
+            .await
+            .map_err(|e| format!("Failed to export relationships: {}", e))?;
+        let mut relationships = Vec::new();
+        while let Ok(Some(row)) = result.next().await {
+            let (Ok(from_id), Ok(rel_type), Ok(to_id)) = (
+                row.get::<String>("from_id"),
+                row.get::<String>("rel_type"),
+                row.get::<String>("to_id"),
+            ) else {
+                continue;
+            };
+            let properties = row.get::<neo4rs::BoltType>("properties")
+                .map(bolt_type_to_json)
+                .unwrap_or(serde_json::Value::Null);
+            relationships.push(Relationship { from_id, to_id, rel_type, properties });
+        }
+
+        Ok((nodes, relationships))
+    }
+    
     pub async fn execute_query(&self, cypher: &str, params: Vec<(&str, neo4rs::BoltType)>) -> Result<serde_json::Value, String> {
         let graph = self.get_graph().await?;
         
@@ -698,4 +868,49 @@ fn bolt_type_to_json(bolt_value: neo4rs::BoltType) -> serde_json::Value {
         },
         _ => serde_json::Value::Null, // For other types we don't handle yet
     }
-}
\ No newline at end of file
+}
+
+/// Custom frontmatter keys become `prop_<key>` node properties so queries can
+/// filter on them. Scalars and lists of one scalar type are stored as they are,
+/// anything else as JSON (Neo4j properties cannot hold maps). Keys stored by the
+/// previous sync that the note no longer has are set to null, which removes them.
+/// Returns the properties and the keys now stored.
+fn custom_properties(note: &Note, previous_keys: &[String]) -> (HashMap<String, BoltType>, Vec<String>) {
+    let mut properties: HashMap<String, BoltType> = previous_keys
+        .iter()
+        .map(|key| (key.clone(), BoltType::Null(BoltNull)))
+        .collect();
+    let mut keys = Vec::new();
+
+    for (key, value) in &note.properties.custom {
+        let stored = match value {
+            serde_json::Value::Null => continue,
+            serde_json::Value::Array(items) => {
+                let items: Option<Vec<BoltType>> = items.iter().map(scalar_property).collect();
+                let same_type = |items: &[BoltType]| {
+                    items.windows(2).all(|w| std::mem::discriminant(&w[0]) == std::mem::discriminant(&w[1]))
+                };
+                match items {
+                    Some(items) if same_type(&items) => BoltType::from(items),
+                    _ => BoltType::from(value.to_string()),
+                }
+            }
+            value => scalar_property(value).unwrap_or_else(|| BoltType::from(value.to_string())),
+        };
+        let key = format!("prop_{}", key);
+        properties.insert(key.clone(), stored);
+        keys.push(key);
+    }
+
+    keys.sort();
+    (properties, keys)
+}
+
+fn scalar_property(value: &serde_json::Value) -> Option<BoltType> {
+    match value {
+        serde_json::Value::Bool(b) => Some(BoltType::from(*b)),
+        serde_json::Value::Number(n) => n.as_i64().map(BoltType::from).or_else(|| n.as_f64().map(BoltType::from)),
+        serde_json::Value::String(s) => Some(BoltType::from(s.clone())),
+        _ => None,
+    }
+}
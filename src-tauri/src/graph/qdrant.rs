@@ -19,42 +19,42 @@ impl QdrantManager {
     }
     
     pub async fn connect(&self, url: &str) -> Result<(), String> {
-        println!("[DEBUG] Connecting to Qdrant at: {}", url);
+        eprintln!("[DEBUG] Connecting to Qdrant at: {}", url);
         
         // Create HTTP client
-        println!("[DEBUG] Creating Qdrant HTTP client...");
+        eprintln!("[DEBUG] Creating Qdrant HTTP client...");
         let client = QdrantHttpClient::new(url.to_string());
         
         // Test connection with timeout
-        println!("[DEBUG] Testing Qdrant connection...");
+        eprintln!("[DEBUG] Testing Qdrant connection...");
         let check_future = client.check_connection();
         match tokio::time::timeout(tokio::time::Duration::from_secs(5), check_future).await {
-            Ok(Ok(_)) => println!("[DEBUG] Qdrant connection test successful"),
+            Ok(Ok(_)) => eprintln!("[DEBUG] Qdrant connection test successful"),
             Ok(Err(e)) => return Err(format!("Failed to connect to Qdrant: {}", e)),
             Err(_) => return Err("Qdrant connection test timed out after 5 seconds".to_string()),
         }
         
         // List collections with timeout
-        println!("[DEBUG] Listing Qdrant collections...");
+        eprintln!("[DEBUG] Listing Qdrant collections...");
         let list_future = client.list_collections();
         let collections = match tokio::time::timeout(tokio::time::Duration::from_secs(5), list_future).await {
             Ok(Ok(collections)) => collections,
             Ok(Err(e)) => return Err(format!("Failed to list Qdrant collections: {}", e)),
             Err(_) => return Err("Qdrant list collections timed out after 5 seconds".to_string()),
         };
-        println!("[DEBUG] Successfully connected to Qdrant. Found {} collections", collections.len());
+        eprintln!("[DEBUG] Successfully connected to Qdrant. Found {} collections", collections.len());
         
         // Initialize collection with timeout
-        println!("[DEBUG] Initializing collection...");
+        eprintln!("[DEBUG] Initializing collection...");
         let init_future = self.initialize_collection(&client);
         match tokio::time::timeout(tokio::time::Duration::from_secs(10), init_future).await {
-            Ok(Ok(_)) => println!("[DEBUG] Collection initialized successfully"),
+            Ok(Ok(_)) => eprintln!("[DEBUG] Collection initialized successfully"),
             Ok(Err(e)) => return Err(format!("Failed to initialize collection: {}", e)),
             Err(_) => return Err("Collection initialization timed out after 10 seconds".to_string()),
         }
         
         *self.client.lock().await = Some(client);
-        println!("[DEBUG] Successfully connected to Qdrant for vault: {}", self.vault_id);
+        eprintln!("[DEBUG] Successfully connected to Qdrant for vault: {}", self.vault_id);
         Ok(())
     }
     
@@ -89,11 +89,11 @@ impl QdrantManager {
         
         // Create collection if it doesn't exist
         if !collection_names.contains(collection_name) {
-            println!("[DEBUG] Creating collection: {}", collection_name);
+            eprintln!("[DEBUG] Creating collection: {}", collection_name);
             client.create_collection(collection_name, 1536).await?; // OpenAI embedding size
-            println!("[DEBUG] Collection created successfully");
+            eprintln!("[DEBUG] Collection created successfully");
         } else {
-            println!("[DEBUG] Collection {} already exists", collection_name);
+            eprintln!("[DEBUG] Collection {} already exists", collection_name);
         }
         
         Ok(())
@@ -149,20 +149,40 @@ impl QdrantManager {
     pub async fn clear_collection(&self) -> Result<(), String> {
         let client = self.get_client().await?;
         
-        println!("[DEBUG] Clearing Qdrant collection: {}", self.collection_name);
+        eprintln!("[DEBUG] Clearing Qdrant collection: {}", self.collection_name);
         
         // Delete all points from the collection
         // Since we don't have a direct "clear all" method, we'll delete and recreate the collection
         match client.delete_collection(&self.collection_name).await {
-            Ok(_) => println!("[DEBUG] Collection deleted successfully"),
-            Err(e) => println!("[DEBUG] Warning: Failed to delete collection: {}", e),
+            Ok(_) => eprintln!("[DEBUG] Collection deleted successfully"),
+            Err(e) => eprintln!("[DEBUG] Warning: Failed to delete collection: {}", e),
         }
         
         // Recreate the collection
         self.initialize_collection(&client).await?;
         
-        println!("[DEBUG] Qdrant collection cleared and recreated");
+        eprintln!("[DEBUG] Qdrant collection cleared and recreated");
         Ok(())
     }
     
-}
\ No newline at end of file
+    /// Embeddings of this vault's notes, for backups
+    pub async fn export_embeddings(&self) -> Result<Vec<PointStruct>, String> {
+        let client = self.get_client().await?;
+        
+        let filter = serde_json::json!({
+            "must": [{ "key": "vault_id", "match": { "value": self.vault_id } }]
+        });
+        client.scroll_points(&self.collection_name, filter).await
+    }
+    
+    /// Writes back embeddings exported from this vault
+    pub async fn import_embeddings(&self, points: Vec<PointStruct>) -> Result<(), String> {
+        let client = self.get_client().await?;
+        
+        for batch in points.chunks(256) {
+            client.upsert_points(&self.collection_name, batch.to_vec()).await?;
+        }
+        
+        Ok(())
+    }
+}
@@ -48,7 +48,7 @@ pub struct VectorParams {
     pub distance: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PointStruct {
     pub id: String,
     pub vector: Vec<f32>,
@@ -79,6 +79,17 @@ pub struct SearchResponse {
     pub result: Vec<SearchResult>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrollResponse {
+    pub result: ScrollResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrollResult {
+    pub points: Vec<PointStruct>,
+    pub next_page_offset: Option<serde_json::Value>,
+}
+
 impl QdrantHttpClient {
     pub fn new(base_url: String) -> Self {
         let client = Client::builder()
@@ -236,4 +247,46 @@ impl QdrantHttpClient {
             Err(format!("Delete points failed: {}", error_text))
         }
     }
+    
+    /// Every point of a collection matching `filter`, with vectors and payloads
+    pub async fn scroll_points(&self, collection_name: &str, filter: serde_json::Value) -> Result<Vec<PointStruct>, String> {
+        let url = format!("{}/collections/{}/points/scroll", self.base_url, collection_name);
+        
+        let mut points = Vec::new();
+        let mut offset = serde_json::Value::Null;
+        loop {
+            let request_body = json!({
+                "filter": filter,
+                "limit": 256,
+                "offset": offset,
+                "with_payload": true,
+                "with_vector": true
+            });
+            
+            let response = self.client
+                .post(&url)
+                .json(&request_body)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to scroll points: {}", e))?;
+            
+            if !response.status().is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(format!("Scroll failed: {}", error_text));
+            }
+            
+            let scroll_response: ScrollResponse = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse scroll response: {}", e))?;
+            
+            points.extend(scroll_response.result.points);
+            match scroll_response.result.next_page_offset {
+                Some(next) if !next.is_null() => offset = next,
+                _ => break,
+            }
+        }
+        
+        Ok(points)
+    }
 }
\ No newline at end of file
@@ -59,7 +59,7 @@ impl SchemaManager {
     }
     
     async fn migrate_v1(&self, graph: &Graph) -> Result<(), String> {
-        println!("Applying schema migration v1...");
+        eprintln!("Applying schema migration v1...");
         
         // Create constraints
         let constraints = vec![
@@ -98,7 +98,7 @@ impl SchemaManager {
         // Set version
         self.set_current_version(graph, 1).await?;
         
-        println!("Schema migration v1 complete");
+        eprintln!("Schema migration v1 complete");
         Ok(())
     }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
 use std::path::Path;
 use std::sync::Arc;
+use crate::index::NoteProperties;
+use crate::vault_ignore::VaultIgnore;
 use super::{GraphManagerTrait, Note};
 use walkdir::WalkDir;
 use chrono::Utc;
@@ -10,8 +12,9 @@ pub async fn sync_vault_simple(
     graph_manager: &Arc<dyn GraphManagerTrait>,
     vault_id: &str,
     skip_relationships: bool,
+    ignore: &VaultIgnore,
 ) -> Result<(usize, usize), String> {
-    println!("Starting simple sync...");
+    eprintln!("Starting simple sync...");
     
     let mut notes = Vec::new();
     let mut file_count = 0;
@@ -21,6 +24,7 @@ pub async fn sync_vault_simple(
         .follow_links(false)
         .max_depth(5)
         .into_iter()
+        .filter_entry(|e| !ignore.is_ignored(e.path(), e.file_type().is_dir()))
         .filter_map(|e| e.ok())
     {
         let path = entry.path();
@@ -31,16 +35,19 @@ pub async fn sync_vault_simple(
         }
         
         file_count += 1;
-        println!("Processing file {}: {}", file_count, path.display());
+        eprintln!("Processing file {}: {}", file_count, path.display());
         
         // Read file content
         let content = match std::fs::read_to_string(&path) {
             Ok(c) => c,
             Err(e) => {
-                println!("Failed to read {}: {}", path.display(), e);
+                eprintln!("Failed to read {}: {}", path.display(), e);
                 continue;
             }
         };
+        if crate::encryption::is_encrypted(&content) {
+            continue;
+        }
         
         // Create note
         let note = create_note_from_file(path, &content, vault_path, vault_id)?;
@@ -57,17 +64,17 @@ pub async fn sync_vault_simple(
         
         // Print progress every 10 files
         if file_count % 10 == 0 {
-            println!("Progress: {} files processed", file_count);
+            eprintln!("Progress: {} files processed", file_count);
         }
     }
     
-    println!("Created {} notes", notes.len());
+    eprintln!("Created {} notes", notes.len());
     
     let relationship_count = if skip_relationships {
-        println!("Skipping relationship building as requested");
+        eprintln!("Skipping relationship building as requested");
         0
     } else {
-        println!("Building relationships...");
+        eprintln!("Building relationships...");
         
         // Build relationships
         use super::semantic_relationships::SemanticRelationshipBuilder;
@@ -89,11 +96,14 @@ fn create_note_from_file(
     let relative_path = path.strip_prefix(vault_path)
         .map_err(|_| "Failed to get relative path")?;
     
-    let title = path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("Untitled")
-        .to_string();
+    // A frontmatter `title:` wins over the file name
+    let properties = NoteProperties::from_content(content);
+    let title = properties.title.clone().unwrap_or_else(|| {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled")
+            .to_string()
+    });
     
     let metadata = std::fs::metadata(path)
         .map_err(|e| format!("Failed to get metadata: {}", e))?;
@@ -125,8 +135,9 @@ fn create_note_from_file(
         path: relative_path.to_string_lossy().to_string(),
         title,
         content: content.to_string(),
-        created: created.with_timezone(&Utc),
+        created: properties.created.unwrap_or(created.with_timezone(&Utc)),
         modified: modified.with_timezone(&Utc),
         vault_id: vault_id.to_string(),
+        properties,
     })
 }
\ No newline at end of file
@@ -5,11 +5,17 @@ use notify::{Event, EventKind, RecursiveMode, Watcher};
 use chrono::Utc;
 use sha2::{Sha256, Digest};
 use crate::vault::Vault;
+use crate::index::links::parse_wiki_links;
+use crate::index::resolver::{is_markdown, NoteResolver};
+use crate::index::tags::parse_tags;
+use crate::index::{NoteProperties, VaultIndex};
 use super::{GraphManagerTrait, Note};
 
 pub struct GraphSyncService {
     graph_manager: Arc<dyn GraphManagerTrait>,
     vault: Arc<Vault>,
+    /// Resolves link targets of changed notes; built on start when not given
+    index: Option<Arc<VaultIndex>>,
     watcher_handle: Option<tokio::task::JoinHandle<()>>,
     shutdown_tx: Option<mpsc::Sender<()>>,
 }
@@ -19,11 +25,19 @@ impl GraphSyncService {
         Self {
             graph_manager,
             vault,
+            index: None,
             watcher_handle: None,
             shutdown_tx: None,
         }
     }
     
+    /// Uses an index kept up to date elsewhere, such as the app's shared
+    /// `VaultIndex`, instead of building and maintaining one
+    pub fn with_index(mut self, index: Arc<VaultIndex>) -> Self {
+        self.index = Some(index);
+        self
+    }
+    
     pub async fn start(&mut self) -> Result<(), String> {
         if self.watcher_handle.is_some() {
             return Ok(()); // Already running
@@ -34,6 +48,11 @@ impl GraphSyncService {
         let vault = self.vault.clone();
         let vault_path = vault.path().to_path_buf();
         let vault_id = self.generate_vault_id(&vault_path);
+        // An index the service builds itself is also kept current by it
+        let (index, owns_index) = match self.index.clone() {
+            Some(index) => (index, false),
+            None => (Arc::new(VaultIndex::build(&vault_path, vault.ignore_rules())?), true),
+        };
         
         let handle = tokio::spawn(async move {
             let (tx, mut rx) = mpsc::channel(100);
@@ -54,10 +73,14 @@ impl GraphSyncService {
             loop {
                 tokio::select! {
                     Some(event) = rx.recv() => {
+                        if owns_index {
+                            index.handle_file_event(&event).await;
+                        }
                         if let Err(e) = Self::handle_file_event(
                             event,
                             &graph_manager,
                             &vault,
+                            &index,
                             &vault_id
                         ).await {
                             eprintln!("Error handling file event: {}", e);
@@ -92,28 +115,30 @@ impl GraphSyncService {
     }
     
     pub async fn initial_sync(&self) -> Result<(), String> {
-        println!("Starting initial sync...");
+        eprintln!("Starting initial sync...");
         
         // Initialize debug logging
-        println!("Initializing debug log...");
+        eprintln!("Initializing debug log...");
         super::debug_logger::init_debug_log(&self.vault.path().to_string_lossy())?;
         
-        println!("Generating vault ID...");
+        eprintln!("Generating vault ID...");
         let vault_id = self.generate_vault_id(self.vault.path());
-        println!("Vault ID: {}", vault_id);
+        eprintln!("Vault ID: {}", vault_id);
         
-        println!("Listing markdown files...");
+        eprintln!("Listing markdown files...");
         
         // Simple file listing without following symlinks
         let mut files = Vec::new();
         let vault_path = self.vault.path();
-        println!("Scanning vault path: {}", vault_path.display());
+        eprintln!("Scanning vault path: {}", vault_path.display());
         
         use walkdir::WalkDir;
+        let ignore = self.vault.ignore();
         for entry in WalkDir::new(vault_path)
             .follow_links(false)  // Don't follow symlinks to avoid loops
             .max_depth(10)        // Limit depth
             .into_iter()
+            .filter_entry(|e| !ignore.is_ignored(e.path(), e.file_type().is_dir()))
             .filter_map(|e| e.ok())
         {
             let path = entry.path();
@@ -122,35 +147,48 @@ impl GraphSyncService {
             }
         }
         
-        println!("Found {} markdown files", files.len());
+        eprintln!("Found {} markdown files", files.len());
         
         let mut notes = Vec::new();
         
         // First pass: Create all notes
-        println!("Creating notes in graph database...");
+        eprintln!("Creating notes in graph database...");
         for (i, file_path) in files.iter().enumerate() {
             if file_path.extension().and_then(|s| s.to_str()) == Some("md") {
-                println!("Processing file {}/{}: {}", i+1, files.len(), file_path.display());
+                eprintln!("Processing file {}/{}: {}", i+1, files.len(), file_path.display());
                 if let Ok(content) = std::fs::read_to_string(&file_path) {
+                    if crate::encryption::is_encrypted(&content) {
+                        eprintln!("🔒 Skipping encrypted note: {}", file_path.display());
+                        continue;
+                    }
                     let note = self.file_to_note(&file_path, &content, &vault_id)?;
                     
                     // Create or update note
-                    println!("Creating note: {}", note.title);
+                    eprintln!("Creating note: {}", note.title);
                     self.graph_manager.create_note(&note).await
                         .map_err(|e| {
-                            println!("Failed to create note: {}", e);
+                            eprintln!("Failed to create note: {}", e);
                             format!("Failed to create note '{}': {}", note.title, e)
                         })?;
                     notes.push(note);
                 } else {
-                    println!("Failed to read file: {}", file_path.display());
+                    eprintln!("Failed to read file: {}", file_path.display());
                 }
             }
         }
         
         super::debug_logger::debug_log(&format!("Created {} notes in graph database", notes.len()));
         
-        // Second pass: Extract tags and create relationships between notes with shared tags
+        // Second pass: Link notes now that every link target exists in the graph
+        let resolver = NoteResolver::from_paths(vault_path, &files);
+        for note in &notes {
+            let links = Self::resolve_links(note, &note.content, &resolver);
+            if let Err(e) = Self::sync_links(note, links, vault_path, &self.graph_manager).await {
+                super::debug_logger::debug_log(&format!("❌ Failed to sync links for '{}': {}", note.title, e));
+            }
+        }
+        
+        // Third pass: Extract tags and create relationships between notes with shared tags
         super::debug_logger::debug_log("📌 EXTRACTING TAGS AND CREATING TAG-BASED RELATIONSHIPS");
         super::debug_logger::debug_log("=========================================================");
         
@@ -159,14 +197,9 @@ impl GraphSyncService {
         
         for note in &notes {
             // Extract tags from the note
-            let tag_regex = regex::Regex::new(r"#([a-zA-Z0-9_/\-]+)").unwrap();
-            let mut note_tags = Vec::new();
-            for cap in tag_regex.captures_iter(&note.content) {
-                if let Some(tag_text) = cap.get(1) {
-                    let tag_name = tag_text.as_str().to_string();
-                    note_tags.push(tag_name.clone());
-                    tag_to_notes.entry(tag_name).or_insert_with(Vec::new).push(note);
-                }
+            let note_tags = parse_tags(&note.content);
+            for tag_name in &note_tags {
+                tag_to_notes.entry(tag_name.to_lowercase()).or_insert_with(Vec::new).push(note);
             }
             if !note_tags.is_empty() {
                 super::debug_logger::debug_log(&format!("Note '{}' has tags: {:?}", note.title, note_tags));
@@ -246,21 +279,37 @@ impl GraphSyncService {
         event: Event,
         graph_manager: &Arc<dyn GraphManagerTrait>,
         vault: &Arc<Vault>,
+        index: &VaultIndex,
         vault_id: &str,
     ) -> Result<(), String> {
         match event.kind {
             EventKind::Create(_) | EventKind::Modify(_) => {
+                // Covers files moving into .gaimplan/trash as well as ignored folders
+                let ignore = index.ignore();
                 for path in event.paths {
+                    if ignore.is_ignored(&path, false) {
+                        continue;
+                    }
                     if path.extension().and_then(|s| s.to_str()) == Some("md") {
-                        Self::sync_file(&path, graph_manager, vault, vault_id).await?;
+                        Self::sync_file(&path, graph_manager, vault, index, vault_id).await?;
                     }
                 }
             }
             EventKind::Remove(_) => {
+                let trash = crate::trash::Trash::new(vault.path());
                 for path in event.paths {
                     if path.extension().and_then(|s| s.to_str()) == Some("md") {
                         let note_id = Self::generate_note_id(&path, vault_id);
-                        graph_manager.delete_note(&note_id).await?;
+                        let relative = path.strip_prefix(vault.path())
+                            .map(|p| p.to_string_lossy().to_string())
+                            .unwrap_or_default();
+                        
+                        // Trashed notes keep their relationships until the trash is purged
+                        if trash.contains_original(&relative) {
+                            graph_manager.set_note_trashed(&note_id, true).await?;
+                        } else {
+                            graph_manager.delete_note(&note_id).await?;
+                        }
                     }
                 }
             }
@@ -274,9 +323,13 @@ impl GraphSyncService {
         path: &Path,
         graph_manager: &Arc<dyn GraphManagerTrait>,
         vault: &Arc<Vault>,
+        index: &VaultIndex,
         vault_id: &str,
     ) -> Result<(), String> {
         if let Ok(content) = std::fs::read_to_string(path) {
+            if crate::encryption::is_encrypted(&content) {
+                return Ok(());
+            }
             let note = Self::file_to_note_static(path, &content, vault_id, vault.path())?;
             
             // Check if note exists
@@ -289,49 +342,66 @@ impl GraphSyncService {
             // Extract and create relationships
             // Extract tags
             Self::extract_and_sync_tags(&note, &content, graph_manager).await?;
-            // Skip wikilinks for now
-            // Self::extract_and_sync_links(&note, &content, graph_manager).await?;
+            
+            // Resolved before syncing so the index is not locked during graph calls
+            let links = Self::resolve_links(&note, &content, index.links.lock().await.resolver());
+            Self::sync_links(&note, links, vault.path(), graph_manager).await?;
         }
         
         Ok(())
     }
     
-    async fn extract_and_sync_links(
+    /// Notes a note's wiki-links point at, as (link text, vault-relative path);
+    /// only links to other notes become graph edges
+    fn resolve_links(note: &Note, content: &str, resolver: &NoteResolver) -> Vec<(String, String)> {
+        let source = note.path.replace('\\', "/");
+        parse_wiki_links(content)
+            .into_iter()
+            .filter_map(|link| match resolver.resolve(&link.target, &source) {
+                Some(resolved) if is_markdown(&resolved) && resolved != source => Some((link.target, resolved)),
+                _ => None,
+            })
+            .collect()
+    }
+    
+    async fn sync_links(
         note: &Note,
-        content: &str,
+        links: Vec<(String, String)>,
+        vault_path: &Path,
         graph_manager: &Arc<dyn GraphManagerTrait>,
     ) -> Result<(), String> {
-        println!("Checking note '{}' for links...", note.title);
-        // Extract [[wiki-links]]
-        let link_regex = regex::Regex::new(r"\[\[([^\]]+)\]\]").unwrap();
+        eprintln!("Checking note '{}' for links...", note.title);
         
+        // Links are rebuilt from scratch so removed links don't linger in the graph
+        graph_manager.delete_outgoing_relationships(&note.id, "LINKS_TO").await?;
+        
+        let mut linked_ids = std::collections::HashSet::new();
         let mut link_count = 0;
-        for cap in link_regex.captures_iter(content) {
-            if let Some(link_text) = cap.get(1) {
-                let link_name = link_text.as_str();
-                
-                // Try to find the linked note
-                // For now, we'll create a placeholder relationship
-                let rel = super::Relationship {
-                    from_id: note.id.clone(),
-                    to_id: format!("placeholder_{}", link_name), // TODO: Resolve to actual note ID
-                    rel_type: "LINKS_TO".to_string(),
-                    properties: serde_json::json!({
-                        "link_text": link_name,
-                    }),
-                };
-                
-                match graph_manager.create_relationship(&rel).await {
-                    Ok(_) => {
-                        println!("Created LINK relationship: {} -> {} (link: {})", note.title, rel.to_id, link_name);
-                        link_count += 1;
-                    },
-                    Err(e) => eprintln!("Failed to create link relationship: {}", e),
-                }
+        for (link_text, resolved) in links {
+            let to_id = Self::generate_note_id(&vault_path.join(&resolved), &note.vault_id);
+            if !linked_ids.insert(to_id.clone()) {
+                continue;
+            }
+            
+            let rel = super::Relationship {
+                from_id: note.id.clone(),
+                to_id,
+                rel_type: "LINKS_TO".to_string(),
+                properties: serde_json::json!({
+                    "link_text": link_text,
+                }),
+            };
+            
+            match graph_manager.create_relationship(&rel).await {
+                Ok(_) => {
+                    eprintln!("Created LINK relationship: {} -> {}", note.title, resolved);
+                    link_count += 1;
+                },
+                Err(e) => eprintln!("Failed to create link relationship: {}", e),
             }
         }
         
-        println!("Found {} links in note: {}", link_count, note.title);
+        eprintln!("Found {} links in note: {}", link_count, note.title);
         
         Ok(())
     }
@@ -341,30 +411,23 @@ impl GraphSyncService {
         content: &str,
         graph_manager: &Arc<dyn GraphManagerTrait>,
     ) -> Result<(), String> {
-        println!("Checking note '{}' for tags...", note.title);
-        // Extract #tags
-        let tag_regex = regex::Regex::new(r"#([a-zA-Z0-9_/\-]+)").unwrap();
-        
-        let mut tag_count = 0;
-        for cap in tag_regex.captures_iter(content) {
-            if let Some(tag_text) = cap.get(1) {
-                let tag_name = tag_text.as_str();
-                
-                // Create tag relationship with vault-specific ID
-                let rel = super::Relationship {
-                    from_id: note.id.clone(),
-                    to_id: format!("tag_{}_{}", note.vault_id, tag_name),
-                    rel_type: "TAGGED_WITH".to_string(),
-                    properties: serde_json::json!({
-                        "tag_name": tag_name,
-                        "vault_id": note.vault_id,
-                    }),
-                };
-                
-                match graph_manager.create_relationship(&rel).await {
-                    Ok(_) => println!("Created relationship: {} -> {}", note.id, rel.to_id),
-                    Err(e) => eprintln!("Failed to create relationship: {}", e),
-                }
+        eprintln!("Checking note '{}' for tags...", note.title);
+        // Extract #tags and frontmatter tags
+        for tag_name in parse_tags(content) {
+            // Create tag relationship with vault-specific ID
+            let rel = super::Relationship {
+                from_id: note.id.clone(),
+                to_id: format!("tag_{}_{}", note.vault_id, tag_name),
+                rel_type: "TAGGED_WITH".to_string(),
+                properties: serde_json::json!({
+                    "tag_name": tag_name,
+                    "vault_id": note.vault_id,
+                }),
+            };
+            
+            match graph_manager.create_relationship(&rel).await {
+                Ok(_) => eprintln!("Created relationship: {} -> {}", note.id, rel.to_id),
+                Err(e) => eprintln!("Failed to create relationship: {}", e),
             }
         }
         
@@ -384,11 +447,14 @@ impl GraphSyncService {
         let relative_path = path.strip_prefix(vault_path)
             .map_err(|_| "Failed to get relative path")?;
         
-        let title = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("Untitled")
-            .to_string();
+        // A frontmatter `title:` wins over the file name
+        let properties = NoteProperties::from_content(content);
+        let title = properties.title.clone().unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Untitled")
+                .to_string()
+        });
         
         let metadata = std::fs::metadata(path)
             .map_err(|e| format!("Failed to get file metadata: {}", e))?;
@@ -414,13 +480,14 @@ impl GraphSyncService {
             path: relative_path.to_string_lossy().to_string(),
             title,
             content: content.to_string(),
-            created: created.with_timezone(&Utc),
+            created: properties.created.unwrap_or(created.with_timezone(&Utc)),
             modified: modified.with_timezone(&Utc),
             vault_id: vault_id.to_string(),
+            properties,
         })
     }
     
-    fn generate_note_id(path: &Path, vault_id: &str) -> String {
+    pub fn generate_note_id(path: &Path, vault_id: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(vault_id.as_bytes());
         hasher.update(path.to_string_lossy().as_bytes());
@@ -450,6 +517,11 @@ pub async fn sync_single_file(
     let content = std::fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
     
+    // Encrypted notes never reach the graph
+    if crate::encryption::is_encrypted(&content) {
+        return Ok(());
+    }
+    
     // Create note from file
     let note = GraphSyncService::file_to_note_static(file_path, &content, &vault_id, vault_path)?;
     
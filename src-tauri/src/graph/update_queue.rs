@@ -4,8 +4,8 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio::time;
-use sha2::{Sha256, Digest};
 
+use crate::vault_ignore::VaultIgnore;
 use super::GraphManagerTrait;
 use super::sync::sync_single_file;
 use super::metrics::{MetricsTracker, QueuePerformanceMonitor};
@@ -88,7 +88,12 @@ impl UpdateQueue {
     }
 
     /// Adds an update to the queue, applying debouncing logic
-    pub async fn add_update(&self, file_path: PathBuf, vault_path: PathBuf, content: &str) -> Result<(), String> {
+    pub async fn add_update(&self, file_path: PathBuf, vault_path: PathBuf, content: &str, ignore: &VaultIgnore) -> Result<(), String> {
+        // Ignored files and encrypted notes never reach the graph
+        if ignore.is_ignored(&file_path, false) || crate::encryption::is_encrypted(content) {
+            return Ok(());
+        }
+        
         let file_size = content.len();
         let content_hash = Self::calculate_content_hash(content);
         
@@ -242,7 +247,7 @@ impl UpdateQueue {
         }
         
         let elapsed = start_time.elapsed();
-        println!(
+        eprintln!(
             "📊 Neo4j sync: {} files (✅ {}, ❌ {}) in {:.2}s", 
             batch_count, success_count, error_count, elapsed.as_secs_f64()
         );
@@ -250,9 +255,7 @@ impl UpdateQueue {
     
     /// Calculates a hash of the content for change detection
     fn calculate_content_hash(content: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(content.as_bytes());
-        format!("{:x}", hasher.finalize())
+        crate::vault::content_hash(content)
     }
     
     /// Gets the current queue size
@@ -0,0 +1,363 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::vault::{content_hash, write_atomic, write_lock, INTERNAL_DIR};
+
+const HISTORY_DIR: &str = "history";
+const OBJECTS_DIR: &str = "objects";
+const NOTES_DIR: &str = "notes";
+
+/// Largest line-count product diffed line by line; bigger inputs are shown as a full replacement
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// One saved version of a note
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NoteVersion {
+    pub id: String,
+    /// SHA-256 of the content, also the name of its object file
+    pub hash: String,
+    pub saved_at: DateTime<Utc>,
+    pub size: usize,
+}
+
+/// How many versions of each note to keep
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryRetention {
+    /// Versions kept per note (0 keeps all)
+    pub max_versions: u32,
+    /// Days a version is kept (0 keeps them forever)
+    pub retention_days: u32,
+}
+
+/// Versions of a single note, oldest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    path: String,
+    versions: Vec<NoteVersion>,
+}
+
+/// Vault-local version history in `.gaimplan/history`. Contents are stored
+/// once per distinct hash in `objects/`; `notes/` holds a manifest per note
+/// listing which objects make up its versions. Changes to the history hold a
+/// lock on its folder, so a manifest is never updated while objects are
+/// collected.
+pub struct NoteHistory {
+    vault_path: PathBuf,
+}
+
+impl NoteHistory {
+    pub fn new(vault_path: &Path) -> Self {
+        Self {
+            vault_path: vault_path.to_path_buf(),
+        }
+    }
+
+    fn history_dir(&self) -> PathBuf {
+        self.vault_path.join(INTERNAL_DIR).join(HISTORY_DIR)
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.history_dir().join(OBJECTS_DIR).join(hash)
+    }
+
+    fn manifest_path(&self, relative_path: &str) -> PathBuf {
+        let key = &content_hash(relative_path)[..16];
+        self.history_dir().join(NOTES_DIR).join(format!("{}.json", key))
+    }
+
+    /// Records `content` as the newest version of a note. Returns `None` when
+    /// it matches the newest version already stored. Objects of pruned
+    /// versions stay until the next `collect_garbage`.
+    pub fn record(&self, relative_path: &str, content: &str, retention: HistoryRetention) -> Result<Option<NoteVersion>, String> {
+        let lock = write_lock(&self.history_dir());
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let relative_path = relative_path.replace('\\', "/");
+        let mut manifest = self.load_manifest(&relative_path)?;
+        let hash = content_hash(content);
+        if manifest.versions.last().is_some_and(|v| v.hash == hash) {
+            return Ok(None);
+        }
+
+        let object_path = self.object_path(&hash);
+        if !object_path.exists() {
+            fs::create_dir_all(self.history_dir().join(OBJECTS_DIR))
+                .map_err(|e| format!("Failed to create history folder: {}", e))?;
+            write_atomic(&object_path, content.as_bytes())
+                .map_err(|e| format!("Failed to store version: {}", e))?;
+        }
+
+        let saved_at = Utc::now();
+        let mut id = format!("{}-{}", saved_at.format("%Y%m%d%H%M%S%3f"), &hash[..8]);
+        if manifest.versions.iter().any(|v| v.id == id) {
+            id = format!("{}-{}", id, manifest.versions.len());
+        }
+        let version = NoteVersion { id, hash, saved_at, size: content.len() };
+        manifest.path = relative_path;
+        manifest.versions.push(version.clone());
+
+        prune(&mut manifest.versions, retention);
+        self.save_manifest(&manifest)?;
+        Ok(Some(version))
+    }
+
+    /// Lists the versions of a note, newest first
+    pub fn list(&self, relative_path: &str) -> Result<Vec<NoteVersion>, String> {
+        let mut versions = self.load_manifest(&relative_path.replace('\\', "/"))?.versions;
+        versions.reverse();
+        Ok(versions)
+    }
+
+    /// Reads the content of one version of a note
+    pub fn read_version(&self, relative_path: &str, version_id: &str) -> Result<String, String> {
+        let version = self.list(relative_path)?
+            .into_iter()
+            .find(|v| v.id == version_id)
+            .ok_or_else(|| format!("Version not found: {}", version_id))?;
+        fs::read_to_string(self.object_path(&version.hash))
+            .map_err(|e| format!("Failed to read version {}: {}", version_id, e))
+    }
+
+    /// Carries a note's history over to its new path after a rename or move
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        let lock = write_lock(&self.history_dir());
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let old_manifest_path = self.manifest_path(from);
+        if !old_manifest_path.exists() {
+            return Ok(());
+        }
+
+        let mut manifest = self.load_manifest(from)?;
+        let mut existing = self.load_manifest(to)?;
+        existing.versions.append(&mut manifest.versions);
+        existing.versions.sort_by_key(|v| v.saved_at);
+        existing.path = to.to_string();
+
+        self.save_manifest(&existing)?;
+        fs::remove_file(&old_manifest_path)
+            .map_err(|e| format!("Failed to move history of {}: {}", from, e))
+    }
+
+    /// Deletes every stored version of a note
+    pub fn forget(&self, relative_path: &str) -> Result<(), String> {
+        let manifest_path = self.manifest_path(&relative_path.replace('\\', "/"));
+        if !manifest_path.exists() {
+            return Ok(());
+        }
+        {
+            let lock = write_lock(&self.history_dir());
+            let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+            fs::remove_file(&manifest_path)
+                .map_err(|e| format!("Failed to delete history of {}: {}", relative_path, e))?;
+        }
+        self.collect_garbage()
+    }
+
+    fn load_manifest(&self, relative_path: &str) -> Result<Manifest, String> {
+        let manifest_path = self.manifest_path(relative_path);
+        if !manifest_path.exists() {
+            return Ok(Manifest { path: relative_path.to_string(), versions: Vec::new() });
+        }
+
+        let json = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid history manifest: {}", e))
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<(), String> {
+        let manifest_path = self.manifest_path(&manifest.path);
+        if let Some(parent) = manifest_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create history folder: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+        write_atomic(&manifest_path, json.as_bytes())
+            .map_err(|e| format!("Failed to write history: {}", e))
+    }
+
+    /// Deletes objects no manifest refers to any more
+    pub fn collect_garbage(&self) -> Result<(), String> {
+        let lock = write_lock(&self.history_dir());
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let notes_dir = self.history_dir().join(NOTES_DIR);
+        if !notes_dir.exists() {
+            return Ok(());
+        }
+        let mut referenced = HashSet::new();
+        for entry in fs::read_dir(&notes_dir).map_err(|e| format!("Failed to read history: {}", e))?.flatten() {
+            let manifest: Manifest = match fs::read_to_string(entry.path()).ok().and_then(|j| serde_json::from_str(&j).ok()) {
+                Some(manifest) => manifest,
+                // Keep everything rather than lose versions of a manifest we cannot read
+                None => return Ok(()),
+            };
+            referenced.extend(manifest.versions.into_iter().map(|v| v.hash));
+        }
+
+        let objects_dir = self.history_dir().join(OBJECTS_DIR);
+        for entry in fs::read_dir(&objects_dir).map_err(|e| format!("Failed to read history: {}", e))?.flatten() {
+            let is_referenced = entry.file_name().to_str().is_some_and(|name| referenced.contains(name));
+            if !is_referenced {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Drops versions past the retention limits; the newest version is always kept
+fn prune(versions: &mut Vec<NoteVersion>, retention: HistoryRetention) {
+    if retention.retention_days > 0 {
+        let cutoff = Utc::now() - Duration::days(retention.retention_days as i64);
+        let newest = versions.len().saturating_sub(1);
+        let mut index = 0;
+        versions.retain(|v| {
+            let keep = index == newest || v.saved_at >= cutoff;
+            index += 1;
+            keep
+        });
+    }
+    if retention.max_versions > 0 && versions.len() > retention.max_versions as usize {
+        let excess = versions.len() - retention.max_versions as usize;
+        versions.drain(..excess);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Equal,
+    Added,
+    Removed,
+}
+
+/// One line of a diff with its 1-based line numbers on each side
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+}
+
+/// Line diff from `old` to `new` based on their longest common subsequence
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut lines = Vec::with_capacity(a.len().max(b.len()));
+    let (mut old_line, mut new_line) = (0, 0);
+    let mut push = |kind: DiffKind, text: &str| {
+        let old_no = if kind != DiffKind::Added { old_line += 1; Some(old_line) } else { None };
+        let new_no = if kind != DiffKind::Removed { new_line += 1; Some(new_line) } else { None };
+        lines.push(DiffLine { kind, text: text.to_string(), old_line: old_no, new_line: new_no });
+    };
+
+    for line in &a[..prefix] {
+        push(DiffKind::Equal, line);
+    }
+
+    let (n, m) = (a_mid.len(), b_mid.len());
+    if n * m > MAX_DIFF_CELLS {
+        a_mid.iter().for_each(|line| push(DiffKind::Removed, line));
+        b_mid.iter().for_each(|line| push(DiffKind::Added, line));
+    } else {
+        // lcs[i * (m + 1) + j] is the LCS length of a_mid[i..] and b_mid[j..]
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if a_mid[i] == b_mid[j] {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && a_mid[i] == b_mid[j] {
+                push(DiffKind::Equal, a_mid[i]);
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+                push(DiffKind::Removed, a_mid[i]);
+                i += 1;
+            } else {
+                push(DiffKind::Added, b_mid[j]);
+                j += 1;
+            }
+        }
+    }
+
+    for line in &a[a.len() - suffix..] {
+        push(DiffKind::Equal, line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEEP_ALL: HistoryRetention = HistoryRetention { max_versions: 0, retention_days: 0 };
+
+    #[test]
+    fn test_record_dedupes_and_prunes_versions() {
+        let vault = std::env::temp_dir().join(format!("gaimplan-history-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&vault).unwrap();
+        let history = NoteHistory::new(&vault);
+
+        let first = history.record("notes/a.md", "one", KEEP_ALL).unwrap().unwrap();
+        assert!(history.record("notes/a.md", "one", KEEP_ALL).unwrap().is_none());
+        history.record("notes/a.md", "two", KEEP_ALL).unwrap().unwrap();
+        // Same content in another note shares the object
+        history.record("b.md", "one", KEEP_ALL).unwrap().unwrap();
+
+        let versions = history.list("notes/a.md").unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(history.read_version("notes/a.md", &first.id).unwrap(), "one");
+
+        let keep_two = HistoryRetention { max_versions: 2, retention_days: 0 };
+        history.record("notes/a.md", "three", keep_two).unwrap().unwrap();
+        let versions = history.list("notes/a.md").unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(history.read_version("notes/a.md", &first.id).is_err());
+        // "one" is still referenced by b.md, so its object survives garbage collection
+        history.collect_garbage().unwrap();
+        assert!(history.object_path(&first.hash).exists());
+        history.forget("b.md").unwrap();
+        assert!(!history.object_path(&first.hash).exists());
+
+        history.rename("notes/a.md", "moved/a.md").unwrap();
+        assert!(history.list("notes/a.md").unwrap().is_empty());
+        assert_eq!(history.list("moved/a.md").unwrap(), versions);
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("a\nb\nc\nd", "a\nc\nx\nd");
+        let summary: Vec<(DiffKind, &str)> = diff.iter().map(|l| (l.kind, l.text.as_str())).collect();
+        assert_eq!(summary, vec![
+            (DiffKind::Equal, "a"),
+            (DiffKind::Removed, "b"),
+            (DiffKind::Equal, "c"),
+            (DiffKind::Added, "x"),
+            (DiffKind::Equal, "d"),
+        ]);
+        assert_eq!((diff[3].old_line, diff[3].new_line), (None, Some(3)));
+        assert_eq!((diff[4].old_line, diff[4].new_line), (Some(4), Some(4)));
+
+        assert!(diff_lines("same", "same").iter().all(|l| l.kind == DiffKind::Equal));
+    }
+}
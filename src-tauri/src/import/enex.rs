@@ -0,0 +1,232 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use md5::{Digest, Md5};
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+
+use crate::index::attachments::IMAGE_TYPES;
+
+lazy_static::lazy_static! {
+    static ref EN_MEDIA: Regex = Regex::new(r"(?s)<en-media\b([^>]*?)/?>(?:\s*</en-media>)?").unwrap();
+    static ref EN_TODO: Regex = Regex::new(r"(?s)<en-todo\b([^>]*?)/?>(?:\s*</en-todo>)?").unwrap();
+    static ref EN_CRYPT: Regex = Regex::new(r"(?s)<en-crypt\b.*?</en-crypt>").unwrap();
+    static ref HASH: Regex = Regex::new(r#"\bhash="([0-9a-fA-F]+)""#).unwrap();
+    static ref CHECKED: Regex = Regex::new(r#"\bchecked="true""#).unwrap();
+}
+
+/// Image sources `en-media` elements are rewritten to before the note is
+/// converted: `en-media/<hash>`
+pub const MEDIA_PREFIX: &str = "en-media/";
+
+/// Stand-ins for checkboxes while the note is converted, so their brackets
+/// are not escaped as text
+const TODO_DONE: &str = "\u{e000}";
+const TODO_OPEN: &str = "\u{e001}";
+
+/// A file attached to an Evernote note
+#[derive(Debug, Clone)]
+pub struct Resource {
+    pub data: Vec<u8>,
+    pub mime: String,
+    pub file_name: Option<String>,
+    /// MD5 of the data, which `en-media` elements refer to
+    pub hash: String,
+}
+
+impl Resource {
+    /// The attachment's file name, or one made from the note title and MIME type
+    pub fn name(&self, note_title: &str) -> String {
+        let sanitized = self.file_name.as_deref()
+            .map(crate::index::attachments::sanitize_file_name)
+            .filter(|name| !name.is_empty());
+        if let Some(name) = sanitized {
+            return name;
+        }
+        let extension = if self.mime == "application/pdf" {
+            "pdf"
+        } else {
+            IMAGE_TYPES.iter().find(|(_, mime)| *mime == self.mime).map(|(ext, _)| *ext).unwrap_or("bin")
+        };
+        format!("{}.{}", note_title, extension)
+    }
+}
+
+/// A note from an `.enex` export
+#[derive(Debug, Clone)]
+pub struct EnexNote {
+    pub title: String,
+    /// The note body in ENML, Evernote's XHTML dialect
+    pub content: String,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub source_url: Option<String>,
+    pub resources: Vec<Resource>,
+}
+
+impl EnexNote {
+    /// The note as markdown with its tags, timestamps and source URL in the
+    /// frontmatter. `link_for` maps `en-media/<hash>` image sources to the
+    /// wiki-link target of the saved resource.
+    pub fn to_markdown(&self, link_for: &dyn Fn(&str) -> Option<String>) -> String {
+        let body = super::html::to_markdown(&prepare_enml(&self.content), link_for)
+            .replace(TODO_DONE, "- [x] ")
+            .replace(TODO_OPEN, "- [ ] ");
+
+        let mut properties = Mapping::new();
+        if !self.tags.is_empty() {
+            let tags = self.tags.iter().map(|tag| Value::String(tag.clone())).collect();
+            properties.insert(Value::String("tags".into()), Value::Sequence(tags));
+        }
+        let timestamp = |time: &DateTime<Utc>| Value::String(time.to_rfc3339_opts(SecondsFormat::Secs, true));
+        if let Some(created) = &self.created {
+            properties.insert(Value::String("created".into()), timestamp(created));
+        }
+        if let Some(updated) = &self.updated {
+            properties.insert(Value::String("updated".into()), timestamp(updated));
+        }
+        if let Some(url) = &self.source_url {
+            properties.insert(Value::String("source".into()), Value::String(url.clone()));
+        }
+        if properties.is_empty() {
+            return body;
+        }
+
+        let yaml = serde_yaml::to_string(&properties).unwrap_or_default();
+        format!("---\n{}---\n{}", yaml, body)
+    }
+}
+
+/// Turns the Evernote-specific elements of ENML into plain HTML: media into
+/// images pointing at `en-media/<hash>`, checkboxes into task markers, and
+/// encrypted sections into a placeholder
+fn prepare_enml(enml: &str) -> String {
+    let html = EN_MEDIA.replace_all(enml, |captures: &regex::Captures| {
+        match HASH.captures(&captures[1]) {
+            Some(hash) => format!(r#"<img src="{}{}">"#, MEDIA_PREFIX, hash[1].to_lowercase()),
+            None => String::new(),
+        }
+    });
+    let html = EN_TODO.replace_all(&html, |captures: &regex::Captures| {
+        if CHECKED.is_match(&captures[1]) { TODO_DONE } else { TODO_OPEN }
+    });
+    EN_CRYPT.replace_all(&html, "<p><em>Encrypted content was not imported</em></p>").into_owned()
+}
+
+/// Evernote writes times as `20240501T093000Z`
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value.trim(), "%Y%m%dT%H%M%SZ").ok().map(|time| time.and_utc())
+}
+
+fn text_of(node: roxmltree::Node) -> String {
+    node.descendants().filter(|n| n.is_text()).filter_map(|n| n.text()).collect()
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    node.children().find(|n| n.has_tag_name(name)).map(text_of)
+}
+
+/// Reads the notes of an `.enex` export
+pub fn parse(xml: &str) -> Result<Vec<EnexNote>, String> {
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let document = roxmltree::Document::parse_with_options(xml, options)
+        .map_err(|e| format!("Invalid ENEX file: {}", e))?;
+    if !document.root_element().has_tag_name("en-export") {
+        return Err("Invalid ENEX file: missing en-export element".to_string());
+    }
+
+    let mut notes = Vec::new();
+    for note in document.root_element().children().filter(|n| n.has_tag_name("note")) {
+        let mut resources = Vec::new();
+        for resource in note.children().filter(|n| n.has_tag_name("resource")) {
+            let encoded: String = child_text(resource, "data")
+                .unwrap_or_default()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            let data = general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("Invalid resource data: {}", e))?;
+            let file_name = resource
+                .children()
+                .find(|n| n.has_tag_name("resource-attributes"))
+                .and_then(|attributes| child_text(attributes, "file-name"));
+            let hash = format!("{:x}", Md5::digest(&data));
+            resources.push(Resource {
+                data,
+                mime: child_text(resource, "mime").unwrap_or_default().trim().to_string(),
+                file_name,
+                hash,
+            });
+        }
+
+        let source_url = note
+            .children()
+            .find(|n| n.has_tag_name("note-attributes"))
+            .and_then(|attributes| child_text(attributes, "source-url"))
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+
+        notes.push(EnexNote {
+            title: child_text(note, "title").map(|t| t.trim().to_string()).unwrap_or_default(),
+            content: child_text(note, "content").unwrap_or_default(),
+            created: child_text(note, "created").as_deref().and_then(parse_time),
+            updated: child_text(note, "updated").as_deref().and_then(parse_time),
+            tags: note
+                .children()
+                .filter(|n| n.has_tag_name("tag"))
+                .map(|tag| text_of(tag).trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            source_url,
+            resources,
+        });
+    }
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_enex_note() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export4.dtd">
+<en-export export-date="20240601T080000Z" application="Evernote">
+  <note>
+    <title>Groceries</title>
+    <created>20240501T093000Z</created>
+    <updated>20240502T101500Z</updated>
+    <tag>home</tag>
+    <tag>lists</tag>
+    <note-attributes><source-url>https://example.com</source-url></note-attributes>
+    <content><![CDATA[<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-note SYSTEM "http://xml.evernote.com/pub/enml2.dtd">
+<en-note><div><en-todo checked="true"/>Milk</div><div><en-todo/>Eggs</div>
+<div><en-media type="image/png" hash="8D777F385D3DFEC8815D20F7496026DC"/></div></en-note>]]></content>
+    <resource>
+      <data encoding="base64">ZGF0
+YQ==</data>
+      <mime>image/png</mime>
+    </resource>
+  </note>
+</en-export>"#;
+
+        let notes = parse(xml).unwrap();
+        assert_eq!(notes.len(), 1);
+        let note = &notes[0];
+        assert_eq!(note.resources[0].data, b"data");
+        assert_eq!(note.resources[0].hash, "8d777f385d3dfec8815d20f7496026dc");
+        assert_eq!(note.resources[0].name(&note.title), "Groceries.png");
+
+        let link_for = |src: &str| {
+            (src.strip_prefix(MEDIA_PREFIX) == Some(note.resources[0].hash.as_str())).then(|| "Groceries.png".to_string())
+        };
+        assert_eq!(
+            note.to_markdown(&link_for),
+            "---\ntags:\n- home\n- lists\ncreated: 2024-05-01T09:30:00Z\nupdated: 2024-05-02T10:15:00Z\n\
+             source: https://example.com\n---\n- [x] Milk\n- [ ] Eggs\n![[Groceries.png]]\n"
+        );
+    }
+}
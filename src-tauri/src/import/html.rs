@@ -0,0 +1,429 @@
+use std::collections::HashSet;
+use regex::Regex;
+
+lazy_static::lazy_static! {
+    static ref TITLE: Regex = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    static ref ATTRIBUTE: Regex = Regex::new(r#"([a-zA-Z_:][-a-zA-Z0-9_:.]*)="([^"]*)""#).unwrap();
+    static ref WHITESPACE: Regex = Regex::new(r"\s+").unwrap();
+    static ref BLANK_LINES: Regex = Regex::new(r"\n{3,}").unwrap();
+}
+
+/// The `<title>` of an HTML document
+pub fn document_title(html: &str) -> Option<String> {
+    TITLE.captures(html)
+        .map(|captures| decode_entities(captures[1].trim()))
+        .filter(|title| !title.is_empty())
+}
+
+/// Strips scripts, styles, event handlers and everything else that is not
+/// plain formatting, leaving well-formed HTML
+pub fn sanitize(html: &str) -> String {
+    let schemes: HashSet<&str> = ["http", "https", "mailto", "data"].into_iter().collect();
+    let clean_content: HashSet<&str> = ["script", "style", "title"].into_iter().collect();
+    ammonia::Builder::default()
+        .url_schemes(schemes)
+        .clean_content_tags(clean_content)
+        .link_rel(None)
+        .clean(html)
+        .to_string()
+}
+
+/// Converts HTML to markdown. `link_for` maps an image source or link
+/// destination to the wiki-link target of a file in the vault, if it is one.
+pub fn to_markdown(html: &str, link_for: &dyn Fn(&str) -> Option<String>) -> String {
+    let clean = sanitize(html);
+    let mut renderer = Renderer {
+        buffers: vec![String::new()],
+        frames: Vec::new(),
+        lists: Vec::new(),
+        tables: Vec::new(),
+        pre: 0,
+        code: false,
+        link_for,
+    };
+
+    let mut rest = clean.as_str();
+    while let Some(start) = rest.find('<') {
+        renderer.text(&rest[..start]);
+        let end = match tag_end(&rest[start..]) {
+            Some(end) => start + end,
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        };
+        renderer.tag(&rest[start + 1..end]);
+        rest = &rest[end + 1..];
+    }
+    renderer.text(rest);
+
+    let markdown = renderer.buffers.concat();
+    let markdown: Vec<&str> = markdown.lines().map(str::trim_end).collect();
+    let markdown = BLANK_LINES.replace_all(&markdown.join("\n"), "\n\n").trim().to_string();
+    if markdown.is_empty() {
+        markdown
+    } else {
+        format!("{}\n", markdown)
+    }
+}
+
+/// Position of the `>` closing the tag at the start of `html`; attribute
+/// values may contain `>`
+fn tag_end(html: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in html.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '>' if !quoted => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest.find(';').filter(|end| *end <= 10).map(|end| &rest[1..end]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        });
+        match (entity, character) {
+            (Some(entity), Some(c)) => {
+                decoded.push(if c == '\u{a0}' { ' ' } else { c });
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Backslash-escapes the characters that would turn plain text into markdown
+/// emphasis, links or code
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Elements whose content is rendered on its own before being placed
+enum Frame {
+    Link(String),
+    Quote,
+    Cell,
+}
+
+struct Renderer<'a> {
+    buffers: Vec<String>,
+    frames: Vec<Frame>,
+    /// Open lists: the next number for ordered lists, `None` for bullets
+    lists: Vec<Option<usize>>,
+    /// Rows of cells of the open tables
+    tables: Vec<Vec<Vec<String>>>,
+    pre: usize,
+    /// Inside an inline code span, where text is not escaped
+    code: bool,
+    link_for: &'a dyn Fn(&str) -> Option<String>,
+}
+
+impl Renderer<'_> {
+    fn out(&mut self) -> &mut String {
+        self.buffers.last_mut().expect("renderer always has a buffer")
+    }
+
+    /// Indentation of the content of the innermost list item
+    fn indent(&self) -> String {
+        self.lists.iter().map(|list| if list.is_some() { "   " } else { "  " }).collect()
+    }
+
+    fn push(&mut self, text: &str) {
+        let indent = self.indent();
+        let out = self.out();
+        if out.ends_with('\n') && !indent.is_empty() {
+            out.push_str(&indent);
+        }
+        out.push_str(text);
+    }
+
+    /// Starts a new line, or a new paragraph with `blank_line`
+    fn block(&mut self, blank_line: bool) {
+        let newlines = if blank_line && self.lists.is_empty() { 2 } else { 1 };
+        let out = self.out();
+        let trimmed = out.trim_end_matches([' ', '\t']).len();
+        out.truncate(trimmed);
+        if out.is_empty() {
+            return;
+        }
+        let existing = out.len() - out.trim_end_matches('\n').len();
+        for _ in existing..newlines {
+            out.push('\n');
+        }
+    }
+
+    fn text(&mut self, raw: &str) {
+        if raw.is_empty() {
+            return;
+        }
+        let text = decode_entities(raw);
+        if self.pre > 0 {
+            self.out().push_str(&text);
+            return;
+        }
+        let collapsed = WHITESPACE.replace_all(&text, " ");
+        let out = self.out();
+        let at_line_start = out.is_empty() || out.ends_with(['\n', ' ']);
+        let collapsed = if at_line_start { collapsed.trim_start() } else { &collapsed };
+        if !collapsed.is_empty() {
+            let collapsed = if self.code { collapsed.to_string() } else { escape_markdown(collapsed) };
+            self.push(&collapsed);
+        }
+    }
+
+    fn tag(&mut self, tag: &str) {
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let name_end = tag.find(|c: char| c.is_whitespace() || c == '/').unwrap_or(tag.len());
+        let name = tag[..name_end].to_lowercase();
+        let attribute = |key: &str| {
+            ATTRIBUTE.captures_iter(&tag[name_end..])
+                .find(|captures| captures[1].eq_ignore_ascii_case(key))
+                .map(|captures| decode_entities(&captures[2]))
+                .unwrap_or_default()
+        };
+
+        match (name.as_str(), closing) {
+            ("p", _) | ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => self.block(true),
+            ("div" | "section" | "article" | "header" | "footer" | "figure" | "dl" | "dt" | "dd", _) => self.block(false),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                self.block(true);
+                let level = name[1..].parse().unwrap_or(1);
+                self.push(&format!("{} ", "#".repeat(level)));
+            }
+            ("br", _) => {
+                if matches!(self.frames.last(), Some(Frame::Cell)) {
+                    self.out().push_str("<br>");
+                } else {
+                    self.out().push('\n');
+                }
+            }
+            ("hr", _) => {
+                self.block(true);
+                self.push("---");
+                self.block(true);
+            }
+            ("ul", false) => {
+                self.block(true);
+                self.lists.push(None);
+            }
+            ("ol", false) => {
+                self.block(true);
+                let start = attribute("start").parse().unwrap_or(1);
+                self.lists.push(Some(start));
+            }
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                self.block(true);
+            }
+            ("li", false) => {
+                self.block(false);
+                let marker = match self.lists.pop() {
+                    Some(Some(n)) => {
+                        self.lists.push(Some(n + 1));
+                        format!("{}. ", n)
+                    }
+                    Some(None) => {
+                        self.lists.push(None);
+                        "- ".to_string()
+                    }
+                    None => "- ".to_string(),
+                };
+                let indent = self.indent();
+                let outer = &indent[..indent.len().saturating_sub(marker.len().min(3))];
+                let out = self.out();
+                out.push_str(outer);
+                out.push_str(&marker);
+            }
+            ("li", true) => self.block(false),
+            ("pre", false) => {
+                self.block(true);
+                self.push("```\n");
+                self.pre += 1;
+            }
+            ("pre", true) => {
+                self.pre = self.pre.saturating_sub(1);
+                if !self.out().ends_with('\n') {
+                    self.out().push('\n');
+                }
+                self.push("```");
+                self.block(true);
+            }
+            ("code" | "kbd" | "samp" | "tt", _) if self.pre == 0 => {
+                self.code = !closing;
+                self.push("`");
+            }
+            ("strong" | "b", _) => self.push("**"),
+            ("em" | "i" | "cite" | "var", _) => self.push("*"),
+            ("s" | "del" | "strike", _) => self.push("~~"),
+            ("mark", _) => self.push("=="),
+            ("img", _) => {
+                let src = attribute("src");
+                if src.is_empty() {
+                    return;
+                }
+                let image = match (self.link_for)(&src) {
+                    Some(target) => format!("![[{}]]", target),
+                    None => format!("![{}]({})", attribute("alt").replace(['[', ']'], ""), src.replace(' ', "%20")),
+                };
+                self.push(&image);
+            }
+            ("a", false) => {
+                self.frames.push(Frame::Link(attribute("href")));
+                self.buffers.push(String::new());
+            }
+            ("blockquote", false) => {
+                self.block(true);
+                self.frames.push(Frame::Quote);
+                self.buffers.push(String::new());
+            }
+            ("table", false) => {
+                self.block(true);
+                self.tables.push(Vec::new());
+            }
+            ("tr", false) => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.push(Vec::new());
+                }
+            }
+            ("td" | "th", false) if !self.tables.is_empty() => {
+                self.frames.push(Frame::Cell);
+                self.buffers.push(String::new());
+            }
+            ("a" | "blockquote" | "td" | "th", true) => self.close_frame(),
+            ("table", true) => {
+                if let Some(rows) = self.tables.pop() {
+                    let table = render_table(rows);
+                    self.push(&table);
+                    self.block(true);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn close_frame(&mut self) {
+        let (frame, content) = match (self.frames.pop(), self.buffers.pop()) {
+            (Some(frame), Some(content)) => (frame, content),
+            (_, content) => {
+                self.buffers.push(content.unwrap_or_default());
+                return;
+            }
+        };
+        match frame {
+            Frame::Link(href) => {
+                let text = content.trim();
+                let link = match (self.link_for)(&href) {
+                    Some(target) if text.is_empty() || text == target => format!("[[{}]]", target),
+                    Some(target) => format!("[[{}|{}]]", target, text.replace(['|', '\n'], " ")),
+                    None if href.is_empty() || href.starts_with('#') || text == href => text.to_string(),
+                    None if text.is_empty() => format!("<{}>", href),
+                    None => format!("[{}]({})", text, href.replace(' ', "%20")),
+                };
+                self.push(&link);
+            }
+            Frame::Quote => {
+                let quoted: Vec<String> = content
+                    .trim()
+                    .lines()
+                    .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                    .collect();
+                self.push(&quoted.join("\n"));
+                self.block(true);
+            }
+            Frame::Cell => {
+                let cell = content.trim().replace('|', "\\|").replace('\n', "<br>");
+                if let Some(row) = self.tables.last_mut().and_then(|rows| rows.last_mut()) {
+                    row.push(cell);
+                }
+            }
+        }
+    }
+}
+
+/// A markdown table with the first row as its header
+fn render_table(rows: Vec<Vec<String>>) -> String {
+    let rows: Vec<Vec<String>> = rows.into_iter().filter(|row| !row.is_empty()).collect();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+    let line = |row: &Vec<String>| {
+        let cells: Vec<&str> = (0..columns).map(|i| row.get(i).map(String::as_str).unwrap_or("")).collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut table = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    table.extend(rows[1..].iter().map(line));
+    table.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_markdown() {
+        let html = r#"<html><head><title>Trip &amp; plans</title><style>p { color: red }</style></head>
+<body><h1>Trip</h1><p>Go <b>early</b> (*not* at 5_00 [sharp]) <code>a_b</code> and see <a href="https://example.com/a b">the site</a>.</p>
+<script>alert(1)</script>
+<ul><li>Pack</li><li>Book<ol><li>Hotel</li></ol></li></ul>
+<blockquote><p>Travel light</p></blockquote>
+<img src="pics/map.png" alt="map"><img src="https://example.com/x.png" alt="[x]">
+<table><tr><th>Day</th><th>Place</th></tr><tr><td>1</td><td>Rome | Ostia</td></tr></table>
+<pre>let x = 1;
+let y = 2;</pre><p><a href="Other.html">Other page</a></p></body></html>"#;
+
+        assert_eq!(document_title(html).as_deref(), Some("Trip & plans"));
+        let link_for = |src: &str| match src {
+            "pics/map.png" => Some("map.png".to_string()),
+            "Other.html" => Some("Other".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            to_markdown(html, &link_for),
+            "# Trip\n\nGo **early** (\\*not\\* at 5\\_00 \\[sharp\\]) `a_b` and see [the site](https://example.com/a%20b).\n\n\
+             - Pack\n- Book\n  1. Hotel\n\n> Travel light\n\n\
+             ![[map.png]]![x](https://example.com/x.png)\n\n\
+             | Day | Place |\n| --- | --- |\n| 1 | Rome \\| Ostia |\n\n\
+             ```\nlet x = 1;\nlet y = 2;\n```\n\n[[Other|Other page]]\n"
+        );
+    }
+}
@@ -0,0 +1,992 @@
+pub mod enex;
+pub mod html;
+pub mod notion;
+pub mod obsidian;
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::encryption::{conceal, UnlockedVault};
+use crate::index::attachments::{is_attachment, sanitize_file_name};
+use crate::index::links::parse_wiki_links;
+use crate::index::refactor::{relative_between, MovedFile};
+use crate::index::resolver::{is_markdown, normalize, parent_dir, relative_path, NoteResolver};
+use crate::index::scanner::{code_ranges, in_ranges, markdown_link_destinations};
+use crate::vault::{resolve_in_vault, write_atomic, Vault};
+
+/// Where an import comes from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportSource {
+    /// An Obsidian vault, with its `.obsidian` settings
+    Obsidian,
+    /// A Notion "Markdown & CSV" export
+    Notion,
+    /// Any other folder of markdown files
+    Markdown,
+    /// Evernote `.enex` exports
+    Evernote,
+    /// Saved web pages and other HTML files
+    Html,
+}
+
+impl ImportSource {
+    /// Guesses the kind of export from an (extracted) export folder or a
+    /// single `.enex`/`.html` file
+    pub fn detect(root: &Path) -> Self {
+        if root.is_file() {
+            let name = root.to_string_lossy();
+            return if is_enex(&name) { ImportSource::Evernote } else { ImportSource::Html };
+        }
+        if obsidian::is_obsidian_vault(root) {
+            return ImportSource::Obsidian;
+        }
+        let files = source_files(root);
+        if files.iter().any(|rel| is_enex(rel)) {
+            ImportSource::Evernote
+        } else if files.iter().any(|rel| rel.split('/').any(notion::has_notion_id)) {
+            ImportSource::Notion
+        } else if files.iter().any(|rel| is_html(rel)) && !files.iter().any(|rel| is_markdown(rel)) {
+            ImportSource::Html
+        } else {
+            ImportSource::Markdown
+        }
+    }
+
+    /// Evernote and HTML imports convert documents instead of copying files
+    fn converts_documents(self) -> bool {
+        matches!(self, ImportSource::Evernote | ImportSource::Html)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportOptions {
+    /// Export folder, `.zip` file, or a single `.enex`/`.html` file
+    pub path: PathBuf,
+    /// Kind of export; detected from its contents when omitted
+    #[serde(default)]
+    pub source: Option<ImportSource>,
+    /// Vault folder the notes go into ("" for the vault root)
+    #[serde(default)]
+    pub target_folder: String,
+}
+
+/// Settings read from the export, as vault-relative paths in the target vault
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ImportedSettings {
+    pub attachment_folder: Option<String>,
+    pub daily_notes_folder: Option<String>,
+    pub daily_note_format: Option<String>,
+    pub daily_note_template: Option<String>,
+    pub templates_folder: Option<String>,
+}
+
+impl ImportedSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Moves the folders and templates under the folder the notes are imported into
+    fn under(self, folder: &str) -> Self {
+        let prefix = |path: Option<String>| path.map(|p| join(folder, &p));
+        Self {
+            attachment_folder: prefix(self.attachment_folder),
+            daily_notes_folder: prefix(self.daily_notes_folder),
+            daily_note_format: self.daily_note_format,
+            daily_note_template: prefix(self.daily_note_template),
+            templates_folder: prefix(self.templates_folder),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnresolvedLink {
+    /// Vault-relative path of the imported note
+    pub note: String,
+    pub target: String,
+}
+
+/// What an import did
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub source: ImportSource,
+    pub notes_imported: usize,
+    pub attachments_copied: usize,
+    pub other_files_copied: usize,
+    pub links_rewritten: usize,
+    /// Files whose name changed: Notion ids removed, or a number added
+    /// because the vault already had a file with that name
+    pub renamed: Vec<MovedFile>,
+    pub skipped: Vec<SkippedFile>,
+    /// Links that point at nothing in the export or the vault
+    pub unresolved_links: Vec<UnresolvedLink>,
+    /// Settings found in the export, when any were applied
+    pub settings: Option<ImportedSettings>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileKind {
+    Note,
+    /// A Notion database, converted to a note with a table
+    Table,
+    Attachment,
+    Other,
+}
+
+/// An export ready to be imported. Zip files are extracted to a temporary
+/// folder that is removed again when the import is dropped.
+pub struct Import {
+    source: ImportSource,
+    root: PathBuf,
+    extracted: Option<PathBuf>,
+    target_folder: String,
+    settings: ImportedSettings,
+}
+
+impl Import {
+    pub fn prepare(options: &ImportOptions) -> Result<Self, String> {
+        let target_folder = normalize(&options.target_folder.replace('\\', "/"))
+            .ok_or_else(|| format!("Invalid target folder: {}", options.target_folder))?;
+
+        let (root, extracted) = if is_zip(&options.path) && options.path.is_file() {
+            let temp = std::env::temp_dir().join(format!("gaimplan-import-{}", uuid::Uuid::new_v4()));
+            if let Err(e) = extract_export(&options.path, &temp) {
+                let _ = fs::remove_dir_all(&temp);
+                return Err(e);
+            }
+            (single_folder(&temp), Some(temp))
+        } else if options.path.is_dir() || (options.path.is_file() && is_document(&options.path.to_string_lossy())) {
+            (options.path.clone(), None)
+        } else {
+            return Err("Import source must be a folder, a .zip file, or an .enex or .html file".to_string());
+        };
+
+        let source = options.source.unwrap_or_else(|| ImportSource::detect(&root));
+        if root.is_file() && !source.converts_documents() {
+            return Err(format!("A single file cannot be imported as {:?}", source));
+        }
+        let settings = match source {
+            ImportSource::Obsidian => obsidian::read_settings(&root).under(&target_folder),
+            _ => ImportedSettings::default(),
+        };
+
+        Ok(Self { source, root, extracted, target_folder, settings })
+    }
+
+    pub fn source(&self) -> ImportSource {
+        self.source
+    }
+
+    pub fn settings(&self) -> &ImportedSettings {
+        &self.settings
+    }
+
+    /// Copies the export into the vault: notes under the target folder,
+    /// attachments into `image_location`, with links rewritten to match. Notes
+    /// landing in an encrypted folder are encrypted, or skipped while the vault
+    /// is locked.
+    pub fn run(&self, vault: &Vault, image_location: &str, unlocked: Option<&UnlockedVault>) -> Result<ImportReport, String> {
+        let image_location = normalize(&image_location.replace('\\', "/"))
+            .ok_or_else(|| format!("Invalid image location: {}", image_location))?;
+
+        let mut report = ImportReport {
+            source: self.source,
+            notes_imported: 0,
+            attachments_copied: 0,
+            other_files_copied: 0,
+            links_rewritten: 0,
+            renamed: Vec::new(),
+            skipped: Vec::new(),
+            unresolved_links: Vec::new(),
+            settings: None,
+        };
+        if self.source.converts_documents() {
+            self.import_documents(vault, &image_location, unlocked, &mut report)?;
+        } else {
+            self.import_files(vault, &image_location, unlocked, &mut report)?;
+        }
+
+        println!("📥 Imported {} notes and {} attachments from {:?} ({} links rewritten, {} skipped)",
+                 report.notes_imported, report.attachments_copied, self.source, report.links_rewritten, report.skipped.len());
+        Ok(report)
+    }
+
+    /// Copies the files of a notes export, rewriting links between them
+    fn import_files(&self, vault: &Vault, image_location: &str, unlocked: Option<&UnlockedVault>, report: &mut ImportReport) -> Result<(), String> {
+        let vault_root = vault.path();
+        let files = source_files(&self.root);
+        let all: HashSet<&str> = files.iter().map(String::as_str).collect();
+        let mut taken = HashSet::new();
+        let mut plan: BTreeMap<String, (String, FileKind)> = BTreeMap::new();
+
+        for rel in &files {
+            let notion = self.source == ImportSource::Notion;
+            let lower = rel.to_lowercase();
+            if notion && lower.ends_with("_all.csv") {
+                let plain = format!("{}.csv", &rel[..rel.len() - "_all.csv".len()]);
+                if all.contains(plain.as_str()) {
+                    report.skipped.push(SkippedFile { path: rel.clone(), reason: format!("Duplicate of {}", plain) });
+                    continue;
+                }
+            }
+
+            let kind = if is_markdown(rel) {
+                FileKind::Note
+            } else if notion && lower.ends_with(".csv") {
+                FileKind::Table
+            } else if is_attachment(rel) {
+                FileKind::Attachment
+            } else {
+                FileKind::Other
+            };
+            let clean = if notion { notion::clean_path(rel) } else { rel.clone() };
+            let desired = match kind {
+                FileKind::Note | FileKind::Other => join(&self.target_folder, &clean),
+                FileKind::Table => join(&self.target_folder, &format!("{}.md", &clean[..clean.len() - 4])),
+                FileKind::Attachment => join(image_location, clean.rsplit('/').next().unwrap_or(&clean)),
+            };
+
+            let dest = unique_path(&desired, &mut taken, vault_root);
+            if file_name(&dest) != file_name(rel) && kind != FileKind::Table {
+                report.renamed.push(MovedFile { from: rel.clone(), to: dest.clone() });
+            }
+            plan.insert(rel.clone(), (dest, kind));
+        }
+
+        let mut source_resolver = NoteResolver::new();
+        for rel in plan.keys() {
+            source_resolver.add_file(rel);
+        }
+        let existing = vault.list_markdown_files().map_err(|e| format!("Failed to list files: {}", e))?;
+        let mut dest_resolver = NoteResolver::from_paths(vault_root, &existing);
+        for (dest, _) in plan.values() {
+            dest_resolver.add_file(dest);
+        }
+        let links = LinkMapper { plan: &plan, source: &source_resolver, dest: &dest_resolver };
+
+        for (rel, (dest, kind)) in &plan {
+            let result = match kind {
+                FileKind::Note => self.import_note(vault_root, &links, rel, dest, unlocked, report),
+                FileKind::Table => self.import_table(vault_root, &links, rel, dest, unlocked),
+                FileKind::Attachment | FileKind::Other => copy_file(&self.root.join(rel), vault_root, dest),
+            };
+            match result {
+                Ok(()) => match kind {
+                    FileKind::Note | FileKind::Table => report.notes_imported += 1,
+                    FileKind::Attachment => report.attachments_copied += 1,
+                    FileKind::Other => report.other_files_copied += 1,
+                },
+                Err(e) => report.skipped.push(SkippedFile { path: rel.clone(), reason: e }),
+            }
+        }
+        Ok(())
+    }
+
+    fn import_note(
+        &self,
+        vault_root: &Path,
+        links: &LinkMapper,
+        rel: &str,
+        dest: &str,
+        unlocked: Option<&UnlockedVault>,
+        report: &mut ImportReport,
+    ) -> Result<(), String> {
+        let bytes = fs::read(self.root.join(rel)).map_err(|e| format!("Failed to read: {}", e))?;
+        let mut content = String::from_utf8_lossy(&bytes).into_owned();
+
+        let notion = self.source == ImportSource::Notion;
+        if notion {
+            // Pages of a database sit in a folder named like the database's CSV
+            let database = self.root.join(format!("{}.csv", parent_dir(rel)));
+            if let Ok(csv) = fs::read_to_string(database) {
+                content = notion::lift_properties(&content, &notion::csv_columns(&csv));
+            }
+        }
+
+        let (content, rewritten, unresolved) = links.rewrite(&content, rel, dest, notion);
+        report.links_rewritten += rewritten;
+        report.unresolved_links.extend(unresolved.into_iter().map(|target| UnresolvedLink { note: dest.to_string(), target }));
+
+        write_note(vault_root, dest, &content, unlocked)
+    }
+
+    fn import_table(&self, vault_root: &Path, links: &LinkMapper, rel: &str, dest: &str, unlocked: Option<&UnlockedVault>) -> Result<(), String> {
+        let csv = fs::read_to_string(self.root.join(rel)).map_err(|e| format!("Failed to read: {}", e))?;
+        let title = file_name(dest).trim_end_matches(".md").to_string();
+        let folder = &rel[..rel.len() - 4];
+
+        // Row titles link to the database's pages
+        let link_for = |row_title: &str| {
+            links.plan.iter()
+                .filter(|(page, (_, kind))| *kind == FileKind::Note && parent_dir(page) == folder)
+                .find(|(page, _)| {
+                    notion::clean_path(file_name(page)).trim_end_matches(".md") == row_title
+                })
+                .map(|(_, (page_dest, _))| links.dest.shortest_link_target(page_dest, dest))
+        };
+        let table = notion::csv_to_table(&title, &csv, &link_for)?;
+        write_note(vault_root, dest, &table, unlocked)
+    }
+}
+
+/// Notes and attachments created while converting documents
+struct DocumentImport<'a> {
+    vault_root: &'a Path,
+    unlocked: Option<&'a UnlockedVault>,
+    image_location: &'a str,
+    resolver: RefCell<NoteResolver>,
+    taken: RefCell<HashSet<String>>,
+    skipped: RefCell<Vec<SkippedFile>>,
+    attachments: RefCell<usize>,
+    /// Attachments saved for the note being converted, removed again if the
+    /// note cannot be written
+    pending: RefCell<Vec<String>>,
+}
+
+impl DocumentImport<'_> {
+    /// Saves an attachment into the image folder and returns its vault path
+    fn save_attachment(&self, name: &str, data: &[u8]) -> Option<String> {
+        let desired = join(self.image_location, name);
+        let path = unique_path(&desired, &mut self.taken.borrow_mut(), self.vault_root);
+        match write_file(self.vault_root, &path, data) {
+            Ok(()) => {
+                self.resolver.borrow_mut().add_file(&path);
+                *self.attachments.borrow_mut() += 1;
+                self.pending.borrow_mut().push(path.clone());
+                Some(path)
+            }
+            Err(e) => {
+                self.skipped.borrow_mut().push(SkippedFile { path: name.to_string(), reason: e });
+                None
+            }
+        }
+    }
+
+    /// Reserves a unique path for a note, recording it when it had to be renamed
+    fn note_path(&self, desired: &str, renamed: &mut Vec<MovedFile>) -> String {
+        let dest = unique_path(desired, &mut self.taken.borrow_mut(), self.vault_root);
+        if dest != desired {
+            renamed.push(MovedFile { from: desired.to_string(), to: dest.clone() });
+        }
+        self.resolver.borrow_mut().add_file(&dest);
+        dest
+    }
+
+    fn link_target(&self, path: &str, from: &str) -> String {
+        self.resolver.borrow().shortest_link_target(path, from)
+    }
+
+    /// Writes a converted note; if that fails, the attachments saved for it
+    /// are deleted again
+    fn write_note(&self, dest: &str, content: &str, modified: Option<SystemTime>) -> bool {
+        let pending = self.pending.take();
+        match write_note(self.vault_root, dest, content, self.unlocked) {
+            Ok(()) => {
+                if let Some(time) = modified {
+                    set_modified(self.vault_root, dest, time);
+                }
+                true
+            }
+            Err(e) => {
+                for path in &pending {
+                    if let Err(e) = fs::remove_file(self.vault_root.join(path)) {
+                        eprintln!("⚠️ Failed to remove attachment {}: {}", path, e);
+                    }
+                    self.resolver.borrow_mut().remove_file(path);
+                    *self.attachments.borrow_mut() -= 1;
+                }
+                self.skipped.borrow_mut().push(SkippedFile { path: dest.to_string(), reason: e });
+                false
+            }
+        }
+    }
+}
+
+impl Import {
+    /// Converts Evernote notes or HTML files to notes under the target folder,
+    /// saving their images and files into `image_location`
+    fn import_documents(&self, vault: &Vault, image_location: &str, unlocked: Option<&UnlockedVault>, report: &mut ImportReport) -> Result<(), String> {
+        let (base, documents) = if self.root.is_file() {
+            let base = self.root.parent().map(Path::to_path_buf).unwrap_or_default();
+            let name = self.root.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            (base, vec![name])
+        } else {
+            let evernote = self.source == ImportSource::Evernote;
+            let documents = source_files(&self.root)
+                .into_iter()
+                .filter(|rel| if evernote { is_enex(rel) } else { is_html(rel) })
+                .collect();
+            (self.root.clone(), documents)
+        };
+
+        let existing = vault.list_markdown_files().map_err(|e| format!("Failed to list files: {}", e))?;
+        let import = DocumentImport {
+            vault_root: vault.path(),
+            unlocked,
+            image_location,
+            resolver: RefCell::new(NoteResolver::from_paths(vault.path(), &existing)),
+            taken: RefCell::new(HashSet::new()),
+            skipped: RefCell::new(Vec::new()),
+            attachments: RefCell::new(0),
+            pending: RefCell::new(Vec::new()),
+        };
+
+        if self.source == ImportSource::Evernote {
+            for rel in &documents {
+                // Each export is a notebook; several of them get a folder each
+                let folder = if documents.len() > 1 {
+                    join(&self.target_folder, &rel[..rel.len() - ".enex".len()])
+                } else {
+                    self.target_folder.clone()
+                };
+                let notes = fs::read_to_string(base.join(rel))
+                    .map_err(|e| format!("Failed to read: {}", e))
+                    .and_then(|xml| enex::parse(&xml));
+                match notes {
+                    Ok(notes) => {
+                        for note in &notes {
+                            if self.import_enex_note(&import, note, &folder, report) {
+                                report.notes_imported += 1;
+                            }
+                        }
+                    }
+                    Err(e) => report.skipped.push(SkippedFile { path: rel.clone(), reason: e }),
+                }
+            }
+        } else {
+            report.notes_imported += self.import_html(&import, &base, &documents, report);
+        }
+
+        report.attachments_copied += import.attachments.into_inner();
+        report.skipped.extend(import.skipped.into_inner());
+        Ok(())
+    }
+
+    fn import_enex_note(&self, import: &DocumentImport, note: &enex::EnexNote, folder: &str, report: &mut ImportReport) -> bool {
+        let title = match sanitize_file_name(&note.title) {
+            title if title.is_empty() => "Untitled".to_string(),
+            title => title,
+        };
+        let dest = import.note_path(&join(folder, &format!("{}.md", title)), &mut report.renamed);
+
+        let mut media: HashMap<&str, String> = HashMap::new();
+        for resource in &note.resources {
+            if media.contains_key(resource.hash.as_str()) {
+                continue;
+            }
+            if let Some(path) = import.save_attachment(&resource.name(&title), &resource.data) {
+                media.insert(&resource.hash, path);
+            }
+        }
+
+        let link_for = |src: &str| {
+            src.strip_prefix(enex::MEDIA_PREFIX)
+                .and_then(|hash| media.get(hash))
+                .map(|path| import.link_target(path, &dest))
+        };
+        let content = note.to_markdown(&link_for);
+        let modified = note.updated.or(note.created).map(SystemTime::from);
+        import.write_note(&dest, &content, modified)
+    }
+
+    /// Converts HTML files, keeping their folder structure. Links between the
+    /// files become wiki-links; local images and files they use are copied and
+    /// `data:` images decoded. Returns the number of notes written.
+    fn import_html(&self, import: &DocumentImport, base: &Path, documents: &[String], report: &mut ImportReport) -> usize {
+        let mut pages = Vec::new();
+        let mut plan: HashMap<&str, String> = HashMap::new();
+        for rel in documents {
+            let html = match fs::read(base.join(rel)) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(e) => {
+                    report.skipped.push(SkippedFile { path: rel.clone(), reason: format!("Failed to read: {}", e) });
+                    continue;
+                }
+            };
+            let stem = rel.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(rel);
+            let name = html::document_title(&html)
+                .map(|title| sanitize_file_name(&title))
+                .filter(|title| !title.is_empty())
+                .unwrap_or_else(|| file_name(stem).to_string());
+            let desired = join(&self.target_folder, &join(parent_dir(rel), &format!("{}.md", name)));
+            plan.insert(rel, import.note_path(&desired, &mut report.renamed));
+            pages.push((rel, html));
+        }
+
+        let copied: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+        let mut written = 0;
+        for (rel, html) in pages {
+            let dest = &plan[rel.as_str()];
+            let stem = file_name(dest).trim_end_matches(".md");
+
+            let link_for = |url: &str| {
+                let path = if let Some(data) = url.strip_prefix("data:") {
+                    let (mime, encoded) = data.split_once(";base64,")?;
+                    let extension = crate::index::attachments::IMAGE_TYPES
+                        .iter()
+                        .find(|(_, image)| image.eq_ignore_ascii_case(mime))?
+                        .0;
+                    let bytes = general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+                    import.save_attachment(&format!("{}.{}", stem, extension), &bytes)?
+                } else {
+                    if url.contains("://") || url.starts_with("mailto:") || url.starts_with('#') {
+                        return None;
+                    }
+                    let path_part = url.split(['#', '?']).next().unwrap_or(url);
+                    let decoded = urlencoding::decode(path_part).map(|d| d.into_owned()).unwrap_or_else(|_| path_part.to_string());
+                    let source = normalize(&join(parent_dir(rel), &decoded))?;
+                    if let Some(note) = plan.get(source.as_str()) {
+                        note.clone()
+                    } else if let Some(path) = copied.borrow().get(&source) {
+                        path.clone()
+                    } else if is_attachment(&source) {
+                        let bytes = fs::read(base.join(&source)).ok()?;
+                        let path = import.save_attachment(file_name(&source), &bytes)?;
+                        copied.borrow_mut().insert(source, path.clone());
+                        path
+                    } else {
+                        return None;
+                    }
+                };
+                Some(import.link_target(&path, dest))
+            };
+
+            let content = html::to_markdown(&html, &link_for);
+            let modified = fs::metadata(base.join(rel)).and_then(|m| m.modified()).ok();
+            if import.write_note(dest, &content, modified) {
+                written += 1;
+            } else {
+                // Files copied for this page were removed with it
+                copied.borrow_mut().retain(|_, path| import.resolver.borrow().contains(path));
+            }
+        }
+        written
+    }
+}
+
+impl Drop for Import {
+    fn drop(&mut self) {
+        if let Some(temp) = &self.extracted {
+            let _ = fs::remove_dir_all(temp);
+        }
+    }
+}
+
+/// Maps links between files of the export to the paths they get in the vault
+struct LinkMapper<'a> {
+    plan: &'a BTreeMap<String, (String, FileKind)>,
+    source: &'a NoteResolver,
+    dest: &'a NoteResolver,
+}
+
+impl LinkMapper<'_> {
+    fn dest_of(&self, source_path: &str) -> Option<&str> {
+        self.plan.get(source_path).map(|(dest, _)| dest.as_str())
+    }
+
+    /// Rewrites the links of note `source` (export path) for its new path
+    /// `dest`. With `to_wiki_links`, markdown links between files of the export
+    /// become wiki-links. Returns the content, the number of links rewritten
+    /// and the targets that could not be found.
+    fn rewrite(&self, content: &str, source: &str, dest: &str, to_wiki_links: bool) -> (String, usize, Vec<String>) {
+        let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+        let mut unresolved = Vec::new();
+
+        for link in parse_wiki_links(content) {
+            if link.target.is_empty() {
+                continue;
+            }
+            let new_path = match self.source.resolve(&link.target, source).and_then(|p| self.dest_of(&p)) {
+                Some(new_path) => new_path,
+                None => {
+                    if self.dest.resolve(&link.target, dest).is_none() {
+                        unresolved.push(link.target.clone());
+                    }
+                    continue;
+                }
+            };
+
+            let text = if link.target.contains('/') {
+                if is_markdown(new_path) && !link.target.to_lowercase().ends_with(".md") {
+                    new_path[..new_path.len() - 3].to_string()
+                } else {
+                    new_path.to_string()
+                }
+            } else {
+                self.dest.shortest_link_target(new_path, dest)
+            };
+            if text == link.target {
+                continue;
+            }
+
+            let inner_start = link.start + if link.is_embed { 3 } else { 2 };
+            let inner = &content[inner_start..link.end - 2];
+            let mut target_end = inner.find(['#', '|']).unwrap_or(inner.len());
+            if inner[..target_end].ends_with('\\') {
+                target_end -= 1;
+            }
+            replacements.push((inner_start, inner_start + target_end, text));
+        }
+
+        let code = code_ranges(content);
+        for (dest_start, dest_end, destination) in markdown_link_destinations(content) {
+            let link = match link_around(content, dest_start, dest_end) {
+                Some(link) => link,
+                None => continue,
+            };
+            if in_ranges(&code, link.start) {
+                continue;
+            }
+
+            let (bracketed, raw) = match destination.strip_prefix('<').and_then(|d| d.strip_suffix('>')) {
+                Some(inner) => (true, inner),
+                None => (false, destination),
+            };
+            if raw.is_empty() || raw.starts_with('#') || raw.contains("://") || raw.starts_with("mailto:") || raw.starts_with("data:") {
+                continue;
+            }
+            let (path_part, fragment) = match raw.find('#') {
+                Some(idx) => (&raw[..idx], &raw[idx..]),
+                None => (raw, ""),
+            };
+            let encoded = path_part.contains('%');
+            let decoded = urlencoding::decode(path_part).map(|d| d.into_owned()).unwrap_or_else(|_| path_part.to_string());
+
+            let target = normalize(&format!("{}/{}", parent_dir(source), decoded))
+                .filter(|p| self.plan.contains_key(p))
+                .or_else(|| normalize(decoded.trim_start_matches('/')).filter(|p| self.plan.contains_key(p)));
+            let new_path = match target.as_deref().and_then(|t| self.dest_of(t)) {
+                Some(new_path) => new_path,
+                None => {
+                    if to_wiki_links && decoded.rsplit('/').next().is_some_and(|name| name.contains('.')) {
+                        unresolved.push(decoded);
+                    }
+                    continue;
+                }
+            };
+
+            if to_wiki_links {
+                let target = self.dest.shortest_link_target(new_path, dest);
+                let label = link.label.trim();
+                let fragment = urlencoding::decode(fragment).map(|f| f.into_owned()).unwrap_or_default();
+                let text = if link.is_embed {
+                    format!("![[{}]]", target)
+                } else if label.is_empty() || label == target {
+                    format!("[[{}{}]]", target, fragment)
+                } else {
+                    format!("[[{}{}|{}]]", target, fragment, label)
+                };
+                replacements.push((link.start, link.end, text));
+            } else {
+                let path = relative_between(parent_dir(dest), new_path);
+                if path == decoded {
+                    continue;
+                }
+                let path = if bracketed {
+                    format!("<{}{}>", path, fragment)
+                } else if encoded || path.contains(' ') {
+                    format!("{}{}", path.replace(' ', "%20"), fragment)
+                } else {
+                    format!("{}{}", path, fragment)
+                };
+                replacements.push((dest_start, dest_end, path));
+            }
+        }
+
+        replacements.sort_by_key(|r| r.0);
+        let count = replacements.len();
+        let mut output = String::with_capacity(content.len());
+        let mut last = 0;
+        for (start, end, text) in replacements {
+            output.push_str(&content[last..start]);
+            output.push_str(&text);
+            last = end;
+        }
+        output.push_str(&content[last..]);
+
+        (output, count, unresolved)
+    }
+}
+
+/// A whole `[text](destination)` or `![alt](destination "title")` on one line
+struct MarkdownLink<'a> {
+    start: usize,
+    end: usize,
+    is_embed: bool,
+    label: &'a str,
+}
+
+/// The link around a destination found by `markdown_link_destinations`
+fn link_around(content: &str, dest_start: usize, dest_end: usize) -> Option<MarkdownLink<'_>> {
+    let label_end = dest_start - 2;
+    let label_start = content[..label_end].rfind(['[', ']', '\n'])?;
+    if content.as_bytes()[label_start] != b'[' {
+        return None;
+    }
+    let close = content[dest_end..].find([')', '\n']).filter(|&i| content.as_bytes()[dest_end + i] == b')')?;
+    let is_embed = content[..label_start].ends_with('!');
+    Some(MarkdownLink {
+        start: if is_embed { label_start - 1 } else { label_start },
+        end: dest_end + close + 1,
+        is_embed,
+        label: &content[label_start + 1..label_end],
+    })
+}
+
+/// Writes an imported note, encrypted when it lands in an encrypted folder
+fn write_note(vault_root: &Path, dest: &str, content: &str, unlocked: Option<&UnlockedVault>) -> Result<(), String> {
+    let content = conceal(vault_root, dest, content, unlocked)?;
+    write_file(vault_root, dest, content.as_bytes())
+}
+
+fn write_file(vault_root: &Path, dest: &str, data: &[u8]) -> Result<(), String> {
+    let path = resolve_in_vault(vault_root, Path::new(dest))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    write_atomic(&path, data).map_err(|e| format!("Failed to write {}: {}", dest, e))
+}
+
+/// Gives an imported note the modification time of the original
+fn set_modified(vault_root: &Path, dest: &str, time: SystemTime) {
+    let result = fs::File::options()
+        .write(true)
+        .open(vault_root.join(dest))
+        .and_then(|file| file.set_modified(time));
+    if let Err(e) = result {
+        eprintln!("⚠️ Failed to keep the modification time of {}: {}", dest, e);
+    }
+}
+
+fn copy_file(source: &Path, vault_root: &Path, dest: &str) -> Result<(), String> {
+    let path = resolve_in_vault(vault_root, Path::new(dest))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    fs::copy(source, &path).map(|_| ()).map_err(|e| format!("Failed to copy to {}: {}", dest, e))
+}
+
+/// Files of an export, relative to its root and sorted; hidden files and
+/// folders (`.obsidian`, `.trash`, `.git`, ...) are left out
+fn source_files(root: &Path) -> Vec<String> {
+    let mut files: Vec<String> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| relative_path(root, e.path()))
+        .collect();
+    files.sort();
+    files
+}
+
+fn is_enex(path: &str) -> bool {
+    path.to_lowercase().ends_with(".enex")
+}
+
+fn is_html(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.ends_with(".html") || lower.ends_with(".htm")
+}
+
+/// Files converted on their own rather than as part of an export folder
+fn is_document(path: &str) -> bool {
+    is_enex(path) || is_html(path)
+}
+
+fn is_zip(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Largest file extracted from an export
+const MAX_ENTRY_BYTES: u64 = 2 << 30;
+/// Most bytes extracted from an export, nested zips included
+const MAX_EXTRACTED_BYTES: u64 = 16 << 30;
+
+/// Extracts an export, including the per-part zips Notion nests inside large exports
+fn extract_export(archive: &Path, into: &Path) -> Result<(), String> {
+    let mut budget = MAX_EXTRACTED_BYTES;
+    extract_zip(archive, into, &mut budget)?;
+
+    let nested: Vec<PathBuf> = WalkDir::new(into)
+        .max_depth(2)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_zip(e.path()))
+        .map(|e| e.into_path())
+        .collect();
+    for zip in nested {
+        extract_zip(&zip, &zip.with_extension(""), &mut budget)?;
+        let _ = fs::remove_file(&zip);
+    }
+    Ok(())
+}
+
+/// Extracts a zip, refusing entries larger than `MAX_ENTRY_BYTES` or than what
+/// is left of `budget`. Sizes are checked as declared and as written.
+fn extract_zip(archive: &Path, into: &Path, budget: &mut u64) -> Result<(), String> {
+    let file = fs::File::open(archive).map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Invalid zip file: {}", e))?;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| format!("Invalid zip file: {}", e))?;
+        // Entries with absolute paths or `..` are never written outside the folder
+        let path = match entry.enclosed_name() {
+            Some(path) => into.join(path),
+            None => continue,
+        };
+        if entry.is_dir() {
+            fs::create_dir_all(&path).map_err(|e| format!("Failed to extract: {}", e))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to extract: {}", e))?;
+        }
+        let size = entry.size();
+        if size > MAX_ENTRY_BYTES || size > *budget {
+            return Err(format!("{} is too large to import", entry.name()));
+        }
+        let mut out = fs::File::create(&path).map_err(|e| format!("Failed to extract: {}", e))?;
+        let written = std::io::copy(&mut std::io::Read::take(&mut entry, size), &mut out)
+            .map_err(|e| format!("Failed to extract: {}", e))?;
+        if written != size {
+            return Err(format!("Failed to extract {}: size does not match the archive", entry.name()));
+        }
+        *budget -= size;
+    }
+    Ok(())
+}
+
+/// Zipped folders extract to a single folder; the export starts inside it
+fn single_folder(dir: &Path) -> PathBuf {
+    let entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    match entries.as_slice() {
+        [only] if only.is_dir() => only.clone(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+fn join(folder: &str, path: &str) -> String {
+    if folder.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", folder, path)
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// `desired`, or `desired` with a number added when the vault or an earlier
+/// file of the import already uses that name
+fn unique_path(desired: &str, taken: &mut HashSet<String>, vault_root: &Path) -> String {
+    let (stem, extension) = match file_name(desired).rsplit_once('.') {
+        Some((name, ext)) if !name.is_empty() => (&desired[..desired.len() - ext.len() - 1], format!(".{}", ext)),
+        _ => (desired, String::new()),
+    };
+
+    let mut n = 0;
+    loop {
+        let candidate = if n == 0 { desired.to_string() } else { format!("{} {}{}", stem, n, extension) };
+        if !taken.contains(&candidate.to_lowercase()) && !vault_root.join(&candidate).exists() {
+            taken.insert(candidate.to_lowercase());
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gaimplan-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_notion_export() {
+        let export = temp_dir("notion-export");
+        let vault_root = temp_dir("notion-vault");
+        let id = "0123456789abcdef0123456789abcdef";
+        let row = "fedcba9876543210fedcba9876543210";
+        write(&export, &format!("Home {}.md", id), &format!(
+            "# Home\n\nSee [Tasks](Tasks%20{id}.csv), [Ship it](Tasks%20{id}/Ship%20it%20{row}.md) and ![](Home%20{id}/chart.png)\n[gone](Missing.md)\n",
+            id = id, row = row
+        ));
+        write(&export, &format!("Tasks {}.csv", id), "Name,Status\nShip it,Done\n");
+        write(&export, &format!("Tasks {}_all.csv", id), "Name,Status\nShip it,Done\n");
+        write(&export, &format!("Tasks {}/Ship it {}.md", id, row), "# Ship it\n\nStatus: Done\n\nBody\n");
+        write(&export, &format!("Home {}/chart.png", id), "png");
+        write(&vault_root, "files/chart.png", "existing");
+
+        let import = Import::prepare(&ImportOptions { path: export.clone(), source: None, target_folder: "Notion".into() }).unwrap();
+        assert_eq!(import.source(), ImportSource::Notion);
+        let vault = Vault::new(vault_root.clone()).unwrap();
+        let report = import.run(&vault, "files/", None).unwrap();
+
+        assert_eq!((report.notes_imported, report.attachments_copied, report.skipped.len()), (3, 1, 1));
+        let home = fs::read_to_string(vault_root.join("Notion/Home.md")).unwrap();
+        assert_eq!(home, "# Home\n\nSee [[Tasks]], [[Ship it]] and ![[chart 1.png]]\n[gone](Missing.md)\n");
+        assert_eq!(report.unresolved_links.len(), 1);
+        assert_eq!(
+            fs::read_to_string(vault_root.join("Notion/Tasks/Ship it.md")).unwrap(),
+            "---\nStatus: Done\n---\n# Ship it\n\nBody\n"
+        );
+        assert!(fs::read_to_string(vault_root.join("Notion/Tasks.md")).unwrap().contains("| [[Ship it]] | Done |"));
+        assert!(vault_root.join("files/chart 1.png").is_file());
+
+        fs::remove_dir_all(&export).unwrap();
+        fs::remove_dir_all(&vault_root).unwrap();
+    }
+
+    #[test]
+    fn test_obsidian_vault() {
+        let source = temp_dir("obsidian-export");
+        let vault_root = temp_dir("obsidian-vault");
+        write(&source, ".obsidian/app.json", r#"{"attachmentFolderPath": "assets"}"#);
+        write(&source, ".obsidian/daily-notes.json", r#"{"folder": "Journal", "format": "YYYY/MM/DD", "template": "Templates/Daily"}"#);
+        write(&source, "Journal/2024/01/02.md", "![[assets/pic.jpg]] [pic](../../../assets/pic.jpg) [[Projects/Plan#Goals|plan]]");
+        write(&source, "Projects/Plan.md", "# Plan");
+        write(&source, "assets/pic.jpg", "jpg");
+
+        let import = Import::prepare(&ImportOptions { path: source.clone(), source: None, target_folder: "Old".into() }).unwrap();
+        assert_eq!(import.source(), ImportSource::Obsidian);
+        assert_eq!(import.settings().attachment_folder.as_deref(), Some("Old/assets"));
+        assert_eq!(import.settings().daily_note_template.as_deref(), Some("Old/Templates/Daily"));
+
+        let vault = Vault::new(vault_root.clone()).unwrap();
+        let report = import.run(&vault, "media", None).unwrap();
+        assert_eq!(report.links_rewritten, 3);
+        assert_eq!(
+            fs::read_to_string(vault_root.join("Old/Journal/2024/01/02.md")).unwrap(),
+            "![[media/pic.jpg]] [pic](../../../../media/pic.jpg) [[Old/Projects/Plan#Goals|plan]]"
+        );
+
+        fs::remove_dir_all(&source).unwrap();
+        fs::remove_dir_all(&vault_root).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+
+use crate::index::scanner::frontmatter_range;
+
+lazy_static::lazy_static! {
+    /// Notion appends a 32 character id to every page, database and folder name
+    static ref NOTION_ID: Regex = Regex::new(r"^(.*?)\s+[0-9a-fA-F]{32}$").unwrap();
+}
+
+/// Whether a file name carries a Notion page id
+pub fn has_notion_id(name: &str) -> bool {
+    let stem = name.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(name);
+    NOTION_ID.is_match(stem)
+}
+
+/// Removes the Notion id from each segment of a path: `Projects 1a2b…/Plan 3c4d….md`
+/// becomes `Projects/Plan.md`
+pub fn clean_path(path: &str) -> String {
+    path.split('/').map(clean_segment).collect::<Vec<_>>().join("/")
+}
+
+fn clean_segment(segment: &str) -> String {
+    let (stem, extension) = match segment.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.contains(' ') => (stem, Some(ext)),
+        _ => (segment, None),
+    };
+    let stem = match NOTION_ID.captures(stem) {
+        Some(captures) if !captures[1].trim().is_empty() => captures[1].trim().to_string(),
+        _ => stem.to_string(),
+    };
+    match extension {
+        Some(ext) => format!("{}.{}", stem, ext),
+        None => stem,
+    }
+}
+
+/// Database columns from the header of a Notion CSV export
+pub fn csv_columns(csv: &str) -> Vec<String> {
+    let mut reader = csv::ReaderBuilder::new().from_reader(csv.trim_start_matches('\u{feff}').as_bytes());
+    reader
+        .headers()
+        .map(|headers| headers.iter().map(|h| h.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Turns the `Column: value` lines Notion writes under a database page's
+/// title into frontmatter; only names from `columns` are taken so ordinary
+/// prose starting with a word and a colon stays in the body
+pub fn lift_properties(content: &str, columns: &[String]) -> String {
+    if columns.is_empty() || frontmatter_range(content).is_some() {
+        return content.to_string();
+    }
+
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut index = 0;
+    let mut head = String::new();
+    if lines.first().is_some_and(|line| line.starts_with("# ")) {
+        head.push_str(lines[0]);
+        index = 1;
+    }
+    while index < lines.len() && lines[index].trim().is_empty() {
+        index += 1;
+    }
+
+    let mut properties = Mapping::new();
+    while index < lines.len() {
+        let property = lines[index].split_once(": ").and_then(|(key, value)| {
+            columns.iter().find(|c| c.as_str() == key.trim()).map(|key| (key.clone(), value.trim()))
+        });
+        match property {
+            Some((key, value)) => {
+                properties.insert(Value::String(key), Value::String(value.to_string()));
+                index += 1;
+            }
+            None => break,
+        }
+    }
+    if properties.is_empty() {
+        return content.to_string();
+    }
+
+    let yaml = serde_yaml::to_string(&properties).unwrap_or_default();
+    let body = lines[index..].concat();
+    let body = body.trim_start_matches('\n');
+    if head.is_empty() {
+        format!("---\n{}---\n{}", yaml, body)
+    } else {
+        format!("---\n{}---\n{}\n{}", yaml, head, body)
+    }
+}
+
+/// Renders a Notion database CSV as a markdown table. `link_for` returns the
+/// wiki-link target of the page a row title belongs to, if it was exported.
+pub fn csv_to_table(title: &str, csv: &str, link_for: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(csv.trim_start_matches('\u{feff}').as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Invalid CSV: {}", e))?
+        .iter()
+        .map(|h| cell(h.trim()))
+        .collect();
+
+    let mut table = format!("# {}\n\n", title);
+    if headers.is_empty() {
+        return Ok(table);
+    }
+    table.push_str(&format!("| {} |\n", headers.join(" | ")));
+    table.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Invalid CSV: {}", e))?;
+        let cells: Vec<String> = (0..headers.len())
+            .map(|i| {
+                let value = record.get(i).unwrap_or("").trim();
+                match link_for(value).filter(|_| i == 0 && !value.is_empty()) {
+                    Some(target) if target == value => format!("[[{}]]", cell(&target)),
+                    Some(target) => format!("[[{}\\|{}]]", cell(&target), cell(value)),
+                    None => cell(value),
+                }
+            })
+            .collect();
+        table.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    Ok(table)
+}
+
+/// Escapes a value for a markdown table cell
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_notion_names() {
+        assert_eq!(
+            clean_path("Projects 0123456789abcdef0123456789abcdef/Plan v2 fedcba9876543210fedcba9876543210.md"),
+            "Projects/Plan v2.md"
+        );
+        assert_eq!(clean_path("Notes/Plain.md"), "Notes/Plain.md");
+        assert!(has_notion_id("Tasks 0123456789abcdef0123456789abcdef.csv"));
+        assert!(!has_notion_id("Tasks.csv"));
+    }
+
+    #[test]
+    fn test_database_properties_and_table() {
+        let columns = csv_columns("\u{feff}Name,Status,Due\n");
+        assert_eq!(columns, vec!["Name", "Status", "Due"]);
+
+        let page = "# Ship it\n\nStatus: Done\nDue: 2024-05-01\n\nNote: this stays\n";
+        assert_eq!(
+            lift_properties(page, &columns),
+            "---\nStatus: Done\nDue: 2024-05-01\n---\n# Ship it\n\nNote: this stays\n"
+        );
+
+        let csv = "Name,Status\nShip it,Done\n\"a|b\",\"multi\nline\"\n";
+        let table = csv_to_table("Tasks", csv, &|title| (title == "Ship it").then(|| "Tasks/Ship it".to_string())).unwrap();
+        assert_eq!(
+            table,
+            "# Tasks\n\n| Name | Status |\n| --- | --- |\n| [[Tasks/Ship it\\|Ship it]] | Done |\n| a\\|b | multi<br>line |\n"
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+
+use super::ImportedSettings;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppConfig {
+    attachment_folder_path: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DailyNotesConfig {
+    folder: Option<String>,
+    format: Option<String>,
+    template: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TemplatesConfig {
+    folder: Option<String>,
+}
+
+/// An Obsidian vault has a `.obsidian` config folder at its root
+pub fn is_obsidian_vault(root: &Path) -> bool {
+    root.join(".obsidian").is_dir()
+}
+
+fn read_config<T: for<'de> Deserialize<'de> + Default>(root: &Path, name: &str) -> T {
+    fs::read_to_string(root.join(".obsidian").join(name))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Reads the attachment folder, daily notes and templates settings from
+/// `.obsidian`. Paths stay relative to the imported vault; unset or
+/// unreadable settings are left out.
+pub fn read_settings(root: &Path) -> ImportedSettings {
+    let app: AppConfig = read_config(root, "app.json");
+    let daily: DailyNotesConfig = read_config(root, "daily-notes.json");
+    let templates: TemplatesConfig = read_config(root, "templates.json");
+
+    let folder = |value: Option<String>| {
+        value.map(|v| v.trim().trim_matches('/').to_string()).filter(|v| !v.is_empty())
+    };
+
+    // "./" and "./sub" put attachments next to each note, which a single
+    // image folder cannot express
+    let attachment_folder = app.attachment_folder_path.filter(|path| !path.trim().starts_with("./"));
+
+    ImportedSettings {
+        attachment_folder: folder(attachment_folder),
+        daily_notes_folder: folder(daily.folder),
+        daily_note_format: daily.format.map(|f| f.trim().to_string()).filter(|f| !f.is_empty()),
+        daily_note_template: folder(daily.template),
+        templates_folder: folder(templates.folder),
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+
+use super::links::parse_wiki_links;
+use super::refactor::MovedFile;
+use super::resolver::{is_markdown, normalize, parent_dir, NoteResolver};
+use super::scanner::{code_ranges, in_ranges, line_number, markdown_link_destinations};
+
+/// Image formats shown inline, with their MIME types
+pub const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+];
+
+/// Lowercased extension of a file name that has a stem
+fn extension(path: &str) -> Option<String> {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => Some(ext.to_lowercase()),
+        _ => None,
+    }
+}
+
+pub fn is_image(path: &str) -> bool {
+    extension(path).is_some_and(|ext| IMAGE_TYPES.iter().any(|(e, _)| *e == ext))
+}
+
+/// Images and PDFs are tracked as attachments
+pub fn is_attachment(path: &str) -> bool {
+    is_image(path) || extension(path).as_deref() == Some("pdf")
+}
+
+/// MIME type of an attachment from its extension
+pub fn mime_type(path: &str) -> Option<&'static str> {
+    let ext = extension(path)?;
+    if ext == "pdf" {
+        return Some("application/pdf");
+    }
+    IMAGE_TYPES.iter().find(|(e, _)| *e == ext).map(|(_, mime)| *mime)
+}
+
+/// Strips characters that are not allowed in file names on any platform
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_control() && !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect::<String>()
+        .trim()
+        .trim_matches('.')
+        .to_string()
+}
+
+/// File name for a pasted image from the `image_naming_pattern` setting.
+/// `{timestamp}`, `{note}` (the note it is pasted into) and `{n}` (a counter)
+/// are filled in; a name for which `exists` is true bumps `{n}`, or gets a
+/// number appended when the pattern has no `{n}`.
+pub fn attachment_file_name(
+    pattern: &str,
+    note: Option<&str>,
+    timestamp: &str,
+    extension: &str,
+    exists: &dyn Fn(&str) -> bool,
+) -> String {
+    let pattern = pattern
+        .replace("{timestamp}", timestamp)
+        .replace("{note}", note.unwrap_or("Untitled"));
+    let mut pattern = sanitize_file_name(&pattern);
+    if pattern.is_empty() {
+        pattern = format!("Pasted image {}", timestamp);
+    }
+
+    let mut n = 1;
+    loop {
+        let stem = if pattern.contains("{n}") {
+            pattern.replace("{n}", &n.to_string())
+        } else if n == 1 {
+            pattern.clone()
+        } else {
+            format!("{} {}", pattern, n - 1)
+        };
+        let name = format!("{}.{}", stem, extension);
+        if !exists(&name) {
+            return name;
+        }
+        n += 1;
+    }
+}
+
+/// A reference to an image or PDF: `![alt](path)`, `[text](file.pdf)`,
+/// `![[embed.png]]` or `[[file.pdf]]`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AttachmentReference {
+    /// Target as written, URL-decoded and without any `#fragment`
+    pub target: String,
+    /// Wiki-style targets resolve by name like other wiki-links; markdown
+    /// targets are paths relative to the note (or the vault root)
+    pub is_wiki_link: bool,
+    /// 1-based line number of the reference
+    pub line: usize,
+}
+
+/// An attachment together with the notes referencing it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentUsage {
+    pub path: String,
+    pub referenced_by: Vec<String>,
+}
+
+/// A reference to an attachment that does not exist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokenReference {
+    pub source_path: String,
+    #[serde(flatten)]
+    pub reference: AttachmentReference,
+}
+
+/// Parses every attachment reference in a note, skipping code spans and fenced blocks
+pub fn parse_attachment_references(content: &str) -> Vec<AttachmentReference> {
+    let mut references: Vec<(usize, AttachmentReference)> = parse_wiki_links(content)
+        .into_iter()
+        .filter(|link| is_attachment(&link.target))
+        .map(|link| (link.start, AttachmentReference { target: link.target, is_wiki_link: true, line: link.line }))
+        .collect();
+
+    let code = code_ranges(content);
+    for (start, _, destination) in markdown_link_destinations(content) {
+        if in_ranges(&code, start) {
+            continue;
+        }
+        if let Some(target) = destination_path(destination).filter(|t| is_attachment(t)) {
+            let line = line_number(content, start);
+            references.push((start, AttachmentReference { target, is_wiki_link: false, line }));
+        }
+    }
+
+    references.sort_by_key(|(start, _)| *start);
+    references.into_iter().map(|(_, reference)| reference).collect()
+}
+
+/// File path of a markdown link destination; URLs and anchors have none
+pub fn destination_path(destination: &str) -> Option<String> {
+    let raw = destination
+        .strip_prefix('<')
+        .and_then(|d| d.strip_suffix('>'))
+        .unwrap_or(destination);
+    if raw.is_empty() || raw.starts_with('#') || raw.contains("://") || raw.starts_with("mailto:") || raw.starts_with("data:") {
+        return None;
+    }
+    let path = raw.split('#').next().unwrap_or(raw);
+    Some(urlencoding::decode(path).map(|p| p.into_owned()).unwrap_or_else(|_| path.to_string()))
+}
+
+/// Resolves a reference written in `source` to an attachment in the vault
+pub fn resolve_reference(resolver: &NoteResolver, reference: &AttachmentReference, source: &str) -> Option<String> {
+    if reference.is_wiki_link {
+        return resolver.resolve(&reference.target, source);
+    }
+
+    let target = reference.target.replace('\\', "/");
+    // Relative to the note first, falling back to the vault root like Obsidian
+    if let Some(absolute) = target.strip_prefix('/') {
+        return normalize(absolute).filter(|p| resolver.contains(p));
+    }
+    normalize(&format!("{}/{}", parent_dir(source), target))
+        .filter(|p| resolver.contains(p))
+        .or_else(|| normalize(&target).filter(|p| resolver.contains(p)))
+}
+
+/// Where an attachment in a note's folder goes when the note moves from
+/// `from` to `to`: the same place relative to the note. Attachments kept
+/// elsewhere (e.g. a shared attachments folder) stay put, and for notes at the
+/// vault root only files right beside them count.
+pub fn companion_path(from: &str, to: &str, attachment: &str) -> Option<String> {
+    let old_dir = parent_dir(from);
+    let new_dir = parent_dir(to);
+    if old_dir == new_dir {
+        return None;
+    }
+
+    let rest = if old_dir.is_empty() {
+        Some(attachment).filter(|a| !a.contains('/'))?
+    } else {
+        attachment.strip_prefix(old_dir)?.strip_prefix('/')?
+    };
+    Some(if new_dir.is_empty() { rest.to_string() } else { format!("{}/{}", new_dir, rest) })
+}
+
+/// Vault-wide index of the attachments each note references. References are
+/// stored as written and resolved against the link index's resolver at query
+/// time, so adding or removing an attachment never requires reparsing notes.
+#[derive(Debug, Clone, Default)]
+pub struct AttachmentIndex {
+    /// Attachment references per markdown note (vault-relative path)
+    references: HashMap<String, Vec<AttachmentReference>>,
+}
+
+impl AttachmentIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or refreshes a file; `content` is only read for markdown notes
+    pub fn update_file(&mut self, rel_path: &str, content: Option<&str>) {
+        let references = match content {
+            Some(content) if is_markdown(rel_path) => parse_attachment_references(content),
+            _ => Vec::new(),
+        };
+        if references.is_empty() {
+            self.references.remove(rel_path);
+        } else {
+            self.references.insert(rel_path.to_string(), references);
+        }
+    }
+
+    /// Removes a file, or every file under a removed folder
+    pub fn remove_path(&mut self, rel_path: &str) {
+        let prefix = format!("{}/", rel_path);
+        self.references.retain(|path, _| path != rel_path && !path.starts_with(&prefix));
+    }
+
+    /// Attachment -> notes referencing it
+    fn referencing_notes(&self, resolver: &NoteResolver) -> BTreeMap<String, BTreeSet<String>> {
+        let mut notes: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (source, references) in &self.references {
+            for reference in references {
+                if let Some(path) = resolve_reference(resolver, reference, source) {
+                    notes.entry(path).or_default().insert(source.clone());
+                }
+            }
+        }
+        notes
+    }
+
+    /// Every attachment in the vault with the notes referencing it, sorted by path
+    pub fn usages(&self, resolver: &NoteResolver) -> Vec<AttachmentUsage> {
+        let mut notes = self.referencing_notes(resolver);
+        resolver
+            .files()
+            .filter(|p| is_attachment(p))
+            .map(|path| AttachmentUsage {
+                path: path.clone(),
+                referenced_by: notes.remove(path).map(|n| n.into_iter().collect()).unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Attachments no note references
+    pub fn orphans(&self, resolver: &NoteResolver) -> Vec<String> {
+        self.usages(resolver)
+            .into_iter()
+            .filter(|usage| usage.referenced_by.is_empty())
+            .map(|usage| usage.path)
+            .collect()
+    }
+
+    /// References to attachments that do not exist, by note and line
+    pub fn broken_references(&self, resolver: &NoteResolver) -> Vec<BrokenReference> {
+        let mut broken: Vec<BrokenReference> = self.references
+            .iter()
+            .flat_map(|(source, references)| {
+                references
+                    .iter()
+                    .filter(|reference| resolve_reference(resolver, reference, source).is_none())
+                    .map(|reference| BrokenReference { source_path: source.clone(), reference: reference.clone() })
+            })
+            .collect();
+        broken.sort_by(|a, b| a.source_path.cmp(&b.source_path).then(a.reference.line.cmp(&b.reference.line)));
+        broken
+    }
+
+    /// Attachments of `note` that no other note references, moved alongside
+    /// it when it moves from `note` to `new_path`
+    pub fn companions(&self, resolver: &NoteResolver, note: &str, new_path: &str) -> Vec<MovedFile> {
+        self.referencing_notes(resolver)
+            .into_iter()
+            .filter(|(_, notes)| notes.len() == 1 && notes.contains(note))
+            .filter_map(|(path, _)| {
+                let to = companion_path(note, new_path, &path)?;
+                Some(MovedFile { from: path, to })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::refactor::plan_rename;
+
+    fn index(files: &[(&str, &str)]) -> (AttachmentIndex, NoteResolver) {
+        let mut index = AttachmentIndex::new();
+        let mut resolver = NoteResolver::new();
+        for (path, content) in files {
+            resolver.add_file(path);
+            index.update_file(path, Some(content));
+        }
+        (index, resolver)
+    }
+
+    #[test]
+    fn test_references_orphans_and_broken() {
+        let (index, resolver) = index(&[
+            ("Notes/A.md", "![x](img/one.png) ![[two.PNG]] [doc](</files/Spec%20v2.pdf#page=2>) [web](https://x.io/a.png)\n`![y](gone.png)` ![c](caf%C3%A9%23.png)"),
+            ("B.md", "![[one.png]]\n![z](missing.jpg) [[Notes/A]]"),
+            ("Notes/img/one.png", ""),
+            ("two.PNG", ""),
+            ("files/Spec v2.pdf", ""),
+            ("files/unused.gif", ""),
+            ("Notes/café#.png", ""),
+        ]);
+
+        let usages = index.usages(&resolver);
+        let one = usages.iter().find(|u| u.path == "Notes/img/one.png").unwrap();
+        assert_eq!(one.referenced_by, vec!["B.md", "Notes/A.md"]);
+        assert_eq!(index.orphans(&resolver), vec!["files/unused.gif"]);
+
+        let broken = index.broken_references(&resolver);
+        assert_eq!(broken.len(), 1);
+        assert_eq!((broken[0].source_path.as_str(), broken[0].reference.target.as_str(), broken[0].reference.line), ("B.md", "missing.jpg", 2));
+    }
+
+    #[test]
+    fn test_companions_follow_the_note() {
+        let (index, resolver) = index(&[
+            ("Notes/A.md", "![a](img/only-a.png) ![b](img/shared.png) ![c](../files/pasted.png)"),
+            ("Notes/B.md", "![b](img/shared.png)"),
+            ("Notes/img/only-a.png", ""),
+            ("Notes/img/shared.png", ""),
+            ("files/pasted.png", ""),
+        ]);
+
+        let companions = index.companions(&resolver, "Notes/A.md", "Archive/A.md");
+        assert_eq!(companions, vec![MovedFile { from: "Notes/img/only-a.png".into(), to: "Archive/img/only-a.png".into() }]);
+        assert!(index.companions(&resolver, "Notes/A.md", "Notes/Renamed.md").is_empty());
+
+        let contents: HashMap<&str, &str> = [("Notes/A.md", "![a](img/only-a.png) ![c](../files/pasted.png)")].into_iter().collect();
+        let plan = plan_rename(&resolver, "Notes/A.md", "Archive/A.md", &companions, &|p| {
+            contents.get(p).map(|c| c.to_string())
+        })
+        .unwrap();
+        // Both the note and its attachment move, so none of its links change
+        assert!(plan.edited_files.is_empty());
+        assert_eq!(plan.moved_files.len(), 2);
+    }
+
+    #[test]
+    fn test_image_types_and_file_names() {
+        assert!(is_attachment("shots/Screen.WEBP") && is_image("a.svg") && !is_image("a.pdf"));
+        assert!(!is_attachment(".png") && !is_attachment("notes.md"));
+        assert_eq!(mime_type("x/diagram.svg"), Some("image/svg+xml"));
+        assert_eq!(mime_type("scan.TIF"), Some("image/tiff"));
+        assert_eq!(mime_type("doc.pdf"), Some("application/pdf"));
+        assert_eq!(mime_type("notes.md"), None);
+
+        let taken = ["Plan 1.png", "Plan 2.png", "Pasted image 20250101.png", "Pasted image 20250101 1.png"];
+        let exists = |name: &str| taken.contains(&name);
+        assert_eq!(attachment_file_name("{note} {n}", Some("Plan"), "20250101", "png", &exists), "Plan 3.png");
+        assert_eq!(attachment_file_name("Pasted image {timestamp}", None, "20250101", "png", &exists), "Pasted image 20250101 2.png");
+        assert_eq!(attachment_file_name("../{note}: {timestamp}", Some("A/B"), "1", "webp", &exists), "AB 1.webp");
+    }
+}
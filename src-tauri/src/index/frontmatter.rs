@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::resolver::is_markdown;
+use super::scanner::frontmatter_range;
+use super::tags::clean_tag;
+
+/// Typed view of a note's YAML frontmatter. Well-known keys get their own
+/// fields; everything else is kept in `custom` as JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NoteProperties {
+    pub title: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Frontmatter tags without `#`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub custom: Map<String, Value>,
+}
+
+impl NoteProperties {
+    /// Parses the frontmatter of a note; notes without one have no properties
+    pub fn from_content(content: &str) -> Self {
+        match frontmatter_range(content) {
+            Some((_, yaml)) => Self::from_yaml(&content[yaml]),
+            None => Self::default(),
+        }
+    }
+
+    /// Parses a frontmatter block. Invalid YAML yields no properties instead of
+    /// an error so a typo never stops a note from loading.
+    pub fn from_yaml(yaml: &str) -> Self {
+        let mut properties = Self::default();
+
+        for (key, value) in parse_mapping(yaml).ok().flatten().unwrap_or_default() {
+            match key.to_lowercase().as_str() {
+                "title" => properties.title = scalar_string(&value).filter(|t| !t.is_empty()),
+                "aliases" | "alias" => properties.aliases.extend(string_list(&value, |c| c == ',')),
+                "tags" | "tag" => properties.tags.extend(
+                    string_list(&value, |c| c == ',' || c.is_whitespace())
+                        .iter()
+                        .filter_map(|t| clean_tag(t.trim_start_matches('#'))),
+                ),
+                "created" => properties.created = scalar_string(&value).and_then(|s| parse_date(&s)),
+                _ => {
+                    properties.custom.insert(key, value);
+                }
+            }
+        }
+
+        properties
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Parses YAML into key/value pairs in document order. `Ok(None)` means the
+/// YAML is valid but not a mapping (e.g. empty).
+fn parse_mapping(yaml: &str) -> Result<Option<Vec<(String, Value)>>, String> {
+    let parsed: serde_yaml::Value = serde_yaml::from_str(yaml)
+        .map_err(|e| format!("Invalid frontmatter: {}", e))?;
+
+    let mapping = match parsed {
+        serde_yaml::Value::Mapping(mapping) => mapping,
+        _ => return Ok(None),
+    };
+
+    let mut entries = Vec::new();
+    for (key, value) in mapping {
+        let key = match serde_json::to_value(&key).ok().as_ref().and_then(scalar_string) {
+            Some(key) => key,
+            None => continue,
+        };
+        if let Ok(value) = serde_json::to_value(&value) {
+            entries.push((key, value));
+        }
+    }
+    Ok(Some(entries))
+}
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Accepts either a YAML list or a single string separated by `separator`
+fn string_list(value: &Value, separator: fn(char) -> bool) -> Vec<String> {
+    let items: Vec<String> = match value {
+        Value::Array(items) => items.iter().filter_map(scalar_string).collect(),
+        Value::String(s) => s.split(separator).map(|s| s.trim().to_string()).collect(),
+        other => scalar_string(other).into_iter().collect(),
+    };
+    items.into_iter().filter(|s| !s.is_empty()).collect()
+}
+
+/// Reads RFC 3339 timestamps as well as the plain `2024-01-31` and
+/// `2024-01-31 09:30` forms people type by hand (taken as UTC)
+pub fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Utc.from_utc_datetime(&naive));
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+/// Sets a single frontmatter property and returns the new note content. The
+/// frontmatter block is created if missing; other keys, comments and the body
+/// are left exactly as written.
+pub fn set_property(content: &str, key: &str, value: &Value) -> Result<String, String> {
+    let key = validate_key(key)?;
+    let entry = render_entry(key, value)?;
+
+    let yaml = match frontmatter_range(content) {
+        Some((_, yaml)) => yaml,
+        None => return Ok(format!("---\n{}---\n{}", entry, content)),
+    };
+    parse_mapping(&content[yaml.clone()])?;
+
+    let mut lines: Vec<&str> = content[yaml.clone()].split_inclusive('\n').collect();
+    match find_entry(&lines, key) {
+        Some(range) => {
+            lines.splice(range, [entry.as_str()]);
+        }
+        None => lines.push(&entry),
+    }
+
+    let new_yaml = lines.concat();
+    parse_mapping(&new_yaml)?;
+    Ok(format!("{}{}{}", &content[..yaml.start], new_yaml, &content[yaml.end..]))
+}
+
+/// Removes a frontmatter property; the whole block is dropped once only
+/// blank lines and comments are left
+pub fn remove_property(content: &str, key: &str) -> Result<String, String> {
+    let key = validate_key(key)?;
+    let (whole, yaml) = match frontmatter_range(content) {
+        Some(ranges) => ranges,
+        None => return Ok(content.to_string()),
+    };
+    parse_mapping(&content[yaml.clone()])?;
+
+    let mut lines: Vec<&str> = content[yaml.clone()].split_inclusive('\n').collect();
+    match find_entry(&lines, key) {
+        Some(range) => {
+            lines.drain(range);
+        }
+        None => return Ok(content.to_string()),
+    }
+
+    let new_yaml = lines.concat();
+    if new_yaml.lines().all(|line| line.trim().is_empty() || line.trim_start().starts_with('#')) {
+        return Ok(content[whole.end..].to_string());
+    }
+    Ok(format!("{}{}{}", &content[..yaml.start], new_yaml, &content[yaml.end..]))
+}
+
+/// Merges frontmatter blocks, later blocks winning for scalar values while
+/// lists (tags, aliases, ...) are combined. Returns the merged YAML, or `None`
+/// when no block has any keys.
+pub fn merge_frontmatter(blocks: &[&str]) -> Result<Option<String>, String> {
+    let mut merged = serde_yaml::Mapping::new();
+    for block in blocks {
+        let parsed: serde_yaml::Value = serde_yaml::from_str(block)
+            .map_err(|e| format!("Invalid frontmatter: {}", e))?;
+        let mapping = match parsed {
+            serde_yaml::Value::Mapping(mapping) => mapping,
+            serde_yaml::Value::Null => continue,
+            _ => return Err("Frontmatter must be a mapping".to_string()),
+        };
+
+        for (key, value) in mapping {
+            match (merged.get_mut(&key), value) {
+                (Some(serde_yaml::Value::Sequence(existing)), serde_yaml::Value::Sequence(items)) => {
+                    for item in items {
+                        if !existing.contains(&item) {
+                            existing.push(item);
+                        }
+                    }
+                }
+                (_, value) => {
+                    merged.insert(key, value);
+                }
+            }
+        }
+    }
+
+    if merged.is_empty() {
+        return Ok(None);
+    }
+    serde_yaml::to_string(&merged)
+        .map(Some)
+        .map_err(|e| format!("Failed to serialize frontmatter: {}", e))
+}
+
+fn validate_key(key: &str) -> Result<&str, String> {
+    let key = key.trim();
+    if key.is_empty() || key.contains('\n') {
+        return Err(format!("Invalid property name: {:?}", key));
+    }
+    Ok(key)
+}
+
+/// Serializes `key: value` as a YAML block ending in a newline
+fn render_entry(key: &str, value: &Value) -> Result<String, String> {
+    let mut mapping = serde_yaml::Mapping::new();
+    let value = serde_yaml::to_value(value).map_err(|e| e.to_string())?;
+    mapping.insert(serde_yaml::Value::String(key.to_string()), value);
+    serde_yaml::to_string(&mapping).map_err(|e| format!("Failed to serialize property: {}", e))
+}
+
+/// Line range of a top-level key and its indented/list continuation lines
+fn find_entry(lines: &[&str], key: &str) -> Option<std::ops::Range<usize>> {
+    let start = lines.iter().position(|line| {
+        if line.starts_with(char::is_whitespace) || line.starts_with('#') || line.starts_with('-') {
+            return false;
+        }
+        line.split_once(':')
+            .map(|(k, _)| k.trim().trim_matches(|c| c == '"' || c == '\'') == key)
+            .unwrap_or(false)
+    })?;
+
+    let len = lines[start + 1..]
+        .iter()
+        .take_while(|line| line.starts_with([' ', '\t', '-']))
+        .count();
+    Some(start..start + 1 + len)
+}
+
+/// Vault-wide index of note frontmatter
+#[derive(Debug, Default)]
+pub struct PropertyIndex {
+    /// Properties per markdown note (vault-relative path) that has any
+    properties: HashMap<String, NoteProperties>,
+}
+
+impl PropertyIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or refreshes a note's properties; non-markdown files are ignored
+    pub fn update_file(&mut self, rel_path: &str, content: Option<&str>) {
+        if !is_markdown(rel_path) {
+            return;
+        }
+        let properties = content.map(NoteProperties::from_content).unwrap_or_default();
+        if properties.is_empty() {
+            self.properties.remove(rel_path);
+        } else {
+            self.properties.insert(rel_path.to_string(), properties);
+        }
+    }
+
+    /// Removes a note, or every note below it when `rel_path` is a folder
+    pub fn remove_path(&mut self, rel_path: &str) {
+        let prefix = format!("{}/", rel_path);
+        self.properties.retain(|path, _| path != rel_path && !path.starts_with(&prefix));
+    }
+
+    pub fn properties_for_file(&self, rel_path: &str) -> NoteProperties {
+        self.properties.get(rel_path).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_known_and_custom_properties() {
+        let content = "---\ntitle: Weekly Review\naliases: [Review, \"Week 12\"]\ntags:\n  - work\n  - \"#planning\"\n\
+                       created: 2024-03-18\nstatus: draft\npriority: 2\n---\nbody";
+        let properties = NoteProperties::from_content(content);
+
+        assert_eq!(properties.title.as_deref(), Some("Weekly Review"));
+        assert_eq!(properties.aliases, vec!["Review", "Week 12"]);
+        assert_eq!(properties.tags, vec!["work", "planning"]);
+        assert_eq!(properties.created.unwrap().to_rfc3339(), "2024-03-18T00:00:00+00:00");
+        assert_eq!(properties.custom.get("status"), Some(&json!("draft")));
+        assert_eq!(properties.custom.get("priority"), Some(&json!(2)));
+
+        assert!(NoteProperties::from_content("---\ntitle: [unclosed\n---\nbody").is_empty());
+    }
+
+    #[test]
+    fn test_set_and_remove_property_keep_other_lines() {
+        let content = "---\n# keep me\ntitle: Old\ntags:\n  - a\n---\nbody\n";
+
+        let updated = set_property(content, "tags", &json!(["b", "c"])).unwrap();
+        assert_eq!(updated, "---\n# keep me\ntitle: Old\ntags:\n- b\n- c\n---\nbody\n");
+
+        let updated = set_property(&updated, "status", &json!("done")).unwrap();
+        assert!(updated.contains("- c\nstatus: done\n---\nbody\n"));
+
+        let removed = remove_property(&updated, "tags").unwrap();
+        assert_eq!(removed, "---\n# keep me\ntitle: Old\nstatus: done\n---\nbody\n");
+
+        let bare = remove_property(&remove_property(&removed, "title").unwrap(), "status").unwrap();
+        assert_eq!(bare, "body\n");
+
+        assert_eq!(set_property("body", "title", &json!("New")).unwrap(), "---\ntitle: New\n---\nbody");
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use super::resolver::{is_markdown, NoteResolver};
+use super::scanner::{code_ranges, in_ranges, line_number};
+
+/// A `[[wiki-link]]` or `![[embed]]` found in a note
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WikiLink {
+    /// Note part of the link, e.g. `folder/Note` (empty for `[[#Heading]]`)
+    pub target: String,
+    pub heading: Option<String>,
+    pub block_id: Option<String>,
+    pub alias: Option<String>,
+    pub is_embed: bool,
+    /// 1-based line number of the link
+    pub line: usize,
+    /// Byte range of the whole link (including `!` and brackets) in the note
+    pub start: usize,
+    pub end: usize,
+}
+
+/// An outgoing link together with the note it resolves to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedLink {
+    #[serde(flatten)]
+    pub link: WikiLink,
+    pub resolved_path: Option<String>,
+}
+
+/// A link pointing at a note from somewhere else in the vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backlink {
+    pub source_path: String,
+    #[serde(flatten)]
+    pub link: WikiLink,
+}
+
+/// Parses all wiki-links in a note, skipping code spans and fenced blocks
+pub fn parse_wiki_links(content: &str) -> Vec<WikiLink> {
+    let code = code_ranges(content);
+    let bytes = content.as_bytes();
+    let mut links = Vec::new();
+    let mut search_from = 0;
+
+    while let Some(found) = content[search_from..].find("[[") {
+        let open = search_from + found;
+        search_from = open + 2;
+
+        if in_ranges(&code, open) {
+            continue;
+        }
+
+        let close = match content[open + 2..].find("]]") {
+            Some(idx) => open + 2 + idx,
+            None => break,
+        };
+        let inner = &content[open + 2..close];
+
+        // Links never span lines and cannot contain another opening bracket pair
+        if inner.is_empty() || inner.contains('\n') || inner.contains("[[") {
+            continue;
+        }
+
+        let is_embed = open > 0 && bytes[open - 1] == b'!';
+        let start = if is_embed { open - 1 } else { open };
+
+        if let Some(mut link) = parse_link_inner(inner) {
+            link.is_embed = is_embed;
+            link.line = line_number(content, start);
+            link.start = start;
+            link.end = close + 2;
+            links.push(link);
+        }
+
+        search_from = close + 2;
+    }
+
+    links
+}
+
+/// Splits `target#heading|alias` / `target#^block|alias` into its parts
+pub fn parse_link_inner(inner: &str) -> Option<WikiLink> {
+    // Inside tables the alias pipe is escaped as `\|`
+    let (target_part, alias) = match inner.find('|') {
+        Some(idx) => {
+            let target = inner[..idx].strip_suffix('\\').unwrap_or(&inner[..idx]);
+            (target, Some(inner[idx + 1..].trim().to_string()))
+        }
+        None => (inner, None),
+    };
+
+    let (target, subpath) = match target_part.find('#') {
+        Some(idx) => (&target_part[..idx], Some(target_part[idx + 1..].trim())),
+        None => (target_part, None),
+    };
+
+    let (heading, block_id) = match subpath {
+        Some(sub) if sub.starts_with('^') => (None, Some(sub[1..].to_string())),
+        Some(sub) if !sub.is_empty() => (Some(sub.to_string()), None),
+        _ => (None, None),
+    };
+
+    let target = target.trim().to_string();
+    if target.is_empty() && heading.is_none() && block_id.is_none() {
+        return None;
+    }
+
+    Some(WikiLink {
+        target,
+        heading,
+        block_id,
+        alias: alias.filter(|a| !a.is_empty()),
+        is_embed: false,
+        line: 0,
+        start: 0,
+        end: 0,
+    })
+}
+
+/// Vault-wide index of wiki-links between notes
+#[derive(Debug, Default)]
+pub struct LinkIndex {
+    resolver: NoteResolver,
+    /// Outgoing links per markdown note (vault-relative path)
+    links: HashMap<String, Vec<WikiLink>>,
+}
+
+impl LinkIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resolver(&self) -> &NoteResolver {
+        &self.resolver
+    }
+
+    /// Adds or refreshes a file; markdown notes are re-parsed from `content`
+    pub fn update_file(&mut self, rel_path: &str, content: Option<&str>) {
+        self.resolver.add_file(rel_path);
+        if is_markdown(rel_path) {
+            let links = content.map(parse_wiki_links).unwrap_or_default();
+            self.links.insert(rel_path.to_string(), links);
+        }
+    }
+
+    /// Removes a file, or every file below it when `rel_path` is a folder
+    pub fn remove_path(&mut self, rel_path: &str) {
+        let prefix = format!("{}/", rel_path);
+        let removed: Vec<String> = self.resolver
+            .files()
+            .filter(|p| p.as_str() == rel_path || p.starts_with(&prefix))
+            .cloned()
+            .collect();
+
+        for path in removed {
+            self.resolver.remove_file(&path);
+            self.links.remove(&path);
+        }
+    }
+
+    pub fn resolve(&self, target: &str, source: &str) -> Option<String> {
+        self.resolver.resolve(target, source)
+    }
+
+    /// Links written in `source`, resolved against the current vault contents
+    pub fn outgoing_links(&self, source: &str) -> Vec<ResolvedLink> {
+        self.links
+            .get(source)
+            .map(|links| {
+                links.iter()
+                    .map(|link| ResolvedLink {
+                        link: link.clone(),
+                        resolved_path: self.resolver.resolve(&link.target, source),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Links in `source` that do not point at any file in the vault
+    pub fn unresolved_links(&self, source: &str) -> Vec<ResolvedLink> {
+        self.outgoing_links(source)
+            .into_iter()
+            .filter(|l| l.resolved_path.is_none())
+            .collect()
+    }
+
+    /// Every link in the vault that resolves to `target`, excluding self-links
+    pub fn backlinks(&self, target: &str) -> Vec<Backlink> {
+        let mut backlinks: Vec<Backlink> = self.links
+            .iter()
+            .filter(|(source, _)| source.as_str() != target)
+            .flat_map(|(source, links)| {
+                links.iter()
+                    .filter(|link| {
+                        !link.target.is_empty()
+                            && self.resolver.resolve(&link.target, source).as_deref() == Some(target)
+                    })
+                    .map(|link| Backlink {
+                        source_path: source.clone(),
+                        link: link.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        backlinks.sort_by(|a, b| a.source_path.cmp(&b.source_path).then(a.link.start.cmp(&b.link.start)));
+        backlinks
+    }
+
+    /// Notes that link to `target`, without duplicates
+    pub fn linking_notes(&self, target: &str) -> Vec<String> {
+        let mut sources: Vec<String> = self.backlinks(target)
+            .into_iter()
+            .map(|b| b.source_path)
+            .collect();
+        sources.dedup();
+        sources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_link_variants() {
+        let content = "See [[Note]], [[Note|alias]], [[Note#Heading]], ![[folder/Note#^abc123]]\n`[[code]]` and [[Table\\|cell]]";
+        let links = parse_wiki_links(content);
+
+        assert_eq!(links.len(), 5);
+        assert_eq!(links[1].alias.as_deref(), Some("alias"));
+        assert_eq!(links[2].heading.as_deref(), Some("Heading"));
+        assert!(links[3].is_embed);
+        assert_eq!(links[3].target, "folder/Note");
+        assert_eq!(links[3].block_id.as_deref(), Some("abc123"));
+        assert_eq!(links[4].target, "Table");
+        assert_eq!(links[4].line, 2);
+        assert_eq!(&content[links[0].start..links[0].end], "[[Note]]");
+    }
+
+    #[test]
+    fn test_backlinks_and_unresolved() {
+        let mut index = LinkIndex::new();
+        index.update_file("Target.md", Some("# Target"));
+        index.update_file("A.md", Some("[[Target]] and [[Nowhere]]"));
+        index.update_file("B/C.md", Some("[[target#Intro|t]]"));
+
+        let backlinks = index.backlinks("Target.md");
+        assert_eq!(backlinks.len(), 2);
+        assert_eq!(backlinks[0].source_path, "A.md");
+        assert_eq!(index.unresolved_links("A.md").len(), 1);
+
+        index.remove_path("B");
+        assert_eq!(index.backlinks("Target.md").len(), 1);
+    }
+}
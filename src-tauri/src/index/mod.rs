@@ -0,0 +1,253 @@
+pub mod scanner;
+pub mod frontmatter;
+pub mod resolver;
+pub mod links;
+pub mod tags;
+pub mod tasks;
+pub mod transclusion;
+pub mod query;
+pub mod refactor;
+pub mod replace;
+pub mod attachments;
+
+pub use attachments::{AttachmentIndex, AttachmentUsage, BrokenReference};
+pub use frontmatter::{NoteProperties, PropertyIndex};
+pub use links::{Backlink, LinkIndex, ResolvedLink, WikiLink};
+pub use resolver::NoteResolver;
+pub use tags::{TagCount, TagIndex};
+pub use tasks::{Task, TaskIndex, TaskQuery};
+pub use transclusion::Transcluder;
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use notify::{Event, EventKind};
+use tokio::sync::Mutex;
+
+use crate::encryption::{is_encrypted, reveal, UnlockedVault};
+use crate::vault::Vault;
+use crate::vault_ignore::{IgnoreRules, VaultIgnore};
+use resolver::{is_markdown, relative_path};
+
+/// In-memory indexes for a single vault, built once and kept current from
+/// file watcher events so queries never need to rescan the vault
+pub struct VaultIndex {
+    vault_path: PathBuf,
+    ignore: Arc<IgnoreRules>,
+    pub links: Mutex<LinkIndex>,
+    pub tags: Mutex<TagIndex>,
+    pub tasks: Mutex<TaskIndex>,
+    pub properties: Mutex<PropertyIndex>,
+    pub attachments: Mutex<AttachmentIndex>,
+    /// Notes stored encrypted; the indexes above only see their ciphertext
+    pub encrypted: Mutex<BTreeSet<String>>,
+}
+
+impl VaultIndex {
+    /// Scans the vault and builds all indexes, leaving out what `ignore` excludes
+    pub fn build(vault_path: &Path, ignore: Arc<IgnoreRules>) -> Result<Self, String> {
+        let vault = Vault::new(vault_path.to_path_buf())
+            .map_err(|e| format!("Failed to open vault: {}", e))?
+            .with_ignore_rules(ignore.clone());
+        let files = vault.list_markdown_files()
+            .map_err(|e| format!("Failed to list files: {}", e))?;
+
+        let mut links = LinkIndex::new();
+        let mut tags = TagIndex::new();
+        let mut tasks = TaskIndex::new();
+        let mut properties = PropertyIndex::new();
+        let mut attachments = AttachmentIndex::new();
+        let mut encrypted = BTreeSet::new();
+        for path in files.iter().filter(|p| p.is_file()) {
+            if let Some(rel) = relative_path(vault_path, path) {
+                let content = read_note(path, &rel);
+                links.update_file(&rel, content.as_deref());
+                tags.update_file(&rel, content.as_deref());
+                tasks.update_file(&rel, content.as_deref());
+                properties.update_file(&rel, content.as_deref());
+                attachments.update_file(&rel, content.as_deref());
+                if content.as_deref().is_some_and(is_encrypted) {
+                    encrypted.insert(rel);
+                }
+            }
+        }
+
+        Ok(Self {
+            vault_path: vault_path.to_path_buf(),
+            ignore,
+            links: Mutex::new(links),
+            tags: Mutex::new(tags),
+            tasks: Mutex::new(tasks),
+            properties: Mutex::new(properties),
+            attachments: Mutex::new(attachments),
+            encrypted: Mutex::new(encrypted),
+        })
+    }
+
+    pub fn vault_path(&self) -> &Path {
+        &self.vault_path
+    }
+
+    /// The vault's ignore rules as of now
+    pub fn ignore(&self) -> Arc<VaultIgnore> {
+        self.ignore.current()
+    }
+
+    /// Normalizes a path from the frontend (absolute or vault-relative) to the
+    /// `/`-separated relative form used as index keys
+    pub fn relative_key(&self, file_path: &str) -> String {
+        let path = Path::new(file_path);
+        if path.is_absolute() {
+            if let Some(rel) = relative_path(&self.vault_path, path) {
+                return rel;
+            }
+        }
+        file_path.replace('\\', "/").trim_start_matches("./").to_string()
+    }
+
+    /// Applies a file system event to every index
+    pub async fn handle_file_event(&self, event: &Event) {
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            return;
+        }
+
+        let ignore = self.ignore();
+        for path in &event.paths {
+            let rel = match relative_path(&self.vault_path, path) {
+                Some(rel) => rel,
+                None => continue,
+            };
+            if ignore.is_ignored(Path::new(&rel), path.is_dir()) {
+                continue;
+            }
+
+            // Renames arrive as separate from/to paths, so the current state of
+            // the disk decides whether a path was added or removed
+            if path.is_file() {
+                self.refresh_file(path, &rel).await;
+            } else if path.is_dir() {
+                for entry in walkdir::WalkDir::new(path)
+                    .follow_links(false)
+                    .into_iter()
+                    .filter_entry(|e| !ignore.is_ignored(e.path(), e.file_type().is_dir()))
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file())
+                {
+                    if let Some(child_rel) = relative_path(&self.vault_path, entry.path()) {
+                        self.refresh_file(entry.path(), &child_rel).await;
+                    }
+                }
+            } else {
+                self.links.lock().await.remove_path(&rel);
+                self.tags.lock().await.remove_path(&rel);
+                self.tasks.lock().await.remove_path(&rel);
+                self.properties.lock().await.remove_path(&rel);
+                self.attachments.lock().await.remove_path(&rel);
+                let inner = format!("{}/", rel);
+                self.encrypted.lock().await.retain(|p| *p != rel && !p.starts_with(&inner));
+            }
+        }
+    }
+
+    async fn refresh_file(&self, path: &Path, rel: &str) {
+        let content = read_note(path, rel);
+
+        self.links.lock().await.update_file(rel, content.as_deref());
+        self.tags.lock().await.update_file(rel, content.as_deref());
+        self.tasks.lock().await.update_file(rel, content.as_deref());
+        self.properties.lock().await.update_file(rel, content.as_deref());
+        self.attachments.lock().await.update_file(rel, content.as_deref());
+        let mut encrypted = self.encrypted.lock().await;
+        if content.as_deref().is_some_and(is_encrypted) {
+            encrypted.insert(rel.to_string());
+        } else {
+            encrypted.remove(rel);
+        }
+    }
+
+    /// The attachment index with the references of encrypted notes read from
+    /// their plaintext. While the vault is locked those references are
+    /// unknown, so this fails rather than treat their attachments as unused.
+    pub async fn readable_attachments(&self, unlocked: Option<&UnlockedVault>) -> Result<AttachmentIndex, String> {
+        let encrypted = self.encrypted.lock().await.clone();
+        let mut attachments = self.attachments.lock().await.clone();
+        if encrypted.is_empty() {
+            return Ok(attachments);
+        }
+
+        let unlocked = unlocked
+            .ok_or_else(|| "The vault has encrypted notes; unlock it to check which attachments they use".to_string())?;
+        for rel in &encrypted {
+            if let Ok(content) = std::fs::read_to_string(self.vault_path.join(rel)) {
+                let plaintext = reveal(rel, content, Some(unlocked))?;
+                attachments.update_file(rel, Some(&plaintext));
+            }
+        }
+        Ok(attachments)
+    }
+}
+
+/// Reads a markdown note's content; other files are indexed by path only
+fn read_note(path: &Path, rel: &str) -> Option<String> {
+    if is_markdown(rel) {
+        std::fs::read_to_string(path).ok()
+    } else {
+        None
+    }
+}
+
+/// Shares one `VaultIndex` per vault across all windows viewing it, along
+/// with each vault's ignore rules
+#[derive(Default)]
+pub struct VaultIndexRegistry {
+    indexes: Mutex<HashMap<PathBuf, Arc<VaultIndex>>>,
+    /// Outlive the indexes, which are dropped whenever the rules change
+    ignore_rules: std::sync::Mutex<HashMap<PathBuf, Arc<IgnoreRules>>>,
+}
+
+impl VaultIndexRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the ignore rules of a vault, with no excluded folders until
+    /// its settings set them
+    pub fn ignore_rules(&self, vault_path: &Path) -> Arc<IgnoreRules> {
+        self.ignore_rules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(vault_path.to_path_buf())
+            .or_insert_with(|| Arc::new(IgnoreRules::new(vault_path, &[])))
+            .clone()
+    }
+
+    /// Applies the excluded folders from a vault's settings
+    pub fn set_excluded_folders(&self, vault_path: &Path, excluded_folders: &[String]) {
+        self.ignore_rules(vault_path).set_excluded_folders(excluded_folders);
+    }
+
+    /// Returns the index for a vault, building it on first use
+    pub async fn get_or_build(&self, vault_path: &Path) -> Result<Arc<VaultIndex>, String> {
+        let mut indexes = self.indexes.lock().await;
+        if let Some(index) = indexes.get(vault_path) {
+            return Ok(index.clone());
+        }
+
+        let start = std::time::Instant::now();
+        let index = Arc::new(VaultIndex::build(vault_path, self.ignore_rules(vault_path))?);
+        eprintln!("📇 Indexed vault {} in {:.2}s", vault_path.display(), start.elapsed().as_secs_f64());
+
+        indexes.insert(vault_path.to_path_buf(), index.clone());
+        Ok(index)
+    }
+
+    /// Returns the index for a vault only if it has already been built
+    pub async fn get(&self, vault_path: &Path) -> Option<Arc<VaultIndex>> {
+        self.indexes.lock().await.get(vault_path).cloned()
+    }
+
+    /// Drops the index for a vault once no window is viewing it
+    pub async fn remove(&self, vault_path: &Path) {
+        self.indexes.lock().await.remove(vault_path);
+    }
+}
@@ -0,0 +1,969 @@
+//! A small Dataview-style query language over note properties:
+//!
+//! ```text
+//! TABLE status, due AS "Due date"
+//! FROM "Projects" AND -#archive
+//! WHERE status = "active" AND due <= date(today)
+//! SORT due ASC, file.name DESC
+//! GROUP BY status
+//! LIMIT 20
+//! ```
+//!
+//! `LIST` takes at most one expression. Fields are frontmatter keys (matched
+//! case-insensitively, `a.b` reaches into nested values) and `file.name`,
+//! `file.path`, `file.folder`, `file.ext`, `file.ctime`, `file.mtime` and
+//! `file.tags` (frontmatter and inline tags). Text compares case-insensitively.
+
+use std::cmp::Ordering;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value as Json};
+
+use crate::file_tree::{FileInfo, FileTreeCache};
+use crate::vault::Vault;
+use super::frontmatter::{parse_date, NoteProperties};
+use super::resolver::is_markdown;
+use super::tags::{parse_tags, tag_matches};
+
+lazy_static::lazy_static! {
+    static ref DATE: Regex = Regex::new(r"^\d{4}-\d{2}-\d{2}(?:[T ]\d{2}:\d{2}(?::\d{2})?)?").unwrap();
+}
+
+const SYMBOLS: &[&str] = &["!=", "<=", ">=", "=", "<", ">", "(", ")", ",", "!", "-"];
+const CLAUSES: &[&str] = &["from", "where", "sort", "group", "limit"];
+
+/// What a query sees of a note
+#[derive(Debug, Clone)]
+pub struct QueryNote {
+    pub file: FileInfo,
+    pub properties: NoteProperties,
+    /// Frontmatter and inline tags
+    pub tags: Vec<String>,
+}
+
+impl QueryNote {
+    /// What a query sees of a note with this content
+    pub fn from_content(file: FileInfo, content: &str) -> Self {
+        QueryNote {
+            properties: NoteProperties::from_content(content),
+            tags: parse_tags(content),
+            file,
+        }
+    }
+
+    /// Reads every note of a vault from disk, for callers without a live index
+    pub fn load_all(vault: &Vault) -> Result<Vec<QueryNote>, String> {
+        let vault_path = vault.path();
+        let tree = FileTreeCache::build(vault_path, vault.ignore_rules())?;
+        Ok(tree.all()
+            .into_iter()
+            .filter(|file| !file.is_dir && is_markdown(&file.path))
+            .map(|file| {
+                let content = std::fs::read_to_string(vault_path.join(&file.path)).unwrap_or_default();
+                QueryNote::from_content(file, &content)
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryKind {
+    Table,
+    List,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QueryResult {
+    pub kind: QueryKind,
+    /// Column headers after the file column
+    pub columns: Vec<String>,
+    pub groups: Vec<ResultGroup>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ResultGroup {
+    /// The `GROUP BY` value; `None` for the single group of an ungrouped query
+    pub key: Option<Json>,
+    pub rows: Vec<ResultRow>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ResultRow {
+    pub path: String,
+    /// File name without `.md`
+    pub name: String,
+    /// One value per column
+    pub values: Vec<Json>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Date(DateTime<Utc>),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn from_json(json: &Json) -> Self {
+        match json {
+            Json::Null => Value::Null,
+            Json::Bool(b) => Value::Bool(*b),
+            Json::Number(n) => n.as_f64().map(Value::Number).unwrap_or(Value::Null),
+            Json::String(s) => Value::Text(s.clone()),
+            Json::Array(items) => Value::List(items.iter().map(Value::from_json).collect()),
+            Json::Object(_) => Value::Text(json.to_string()),
+        }
+    }
+
+    fn to_json(&self) -> Json {
+        match self {
+            Value::Null => Json::Null,
+            Value::Bool(b) => json!(b),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => json!(*n as i64),
+            Value::Number(n) => json!(n),
+            Value::Text(s) => json!(s),
+            Value::Date(d) if d.time() == chrono::NaiveTime::MIN => json!(d.format("%Y-%m-%d").to_string()),
+            Value::Date(d) => json!(d.to_rfc3339()),
+            Value::List(items) => Json::Array(items.iter().map(Value::to_json).collect()),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0,
+            Value::Text(s) => !s.is_empty(),
+            Value::Date(_) => true,
+            Value::List(items) => !items.is_empty(),
+        }
+    }
+
+    /// Orders values of different types that cannot be compared
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::Bool(_) => 0,
+            Value::Number(_) => 1,
+            Value::Date(_) => 2,
+            Value::Text(_) => 3,
+            Value::List(_) => 4,
+            Value::Null => 5,
+        }
+    }
+}
+
+fn timestamp(seconds: Option<i64>) -> Value {
+    seconds
+        .and_then(|s| Utc.timestamp_opt(s, 0).single())
+        .map(Value::Date)
+        .unwrap_or(Value::Null)
+}
+
+fn date_value(date: NaiveDate) -> Value {
+    Value::Date(Utc.from_utc_datetime(&date.and_time(chrono::NaiveTime::MIN)))
+}
+
+/// Compares two values; `None` when they cannot be compared (`null` with
+/// anything else, or unrelated types). Text that reads as a date or number
+/// compares as one.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+        (Value::Number(x), Value::Number(y)) => x.partial_cmp(y),
+        (Value::Date(x), Value::Date(y)) => Some(x.cmp(y)),
+        (Value::Date(x), Value::Text(s)) => parse_date(s).map(|y| x.cmp(&y)),
+        (Value::Text(s), Value::Date(y)) => parse_date(s).map(|x| x.cmp(y)),
+        (Value::Number(x), Value::Text(s)) => s.trim().parse::<f64>().ok().and_then(|y| x.partial_cmp(&y)),
+        (Value::Text(s), Value::Number(y)) => s.trim().parse::<f64>().ok().and_then(|x| x.partial_cmp(y)),
+        (Value::Text(x), Value::Text(y)) => Some(x.to_lowercase().cmp(&y.to_lowercase())),
+        (Value::List(x), Value::List(y)) => {
+            for (a, b) in x.iter().zip(y) {
+                match compare(a, b)? {
+                    Ordering::Equal => continue,
+                    other => return Some(other),
+                }
+            }
+            Some(x.len().cmp(&y.len()))
+        }
+        _ => None,
+    }
+}
+
+/// Total order for sorting: `null` always last, then by type, then by value
+/// within a type. Unlike `compare`, text never sorts as a number or date, as
+/// mixing those would break transitivity.
+fn sort_order(a: &Value, b: &Value, descending: bool) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => {
+            let order = total_order(a, b);
+            if descending { order.reverse() } else { order }
+        }
+    }
+}
+
+fn total_order(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        // NaN sorts after every other number
+        (Value::Number(x), Value::Number(y)) => x.partial_cmp(y)
+            .unwrap_or_else(|| x.is_nan().cmp(&y.is_nan())),
+        (Value::Date(x), Value::Date(y)) => x.cmp(y),
+        (Value::Text(x), Value::Text(y)) => x.to_lowercase().cmp(&y.to_lowercase()),
+        (Value::List(x), Value::List(y)) => x.iter()
+            .zip(y)
+            .map(|(a, b)| total_order(a, b))
+            .find(|order| *order != Ordering::Equal)
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        _ => a.type_rank().cmp(&b.type_rank()),
+    }
+}
+
+fn contains(haystack: &Value, needle: &Value) -> bool {
+    match (haystack, needle) {
+        (Value::List(items), _) => items.iter().any(|item| compare(item, needle) == Some(Ordering::Equal)),
+        (Value::Text(text), Value::Text(part)) => text.to_lowercase().contains(&part.to_lowercase()),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    /// Lowercased path segments, e.g. `["file", "name"]`
+    Field(Vec<String>),
+    Literal(Value),
+    /// `date(today)` and friends, in days from today
+    Today(i64),
+    Now,
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+}
+
+/// Which notes a query looks at
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Folder(String),
+    Tag(String),
+    Not(Box<Source>),
+    And(Box<Source>, Box<Source>),
+    Or(Box<Source>, Box<Source>),
+}
+
+impl Source {
+    fn matches(&self, note: &QueryNote) -> bool {
+        match self {
+            Source::Folder(folder) => {
+                folder.is_empty()
+                    || note.file.path.starts_with(&format!("{}/", folder))
+                    || note.file.path == *folder
+                    || note.file.path.strip_suffix(".md") == Some(folder)
+            }
+            Source::Tag(tag) => note.tags.iter().any(|t| tag_matches(t, tag)),
+            Source::Not(source) => !source.matches(note),
+            Source::And(a, b) => a.matches(note) && b.matches(note),
+            Source::Or(a, b) => a.matches(note) || b.matches(note),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Column {
+    header: String,
+    expr: Expr,
+}
+
+/// A parsed query, ready to run against any set of notes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    kind: QueryKind,
+    columns: Vec<Column>,
+    from: Option<Source>,
+    filter: Option<Expr>,
+    sort: Vec<(Expr, bool)>,
+    group_by: Option<Expr>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Number(f64),
+    Date(DateTime<Utc>),
+    Tag(String),
+    Symbol(&'static str),
+}
+
+#[derive(Debug, Clone)]
+struct Lexed {
+    token: Token,
+    start: usize,
+    end: usize,
+}
+
+fn lex(input: &str) -> Result<Vec<Lexed>, String> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while let Some(c) = input[pos..].chars().next() {
+        let start = pos;
+        let rest = &input[pos..];
+        let token = if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            let mut escaped = false;
+            let mut end = None;
+            for (i, ch) in rest.char_indices().skip(1) {
+                if escaped {
+                    value.push(ch);
+                    escaped = false;
+                } else if ch == '\\' {
+                    escaped = true;
+                } else if ch == c {
+                    end = Some(i + ch.len_utf8());
+                    break;
+                } else {
+                    value.push(ch);
+                }
+            }
+            pos += end.ok_or_else(|| format!("Unclosed string at position {}", start))?;
+            Token::Str(value)
+        } else if c == '#' {
+            let len: usize = rest[1..]
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                .map(char::len_utf8)
+                .sum();
+            if len == 0 {
+                return Err(format!("Expected a tag after '#' at position {}", start));
+            }
+            pos += 1 + len;
+            Token::Tag(rest[1..1 + len].to_string())
+        } else if let Some(m) = DATE.find(rest) {
+            pos += m.end();
+            Token::Date(parse_date(m.as_str()).ok_or_else(|| format!("Invalid date: {}", m.as_str()))?)
+        } else if c.is_ascii_digit() {
+            let len = rest.chars().take_while(|c| c.is_ascii_digit() || *c == '.').count();
+            pos += len;
+            Token::Number(rest[..len].parse().map_err(|_| format!("Invalid number: {}", &rest[..len]))?)
+        } else if c.is_alphabetic() || c == '_' {
+            let len: usize = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                .map(char::len_utf8)
+                .sum();
+            pos += len;
+            Token::Word(rest[..len].to_string())
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            pos += symbol.len();
+            Token::Symbol(symbol)
+        } else {
+            return Err(format!("Unexpected '{}' at position {}", c, start));
+        };
+        tokens.push(Lexed { token, start, end: pos });
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Lexed>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|t| t.token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.at_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' but found {}", symbol, self.describe()))
+        }
+    }
+
+    /// The upcoming token as written, for error messages
+    fn describe(&self) -> String {
+        match self.tokens.get(self.pos) {
+            Some(t) => format!("'{}'", &self.input[t.start..t.end]),
+            None => "the end of the query".to_string(),
+        }
+    }
+
+    fn at_clause_or_end(&self) -> bool {
+        self.peek().is_none() || CLAUSES.iter().any(|clause| self.at_keyword(clause))
+    }
+
+    fn parse_query(&mut self) -> Result<Query, String> {
+        let kind = if self.eat_keyword("table") {
+            QueryKind::Table
+        } else if self.eat_keyword("list") {
+            QueryKind::List
+        } else {
+            return Err("A query starts with TABLE or LIST".to_string());
+        };
+
+        let mut columns = Vec::new();
+        while !self.at_clause_or_end() {
+            let start = self.tokens[self.pos].start;
+            let expr = self.parse_expr()?;
+            let end = self.tokens[self.pos - 1].end;
+            let header = if self.eat_keyword("as") {
+                match self.next() {
+                    Some(Token::Str(name)) | Some(Token::Word(name)) => name,
+                    _ => return Err("Expected a column name after AS".to_string()),
+                }
+            } else {
+                self.input[start..end].trim().to_string()
+            };
+            columns.push(Column { header, expr });
+            if kind == QueryKind::List || !self.eat_symbol(",") {
+                break;
+            }
+        }
+
+        let mut query = Query { kind, columns, from: None, filter: None, sort: Vec::new(), group_by: None, limit: None };
+        while self.peek().is_some() {
+            if self.eat_keyword("from") {
+                if query.from.is_some() {
+                    return Err("Only one FROM clause is allowed".to_string());
+                }
+                query.from = Some(self.parse_source()?);
+            } else if self.eat_keyword("where") {
+                let filter = self.parse_expr()?;
+                query.filter = Some(match query.filter.take() {
+                    Some(previous) => Expr::And(Box::new(previous), Box::new(filter)),
+                    None => filter,
+                });
+            } else if self.eat_keyword("sort") {
+                loop {
+                    let expr = self.parse_expr()?;
+                    let descending = self.eat_keyword("desc") || self.eat_keyword("descending");
+                    if !descending && !self.eat_keyword("asc") {
+                        self.eat_keyword("ascending");
+                    }
+                    query.sort.push((expr, descending));
+                    if !self.eat_symbol(",") {
+                        break;
+                    }
+                }
+            } else if self.eat_keyword("group") {
+                if !self.eat_keyword("by") {
+                    return Err(format!("Expected BY after GROUP but found {}", self.describe()));
+                }
+                query.group_by = Some(self.parse_expr()?);
+            } else if self.eat_keyword("limit") {
+                match self.next() {
+                    Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => query.limit = Some(n as usize),
+                    _ => return Err("Expected a whole number after LIMIT".to_string()),
+                }
+            } else {
+                return Err(format!("Unexpected {}", self.describe()));
+            }
+        }
+        Ok(query)
+    }
+
+    fn parse_source(&mut self) -> Result<Source, String> {
+        let mut source = self.parse_source_and()?;
+        while self.eat_keyword("or") {
+            source = Source::Or(Box::new(source), Box::new(self.parse_source_and()?));
+        }
+        Ok(source)
+    }
+
+    fn parse_source_and(&mut self) -> Result<Source, String> {
+        let mut source = self.parse_source_unary()?;
+        while self.eat_keyword("and") {
+            source = Source::And(Box::new(source), Box::new(self.parse_source_unary()?));
+        }
+        Ok(source)
+    }
+
+    fn parse_source_unary(&mut self) -> Result<Source, String> {
+        if self.eat_symbol("-") || self.eat_symbol("!") || self.eat_keyword("not") {
+            return Ok(Source::Not(Box::new(self.parse_source_unary()?)));
+        }
+        if self.eat_symbol("(") {
+            let source = self.parse_source()?;
+            self.expect_symbol(")")?;
+            return Ok(source);
+        }
+        match self.peek().cloned() {
+            Some(Token::Str(folder)) => {
+                self.pos += 1;
+                Ok(Source::Folder(folder.replace('\\', "/").trim_matches('/').to_string()))
+            }
+            Some(Token::Tag(tag)) => {
+                self.pos += 1;
+                Ok(Source::Tag(tag))
+            }
+            _ => Err(format!("Expected a \"folder\" or #tag after FROM but found {}", self.describe())),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_not()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.eat_symbol("!") || self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        let left = self.parse_primary()?;
+        let op = match self.peek() {
+            Some(Token::Symbol("=")) => CompareOp::Eq,
+            Some(Token::Symbol("!=")) => CompareOp::Ne,
+            Some(Token::Symbol("<")) => CompareOp::Lt,
+            Some(Token::Symbol("<=")) => CompareOp::Le,
+            Some(Token::Symbol(">")) => CompareOp::Gt,
+            Some(Token::Symbol(">=")) => CompareOp::Ge,
+            _ if self.at_keyword("contains") => CompareOp::Contains,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Expr::Compare(Box::new(left), op, Box::new(self.parse_primary()?)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        if self.eat_symbol("(") {
+            let expr = self.parse_expr()?;
+            self.expect_symbol(")")?;
+            return Ok(expr);
+        }
+        if self.eat_symbol("-") {
+            return match self.next() {
+                Some(Token::Number(n)) => Ok(Expr::Literal(Value::Number(-n))),
+                _ => Err("Expected a number after '-'".to_string()),
+            };
+        }
+
+        let description = self.describe();
+        let expr = match self.next() {
+            Some(Token::Str(s)) => Expr::Literal(Value::Text(s)),
+            Some(Token::Tag(tag)) => Expr::Literal(Value::Text(tag)),
+            Some(Token::Number(n)) => Expr::Literal(Value::Number(n)),
+            Some(Token::Date(d)) => Expr::Literal(Value::Date(d)),
+            Some(Token::Word(word)) => match word.to_lowercase().as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "null" => Expr::Literal(Value::Null),
+                "date" if self.eat_symbol("(") => {
+                    let description = self.describe();
+                    let expr = match self.next() {
+                        Some(Token::Word(w)) if w.eq_ignore_ascii_case("today") => Expr::Today(0),
+                        Some(Token::Word(w)) if w.eq_ignore_ascii_case("tomorrow") => Expr::Today(1),
+                        Some(Token::Word(w)) if w.eq_ignore_ascii_case("yesterday") => Expr::Today(-1),
+                        Some(Token::Word(w)) if w.eq_ignore_ascii_case("now") => Expr::Now,
+                        Some(Token::Date(d)) => Expr::Literal(Value::Date(d)),
+                        Some(Token::Str(s)) => Expr::Literal(Value::Date(
+                            parse_date(&s).ok_or_else(|| format!("Invalid date: {}", s))?,
+                        )),
+                        _ => return Err(format!("Expected a date, today, tomorrow, yesterday or now but found {}", description)),
+                    };
+                    self.expect_symbol(")")?;
+                    expr
+                }
+                lower => Expr::Field(lower.split('.').filter(|s| !s.is_empty()).map(str::to_string).collect()),
+            },
+            _ => return Err(format!("Expected a value or field but found {}", description)),
+        };
+        Ok(expr)
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = Parser { input, tokens: lex(input)?, pos: 0 };
+        parser.parse_query().map_err(|e| format!("Invalid query: {}", e))
+    }
+
+    /// Runs the query; `today` anchors `date(today)` and friends
+    pub fn run(&self, notes: &[QueryNote], today: NaiveDate) -> QueryResult {
+        let eval = |expr: &Expr, note: &QueryNote| evaluate(expr, note, today);
+
+        let mut matched: Vec<&QueryNote> = notes
+            .iter()
+            .filter(|note| self.from.as_ref().is_none_or(|source| source.matches(note)))
+            .filter(|note| self.filter.as_ref().is_none_or(|filter| eval(filter, note).is_truthy()))
+            .collect();
+        matched.sort_by(|a, b| {
+            self.sort
+                .iter()
+                .map(|(expr, descending)| sort_order(&eval(expr, a), &eval(expr, b), *descending))
+                .find(|order| *order != Ordering::Equal)
+                .unwrap_or_else(|| a.file.path.cmp(&b.file.path))
+        });
+        if let Some(limit) = self.limit {
+            matched.truncate(limit);
+        }
+
+        let row = |note: &QueryNote| ResultRow {
+            path: note.file.path.clone(),
+            name: note.file.name.strip_suffix(".md").unwrap_or(&note.file.name).to_string(),
+            values: self.columns.iter().map(|column| eval(&column.expr, note).to_json()).collect(),
+        };
+
+        let groups = match &self.group_by {
+            None => vec![ResultGroup { key: None, rows: matched.into_iter().map(row).collect() }],
+            Some(group_by) => {
+                let mut groups: Vec<(Value, Vec<ResultRow>)> = Vec::new();
+                for note in matched {
+                    let key = eval(group_by, note);
+                    match groups.iter_mut().find(|(existing, _)| existing.to_json() == key.to_json()) {
+                        Some((_, rows)) => rows.push(row(note)),
+                        None => groups.push((key, vec![row(note)])),
+                    }
+                }
+                groups.sort_by(|a, b| sort_order(&a.0, &b.0, false));
+                groups
+                    .into_iter()
+                    .map(|(key, rows)| ResultGroup { key: Some(key.to_json()), rows })
+                    .collect()
+            }
+        };
+
+        QueryResult {
+            kind: self.kind,
+            columns: self.columns.iter().map(|column| column.header.clone()).collect(),
+            groups,
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, note: &QueryNote, today: NaiveDate) -> Value {
+    match expr {
+        Expr::Field(path) => field(note, path),
+        Expr::Literal(value) => value.clone(),
+        Expr::Today(days) => date_value(today + Duration::days(*days)),
+        Expr::Now => Value::Date(Utc::now()),
+        Expr::Not(expr) => Value::Bool(!evaluate(expr, note, today).is_truthy()),
+        Expr::And(a, b) => Value::Bool(evaluate(a, note, today).is_truthy() && evaluate(b, note, today).is_truthy()),
+        Expr::Or(a, b) => Value::Bool(evaluate(a, note, today).is_truthy() || evaluate(b, note, today).is_truthy()),
+        Expr::Compare(a, op, b) => {
+            let (a, b) = (evaluate(a, note, today), evaluate(b, note, today));
+            let order = compare(&a, &b);
+            Value::Bool(match op {
+                CompareOp::Eq => order == Some(Ordering::Equal),
+                CompareOp::Ne => order != Some(Ordering::Equal),
+                CompareOp::Lt => order == Some(Ordering::Less),
+                CompareOp::Le => matches!(order, Some(Ordering::Less | Ordering::Equal)),
+                CompareOp::Gt => order == Some(Ordering::Greater),
+                CompareOp::Ge => matches!(order, Some(Ordering::Greater | Ordering::Equal)),
+                CompareOp::Contains => contains(&a, &b),
+            })
+        }
+    }
+}
+
+fn field(note: &QueryNote, path: &[String]) -> Value {
+    let Some((first, rest)) = path.split_first() else { return Value::Null };
+    let list = |items: &[String]| Value::List(items.iter().cloned().map(Value::Text).collect());
+
+    if first == "file" {
+        let file = &note.file;
+        return match rest.first().map(String::as_str) {
+            Some("name") => Value::Text(file.name.strip_suffix(".md").unwrap_or(&file.name).to_string()),
+            Some("path") => Value::Text(file.path.clone()),
+            Some("folder") => Value::Text(file.parent_path.clone().unwrap_or_default()),
+            Some("ext") => file.extension.clone().map(Value::Text).unwrap_or(Value::Null),
+            Some("ctime") | Some("cday") => timestamp(file.created),
+            Some("mtime") | Some("mday") => timestamp(file.modified),
+            Some("tags") => list(&note.tags),
+            _ => Value::Null,
+        };
+    }
+
+    let properties = &note.properties;
+    let value = match first.as_str() {
+        "title" => return properties.title.clone().map(Value::Text).unwrap_or(Value::Null),
+        "tags" | "tag" => return list(&properties.tags),
+        "aliases" | "alias" => return list(&properties.aliases),
+        "created" => return properties.created.map(Value::Date).unwrap_or(Value::Null),
+        _ => properties.custom.iter().find(|(key, _)| key.to_lowercase() == *first).map(|(_, value)| value),
+    };
+
+    let mut value = match value {
+        Some(value) => value,
+        None => return Value::Null,
+    };
+    for segment in rest {
+        let next = value
+            .as_object()
+            .and_then(|object| object.iter().find(|(key, _)| key.to_lowercase() == *segment))
+            .map(|(_, value)| value);
+        match next {
+            Some(next) => value = next,
+            None => return Value::Null,
+        }
+    }
+    Value::from_json(value)
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn display(value: &Json) -> String {
+    match value {
+        Json::Null => String::new(),
+        Json::String(s) => s.clone(),
+        Json::Array(items) => items.iter().map(display).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+/// Renders a result as an HTML table or list, one per group
+pub fn render_html(result: &QueryResult) -> String {
+    if result.groups.iter().all(|group| group.rows.is_empty()) {
+        return "<p><em>No results</em></p>".to_string();
+    }
+
+    let mut html = String::from("<div>");
+    for group in &result.groups {
+        if let Some(key) = &group.key {
+            html.push_str(&format!("<h4>{}</h4>", escape_html(&display(key))));
+        }
+        match result.kind {
+            QueryKind::Table => {
+                html.push_str("<table><thead><tr><th>File</th>");
+                for column in &result.columns {
+                    html.push_str(&format!("<th>{}</th>", escape_html(column)));
+                }
+                html.push_str("</tr></thead><tbody>");
+                for row in &group.rows {
+                    html.push_str(&format!("<tr><td>{}</td>", escape_html(&row.name)));
+                    for value in &row.values {
+                        html.push_str(&format!("<td>{}</td>", escape_html(&display(value))));
+                    }
+                    html.push_str("</tr>");
+                }
+                html.push_str("</tbody></table>");
+            }
+            QueryKind::List => {
+                html.push_str("<ul>");
+                for row in &group.rows {
+                    let value = row.values.first().map(display).filter(|v| !v.is_empty());
+                    match value {
+                        Some(value) => html.push_str(&format!("<li>{}: {}</li>", escape_html(&row.name), escape_html(&value))),
+                        None => html.push_str(&format!("<li>{}</li>", escape_html(&row.name))),
+                    }
+                }
+                html.push_str("</ul>");
+            }
+        }
+    }
+    html.push_str("</div>");
+    html
+}
+
+/// Info strings of fenced blocks that hold a query
+fn is_query_fence(line: &str) -> bool {
+    let info = line.trim().trim_start_matches('`');
+    line.trim_start().starts_with("```") && matches!(info.trim(), "query" | "dataview")
+}
+
+pub fn has_query_blocks(markdown: &str) -> bool {
+    markdown.lines().any(is_query_fence)
+}
+
+/// Replaces every ```` ```query ```` (or ```` ```dataview ````) block with its
+/// rendered result; a query that fails shows its error instead
+pub fn expand_query_blocks(markdown: &str, notes: &[QueryNote], today: NaiveDate) -> String {
+    let mut output = String::new();
+    let mut query: Option<String> = None;
+    for line in markdown.split_inclusive('\n') {
+        match query.as_mut() {
+            None if is_query_fence(line) => query = Some(String::new()),
+            None => output.push_str(line),
+            Some(source) if line.trim() == "```" => {
+                let html = match Query::parse(source) {
+                    Ok(parsed) => render_html(&parsed.run(notes, today)),
+                    Err(e) => format!("<p><em>{}</em></p>", escape_html(&e)),
+                };
+                output.push_str(&format!("\n{}\n\n", html));
+                query = None;
+            }
+            Some(source) => source.push_str(line),
+        }
+    }
+    // An unclosed block is left as written
+    if let Some(source) = query {
+        output.push_str("```query\n");
+        output.push_str(&source);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(path: &str, content: &str, modified: i64) -> QueryNote {
+        let name = path.rsplit('/').next().unwrap().to_string();
+        let parent = path.rsplit_once('/').map(|(parent, _)| parent.to_string());
+        QueryNote {
+            file: FileInfo {
+                path: path.to_string(),
+                name,
+                is_dir: false,
+                extension: Some("md".to_string()),
+                depth: path.split('/').count(),
+                parent_path: parent,
+                created: None,
+                modified: Some(modified),
+                git_status: None,
+            },
+            properties: NoteProperties::from_content(content),
+            tags: parse_tags(content),
+        }
+    }
+
+    fn notes() -> Vec<QueryNote> {
+        vec![
+            note("Projects/Alpha.md", "---\nstatus: active\ndue: 2026-11-01\n---\n#work", 3),
+            note("Projects/Beta.md", "---\nstatus: Active\ndue: 2026-10-15\nowner:\n  name: Ana\n---\n", 2),
+            note("Projects/Gamma.md", "---\nstatus: done\n---\n#work/old", 1),
+            note("Inbox/Delta.md", "---\nstatus: active\n---\n#work", 4),
+        ]
+    }
+
+    #[test]
+    fn test_table_with_from_where_sort_and_limit() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let query = Query::parse(
+            "TABLE due, owner.name AS Owner FROM \"Projects\" WHERE status = \"active\" SORT due DESC LIMIT 5",
+        ).unwrap();
+        let result = query.run(&notes(), today);
+
+        assert_eq!(result.columns, vec!["due", "Owner"]);
+        let rows = &result.groups[0].rows;
+        assert_eq!(rows.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["Alpha", "Beta"]);
+        assert_eq!(rows[1].values, vec![json!("2026-10-15"), json!("Ana")]);
+
+        let overdue = Query::parse("LIST due FROM #work OR \"Projects\" AND -#work WHERE due < date(today)").unwrap();
+        let result = overdue.run(&notes(), today);
+        assert_eq!(result.groups[0].rows.iter().map(|r| r.path.as_str()).collect::<Vec<_>>(), vec!["Projects/Beta.md"]);
+
+        let recent = Query::parse("LIST SORT file.mtime DESC LIMIT 2").unwrap();
+        let names: Vec<_> = recent.run(&notes(), today).groups[0].rows.iter().map(|r| r.name.clone()).collect();
+        assert_eq!(names, vec!["Delta", "Alpha"]);
+    }
+
+    #[test]
+    fn test_group_by_render_and_errors() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let query = Query::parse("list from #work group by status").unwrap();
+        let result = query.run(&notes(), today);
+
+        let keys: Vec<_> = result.groups.iter().map(|g| g.key.clone().unwrap()).collect();
+        assert_eq!(keys, vec![json!("active"), json!("done")]);
+        assert_eq!(result.groups[0].rows.len(), 2);
+
+        let markdown = "Before\n```query\nTABLE status FROM \"Inbox\"\n```\n```dataview\nLIST WHERE\n```\nAfter\n";
+        let expanded = expand_query_blocks(markdown, &notes(), today);
+        assert!(expanded.starts_with("Before\n\n<div><table><thead><tr><th>File</th><th>status</th>"));
+        assert!(expanded.contains("<td>Delta</td><td>active</td>"));
+        assert!(expanded.contains("<p><em>Invalid query: Expected a value or field but found the end of the query</em></p>"));
+        assert!(expanded.ends_with("After\n"));
+
+        assert!(Query::parse("SELECT x").is_err());
+        assert!(Query::parse("TABLE x FROM Projects").is_err());
+    }
+
+    #[test]
+    fn test_sort_mixes_numbers_and_text() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        // More notes than the standard sort's insertion-sort cutoff, with
+        // numbers, numeric text and words that `compare` cannot order together
+        let notes: Vec<QueryNote> = (0..40)
+            .map(|i| {
+                let rank = match i % 4 {
+                    0 => format!("{}", 40 - i),
+                    1 => format!("\"{}\"", i),
+                    2 => format!("word{}", i % 7),
+                    _ => String::new(),
+                };
+                note(&format!("N{:02}.md", i), &format!("---\nrank: {}\n---\n", rank), i)
+            })
+            .collect();
+
+        let sorted = Query::parse("TABLE rank SORT rank").unwrap().run(&notes, today);
+        let values: Vec<_> = sorted.groups[0].rows.iter().map(|r| r.values[0].clone()).collect();
+        assert_eq!(values.len(), 40);
+        assert_eq!(values[0], json!(4));
+        assert_eq!(values[9], json!(40));
+        assert!(values[10..30].iter().all(|v| v.is_string()));
+        assert!(values[30..].iter().all(|v| v.is_null()));
+
+        let grouped = Query::parse("LIST GROUP BY rank").unwrap().run(&notes, today);
+        let keys: Vec<_> = grouped.groups.iter().map(|g| g.key.clone().unwrap()).collect();
+        assert_eq!(keys.first(), Some(&json!(4)));
+        assert_eq!(keys.last(), Some(&json!(null)));
+    }
+}
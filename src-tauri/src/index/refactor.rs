@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::vault::content_hash;
+use super::links::parse_wiki_links;
+use super::resolver::{is_markdown, normalize, parent_dir, NoteResolver};
+use super::scanner::{code_ranges, in_ranges, markdown_link_destinations};
+
+/// A file that changes location as part of a rename
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MovedFile {
+    pub from: String,
+    pub to: String,
+}
+
+/// A note whose links are rewritten by a rename
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditedFile {
+    /// Path of the note before the rename
+    pub path: String,
+    /// Path of the note after the rename (differs when the note itself moves)
+    pub new_path: String,
+    pub links_updated: usize,
+    #[serde(skip)]
+    pub new_content: String,
+    /// Hash of the content `new_content` was rewritten from, to notice edits
+    /// made after planning
+    #[serde(skip)]
+    pub original_hash: String,
+}
+
+/// Everything a rename or move will change, computed before touching the disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenamePlan {
+    pub from: String,
+    pub to: String,
+    pub moved_files: Vec<MovedFile>,
+    pub edited_files: Vec<EditedFile>,
+}
+
+impl RenamePlan {
+    pub fn links_updated(&self) -> usize {
+        self.edited_files.iter().map(|f| f.links_updated).sum()
+    }
+}
+
+/// Plans renaming/moving the file or folder `from` to `to` (vault-relative),
+/// rewriting every wiki-link and relative markdown link that would break.
+/// `companions` are other files moving along with it, such as a note's attachments.
+pub fn plan_rename(
+    resolver: &NoteResolver,
+    from: &str,
+    to: &str,
+    companions: &[MovedFile],
+    read_note: &dyn Fn(&str) -> Option<String>,
+) -> Result<RenamePlan, String> {
+    let from = from.trim_matches('/');
+    let to = to.trim_matches('/');
+    if from.is_empty() || to.is_empty() {
+        return Err("Path cannot be empty".to_string());
+    }
+    if from == to {
+        return Err("Source and destination are the same".to_string());
+    }
+    if to.starts_with(&format!("{}/", from)) {
+        return Err("Cannot move a folder into itself".to_string());
+    }
+
+    let prefix = format!("{}/", from);
+    let mut moves: HashMap<String, String> = resolver
+        .files()
+        .filter(|p| p.starts_with(&prefix))
+        .map(|p| (p.clone(), format!("{}/{}", to, &p[prefix.len()..])))
+        .collect();
+
+    // Single files, empty folders and files the index does not track move as-is
+    if moves.is_empty() {
+        moves.insert(from.to_string(), to.to_string());
+    }
+    for companion in companions {
+        moves.entry(companion.from.clone()).or_insert_with(|| companion.to.clone());
+    }
+    if let Some(existing) = moves.values().find(|new| resolver.contains(new) && !moves.contains_key(*new)) {
+        return Err(format!("A file already exists at {}", existing));
+    }
+
+    let mut after = resolver.clone();
+    for old in moves.keys() {
+        after.remove_file(old);
+    }
+    for new in moves.values() {
+        after.add_file(new);
+    }
+
+    let mut edited_files = Vec::new();
+    for source in resolver.notes() {
+        let content = match read_note(source) {
+            Some(content) => content,
+            None => continue,
+        };
+        let new_source = moves.get(source).cloned().unwrap_or_else(|| source.clone());
+
+        let rewriter = LinkRewriter {
+            before: resolver,
+            after: &after,
+            moves: &moves,
+            source,
+            new_source: &new_source,
+        };
+        let (new_content, links_updated) = rewriter.rewrite(&content);
+
+        if links_updated > 0 {
+            edited_files.push(EditedFile {
+                path: source.clone(),
+                new_path: new_source,
+                links_updated,
+                new_content,
+                original_hash: content_hash(&content),
+            });
+        }
+    }
+
+    let mut moved_files: Vec<MovedFile> = moves
+        .into_iter()
+        .map(|(from, to)| MovedFile { from, to })
+        .collect();
+    moved_files.sort_by(|a, b| a.from.cmp(&b.from));
+    edited_files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(RenamePlan {
+        from: from.to_string(),
+        to: to.to_string(),
+        moved_files,
+        edited_files,
+    })
+}
+
+struct LinkRewriter<'a> {
+    before: &'a NoteResolver,
+    after: &'a NoteResolver,
+    moves: &'a HashMap<String, String>,
+    source: &'a str,
+    new_source: &'a str,
+}
+
+impl LinkRewriter<'_> {
+    fn rewrite(&self, content: &str) -> (String, usize) {
+        let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+
+        for link in parse_wiki_links(content) {
+            if link.target.is_empty() {
+                continue;
+            }
+            let target = match self.before.resolve(&link.target, self.source) {
+                Some(target) => target,
+                None => continue,
+            };
+            let new_target = self.moves.get(&target).cloned().unwrap_or(target);
+            if self.after.resolve(&link.target, self.new_source).as_deref() == Some(new_target.as_str()) {
+                continue;
+            }
+
+            let text = if link.target.contains('/') {
+                if is_markdown(&new_target) && !link.target.to_lowercase().ends_with(".md") {
+                    new_target[..new_target.len() - 3].to_string()
+                } else {
+                    new_target.clone()
+                }
+            } else {
+                self.after.shortest_link_target(&new_target, self.new_source)
+            };
+
+            // Only the target part is replaced so headings, block ids and aliases survive
+            let inner_start = link.start + if link.is_embed { 3 } else { 2 };
+            let inner = &content[inner_start..link.end - 2];
+            let mut target_end = inner.find(['#', '|']).unwrap_or(inner.len());
+            if inner[..target_end].ends_with('\\') {
+                target_end -= 1;
+            }
+            replacements.push((inner_start, inner_start + target_end, text));
+        }
+
+        let code = code_ranges(content);
+        for (start, end, destination) in markdown_link_destinations(content) {
+            if in_ranges(&code, start) {
+                continue;
+            }
+            if let Some(text) = self.rewrite_destination(destination) {
+                replacements.push((start, end, text));
+            }
+        }
+
+        replacements.sort_by_key(|r| r.0);
+        let count = replacements.len();
+        let mut output = String::with_capacity(content.len());
+        let mut last = 0;
+        for (start, end, text) in replacements {
+            output.push_str(&content[last..start]);
+            output.push_str(&text);
+            last = end;
+        }
+        output.push_str(&content[last..]);
+
+        (output, count)
+    }
+
+    /// Rewrites a `[text](destination)` target when the file it points at (or
+    /// the note containing it) moves; URLs and anchors are left alone
+    fn rewrite_destination(&self, destination: &str) -> Option<String> {
+        let (bracketed, raw) = match destination.strip_prefix('<').and_then(|d| d.strip_suffix('>')) {
+            Some(inner) => (true, inner),
+            None => (false, destination),
+        };
+        if raw.is_empty() || raw.starts_with('#') || raw.contains("://") || raw.starts_with("mailto:") {
+            return None;
+        }
+
+        let (path_part, fragment) = match raw.find('#') {
+            Some(idx) => (&raw[..idx], &raw[idx..]),
+            None => (raw, ""),
+        };
+        let encoded = path_part.contains("%20");
+        let decoded = path_part.replace("%20", " ");
+
+        // Links are relative to the note, falling back to the vault root like Obsidian
+        let vault_absolute = decoded.starts_with('/');
+        let relative = normalize(&format!("{}/{}", parent_dir(self.source), decoded))
+            .filter(|p| !vault_absolute && self.before.contains(p));
+        let (target, vault_absolute) = match relative {
+            Some(target) => (target, false),
+            None => {
+                let target = normalize(&decoded)?;
+                if !self.before.contains(&target) {
+                    return None;
+                }
+                (target, true)
+            }
+        };
+
+        let new_target = self.moves.get(&target).cloned().unwrap_or_else(|| target.clone());
+        if new_target == target && self.new_source == self.source {
+            return None;
+        }
+
+        let mut path = if vault_absolute {
+            if decoded.starts_with('/') {
+                format!("/{}", new_target)
+            } else {
+                new_target
+            }
+        } else {
+            relative_between(parent_dir(self.new_source), &new_target)
+        };
+        if path == decoded {
+            return None;
+        }
+        if encoded {
+            path = path.replace(' ', "%20");
+        }
+
+        let rewritten = format!("{}{}", path, fragment);
+        Some(if bracketed { format!("<{}>", rewritten) } else { rewritten })
+    }
+}
+
+/// Relative path from folder `from_dir` to file `to`, both vault-relative
+pub fn relative_between(from_dir: &str, to: &str) -> String {
+    let from: Vec<&str> = from_dir.split('/').filter(|s| !s.is_empty()).collect();
+    let target: Vec<&str> = to.split('/').collect();
+
+    let common = from
+        .iter()
+        .zip(target.iter())
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(target.len() - 1);
+
+    let mut parts: Vec<&str> = vec![".."; from.len() - common];
+    parts.extend(&target[common..]);
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(files: &[(&str, &str)], from: &str, to: &str) -> RenamePlan {
+        let mut resolver = NoteResolver::new();
+        for (path, _) in files {
+            resolver.add_file(path);
+        }
+        let contents: HashMap<String, String> = files
+            .iter()
+            .map(|(p, c)| (p.to_string(), c.to_string()))
+            .collect();
+        plan_rename(&resolver, from, to, &[], &|p| contents.get(p).cloned()).unwrap()
+    }
+
+    fn edited<'a>(plan: &'a RenamePlan, path: &str) -> &'a str {
+        &plan.edited_files.iter().find(|f| f.path == path).unwrap().new_content
+    }
+
+    #[test]
+    fn test_rename_note_rewrites_inbound_links() {
+        let plan = plan(
+            &[
+                ("Old.md", "self"),
+                ("A.md", "[[Old]] [[Old#Intro|alias]] ![[Old#^b1]] [x](Old.md#top) `[[Old]]`"),
+                ("sub/B.md", "[[Other]] [y](../Old.md)"),
+                ("Other.md", ""),
+            ],
+            "Old.md",
+            "New.md",
+        );
+
+        assert_eq!(edited(&plan, "A.md"), "[[New]] [[New#Intro|alias]] ![[New#^b1]] [x](New.md#top) `[[Old]]`");
+        assert_eq!(edited(&plan, "sub/B.md"), "[[Other]] [y](../New.md)");
+        assert_eq!(plan.links_updated(), 5);
+    }
+
+    #[test]
+    fn test_move_folder_updates_relative_links_inside_it() {
+        let plan = plan(
+            &[
+                ("Projects/Plan.md", "[[Projects/Notes]] ![img](files/a%20b.png) [r](../Root.md)"),
+                ("Projects/Notes.md", ""),
+                ("Projects/files/a b.png", ""),
+                ("Root.md", "[[Plan]] [p](Projects/Plan.md)"),
+            ],
+            "Projects",
+            "Archive/Projects",
+        );
+
+        assert_eq!(plan.moved_files.len(), 3);
+        assert_eq!(edited(&plan, "Projects/Plan.md"), "[[Projects/Notes]] ![img](files/a%20b.png) [r](../../Root.md)");
+        assert_eq!(edited(&plan, "Root.md"), "[[Plan]] [p](Archive/Projects/Plan.md)");
+    }
+
+    #[test]
+    fn test_relative_between() {
+        assert_eq!(relative_between("a/b", "a/c/d.md"), "../c/d.md");
+        assert_eq!(relative_between("", "x.md"), "x.md");
+        assert_eq!(relative_between("a", "a/x.md"), "x.md");
+    }
+}
@@ -0,0 +1,223 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::resolver::is_markdown;
+use super::scanner::line_number;
+use super::tags::tag_matches;
+
+/// Longest line excerpt kept around a match in a preview
+const MAX_PREVIEW_CHARS: usize = 200;
+
+/// A vault-wide search for find and replace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceQuery {
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: String,
+    /// Treat `pattern` as a regular expression; `$1`/`${name}` in the
+    /// replacement then refer to its groups
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    /// Only search notes in these folders (vault-relative); all when empty
+    #[serde(default)]
+    pub folders: Vec<String>,
+    /// Only search notes with one of these tags (nested tags included); all when empty
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A query compiled to a regex
+pub struct Replacer {
+    regex: Regex,
+    replacement: String,
+    expand: bool,
+    folders: Vec<String>,
+    tags: Vec<String>,
+}
+
+/// One occurrence of the pattern and what it will be replaced with
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplaceMatch {
+    /// Byte offsets of the match in the file
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub matched: String,
+    pub replacement: String,
+    /// The line(s) containing the match, before and after replacing it
+    pub before: String,
+    pub after: String,
+}
+
+/// Every match in a note. `hash` is the content the preview was computed
+/// from, so a note edited since then is not changed blindly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMatches {
+    pub path: String,
+    pub hash: String,
+    pub matches: Vec<ReplaceMatch>,
+}
+
+impl ReplaceQuery {
+    pub fn compile(&self) -> Result<Replacer, String> {
+        if self.pattern.is_empty() {
+            return Err("Search pattern cannot be empty".to_string());
+        }
+        let pattern = if self.regex { self.pattern.clone() } else { regex::escape(&self.pattern) };
+        let pattern = if self.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| format!("Invalid pattern: {}", e))?;
+
+        let folders = self.folders
+            .iter()
+            .map(|f| f.replace('\\', "/").trim_matches('/').to_string())
+            .filter(|f| !f.is_empty())
+            .collect();
+        Ok(Replacer {
+            regex,
+            replacement: self.replacement.clone(),
+            expand: self.regex,
+            folders,
+            tags: self.tags.clone(),
+        })
+    }
+}
+
+impl Replacer {
+    /// Whether a note is in scope, given its vault-relative path and tags
+    pub fn includes(&self, rel_path: &str, note_tags: &[String]) -> bool {
+        if !is_markdown(rel_path) {
+            return false;
+        }
+        let in_folder = self.folders.is_empty()
+            || self.folders.iter().any(|folder| rel_path.starts_with(&format!("{}/", folder)));
+        let tagged = self.tags.is_empty()
+            || self.tags.iter().any(|query| note_tags.iter().any(|tag| tag_matches(tag, query)));
+        in_folder && tagged
+    }
+
+    /// Every non-empty match in `content`
+    pub fn find(&self, content: &str) -> Vec<ReplaceMatch> {
+        let mut matches = Vec::new();
+        for captures in self.regex.captures_iter(content) {
+            let whole = captures.get(0).unwrap();
+            if whole.as_str().is_empty() {
+                continue;
+            }
+            let replacement = if self.expand {
+                let mut expanded = String::new();
+                captures.expand(&self.replacement, &mut expanded);
+                expanded
+            } else {
+                self.replacement.clone()
+            };
+
+            let line_start = content[..whole.start()].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let line_end = content[whole.end()..].find('\n').map(|i| whole.end() + i).unwrap_or(content.len());
+            let before = &content[line_start..line_end];
+            let after = format!(
+                "{}{}{}",
+                &content[line_start..whole.start()],
+                replacement,
+                &content[whole.end()..line_end]
+            );
+
+            matches.push(ReplaceMatch {
+                start: whole.start(),
+                end: whole.end(),
+                line: line_number(content, whole.start()),
+                matched: whole.as_str().to_string(),
+                replacement,
+                before: excerpt(before),
+                after: excerpt(&after),
+            });
+        }
+        matches
+    }
+}
+
+/// Shortens long lines (minified tables, base64 images) for previews
+fn excerpt(text: &str) -> String {
+    let text = text.trim_end_matches('\r');
+    if text.chars().count() <= MAX_PREVIEW_CHARS {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(MAX_PREVIEW_CHARS).collect();
+    short.push('…');
+    short
+}
+
+/// Replaces the matches starting at one of the `selected` offsets; matches
+/// must come from `find` on the same content
+pub fn apply(content: &str, matches: &[ReplaceMatch], selected: &[usize]) -> (String, usize) {
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    let mut replaced = 0;
+    for m in matches.iter().filter(|m| selected.contains(&m.start)) {
+        output.push_str(&content[last..m.start]);
+        output.push_str(&m.replacement);
+        last = m.end;
+        replaced += 1;
+    }
+    output.push_str(&content[last..]);
+    (output, replaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(pattern: &str, replacement: &str) -> ReplaceQuery {
+        ReplaceQuery {
+            pattern: pattern.into(),
+            replacement: replacement.into(),
+            regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            folders: vec![],
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_find_and_apply_selected() {
+        let content = "Meet Alice.\nalice and ALICE$ met $1 Alicea\n";
+        let replacer = ReplaceQuery { whole_word: true, ..query("alice", "Bob $1") }.compile().unwrap();
+        let matches = replacer.find(content);
+        assert_eq!(matches.iter().map(|m| m.line).collect::<Vec<_>>(), vec![1, 2, 2]);
+        assert_eq!(matches[1].before, "alice and ALICE$ met $1 Alicea");
+        assert_eq!(matches[1].after, "Bob $1 and ALICE$ met $1 Alicea");
+
+        let (updated, count) = apply(content, &matches, &[matches[0].start, matches[2].start]);
+        assert_eq!(count, 2);
+        assert_eq!(updated, "Meet Bob $1.\nalice and Bob $1$ met $1 Alicea\n");
+
+        let replacer = ReplaceQuery { regex: true, case_sensitive: true, ..query(r"(\w+)@(\w+)", "$2 at ${1}") }.compile().unwrap();
+        let matches = replacer.find("mail me@home or you@work");
+        let all: Vec<usize> = matches.iter().map(|m| m.start).collect();
+        assert_eq!(apply("mail me@home or you@work", &matches, &all).0, "mail home at me or work at you");
+    }
+
+    #[test]
+    fn test_scope_by_folder_and_tag() {
+        let replacer = ReplaceQuery {
+            folders: vec!["Projects/".into()],
+            tags: vec!["#work".into()],
+            ..query("x", "y")
+        }
+        .compile()
+        .unwrap();
+        let tags = vec!["work/alpha".to_string()];
+        assert!(replacer.includes("Projects/Plan.md", &tags));
+        assert!(!replacer.includes("Projects/Plan.md", &[]));
+        assert!(!replacer.includes("Archive/Plan.md", &tags));
+        assert!(!replacer.includes("Projects/image.png", &tags));
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// Resolves wiki-link targets to vault-relative file paths the way Obsidian does:
+/// a path relative to the linking note or the vault root wins, otherwise the
+/// target is matched against file names and path suffixes, preferring the note
+/// next to the linking note and then the shortest path.
+#[derive(Debug, Default, Clone)]
+pub struct NoteResolver {
+    /// Every indexed file, vault-relative with `/` separators
+    files: BTreeSet<String>,
+    /// Lowercased file name (without `.md` for notes) -> files with that name
+    by_name: HashMap<String, Vec<String>>,
+    /// Lowercased relative path -> relative path, for exact path links
+    by_path: HashMap<String, String>,
+}
+
+impl NoteResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a resolver from absolute paths inside `vault_root`
+    pub fn from_paths(vault_root: &Path, paths: &[std::path::PathBuf]) -> Self {
+        let mut resolver = Self::new();
+        for path in paths {
+            if path.is_file() {
+                if let Some(rel) = relative_path(vault_root, path) {
+                    resolver.add_file(&rel);
+                }
+            }
+        }
+        resolver
+    }
+
+    pub fn add_file(&mut self, rel_path: &str) {
+        if !self.files.insert(rel_path.to_string()) {
+            return;
+        }
+        self.by_path.insert(rel_path.to_lowercase(), rel_path.to_string());
+        let entries = self.by_name.entry(name_key(rel_path)).or_default();
+        entries.push(rel_path.to_string());
+        entries.sort();
+    }
+
+    pub fn remove_file(&mut self, rel_path: &str) {
+        if !self.files.remove(rel_path) {
+            return;
+        }
+        self.by_path.remove(&rel_path.to_lowercase());
+        let key = name_key(rel_path);
+        if let Some(entries) = self.by_name.get_mut(&key) {
+            entries.retain(|p| p != rel_path);
+            if entries.is_empty() {
+                self.by_name.remove(&key);
+            }
+        }
+    }
+
+    pub fn contains(&self, rel_path: &str) -> bool {
+        self.files.contains(rel_path)
+    }
+
+    /// All indexed files
+    pub fn files(&self) -> impl Iterator<Item = &String> {
+        self.files.iter()
+    }
+
+    /// All indexed markdown notes
+    pub fn notes(&self) -> impl Iterator<Item = &String> {
+        self.files.iter().filter(|p| is_markdown(p))
+    }
+
+    /// Resolves a link target (the part before `#` or `|`) written in `source`
+    pub fn resolve(&self, target: &str, source: &str) -> Option<String> {
+        let target = target.trim().replace('\\', "/");
+        if target.is_empty() {
+            return self.files.get(source).cloned();
+        }
+
+        let source_dir = parent_dir(source);
+
+        // Relative links (./Note, ../Note) are resolved against the source folder only
+        if target.starts_with("./") || target.starts_with("../") {
+            let joined = normalize(&format!("{}/{}", source_dir, target))?;
+            return self.lookup_exact(&joined);
+        }
+
+        let target = target.trim_start_matches('/');
+
+        // A path next to the linking note wins, then an exact vault-relative path
+        if !source_dir.is_empty() {
+            if let Some(found) = self.lookup_exact(&format!("{}/{}", source_dir, target)) {
+                return Some(found);
+            }
+        }
+        if let Some(found) = self.lookup_exact(target) {
+            return Some(found);
+        }
+
+        let (dir_part, name_part) = match target.rfind('/') {
+            Some(idx) => (&target[..idx], &target[idx + 1..]),
+            None => ("", target),
+        };
+        let candidates = self.by_name.get(&name_key(name_part))?;
+
+        let suffix = format!("/{}", dir_part.to_lowercase());
+        let mut matches: Vec<&String> = candidates
+            .iter()
+            .filter(|path| {
+                if dir_part.is_empty() {
+                    return true;
+                }
+                let dir = parent_dir(path).to_lowercase();
+                dir == dir_part.to_lowercase() || dir.ends_with(&suffix)
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            let a_local = parent_dir(a) == source_dir;
+            let b_local = parent_dir(b) == source_dir;
+            b_local
+                .cmp(&a_local)
+                .then(a.matches('/').count().cmp(&b.matches('/').count()))
+                .then(a.len().cmp(&b.len()))
+                .then(a.cmp(b))
+        });
+
+        matches.first().map(|p| p.to_string())
+    }
+
+    /// Returns a link target that unambiguously resolves to `rel_path` from
+    /// `source`: the bare name when it is unique, otherwise the shortest
+    /// distinguishing path suffix.
+    pub fn shortest_link_target(&self, rel_path: &str, source: &str) -> String {
+        let without_ext = strip_md(rel_path);
+        let segments: Vec<&str> = without_ext.split('/').collect();
+
+        for take in 1..=segments.len() {
+            let candidate = segments[segments.len() - take..].join("/");
+            if self.resolve(&candidate, source).as_deref() == Some(rel_path) {
+                return candidate;
+            }
+        }
+
+        without_ext.to_string()
+    }
+
+    fn lookup_exact(&self, target: &str) -> Option<String> {
+        let lower = target.to_lowercase();
+        if has_extension(&lower) {
+            if let Some(found) = self.by_path.get(&lower) {
+                return Some(found.clone());
+            }
+        }
+        self.by_path.get(&format!("{}.md", lower)).cloned()
+    }
+}
+
+/// Converts an absolute path inside the vault to a `/`-separated relative path
+pub fn relative_path(vault_root: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(vault_root)
+        .ok()
+        .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+        .filter(|rel| !rel.is_empty())
+}
+
+pub fn is_markdown(path: &str) -> bool {
+    path.to_lowercase().ends_with(".md")
+}
+
+/// Folder part of a vault-relative path ("" for the vault root)
+pub fn parent_dir(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(idx) => &path[..idx],
+        None => "",
+    }
+}
+
+fn strip_md(path: &str) -> &str {
+    if is_markdown(path) {
+        &path[..path.len() - 3]
+    } else {
+        path
+    }
+}
+
+/// Lookup key for a file name: lowercased, `.md` stripped from notes
+fn name_key(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    strip_md(name).to_lowercase()
+}
+
+fn has_extension(target: &str) -> bool {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    match name.rfind('.') {
+        Some(idx) => idx > 0 && idx < name.len() - 1 && !name[idx + 1..].contains(' '),
+        None => false,
+    }
+}
+
+/// Collapses `.` and `..` segments; returns None when the path escapes the vault
+pub fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            other => parts.push(other),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver(files: &[&str]) -> NoteResolver {
+        let mut resolver = NoteResolver::new();
+        for file in files {
+            resolver.add_file(file);
+        }
+        resolver
+    }
+
+    #[test]
+    fn test_resolve_by_name_and_path() {
+        let r = resolver(&["Ideas.md", "Projects/Alpha.md", "Archive/Alpha.md", "files/diagram.png"]);
+
+        assert_eq!(r.resolve("ideas", "Projects/Alpha.md").as_deref(), Some("Ideas.md"));
+        assert_eq!(r.resolve("Archive/Alpha", "Ideas.md").as_deref(), Some("Archive/Alpha.md"));
+        assert_eq!(r.resolve("diagram.png", "Ideas.md").as_deref(), Some("files/diagram.png"));
+        assert_eq!(r.resolve("Missing", "Ideas.md"), None);
+    }
+
+    #[test]
+    fn test_ambiguous_names_prefer_local_then_shortest() {
+        let r = resolver(&["Alpha.md", "Projects/Alpha.md", "Projects/Deep/Alpha.md"]);
+
+        assert_eq!(r.resolve("Alpha", "Projects/Plan.md").as_deref(), Some("Projects/Alpha.md"));
+        assert_eq!(r.resolve("Alpha", "Other/Plan.md").as_deref(), Some("Alpha.md"));
+        assert_eq!(r.resolve("../Alpha", "Projects/Plan.md").as_deref(), Some("Alpha.md"));
+        assert_eq!(r.shortest_link_target("Projects/Deep/Alpha.md", "Other.md"), "Deep/Alpha");
+    }
+}
@@ -0,0 +1,192 @@
+use std::ops::Range;
+
+/// Returns the byte ranges of `content` that are code (fenced blocks and inline
+/// code spans). Link and tag parsers skip anything inside these ranges.
+pub fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut fence: Option<(char, usize, usize)> = None; // (fence char, fence length, block start)
+    let mut prose_start = 0;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim_start_matches(' ');
+        let indent = line.len() - trimmed.len();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char
+            .map(|c| trimmed.chars().take_while(|x| *x == c).count())
+            .unwrap_or(0);
+
+        match fence {
+            Some((open_char, open_len, block_start)) => {
+                let rest = trimmed[fence_len..].trim();
+                if indent < 4 && fence_char == Some(open_char) && fence_len >= open_len && rest.is_empty() {
+                    ranges.push(block_start..offset);
+                    fence = None;
+                    prose_start = offset;
+                }
+            }
+            None => {
+                if indent < 4 && fence_len >= 3 {
+                    let c = fence_char.unwrap();
+                    // A backtick fence's info string cannot itself contain backticks
+                    if c == '~' || !trimmed[fence_len..].contains('`') {
+                        ranges.extend(inline_code_ranges(content, prose_start..line_start));
+                        fence = Some((c, fence_len, line_start));
+                    }
+                }
+            }
+        }
+    }
+
+    match fence {
+        // An unclosed fence runs to the end of the document
+        Some((_, _, block_start)) => ranges.push(block_start..content.len()),
+        None => ranges.extend(inline_code_ranges(content, prose_start..content.len())),
+    }
+
+    ranges.sort_by_key(|r| r.start);
+    ranges
+}
+
+/// Finds inline code spans (matching backtick runs) within a prose region
+fn inline_code_ranges(content: &str, region: Range<usize>) -> Vec<Range<usize>> {
+    let bytes = content.as_bytes();
+    let mut ranges = Vec::new();
+    let mut i = region.start;
+
+    while i < region.end {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+
+        let open_start = i;
+        while i < region.end && bytes[i] == b'`' {
+            i += 1;
+        }
+        let run = i - open_start;
+
+        // Look for a closing run of exactly the same length
+        let mut j = i;
+        let mut close_end = None;
+        while j < region.end {
+            if bytes[j] == b'`' {
+                let close_start = j;
+                while j < region.end && bytes[j] == b'`' {
+                    j += 1;
+                }
+                if j - close_start == run {
+                    close_end = Some(j);
+                    break;
+                }
+            } else {
+                j += 1;
+            }
+        }
+
+        if let Some(end) = close_end {
+            ranges.push(open_start..end);
+            i = end;
+        }
+    }
+
+    ranges
+}
+
+/// Returns the byte range of a leading `---` frontmatter block (including both
+/// delimiter lines) and the range of the YAML between them
+pub fn frontmatter_range(content: &str) -> Option<(Range<usize>, Range<usize>)> {
+    let first_line_end = content.find('\n')?;
+    if content[..first_line_end].trim_end() != "---" {
+        return None;
+    }
+
+    let yaml_start = first_line_end + 1;
+    let mut offset = yaml_start;
+    for line in content[yaml_start..].split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some((0..offset, yaml_start..line_start));
+        }
+    }
+
+    None
+}
+
+/// Byte ranges of the destinations of `[text](destination)` and `![alt](destination)`
+pub fn markdown_link_destinations(content: &str) -> Vec<(usize, usize, &str)> {
+    let bytes = content.as_bytes();
+    let mut destinations = Vec::new();
+
+    for (pos, _) in content.match_indices("](") {
+        // Skip wiki-link closers like `]]( ` which are not markdown links
+        if pos > 0 && bytes[pos - 1] == b']' {
+            continue;
+        }
+        let start = pos + 2;
+        let rest = &content[start..];
+        let len = if rest.starts_with('<') {
+            match rest.find('>') {
+                Some(idx) => idx + 1,
+                None => continue,
+            }
+        } else {
+            rest.find(|c: char| c == ')' || c.is_whitespace()).unwrap_or(rest.len())
+        };
+        if len > 0 {
+            destinations.push((start, start + len, &content[start..start + len]));
+        }
+    }
+
+    destinations
+}
+
+/// Checks whether a byte offset falls inside any of the given (sorted) ranges
+pub fn in_ranges(ranges: &[Range<usize>], pos: usize) -> bool {
+    let idx = ranges.partition_point(|r| r.end <= pos);
+    idx < ranges.len() && ranges[idx].start <= pos
+}
+
+/// Returns the 1-based line number for a byte offset
+pub fn line_number(content: &str, pos: usize) -> usize {
+    content[..pos.min(content.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fenced_and_inline_code() {
+        let content = "a `[[x]]` b\n```\n[[y]]\n```\n[[z]]";
+        let ranges = code_ranges(content);
+
+        assert!(in_ranges(&ranges, content.find("[[x]]").unwrap()));
+        assert!(in_ranges(&ranges, content.find("[[y]]").unwrap()));
+        assert!(!in_ranges(&ranges, content.find("[[z]]").unwrap()));
+    }
+
+    #[test]
+    fn test_frontmatter_range() {
+        let content = "---\ntags: [a]\n---\nbody";
+        let (whole, yaml) = frontmatter_range(content).unwrap();
+
+        assert_eq!(&content[yaml], "tags: [a]\n");
+        assert_eq!(&content[whole.end..], "body");
+        assert!(frontmatter_range("no frontmatter\n---\n").is_none());
+    }
+
+    #[test]
+    fn test_unclosed_fence_and_backticks() {
+        let content = "``not code` here\n~~~\ninside";
+        let ranges = code_ranges(content);
+
+        assert!(!in_ranges(&ranges, content.find("here").unwrap()));
+        assert!(in_ranges(&ranges, content.find("inside").unwrap()));
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
+
+use super::frontmatter::NoteProperties;
+use super::links::parse_wiki_links;
+use super::resolver::is_markdown;
+use super::scanner::{code_ranges, frontmatter_range, in_ranges};
+
+/// A tag together with the number of notes using it (or any of its children)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Extracts the tags of a note: frontmatter `tags:` entries followed by inline
+/// `#tags` in the body. Tags are returned without `#`, de-duplicated
+/// case-insensitively, in order of first appearance.
+pub fn parse_tags(content: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut body_start = 0;
+
+    if let Some((whole, yaml)) = frontmatter_range(content) {
+        tags.extend(NoteProperties::from_yaml(&content[yaml]).tags);
+        body_start = whole.end;
+    }
+
+    tags.extend(inline_tags(content, body_start));
+
+    let mut seen = std::collections::HashSet::new();
+    tags.retain(|tag| seen.insert(tag.to_lowercase()));
+    tags
+}
+
+/// Finds `#tags` in the body, ignoring code, links, inline HTML and headings
+fn inline_tags(content: &str, body_start: usize) -> Vec<String> {
+    let mut skip = code_ranges(content);
+    skip.extend(parse_wiki_links(content).into_iter().map(|l| l.start..l.end));
+    skip.extend(html_tag_ranges(content));
+    skip.sort_by_key(|r| r.start);
+
+    let mut tags = Vec::new();
+    for (pos, _) in content[body_start..].match_indices('#') {
+        let pos = body_start + pos;
+
+        // A tag starts a word: `page#frag`, `](#anchor)` and `&#123;` are not tags
+        let preceded_ok = content[..pos]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        if !preceded_ok || in_ranges(&skip, pos) {
+            continue;
+        }
+
+        let rest = &content[pos + 1..];
+        let len: usize = rest
+            .chars()
+            .take_while(|c| is_tag_char(*c))
+            .map(char::len_utf8)
+            .sum();
+
+        if let Some(tag) = clean_tag(&rest[..len]) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+/// Byte ranges of inline HTML tags such as `<span style="color: #fff">`
+fn html_tag_ranges(content: &str) -> Vec<Range<usize>> {
+    let bytes = content.as_bytes();
+    let mut ranges = Vec::new();
+
+    for (pos, _) in content.match_indices('<') {
+        let next = bytes.get(pos + 1).copied().unwrap_or(b' ');
+        if !(next.is_ascii_alphabetic() || next == b'/' || next == b'!') {
+            continue;
+        }
+        if let Some(end) = content[pos..].find(['>', '\n']) {
+            if bytes[pos + end] == b'>' {
+                ranges.push(pos..pos + end + 1);
+            }
+        }
+    }
+
+    ranges
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '/'
+}
+
+/// Validates a tag name: no empty segments and at least one non-digit
+pub(super) fn clean_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim_matches('/');
+    if tag.is_empty() || !tag.chars().all(is_tag_char) || tag.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    if tag.split('/').any(|segment| segment.is_empty()) {
+        return None;
+    }
+    Some(tag.to_string())
+}
+
+/// Normalizes a tag query: strips `#` and compares case-insensitively
+fn tag_key(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').trim_matches('/').to_lowercase()
+}
+
+/// Checks whether `tag` is `query` or nested below it (`project/alpha` matches `project`)
+pub fn tag_matches(tag: &str, query: &str) -> bool {
+    let tag = tag_key(tag);
+    let query = tag_key(query);
+    tag == query || (tag.len() > query.len() && tag.starts_with(&query) && tag.as_bytes()[query.len()] == b'/')
+}
+
+/// Vault-wide index of note tags
+#[derive(Debug, Default)]
+pub struct TagIndex {
+    /// Tags per markdown note (vault-relative path)
+    tags: HashMap<String, Vec<String>>,
+}
+
+impl TagIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or refreshes a note's tags; non-markdown files are ignored
+    pub fn update_file(&mut self, rel_path: &str, content: Option<&str>) {
+        if !is_markdown(rel_path) {
+            return;
+        }
+        let tags = content.map(parse_tags).unwrap_or_default();
+        if tags.is_empty() {
+            self.tags.remove(rel_path);
+        } else {
+            self.tags.insert(rel_path.to_string(), tags);
+        }
+    }
+
+    /// Removes a note, or every note below it when `rel_path` is a folder
+    pub fn remove_path(&mut self, rel_path: &str) {
+        let prefix = format!("{}/", rel_path);
+        self.tags.retain(|path, _| path != rel_path && !path.starts_with(&prefix));
+    }
+
+    pub fn tags_for_file(&self, rel_path: &str) -> Vec<String> {
+        self.tags.get(rel_path).cloned().unwrap_or_default()
+    }
+
+    /// Every tag with its note count. Parent tags are listed too and count
+    /// the notes of all their nested tags.
+    pub fn all_tags(&self) -> Vec<TagCount> {
+        // Lowercased tag -> (display name, number of notes)
+        let mut counts: BTreeMap<String, (String, usize)> = BTreeMap::new();
+
+        for tags in self.tags.values() {
+            let mut keys: Vec<(String, String)> = Vec::new();
+            for tag in tags {
+                let mut end = 0;
+                for segment in tag.split('/') {
+                    end += segment.len();
+                    let prefix = &tag[..end];
+                    keys.push((prefix.to_lowercase(), prefix.to_string()));
+                    end += 1;
+                }
+            }
+            keys.sort();
+            keys.dedup_by(|a, b| a.0 == b.0);
+
+            for (key, display) in keys {
+                counts.entry(key).or_insert((display, 0)).1 += 1;
+            }
+        }
+
+        counts
+            .into_values()
+            .map(|(tag, count)| TagCount { tag, count })
+            .collect()
+    }
+
+    /// Notes tagged with `tag` or any tag nested below it, sorted by path
+    pub fn notes_with_tag(&self, tag: &str) -> Vec<String> {
+        let mut notes: Vec<String> = self.tags
+            .iter()
+            .filter(|(_, tags)| tags.iter().any(|t| tag_matches(t, tag)))
+            .map(|(path, _)| path.clone())
+            .collect();
+        notes.sort();
+        notes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tags_skips_code_links_and_html() {
+        let content = "---\ntags: [alpha, \"#beta\"]\n---\n# Heading\n#gamma and #project/alpha\n\
+                       `#code` [[Note#Heading]] http://x.com/page#frag <span style=\"color: #fff\">x</span> #123 #Alpha\n\
+                       ```\n#fenced\n```";
+
+        assert_eq!(parse_tags(content), vec!["alpha", "beta", "gamma", "project/alpha"]);
+    }
+
+    #[test]
+    fn test_frontmatter_block_list() {
+        let content = "---\ntitle: x\ntags:\n  - one\n  - two/three\naliases: [y]\n---\nbody";
+
+        assert_eq!(parse_tags(content), vec!["one", "two/three"]);
+    }
+
+    #[test]
+    fn test_nested_tag_queries_and_counts() {
+        let mut index = TagIndex::new();
+        index.update_file("a.md", Some("#project/alpha"));
+        index.update_file("b.md", Some("#Project #projects"));
+        index.update_file("c.md", Some("no tags"));
+
+        assert_eq!(index.notes_with_tag("#project"), vec!["a.md", "b.md"]);
+        assert_eq!(index.notes_with_tag("project/alpha"), vec!["a.md"]);
+
+        let counts = index.all_tags();
+        let project = counts.iter().find(|t| t.tag.eq_ignore_ascii_case("project")).unwrap();
+        assert_eq!(project.count, 2);
+
+        index.remove_path("a.md");
+        assert_eq!(index.notes_with_tag("project"), vec!["b.md"]);
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+use chrono::{Datelike, Duration, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::resolver::is_markdown;
+use super::scanner::{code_ranges, frontmatter_range, in_ranges};
+use super::tags::{parse_tags, tag_matches};
+
+lazy_static::lazy_static! {
+    /// `- [ ] text`, `* [x] text`, `1. [/] text`; groups: status, text
+    static ref TASK: Regex = Regex::new(r"^[ \t]*(?:>[ \t]*)*(?:[-*+]|\d+[.)])[ \t]+\[(.)\][ \t]+(\S.*?)[ \t]*$").unwrap();
+    /// `📅 2026-10-20` and the other Tasks-plugin emoji dates
+    static ref EMOJI_DATE: Regex = Regex::new(r"(📅|⏳|⌛|🛫|✅)\x{FE0F}?[ \t]*(\d{4}-\d{2}-\d{2})").unwrap();
+    /// `due: 2026-10-20`, `[due:: 2026-10-20]`
+    static ref KEY_DATE: Regex = Regex::new(r"(?i)\[?\b(due|scheduled|start|done|completion)::?[ \t]*(\d{4}-\d{2}-\d{2})\]?").unwrap();
+    static ref EMOJI_PRIORITY: Regex = Regex::new(r"🔺|⏫|🔼|🔽|⏬").unwrap();
+    /// `priority: high`, `[priority:: high]`
+    static ref KEY_PRIORITY: Regex = Regex::new(r"(?i)\[?\bpriority::?[ \t]*(highest|high|medium|low|lowest)\]?").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// `[ ]`, and any marker not listed below
+    Todo,
+    /// `[/]`
+    InProgress,
+    /// `[x]`
+    Done,
+    /// `[-]`
+    Cancelled,
+}
+
+impl TaskStatus {
+    fn from_marker(marker: char) -> Self {
+        match marker {
+            'x' | 'X' => TaskStatus::Done,
+            '/' => TaskStatus::InProgress,
+            '-' => TaskStatus::Cancelled,
+            _ => TaskStatus::Todo,
+        }
+    }
+
+    pub fn is_open(self) -> bool {
+        matches!(self, TaskStatus::Todo | TaskStatus::InProgress)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskPriority {
+    Highest,
+    High,
+    Medium,
+    Low,
+    Lowest,
+}
+
+impl TaskPriority {
+    fn from_emoji(emoji: &str) -> Option<Self> {
+        match emoji {
+            "🔺" => Some(TaskPriority::Highest),
+            "⏫" => Some(TaskPriority::High),
+            "🔼" => Some(TaskPriority::Medium),
+            "🔽" => Some(TaskPriority::Low),
+            "⏬" => Some(TaskPriority::Lowest),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "highest" => Some(TaskPriority::Highest),
+            "high" => Some(TaskPriority::High),
+            "medium" => Some(TaskPriority::Medium),
+            "low" => Some(TaskPriority::Low),
+            "lowest" => Some(TaskPriority::Lowest),
+            _ => None,
+        }
+    }
+}
+
+/// Sort rank of a priority; tasks without one sort between medium and low
+fn priority_rank(priority: Option<TaskPriority>) -> u8 {
+    match priority {
+        Some(TaskPriority::Highest) => 0,
+        Some(TaskPriority::High) => 1,
+        Some(TaskPriority::Medium) => 2,
+        None => 3,
+        Some(TaskPriority::Low) => 4,
+        Some(TaskPriority::Lowest) => 5,
+    }
+}
+
+/// A checkbox list item in a note
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Task {
+    pub path: String,
+    /// 1-based line of the checkbox
+    pub line: usize,
+    pub status: TaskStatus,
+    /// Everything after the checkbox, as written
+    pub text: String,
+    /// `text` without its date and priority annotations
+    pub description: String,
+    pub due: Option<NaiveDate>,
+    pub scheduled: Option<NaiveDate>,
+    pub start: Option<NaiveDate>,
+    pub done: Option<NaiveDate>,
+    pub priority: Option<TaskPriority>,
+    /// Inline `#tags` of the task itself
+    pub tags: Vec<String>,
+}
+
+impl Task {
+    fn parse(path: &str, line: usize, marker: char, text: &str) -> Self {
+        let mut task = Task {
+            path: path.to_string(),
+            line,
+            status: TaskStatus::from_marker(marker),
+            text: text.to_string(),
+            description: String::new(),
+            due: None,
+            scheduled: None,
+            start: None,
+            done: None,
+            priority: None,
+            tags: parse_tags(text),
+        };
+
+        let dates = EMOJI_DATE.captures_iter(text)
+            .map(|c| (c.get(1).unwrap().as_str().to_string(), c.get(2).unwrap().as_str().to_string()))
+            .chain(KEY_DATE.captures_iter(text)
+                .map(|c| (c.get(1).unwrap().as_str().to_lowercase(), c.get(2).unwrap().as_str().to_string())));
+        for (kind, date) in dates {
+            let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else { continue };
+            let slot = match kind.as_str() {
+                "📅" | "due" => &mut task.due,
+                "⏳" | "⌛" | "scheduled" => &mut task.scheduled,
+                "🛫" | "start" => &mut task.start,
+                _ => &mut task.done,
+            };
+            slot.get_or_insert(date);
+        }
+
+        task.priority = EMOJI_PRIORITY.find(text)
+            .and_then(|m| TaskPriority::from_emoji(m.as_str()))
+            .or_else(|| KEY_PRIORITY.captures(text).and_then(|c| TaskPriority::from_name(&c[1])));
+
+        let mut description = text.to_string();
+        for regex in [&*EMOJI_DATE, &*KEY_DATE, &*EMOJI_PRIORITY, &*KEY_PRIORITY] {
+            description = regex.replace_all(&description, "").into_owned();
+        }
+        task.description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+        task
+    }
+}
+
+/// Finds every task in a note, skipping frontmatter and code blocks
+pub fn parse_tasks(path: &str, content: &str) -> Vec<Task> {
+    let code = code_ranges(content);
+    let body_start = frontmatter_range(content).map(|(whole, _)| whole.end).unwrap_or(0);
+
+    let mut tasks = Vec::new();
+    let mut offset = 0;
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let line_start = offset;
+        offset += line.len();
+        if line_start < body_start || in_ranges(&code, line_start) {
+            continue;
+        }
+        if let Some(captures) = TASK.captures(line.trim_end_matches(['\n', '\r'])) {
+            let marker = captures[1].chars().next().unwrap_or(' ');
+            tasks.push(Task::parse(path, i + 1, marker, &captures[2]));
+        }
+    }
+    tasks
+}
+
+/// Flips the task on `line` between open and done, returning the new content
+/// and task. `expected_text` is the task's `text` as last read; if the line no
+/// longer holds that task the note is left alone. Completing a task adds a
+/// `✅` done date, reopening it removes the date again.
+pub fn toggle_task(path: &str, content: &str, line: usize, expected_text: &str, today: NaiveDate) -> Result<(String, Task), String> {
+    let task = parse_tasks(path, content)
+        .into_iter()
+        .find(|t| t.line == line && t.text == expected_text)
+        .ok_or_else(|| format!("Line {} no longer holds this task; refresh and try again", line))?;
+
+    let mut lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let original = lines[line - 1];
+    let body = original.trim_end_matches(['\n', '\r']);
+    let ending = &original[body.len()..];
+    let captures = TASK.captures(body).ok_or("Task line could not be parsed")?;
+    let status = captures.get(1).unwrap();
+    let text = captures.get(2).unwrap();
+
+    let (marker, new_text) = if task.status.is_open() {
+        let new_text = if task.done.is_some() {
+            text.as_str().to_string()
+        } else {
+            format!("{} ✅ {}", text.as_str(), today.format("%Y-%m-%d"))
+        };
+        ("x", new_text)
+    } else {
+        let without_done = EMOJI_DATE.replace_all(text.as_str(), |c: &regex::Captures| {
+            if &c[1] == "✅" { String::new() } else { c[0].to_string() }
+        });
+        let without_done = KEY_DATE.replace_all(&without_done, |c: &regex::Captures| {
+            if c[1].eq_ignore_ascii_case("done") || c[1].eq_ignore_ascii_case("completion") { String::new() } else { c[0].to_string() }
+        });
+        (" ", without_done.trim_end().to_string())
+    };
+
+    let new_line = format!(
+        "{}{}{}{}{}{}",
+        &body[..status.start()],
+        marker,
+        &body[status.end()..text.start()],
+        new_text,
+        &body[text.end()..],
+        ending,
+    );
+    lines[line - 1] = &new_line;
+    let updated = lines.concat();
+
+    let marker = marker.chars().next().unwrap();
+    Ok((updated, Task::parse(&task.path, line, marker, &new_text)))
+}
+
+/// Relative due-date windows, evaluated against the current day
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DueWindow {
+    /// Due before today
+    Overdue,
+    Today,
+    /// Due Monday to Sunday of the current week
+    ThisWeek,
+    /// No due date
+    None,
+}
+
+/// Filters for `TaskIndex::query`; every field is optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskQuery {
+    /// Statuses to include; all when empty
+    #[serde(default)]
+    pub status: Vec<TaskStatus>,
+    #[serde(default)]
+    pub due: Option<DueWindow>,
+    /// Inclusive due date bounds; tasks without a due date never match them
+    #[serde(default)]
+    pub due_after: Option<NaiveDate>,
+    #[serde(default)]
+    pub due_before: Option<NaiveDate>,
+    /// Only tasks with one of these tags (nested tags included); all when empty
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Only tasks in notes in these folders (vault-relative); all when empty
+    #[serde(default)]
+    pub folders: Vec<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl TaskQuery {
+    fn matches(&self, task: &Task, today: NaiveDate) -> bool {
+        if !self.status.is_empty() && !self.status.contains(&task.status) {
+            return false;
+        }
+        let due_ok = match (self.due, task.due) {
+            (None, _) => true,
+            (Some(DueWindow::None), due) => due.is_none(),
+            (Some(_), None) => false,
+            (Some(DueWindow::Overdue), Some(due)) => due < today,
+            (Some(DueWindow::Today), Some(due)) => due == today,
+            (Some(DueWindow::ThisWeek), Some(due)) => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                due >= monday && due < monday + Duration::days(7)
+            }
+        };
+        let after_ok = self.due_after.is_none_or(|after| task.due.is_some_and(|due| due >= after));
+        let before_ok = self.due_before.is_none_or(|before| task.due.is_some_and(|due| due <= before));
+        let in_folder = self.folders.is_empty()
+            || self.folders.iter().any(|folder| {
+                let folder = folder.replace('\\', "/");
+                let folder = folder.trim_matches('/');
+                folder.is_empty() || task.path.starts_with(&format!("{}/", folder))
+            });
+        let tagged = self.tags.is_empty()
+            || self.tags.iter().any(|query| task.tags.iter().any(|tag| tag_matches(tag, query)));
+        due_ok && after_ok && before_ok && in_folder && tagged
+    }
+}
+
+/// Vault-wide index of tasks
+#[derive(Debug, Default)]
+pub struct TaskIndex {
+    /// Tasks per markdown note (vault-relative path), in line order
+    tasks: HashMap<String, Vec<Task>>,
+}
+
+impl TaskIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or refreshes a note's tasks; non-markdown files are ignored
+    pub fn update_file(&mut self, rel_path: &str, content: Option<&str>) {
+        if !is_markdown(rel_path) {
+            return;
+        }
+        let tasks = content.map(|c| parse_tasks(rel_path, c)).unwrap_or_default();
+        if tasks.is_empty() {
+            self.tasks.remove(rel_path);
+        } else {
+            self.tasks.insert(rel_path.to_string(), tasks);
+        }
+    }
+
+    /// Removes a note, or every note below it when `rel_path` is a folder
+    pub fn remove_path(&mut self, rel_path: &str) {
+        let prefix = format!("{}/", rel_path);
+        self.tasks.retain(|path, _| path != rel_path && !path.starts_with(&prefix));
+    }
+
+    pub fn tasks_for_file(&self, rel_path: &str) -> Vec<Task> {
+        self.tasks.get(rel_path).cloned().unwrap_or_default()
+    }
+
+    /// Tasks matching `query`, soonest due first, then by priority and position
+    pub fn query(&self, query: &TaskQuery, today: NaiveDate) -> Vec<Task> {
+        let mut tasks: Vec<Task> = self.tasks
+            .values()
+            .flatten()
+            .filter(|task| query.matches(task, today))
+            .cloned()
+            .collect();
+        tasks.sort_by(|a, b| {
+            (a.due.is_none(), a.due, priority_rank(a.priority), &a.path, a.line)
+                .cmp(&(b.due.is_none(), b.due, priority_rank(b.priority), &b.path, b.line))
+        });
+        if let Some(limit) = query.limit {
+            tasks.truncate(limit);
+        }
+        tasks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_tasks_and_query() {
+        let content = "---\nlist:\n- [ ] not a task\n---\n\
+                       - [ ] Call Ana 📅 2026-10-20 ⏫ #work\n\
+                       \t* [x] Sent invoice due: 2026-10-01 ✅ 2026-10-02\n\
+                       ```\n- [ ] in code\n```\n\
+                       1. [/] Draft [due:: 2026-10-25] [priority:: low] #work/writing\n\
+                       - [ ] Someday\n\
+                       - not a task [ ]\n";
+        let tasks = parse_tasks("Projects/a.md", content);
+
+        assert_eq!(tasks.len(), 4);
+        assert_eq!(tasks[0].line, 5);
+        assert_eq!(tasks[0].description, "Call Ana #work");
+        assert_eq!(tasks[0].due, Some(date("2026-10-20")));
+        assert_eq!(tasks[0].priority, Some(TaskPriority::High));
+        assert_eq!(tasks[1].status, TaskStatus::Done);
+        assert_eq!(tasks[1].done, Some(date("2026-10-02")));
+        assert_eq!(tasks[2].status, TaskStatus::InProgress);
+        assert_eq!(tasks[2].priority, Some(TaskPriority::Low));
+        assert_eq!(tasks[2].tags, vec!["work/writing"]);
+
+        let mut index = TaskIndex::new();
+        index.update_file("Projects/a.md", Some(content));
+        index.update_file("b.md", Some("- [ ] Elsewhere 📅 2026-10-19"));
+
+        // 2026-10-21 is a Wednesday
+        let today = date("2026-10-21");
+        let this_week = TaskQuery {
+            status: vec![TaskStatus::Todo, TaskStatus::InProgress],
+            due: Some(DueWindow::ThisWeek),
+            ..Default::default()
+        };
+        let found: Vec<_> = index.query(&this_week, today).into_iter().map(|t| t.description).collect();
+        assert_eq!(found, vec!["Elsewhere", "Call Ana #work", "Draft #work/writing"]);
+
+        let by_tag = TaskQuery { tags: vec!["work".into()], folders: vec!["Projects".into()], ..Default::default() };
+        assert_eq!(index.query(&by_tag, today).len(), 2);
+
+        index.remove_path("Projects");
+        assert_eq!(index.query(&TaskQuery::default(), today).len(), 1);
+    }
+
+    #[test]
+    fn test_toggle_task_writes_back_to_its_line() {
+        let content = "# Tasks\r\n- [ ] First 📅 2026-10-20\r\n- [x] Second ✅ 2026-10-01\r\n";
+        let today = date("2026-10-21");
+
+        let (updated, task) = toggle_task("a.md", content, 2, "First 📅 2026-10-20", today).unwrap();
+        assert_eq!(updated, "# Tasks\r\n- [x] First 📅 2026-10-20 ✅ 2026-10-21\r\n- [x] Second ✅ 2026-10-01\r\n");
+        assert_eq!(task.status, TaskStatus::Done);
+        assert_eq!(task.done, Some(today));
+
+        let (updated, task) = toggle_task("a.md", &updated, 3, "Second ✅ 2026-10-01", today).unwrap();
+        assert!(updated.ends_with("- [ ] Second\r\n"));
+        assert_eq!(task.status, TaskStatus::Todo);
+
+        assert!(toggle_task("a.md", content, 2, "Something else", today).is_err());
+        assert!(toggle_task("a.md", content, 1, "Tasks", today).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use super::links::parse_wiki_links;
+use super::resolver::{is_markdown, NoteResolver};
+use super::scanner::{code_ranges, frontmatter_range, in_ranges};
+
+/// How deep `![[embeds]]` inside embedded content are followed
+const MAX_EMBED_DEPTH: usize = 8;
+
+/// Resolves `![[Note]]`, `![[Note#Heading]]` and `![[Note#^block]]` embeds to
+/// the markdown they point at. Notes are loaded through `read_note`, which
+/// takes a vault-relative path, so callers decide where content comes from.
+pub struct Transcluder<'a> {
+    resolver: &'a NoteResolver,
+    read_note: &'a dyn Fn(&str) -> Option<String>,
+}
+
+impl<'a> Transcluder<'a> {
+    pub fn new(resolver: &'a NoteResolver, read_note: &'a dyn Fn(&str) -> Option<String>) -> Self {
+        Self { resolver, read_note }
+    }
+
+    /// Returns the markdown an embed points at, with nested embeds expanded.
+    /// None when the note, heading or block does not exist.
+    pub fn fragment(
+        &self,
+        target: &str,
+        heading: Option<&str>,
+        block_id: Option<&str>,
+        source: &str,
+    ) -> Option<String> {
+        self.fragment_inner(target, heading, block_id, source, &mut Vec::new())
+    }
+
+    /// Replaces every note embed in `content` with the embedded markdown.
+    /// Attachment embeds (images, PDFs) and unresolved embeds are left as written.
+    pub fn expand(&self, content: &str, source: &str) -> String {
+        self.expand_inner(content, source, &mut Vec::new())
+    }
+
+    fn fragment_inner(
+        &self,
+        target: &str,
+        heading: Option<&str>,
+        block_id: Option<&str>,
+        source: &str,
+        stack: &mut Vec<String>,
+    ) -> Option<String> {
+        let path = self.resolver.resolve(target, source)?;
+        if !is_markdown(&path) {
+            return None;
+        }
+
+        let content = (self.read_note)(&path)?;
+        let fragment = match (heading, block_id) {
+            (_, Some(block_id)) => block_content(&content, block_id)?,
+            (Some(heading), None) => heading_section(&content, heading)?.to_string(),
+            (None, None) => strip_frontmatter(&content).to_string(),
+        };
+
+        let key = embed_key(&path, heading, block_id);
+        stack.push(key);
+        let expanded = self.expand_inner(&fragment, &path, stack);
+        stack.pop();
+
+        Some(expanded)
+    }
+
+    fn expand_inner(&self, content: &str, source: &str, stack: &mut Vec<String>) -> String {
+        let mut output = String::with_capacity(content.len());
+        let mut last = 0;
+
+        for link in parse_wiki_links(content).into_iter().filter(|l| l.is_embed) {
+            let path = match self.resolver.resolve(&link.target, source) {
+                Some(path) if is_markdown(&path) => path,
+                _ => continue,
+            };
+
+            let heading = link.heading.as_deref();
+            let block_id = link.block_id.as_deref();
+
+            let replacement = if stack.contains(&embed_key(&path, heading, block_id)) || stack.len() >= MAX_EMBED_DEPTH {
+                Some(format!("*(circular embed: {})*", &content[link.start + 3..link.end - 2]))
+            } else {
+                self.fragment_inner(&link.target, heading, block_id, source, stack)
+            };
+
+            if let Some(replacement) = replacement {
+                output.push_str(&content[last..link.start]);
+                output.push_str(replacement.trim_end());
+                last = link.end;
+            }
+        }
+
+        output.push_str(&content[last..]);
+        output
+    }
+}
+
+fn embed_key(path: &str, heading: Option<&str>, block_id: Option<&str>) -> String {
+    match (heading, block_id) {
+        (_, Some(block_id)) => format!("{}#^{}", path, block_id),
+        (Some(heading), None) => format!("{}#{}", path, normalize_heading(heading)),
+        (None, None) => path.to_string(),
+    }
+}
+
+fn strip_frontmatter(content: &str) -> &str {
+    match frontmatter_range(content) {
+        Some((whole, _)) => content[whole.end..].trim_start_matches('\n'),
+        None => content,
+    }
+}
+
+/// Returns the level and text of an ATX heading line (`## Text ##`)
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() >= 4 {
+        return None;
+    }
+
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    if level == 0 || level > 6 || !(rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n'])) {
+        return None;
+    }
+
+    let text = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, text))
+}
+
+/// Headings in links drop characters Obsidian cannot put in a link, so both
+/// sides are compared with whitespace collapsed and case ignored
+fn normalize_heading(heading: &str) -> String {
+    heading
+        .split(|c: char| c.is_whitespace() || matches!(c, '#' | '|' | '^' | ':' | '[' | ']'))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Slices out a heading and everything below it up to the next heading of the
+/// same or a higher level
+pub fn heading_section<'c>(content: &'c str, heading: &str) -> Option<&'c str> {
+    let code = code_ranges(content);
+    let wanted = normalize_heading(heading);
+    let mut section: Option<(usize, usize)> = None; // (start, level)
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        if in_ranges(&code, line_start) {
+            continue;
+        }
+        let (level, text) = match parse_heading(line) {
+            Some(found) => found,
+            None => continue,
+        };
+
+        match section {
+            Some((start, open_level)) if level <= open_level => {
+                return Some(content[start..line_start].trim_end());
+            }
+            None if normalize_heading(text) == wanted => section = Some((line_start, level)),
+            _ => {}
+        }
+    }
+
+    section.map(|(start, _)| content[start..].trim_end())
+}
+
+/// Returns the block anchored with `^block_id`: the paragraph, list item (with
+/// its nested items) or heading the marker ends, or the block right above a
+/// marker that sits on its own line. The marker itself is removed.
+pub fn block_content(content: &str, block_id: &str) -> Option<String> {
+    let code = code_ranges(content);
+    let marker = format!("^{}", block_id);
+    let lines: Vec<(usize, &str)> = content
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line.trim_end_matches(['\n', '\r'])))
+        })
+        .collect();
+
+    let idx = lines.iter().position(|(start, line)| {
+        let trimmed = line.trim_end();
+        !in_ranges(&code, *start)
+            && trimmed.ends_with(&marker)
+            && trimmed[..trimmed.len() - marker.len()]
+                .chars()
+                .next_back()
+                .is_none_or(char::is_whitespace)
+    })?;
+
+    let line = lines[idx].1.trim_end();
+    let without_marker = line[..line.len() - marker.len()].trim_end();
+
+    // A marker on its own line refers to the block above it
+    if without_marker.trim().is_empty() {
+        let end = lines[..idx].iter().rposition(|(_, l)| !l.trim().is_empty())?;
+        let start = paragraph_start(&lines, end);
+        return Some(join_lines(&lines[start..=end]));
+    }
+
+    if parse_heading(line).is_some() {
+        return Some(without_marker.to_string());
+    }
+
+    if is_list_item(line) {
+        let indent = indent_of(line);
+        let mut block = vec![without_marker.to_string()];
+        for (_, child) in &lines[idx + 1..] {
+            if child.trim().is_empty() || indent_of(child) <= indent {
+                break;
+            }
+            block.push(child.to_string());
+        }
+        return Some(block.join("\n"));
+    }
+
+    let start = paragraph_start(&lines, idx);
+    let mut block = join_lines(&lines[start..idx]);
+    if !block.is_empty() {
+        block.push('\n');
+    }
+    block.push_str(without_marker);
+    Some(block)
+}
+
+/// Index of the first line of the paragraph containing line `end`
+fn paragraph_start(lines: &[(usize, &str)], end: usize) -> usize {
+    let mut start = end;
+    while start > 0 {
+        let previous = lines[start - 1].1;
+        if previous.trim().is_empty() || parse_heading(previous).is_some() {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+fn join_lines(lines: &[(usize, &str)]) -> String {
+    lines.iter().map(|(_, l)| *l).collect::<Vec<_>>().join("\n")
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ ") {
+        return true;
+    }
+    let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && (trimmed[digits..].starts_with(". ") || trimmed[digits..].starts_with(") "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_heading_section_and_blocks() {
+        let content = "# Title\nintro\n## Setup\nstep one\n### Detail\nmore\n## Next\nother\n\n\
+                       A paragraph\nthat continues ^para\n\n- item ^item\n  - child\n- sibling\n\n> quote\n\n^quote";
+
+        assert_eq!(heading_section(content, "setup"), Some("## Setup\nstep one\n### Detail\nmore"));
+        assert_eq!(block_content(content, "para").as_deref(), Some("A paragraph\nthat continues"));
+        assert_eq!(block_content(content, "item").as_deref(), Some("- item\n  - child"));
+        assert_eq!(block_content(content, "quote").as_deref(), Some("> quote"));
+        assert_eq!(block_content(content, "missing"), None);
+    }
+
+    #[test]
+    fn test_nested_embeds_and_cycles() {
+        let notes: HashMap<&str, &str> = [
+            ("A.md", "---\ntags: [x]\n---\nA body ![[B#Part]]"),
+            ("B.md", "# Part\nB part ![[C]]\n# Other"),
+            ("C.md", "C embeds ![[A]] and ![[pic.png]]"),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut resolver = NoteResolver::new();
+        for path in notes.keys() {
+            resolver.add_file(path);
+        }
+        let read = |path: &str| notes.get(path).map(|c| c.to_string());
+        let transcluder = Transcluder::new(&resolver, &read);
+
+        let expanded = transcluder.fragment("A", None, None, "").unwrap();
+        assert_eq!(
+            expanded,
+            "A body # Part\nB part C embeds *(circular embed: A)* and ![[pic.png]]"
+        );
+    }
+}
@@ -1,7 +1,21 @@
 // Re-export modules needed by the test binary
 pub mod vault;
+pub mod vault_ignore;
 pub mod vault_id;
 pub mod graph;
+pub mod index;
+pub mod search;
+pub mod pdf_export;
+pub mod file_tree;
+pub mod trash;
+pub mod history;
+pub mod encryption;
+pub mod git;
+pub mod publish;
+pub mod backup;
+pub mod periodic;
+pub mod templates;
+pub mod import;
 pub mod docker;
 pub mod ai_settings;
 pub mod ai_settings_multi;
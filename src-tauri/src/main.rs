@@ -14,6 +14,7 @@ use std::sync::mpsc::channel;
 use std::time::Duration;
 
 mod vault;
+mod vault_ignore;
 mod vault_id;
 mod editor;
 mod pdf_export;
@@ -29,6 +30,17 @@ mod docker;
 mod graph;
 mod commands;
 mod search;
+mod index;
+mod file_tree;
+mod trash;
+mod history;
+mod encryption;
+mod git;
+mod publish;
+mod backup;
+mod periodic;
+mod templates;
+mod import;
 mod window_state;
 mod refactored_app_state;
 mod window_factory;
@@ -67,26 +79,20 @@ pub struct AppState {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct VaultInfo {
-    path: String,
-    name: String,
+struct VersionedContent {
+    content: String,
+    version: vault::FileVersion,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct FileInfo {
+struct VaultInfo {
     path: String,
     name: String,
-    is_dir: bool,
-    extension: Option<String>,
-    depth: usize,
-    parent_path: Option<String>,
-    created: Option<i64>,  // Unix timestamp
-    modified: Option<i64>, // Unix timestamp
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct FileTree {
-    files: Vec<FileInfo>,
+    files: Vec<file_tree::FileInfo>,
 }
 
 #[tauri::command]
@@ -155,6 +161,21 @@ async fn open_vault(path: String, window: tauri::Window, app: tauri::AppHandle,
         refactored_state.register_window_with_id(window_id.clone(), app.clone()).await?;
     }
     
+    // Apply excluded folders before anything scans the vault, and purge trash
+    // entries past the vault's retention period; their notes leave the graph
+    // once it is connected
+    let mut purged_notes = Vec::new();
+    match vault_settings::get_vault_settings(app.clone(), path.clone()).await {
+        Ok(settings) => {
+            refactored_state.set_excluded_folders(&vault_path, &settings.files.excluded_folders);
+            match trash::Trash::new(&vault_path).purge_expired(settings.files.trash_retention_days) {
+                Ok(notes) => purged_notes = notes,
+                Err(e) => eprintln!("⚠️ Failed to purge expired trash entries: {}", e),
+            }
+        }
+        Err(e) => eprintln!("⚠️ Failed to load vault settings: {}", e),
+    }
+    
     // Register the window with the vault
     refactored_state.register_window_vault(&window_id, vault_path.clone()).await?;
     
@@ -199,6 +220,7 @@ async fn open_vault(path: String, window: tauri::Window, app: tauri::AppHandle,
                         match new_graph_manager.connect(&graph_config).await {
                             Ok(_) => {
                                 println!("✅ Connected to graph databases for window {}", window_id_clone);
+                                commands::trash::delete_graph_notes(&new_graph_manager, &vault_path_clone, &purged_notes).await;
                                 
                                 // Initialize update queue for this vault
                                 let update_queue_config = UpdateQueueConfig::default();
@@ -223,10 +245,19 @@ async fn open_vault(path: String, window: tauri::Window, app: tauri::AppHandle,
 }
 
 #[tauri::command]
-async fn read_file_base64(path: String) -> Result<String, String> {
+async fn read_file_base64(path: String, window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<String, String> {
     use base64::{Engine as _, engine::general_purpose};
     
-    let file_path = PathBuf::from(&path);
+    let window_id = extract_window_id(&window);
+    let window_state = refactored_state.get_window_state(&window_id).await
+        .ok_or_else(|| "Window not found".to_string())?;
+    let file_path = {
+        let vault_lock = window_state.vault.lock().await;
+        match &*vault_lock {
+            Some(vault) => vault.resolve_path(std::path::Path::new(&path))?,
+            None => return Err("No vault opened".to_string()),
+        }
+    };
     
     if !file_path.exists() {
         return Err(format!("File does not exist: {}", path));
@@ -392,13 +423,11 @@ async fn create_new_vault(parent_path: String, vault_name: String, window: tauri
         .map_err(|e| format!("Failed to create vault directory: {}", e))?;
     
     // Create a welcome note
-    let welcome_content = format!(
-        "# Welcome to {}\n\nThis is your new gaimplan vault! Start taking notes by creating new markdown files.\n\n## Getting Started\n\n- Create new notes by clicking the + button\n- Organize your thoughts in folders\n- All your notes are stored as plain markdown files\n\nHappy note-taking! ✨\n",
-        vault_name.trim()
-    );
+    let welcome = templates::Templates::new(&vault_path, &vault_settings::FileSettings::default().templates_folder)
+        .render_source(templates::WELCOME_TEMPLATE, &templates::TemplateContext::new(vault_name.trim()))?;
     
     let welcome_path = vault_path.join("Welcome.md");
-    std::fs::write(&welcome_path, welcome_content)
+    std::fs::write(&welcome_path, welcome.content)
         .map_err(|e| format!("Failed to create welcome note: {}", e))?;
     
     // Now open the vault
@@ -406,98 +435,17 @@ async fn create_new_vault(parent_path: String, vault_name: String, window: tauri
     open_vault(vault_path_str, window, app, refactored_state).await
 }
 
+/// Lists every folder, note and attachment from the vault's cached tree
 #[tauri::command]
 async fn get_file_tree(window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<FileTree, String> {
     let window_id = extract_window_id(&window);
-    
-    match refactored_state.get_window_state(&window_id).await {
-        Some(window_state) => {
-            let vault_lock = window_state.vault.lock().await;
-            match &*vault_lock {
-        Some(vault) => {
-            let files = vault.list_markdown_files()
-                .map_err(|e| format!("Failed to list files: {}", e))?;
-            
-            let mut file_infos = Vec::new();
-            
-            // Add all directories and files with hierarchy info
-            for file_path in files {
-                // Debug log
-                // Processing file
-                
-                // Get relative path from vault root
-                let relative_path = match file_path.strip_prefix(vault.path()) {
-                    Ok(rel_path) => rel_path,
-                    Err(_) => {
-                        println!("⚠️ Could not strip prefix from path: {:?}", file_path);
-                        continue;
-                    }
-                };
-                
-                // Relative path
-                
-                // Calculate depth and parent
-                let path_str = relative_path.to_string_lossy().to_string();
-                let components: Vec<_> = relative_path.components().collect();
-                let depth = components.len() - 1; // Depth is number of path segments minus 1
-                let parent_path = if components.len() > 1 {
-                    Some(relative_path.parent().unwrap().to_string_lossy().to_string())
-                } else {
-                    None
-                };
-                
-                // File components
-                
-                // Get file metadata
-                let (created, modified) = match std::fs::metadata(&file_path) {
-                    Ok(metadata) => {
-                        let modified = metadata.modified()
-                            .ok()
-                            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
-                            .map(|duration| duration.as_secs() as i64);
-                        
-                        // Note: created() is not available on all platforms
-                        let created = metadata.created()
-                            .ok()
-                            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
-                            .map(|duration| duration.as_secs() as i64);
-                        
-                        (created, modified)
-                    }
-                    Err(e) => {
-                        println!("⚠️ Failed to get metadata for {:?}: {}", file_path, e);
-                        (None, None)
-                    }
-                };
-                
-                let file_info = FileInfo {
-                    path: path_str,
-                    name: relative_path.file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    is_dir: file_path.is_dir(),
-                    extension: file_path.extension().and_then(|s| s.to_str()).map(|s| s.to_string()),
-                    depth: components.len(),
-                    parent_path,
-                    created,
-                    modified,
-                };
-                
-                // Created FileInfo
-                file_infos.push(file_info);
-            }
-            
-            // Sort files for consistent tree view
-            file_infos.sort_by(|a, b| a.path.cmp(&b.path));
-            
-                    Ok(FileTree { files: file_infos })
-                }
-                None => Err("Vault not open".to_string()),
-            }
-        }
-        None => Err("Window not found".to_string()),
+    let tree = refactored_state.get_file_tree(&window_id).await?;
+    let mut files = tree.lock().await.all();
+
+    if let Some(vault_path) = refactored_state.get_window_vault_path(&window_id).await {
+        commands::files::mark_git_status(vault_path, &mut files).await;
     }
+    Ok(FileTree { files })
 }
 
 #[tauri::command]
@@ -515,11 +463,13 @@ async fn read_file_content(file_path: String, window: tauri::Window, refactored_
                     println!("📁 Vault path: {:?}", vault.path());
                     println!("📄 Reading relative path: {:?}", path);
 
-                    vault.read_file(path)
+                    let content = vault.read_file(path)
                         .map_err(|e| {
                             println!("❌ Failed to read file: {}", e);
                             format!("Failed to read file: {}", e)
-                        })
+                        })?;
+                    let unlocked = refactored_state.keyring.get(vault.path()).await;
+                    encryption::reveal(&file_path, content, unlocked.as_deref())
                 }
                 None => Err("No vault opened".to_string()),
             }
@@ -528,8 +478,40 @@ async fn read_file_content(file_path: String, window: tauri::Window, refactored_
     }
 }
 
+/// Reads a file along with the version to pass back to `write_file_content`
 #[tauri::command]
-async fn write_file_content(file_path: String, content: String, window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<(), String> {
+async fn read_file_versioned(file_path: String, window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<VersionedContent, String> {
+    let window_id = extract_window_id(&window);
+
+    match refactored_state.get_window_state(&window_id).await {
+        Some(window_state) => {
+            let vault_lock = window_state.vault.lock().await;
+            match &*vault_lock {
+                Some(vault) => {
+                    let (content, version) = vault.read_file_versioned(std::path::Path::new(&file_path))
+                        .map_err(|e| format!("Failed to read file: {}", e))?;
+                    // The version stays that of the file on disk, as saves compare against it
+                    let unlocked = refactored_state.keyring.get(vault.path()).await;
+                    let content = encryption::reveal(&file_path, content, unlocked.as_deref())?;
+                    Ok(VersionedContent { content, version })
+                }
+                None => Err("No vault opened".to_string()),
+            }
+        }
+        None => Err("Window not found".to_string()),
+    }
+}
+
+#[tauri::command]
+async fn write_file_content(
+    file_path: String,
+    content: String,
+    expected_hash: Option<String>,
+    expected_modified: Option<i64>,
+    app: AppHandle,
+    window: tauri::Window,
+    refactored_state: State<'_, RefactoredAppState>,
+) -> Result<vault::FileVersion, vault::WriteError> {
     let window_id = extract_window_id(&window);
 
     match refactored_state.get_window_state(&window_id).await {
@@ -539,19 +521,40 @@ async fn write_file_content(file_path: String, content: String, window: tauri::W
                 Some(vault) => {
                     let path = std::path::Path::new(&file_path);
                     
-                    // First, write the file to disk
-                    vault.write_file(path, &content)
-                        .map_err(|e| format!("Failed to write file: {}", e))?;
+                    // Encrypted notes, and notes in encrypted folders, only reach the disk encrypted
+                    let unlocked = refactored_state.keyring.get(vault.path()).await;
+                    let full_path = vault.resolve_path(path).map_err(String::from)?;
+                    let rel_path = index::resolver::relative_path(vault.path(), &full_path).unwrap_or_else(|| file_path.clone());
+                    let disk_content = encryption::conceal(vault.path(), &rel_path, &content, unlocked.as_deref())?;
+                    
+                    // First, write the file to disk, refusing if it changed since the client read it
+                    let version = vault.write_file_checked(path, &disk_content, expected_hash.as_deref(), expected_modified)
+                        .map_err(|e| {
+                            println!("⚠️ Not writing {}: {}", file_path, e);
+                            match e {
+                                vault::WriteError::Conflict { path, disk_content, disk_version, .. } => vault::WriteError::Conflict {
+                                    disk_content: encryption::reveal(&path, disk_content.clone(), unlocked.as_deref()).unwrap_or(disk_content),
+                                    client_content: content.clone(),
+                                    path,
+                                    disk_version,
+                                },
+                                e => e,
+                            }
+                        })?;
+                    
+                    // Keep the saved content in the note's version history, encrypted if the note is
+                    commands::history::record_save(&app, vault, &file_path, &disk_content).await;
+                    commands::git::note_saved(&app, &refactored_state.auto_commit, vault.path()).await;
                     
                     // For now, skip the update queue integration - it needs per-window setup
                     // TODO: Implement per-window update queue in the WindowState
                     
-                    Ok(())
+                    Ok(version)
                 }
-                None => Err("No vault opened".to_string()),
+                None => Err("No vault opened".to_string().into()),
             }
         }
-        None => Err("Window not found".to_string()),
+        None => Err("Window not found".to_string().into()),
     }
 }
 
@@ -582,7 +585,7 @@ async fn fetch_image_as_base64(url: String) -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn create_new_file(file_name: String, window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<(), String> {
+async fn create_new_file(file_name: String, app: AppHandle, window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<(), String> {
     println!("📝 create_new_file called with name: {}", file_name);
 
     let window_id = extract_window_id(&window);
@@ -596,14 +599,24 @@ async fn create_new_file(file_name: String, window: tauri::Window, refactored_st
                     println!("📁 Vault path: {:?}", vault.path());
                     println!("📄 Creating file: {:?}", path);
 
-                    // Create default content for new file
-                    let default_content = format!("# {}",
-                        path.file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("Untitled")
-                    );
+                    // Create default content for new file from the vault's new note template
+                    let title = path.file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("Untitled");
+                    let context = templates::TemplateContext::new(title);
+                    let templates = commands::templates::vault_templates(&app, vault.path()).await;
+                    let template = match get_vault_settings(app.clone(), vault.path().to_string_lossy().to_string()).await {
+                        Ok(settings) => settings.files.new_note_template,
+                        Err(_) => String::new(),
+                    };
+                    let rendered = if template.trim().is_empty() {
+                        templates.render_source(templates::NEW_NOTE_TEMPLATE, &context)?
+                    } else {
+                        templates.render(&template, &context)?
+                    };
 
-                    vault.write_file(path, &default_content)
+                    let unlocked = refactored_state.keyring.get(vault.path()).await;
+                    encryption::write_note(vault, path, &rendered.content, unlocked.as_deref())
                         .map_err(|e| {
                             println!("❌ Failed to create file: {}", e);
                             format!("Failed to create file: {}", e)
@@ -627,7 +640,7 @@ async fn create_new_folder(folder_name: String, window: tauri::Window, refactore
             let vault_lock = window_state.vault.lock().await;
             match &*vault_lock {
         Some(vault) => {
-            let folder_path = vault.path().join(&folder_name);
+            let folder_path = vault.resolve_path(std::path::Path::new(&folder_name))?;
             println!("📁 Creating folder at: {:?}", folder_path);
 
             std::fs::create_dir_all(&folder_path)
@@ -644,7 +657,7 @@ async fn create_new_folder(folder_name: String, window: tauri::Window, refactore
 }
 
 #[tauri::command]
-async fn delete_file(file_path: String, window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<(), String> {
+async fn delete_file(file_path: String, window: tauri::Window, state: State<'_, AppState>, refactored_state: State<'_, RefactoredAppState>) -> Result<(), String> {
     println!("🗑️ delete_file called with path: {}", file_path);
 
     let window_id = extract_window_id(&window);
@@ -654,17 +667,26 @@ async fn delete_file(file_path: String, window: tauri::Window, refactored_state:
             let vault_lock = window_state.vault.lock().await;
             match &*vault_lock {
         Some(vault) => {
-            let path = vault.path().join(&file_path);
+            let path = vault.resolve_path(std::path::Path::new(&file_path))?;
             println!("📁 Deleting file at: {:?}", path);
 
-            if path.is_file() {
-                std::fs::remove_file(&path)
+            if path.exists() {
+                // Deleted files and folders go to the vault trash so they can be restored
+                let trash = trash::Trash::new(vault.path());
+                let entry = trash.move_to_trash(&file_path)
                     .map_err(|e| {
                         println!("❌ Failed to delete file: {}", e);
                         format!("Failed to delete file: {}", e)
-                    })
+                    })?;
+
+                // The watcher only sees a rename, so the graph is told here
+                if let Some(graph_manager) = state.graph_manager.lock().await.clone() {
+                    let notes = trash.note_paths(&entry);
+                    commands::trash::mark_graph_notes_trashed(&graph_manager, vault.path(), &notes, true).await;
+                }
+                Ok(())
             } else {
-                Err("Path is not a file".to_string())
+                Err(format!("File does not exist: {}", file_path))
             }
                 }
                 None => Err("No vault opened".to_string()),
@@ -675,73 +697,39 @@ async fn delete_file(file_path: String, window: tauri::Window, refactored_state:
 }
 
 #[tauri::command]
-async fn move_file(old_path: String, new_path: String, window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<(), String> {
+async fn move_file(old_path: String, new_path: String, with_attachments: Option<bool>, window: tauri::Window, state: State<'_, AppState>, refactored_state: State<'_, RefactoredAppState>) -> Result<index::refactor::RenamePlan, String> {
     println!("📦 move_file called: {} -> {}", old_path, new_path);
 
     let window_id = extract_window_id(&window);
+    let index = refactored_state.get_vault_index(&window_id).await?;
+    let graph_manager = commands::refactor::current_graph_manager(&state).await;
+    let unlocked = refactored_state.keyring.get(index.vault_path()).await;
 
-    match refactored_state.get_window_state(&window_id).await {
-        Some(window_state) => {
-            let vault_lock = window_state.vault.lock().await;
-            match &*vault_lock {
-        Some(vault) => {
-            let old_full_path = vault.path().join(&old_path);
-            let new_full_path = vault.path().join(&new_path);
-            
-            println!("📁 Moving file: {:?} -> {:?}", old_full_path, new_full_path);
-
-            // Create parent directories if they don't exist
-            if let Some(parent) = new_full_path.parent() {
-                std::fs::create_dir_all(parent)
-                    .map_err(|e| format!("Failed to create parent directory: {}", e))?;
-            }
-
-            std::fs::rename(&old_full_path, &new_full_path)
-                .map_err(|e| {
-                    println!("❌ Failed to move file: {}", e);
-                    format!("Failed to move file: {}", e)
-                })
-                }
-                None => Err("No vault opened".to_string()),
-            }
-        }
-        None => Err("Window not found".to_string()),
-    }
+    // Moves the file or folder and rewrites every link pointing into it
+    commands::refactor::rename_with_links(&index, graph_manager, unlocked.as_deref(), &old_path, &new_path, with_attachments.unwrap_or(false))
+        .await
+        .map_err(|e| {
+            println!("❌ Failed to move file: {}", e);
+            e
+        })
 }
 
 #[tauri::command]
-async fn rename_file(old_path: String, new_path: String, window: tauri::Window, refactored_state: State<'_, RefactoredAppState>) -> Result<(), String> {
+async fn rename_file(old_path: String, new_path: String, window: tauri::Window, state: State<'_, AppState>, refactored_state: State<'_, RefactoredAppState>) -> Result<index::refactor::RenamePlan, String> {
     println!("✏️ rename_file called: {} -> {}", old_path, new_path);
 
     let window_id = extract_window_id(&window);
+    let index = refactored_state.get_vault_index(&window_id).await?;
+    let graph_manager = commands::refactor::current_graph_manager(&state).await;
+    let unlocked = refactored_state.keyring.get(index.vault_path()).await;
 
-    match refactored_state.get_window_state(&window_id).await {
-        Some(window_state) => {
-            let vault_lock = window_state.vault.lock().await;
-            match &*vault_lock {
-        Some(vault) => {
-            let old_full_path = vault.path().join(&old_path);
-            let new_full_path = vault.path().join(&new_path);
-            
-            println!("📁 Renaming file: {:?} -> {:?}", old_full_path, new_full_path);
-
-            // Create parent directories if they don't exist
-            if let Some(parent) = new_full_path.parent() {
-                std::fs::create_dir_all(parent)
-                    .map_err(|e| format!("Failed to create parent directory: {}", e))?;
-            }
-
-            std::fs::rename(&old_full_path, &new_full_path)
-                .map_err(|e| {
-                    println!("❌ Failed to rename file: {}", e);
-                    format!("Failed to rename file: {}", e)
-                })
-                }
-                None => Err("No vault opened".to_string()),
-            }
-        }
-        None => Err("Window not found".to_string()),
-    }
+    // Moves the file or folder and rewrites every link pointing into it
+    commands::refactor::rename_with_links(&index, graph_manager, unlocked.as_deref(), &old_path, &new_path, false)
+        .await
+        .map_err(|e| {
+            println!("❌ Failed to rename file: {}", e);
+            e
+        })
 }
 
 #[tauri::command]
@@ -797,15 +785,22 @@ async fn save_last_vault(app: tauri::AppHandle, vault_path: String) -> Result<()
 async fn save_pasted_image(
     app: AppHandle,
     image_data: String, // Base64 encoded
-    extension: String,  // png, jpg, or gif
+    extension: String,  // png, jpg, webp, svg, ...
+    note_path: Option<String>, // Note the image is pasted into
     window: tauri::Window,
     refactored_state: State<'_, RefactoredAppState>
 ) -> Result<String, String> {
     use chrono::Local;
     use base64::{Engine as _, engine::general_purpose};
+    use index::attachments::{attachment_file_name, is_image, sanitize_file_name};
     
     println!("📸 save_pasted_image called with extension: {}", extension);
     
+    let extension = extension.trim_start_matches('.').to_lowercase();
+    if !is_image(&format!("image.{}", extension)) {
+        return Err(format!("Unsupported image format: {}", extension));
+    }
+    
     let window_id = extract_window_id(&window);
 
     match refactored_state.get_window_state(&window_id).await {
@@ -815,20 +810,38 @@ async fn save_pasted_image(
                 Some(vault) => {
                     let vault_path = vault.path();
                     
-                    // Get vault settings to determine image location
-                    let image_location = match vault_settings::get_vault_settings(app, vault_path.to_string_lossy().to_string()).await {
-                        Ok(settings) => settings.files.image_location,
-                        Err(_) => "files/".to_string() // Default location
+                    // Get vault settings to determine image location and naming
+                    let files = match vault_settings::get_vault_settings(app, vault_path.to_string_lossy().to_string()).await {
+                        Ok(settings) => settings.files,
+                        Err(_) => vault_settings::FileSettings::default()
                     };
+                    let note_name = note_path.as_deref()
+                        .and_then(|p| std::path::Path::new(p).file_stem())
+                        .and_then(|s| s.to_str())
+                        .map(sanitize_file_name)
+                        .filter(|name| !name.is_empty());
+                    
+                    let mut image_location = PathBuf::from(files.image_location.trim_matches('/'));
+                    if files.image_subfolder_per_note {
+                        if let Some(name) = &note_name {
+                            image_location.push(name);
+                        }
+                    }
                     
                     // Create image directory if it doesn't exist
-                    let image_dir = vault_path.join(&image_location);
+                    let image_dir = vault.resolve_path(&image_location)?;
                     std::fs::create_dir_all(&image_dir)
                         .map_err(|e| format!("Failed to create image directory: {}", e))?;
                     
-                    // Generate filename with timestamp
+                    // Name the file from the pattern, never overwriting an existing image
                     let timestamp = Local::now().format("%Y%m%d%H%M%S").to_string();
-                    let filename = format!("Pasted image {}.{}", timestamp, extension);
+                    let filename = attachment_file_name(
+                        &files.image_naming_pattern,
+                        note_name.as_deref(),
+                        &timestamp,
+                        &extension,
+                        &|name| image_dir.join(name).exists(),
+                    );
                     let file_path = image_dir.join(&filename);
                     
                     println!("💾 Saving image to: {:?}", file_path);
@@ -841,7 +854,8 @@ async fn save_pasted_image(
                         .map_err(|e| format!("Failed to write image file: {}", e))?;
                     
                     // Return the relative path from vault root
-                    let relative_path = format!("{}{}", image_location, filename);
+                    let relative_path = index::resolver::relative_path(vault_path, &file_path)
+                        .unwrap_or(filename);
                     println!("✅ Image saved successfully: {}", relative_path);
                     Ok(relative_path)
                 }
@@ -869,7 +883,7 @@ async fn read_image_as_base64(
             let vault_lock = window_state.vault.lock().await;
             match &*vault_lock {
                 Some(vault) => {
-                    let full_path = vault.path().join(&file_path);
+                    let full_path = vault.resolve_path(std::path::Path::new(&file_path))?;
                     println!("📁 Reading image from: {:?}", full_path);
                     
                     // Read the file as bytes
@@ -877,17 +891,8 @@ async fn read_image_as_base64(
                         .map_err(|e| format!("Failed to read image file: {}", e))?;
                     
                     // Determine content type from extension
-                    let extension = full_path.extension()
-                        .and_then(|ext| ext.to_str())
-                        .unwrap_or("png")
-                        .to_lowercase();
-                    
-                    let content_type = match extension.as_str() {
-                        "jpg" | "jpeg" => "image/jpeg",
-                        "png" => "image/png",
-                        "gif" => "image/gif",
-                        _ => "image/png"
-                    };
+                    let content_type = index::attachments::mime_type(&full_path.to_string_lossy())
+                        .unwrap_or("application/octet-stream");
                     
                     // Encode to base64
                     let base64_string = general_purpose::STANDARD.encode(&image_bytes);
@@ -903,13 +908,15 @@ async fn read_image_as_base64(
 
 #[tauri::command]
 async fn create_directory(
-    vault_path: String,
     dir_path: String,
+    window: tauri::Window,
+    refactored_state: State<'_, RefactoredAppState>,
 ) -> Result<(), String> {
     println!("📁 create_directory called with path: {}", dir_path);
     
-    let vault_path_buf = PathBuf::from(&vault_path);
-    let full_path = vault_path_buf.join(&dir_path);
+    let vault = refactored_state.window_vault(&extract_window_id(&window)).await?;
+    let full_path = vault.resolve_path(std::path::Path::new(&dir_path))
+        .map_err(|e| format!("Invalid directory path: {}", e))?;
     
     // Create directory and all parent directories if they don't exist
     std::fs::create_dir_all(&full_path)
@@ -1023,6 +1030,7 @@ async fn export_to_word(
 
 #[tauri::command]
 async fn export_chat_to_vault(
+    app: AppHandle,
     refactored_state: State<'_, RefactoredAppState>,
     content: String,
     filename: Option<String>,
@@ -1053,8 +1061,24 @@ async fn export_chat_to_vault(
             
             let file_path = chat_history_dir.join(&file_name);
             
-            // Write the chat content to the file
-            std::fs::write(&file_path, content)
+            // Wrap the chat in the vault's chat export template, if one is set
+            let template = match get_vault_settings(app.clone(), vault_path.to_string_lossy().to_string()).await {
+                Ok(settings) => settings.files.chat_export_template,
+                Err(_) => String::new(),
+            };
+            let content = if template.trim().is_empty() {
+                content
+            } else {
+                let title = std::path::Path::new(&file_name).file_stem().and_then(|s| s.to_str()).unwrap_or("Chat");
+                let context = templates::TemplateContext::new(title).with_variable("content", &content);
+                commands::templates::vault_templates(&app, vault_path).await
+                    .render(&template, &context)?
+                    .content
+            };
+            
+            // Write the chat content to the file, encrypted if Chat History is an encrypted folder
+            let unlocked = refactored_state.keyring.get(vault_path).await;
+            encryption::write_note(vault, &std::path::Path::new("Chat History").join(&file_name), &content, unlocked.as_deref())
                 .map_err(|e| format!("Failed to write chat file: {}", e))?;
             
             Ok(file_path.to_string_lossy().to_string())
@@ -1135,6 +1159,7 @@ fn main() {
             get_file_tree,
             read_file_content,
             write_file_content,
+            read_file_versioned,
             fetch_image_as_base64,
             create_new_file,
             create_new_folder,
@@ -1208,6 +1233,66 @@ fn main() {
             stop_docker_containers,
             get_docker_status,
             wait_for_docker_healthy,
+            commands::links::get_outgoing_links,
+            commands::links::get_backlinks,
+            commands::links::get_unresolved_links,
+            commands::links::resolve_wiki_link,
+            commands::properties::get_note_properties,
+            commands::properties::set_note_property,
+            commands::properties::remove_note_property,
+            commands::tags::get_all_tags,
+            commands::tags::get_note_tags,
+            commands::tasks::query_tasks,
+            commands::tasks::get_note_tasks,
+            commands::tasks::toggle_task,
+            commands::refactor::preview_rename,
+            commands::trash::list_trash,
+            commands::trash::restore_from_trash,
+            commands::trash::purge_trash_entry,
+            commands::trash::empty_trash,
+            commands::attachments::list_attachments,
+            commands::attachments::get_attachment_references,
+            commands::attachments::find_orphaned_attachments,
+            commands::attachments::find_broken_attachment_references,
+            commands::attachments::delete_orphaned_attachments,
+            commands::import::preview_import,
+            commands::import::import_into_vault,
+            commands::replace::preview_replace,
+            commands::replace::apply_replace,
+            commands::files::list_directory,
+            commands::query::run_query,
+            commands::encryption::get_encryption_status,
+            commands::encryption::set_up_encryption,
+            commands::encryption::unlock_vault,
+            commands::encryption::lock_vault,
+            commands::encryption::encrypt_note,
+            commands::encryption::decrypt_note,
+            commands::encryption::encrypt_folder,
+            commands::encryption::decrypt_folder,
+            commands::encryption::read_note_for_ai,
+            commands::git::git_status,
+            commands::git::git_init,
+            commands::git::git_commit,
+            commands::git::git_note_log,
+            commands::git::git_note_diff,
+            commands::git::git_set_remote,
+            commands::git::git_pull,
+            commands::git::git_push,
+            commands::git::resolve_git_conflict,
+            commands::publish::publish_site,
+            commands::backup::create_backup,
+            commands::backup::read_backup_manifest,
+            commands::backup::restore_backup,
+            commands::history::list_note_versions,
+            commands::history::get_note_version,
+            commands::history::diff_note_versions,
+            commands::history::restore_note_version,
+            commands::periodic::open_periodic_note,
+            commands::periodic::adjacent_periodic_note,
+            commands::periodic::list_periodic_notes,
+            commands::templates::list_templates,
+            commands::templates::list_template_prompts,
+            commands::templates::create_note_from_template,
             commands::graph::ensure_graph_services_running,
             commands::graph::get_graph_services_status,
             commands::graph::stop_graph_services,
@@ -1266,6 +1351,19 @@ fn main() {
             ).expect("Failed to create RefactoredAppState");
             app.manage(refactored_app_state);
             
+            // Commit and back up vaults on their schedules
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(60));
+                loop {
+                    interval.tick().await;
+                    let state = app_handle.state::<RefactoredAppState>();
+                    let vaults = state.get_watched_vaults().await;
+                    commands::git::commit_due_vaults(&app_handle, &state.auto_commit, vaults.clone()).await;
+                    commands::backup::back_up_due_vaults(&app_handle, vaults).await;
+                }
+            });
+            
             // Run AI settings migration on startup
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
@@ -140,7 +140,10 @@ impl MCPManager {
             ServerStatus::Error(ref msg) => return Err(anyhow!("Server {} has error: {}", server_id, msg)),
             _ => return Err(anyhow!("Server {} is not ready", server_id)),
         }
-        
+
+        // Refuse tool calls the server is not permitted to make or that leave its vault
+        server.config.authorize(&message)?;
+
         // Send message via transport
         server.transport.send_message(message).await
     }
@@ -28,6 +28,47 @@ impl ServerConfig {
             }
         }
     }
+
+    /// The vault a stdio server was started for, if any
+    pub fn vault_path(&self) -> Option<&str> {
+        match &self.transport {
+            TransportType::Stdio { env, .. } => env.get("VAULT_PATH").map(String::as_str),
+            TransportType::Http { .. } => None,
+        }
+    }
+
+    /// Checks a filesystem tool call against the server's permissions and
+    /// refuses any path argument that resolves outside its vault
+    pub fn authorize(&self, message: &JsonRpcMessage) -> Result<(), crate::mcp::error::MCPError> {
+        use crate::mcp::error::MCPError;
+
+        if message.method != "tools/call" {
+            return Ok(());
+        }
+        let params = message.params.as_ref();
+        let tool = params.and_then(|p| p.get("name")).and_then(|n| n.as_str()).unwrap_or_default();
+
+        let allowed = match tool {
+            "list_files" | "read_file" | "search_files" => self.permissions.read,
+            "write_file" | "create_directory" | "move_file" => self.permissions.write,
+            "delete_file" => self.permissions.delete,
+            _ => true,
+        };
+        if !allowed {
+            return Err(MCPError::PermissionDenied(format!("Tool {} is not permitted for this server", tool)));
+        }
+
+        if let Some(vault_path) = self.vault_path() {
+            let arguments = params.and_then(|p| p.get("arguments"));
+            for key in ["path", "source", "destination"] {
+                if let Some(path) = arguments.and_then(|a| a.get(key)).and_then(|v| v.as_str()) {
+                    crate::vault::resolve_in_vault(std::path::Path::new(vault_path), std::path::Path::new(path))
+                        .map_err(|e| MCPError::PermissionDenied(e.to_string()))?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Transport type configuration
@@ -5,6 +5,9 @@ use base64::{Engine as _, engine::general_purpose};
 use headless_chrome::{Browser, LaunchOptions};
 use pulldown_cmark::{Parser, Options, html};
 use serde::{Serialize, Deserialize};
+use crate::index::{NoteResolver, Transcluder};
+use crate::index::query::{expand_query_blocks, has_query_blocks, QueryNote};
+use crate::index::attachments::{destination_path, mime_type, IMAGE_TYPES};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExportOptions {
@@ -12,6 +15,10 @@ pub struct ExportOptions {
     pub include_styles: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub paper_size: Option<String>,
+    /// Path of the exported note, absolute or vault-relative; embeds resolve
+    /// relative to its folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_path: Option<String>,
 }
 
 impl Default for ExportOptions {
@@ -20,6 +27,7 @@ impl Default for ExportOptions {
             theme: "light".to_string(),
             include_styles: true,
             paper_size: Some("A4".to_string()),
+            source_path: None,
         }
     }
 }
@@ -28,6 +36,15 @@ pub struct PdfExporter {
     vault_path: PathBuf,
 }
 
+/// The notes a render may draw on instead of the whole vault: embeds resolve
+/// through `resolver` and read through `read_note`, and ```query blocks run
+/// over `query_notes`
+pub(crate) struct NoteScope<'a> {
+    pub resolver: &'a NoteResolver,
+    pub read_note: &'a dyn Fn(&str) -> Option<String>,
+    pub query_notes: &'a [QueryNote],
+}
+
 impl PdfExporter {
     pub fn new(vault_path: PathBuf) -> Self {
         Self { vault_path }
@@ -40,10 +57,10 @@ impl PdfExporter {
         output_path: &Path,
         options: ExportOptions,
     ) -> Result<(), String> {
-        println!("📄 Starting PDF export to: {:?}", output_path);
+        eprintln!("📄 Starting PDF export to: {:?}", output_path);
 
         // Convert markdown to HTML
-        let html_content = self.markdown_to_html(markdown_content)?;
+        let html_content = self.markdown_to_html(markdown_content, options.source_path.as_deref().unwrap_or_default())?;
         
         // Create styled HTML document
         let full_html = self.create_styled_html(&html_content, &options)?;
@@ -51,16 +68,33 @@ impl PdfExporter {
         // Generate PDF using headless Chrome
         self.html_to_pdf(&full_html, output_path).await?;
         
-        println!("✅ PDF export completed successfully");
+        eprintln!("✅ PDF export completed successfully");
         Ok(())
     }
 
-    /// Convert markdown to HTML with embedded images
-    fn markdown_to_html(&self, markdown_content: &str) -> Result<String, String> {
-        println!("🔄 Converting markdown to HTML...");
+    /// Convert markdown to HTML with embedded images; `source_path` is the
+    /// note the markdown came from, empty when unknown
+    pub(crate) fn markdown_to_html(&self, markdown_content: &str, source_path: &str) -> Result<String, String> {
+        self.render_html(markdown_content, source_path, None)
+    }
+
+    /// Like `markdown_to_html`, but embeds and ```query blocks only see the
+    /// notes in `scope` rather than every note in the vault
+    pub(crate) fn markdown_to_html_over(&self, markdown_content: &str, source_path: &str, scope: &NoteScope) -> Result<String, String> {
+        self.render_html(markdown_content, source_path, Some(scope))
+    }
+
+    fn render_html(&self, markdown_content: &str, source_path: &str, scope: Option<&NoteScope>) -> Result<String, String> {
+        eprintln!("🔄 Converting markdown to HTML...");
+        
+        // Replace note embeds with the content they point at
+        let transcluded_markdown = self.process_note_embeds(markdown_content, &self.relative_source(source_path), scope)?;
+        
+        // Render ```query blocks as tables and lists of their results
+        let transcluded_markdown = self.process_queries(&transcluded_markdown, scope.map(|scope| scope.query_notes))?;
         
         // Process markdown to handle local images
-        let processed_markdown = self.process_markdown_images(markdown_content)?;
+        let processed_markdown = self.process_markdown_images(&transcluded_markdown, &self.relative_source(source_path))?;
         
         // Process highlight syntax (==text==) before markdown parsing
         let processed_markdown = self.process_highlight_syntax(&processed_markdown)?;
@@ -108,6 +142,52 @@ impl PdfExporter {
         Ok(clean_html)
     }
 
+    /// Expand ![[Note]], ![[Note#Heading]] and ![[Note#^block]] embeds into the
+    /// embedded markdown, reading notes from `scope` or else from the vault on disk
+    fn process_note_embeds(&self, markdown: &str, source: &str, scope: Option<&NoteScope>) -> Result<String, String> {
+        if let Some(scope) = scope {
+            return Ok(Transcluder::new(scope.resolver, scope.read_note).expand(markdown, source));
+        }
+        let vault = crate::vault::Vault::new(self.vault_path.clone())
+            .map_err(|e| format!("Failed to open vault: {}", e))?;
+        let files = vault.list_markdown_files()
+            .map_err(|e| format!("Failed to list files: {}", e))?;
+        let resolver = NoteResolver::from_paths(&self.vault_path, &files);
+        
+        let read_note = |rel: &str| fs::read_to_string(self.vault_path.join(rel)).ok();
+        let transcluder = Transcluder::new(&resolver, &read_note);
+        
+        Ok(transcluder.expand(markdown, source))
+    }
+
+    /// Vault-relative form of a note path from the frontend
+    fn relative_source(&self, source_path: &str) -> String {
+        let path = Path::new(source_path);
+        if path.is_absolute() {
+            return crate::index::resolver::relative_path(&self.vault_path, path).unwrap_or_default();
+        }
+        source_path.replace('\\', "/").trim_start_matches("./").to_string()
+    }
+
+    /// Replace ```query blocks with their results over `notes`, or over the
+    /// vault on disk when no notes are given
+    fn process_queries(&self, markdown: &str, notes: Option<&[QueryNote]>) -> Result<String, String> {
+        if !has_query_blocks(markdown) {
+            return Ok(markdown.to_string());
+        }
+        let loaded;
+        let notes: &[QueryNote] = match notes {
+            Some(notes) => notes,
+            None => {
+                let vault = crate::vault::Vault::new(self.vault_path.clone())
+                    .map_err(|e| format!("Failed to open vault: {}", e))?;
+                loaded = QueryNote::load_all(&vault)?;
+                &loaded
+            }
+        };
+        Ok(expand_query_blocks(markdown, notes, chrono::Local::now().date_naive()))
+    }
+
     /// Process highlight syntax (==text==) to HTML <mark> tags
     fn process_highlight_syntax(&self, markdown: &str) -> Result<String, String> {
         // Process line by line to handle highlights properly
@@ -165,27 +245,31 @@ impl PdfExporter {
         Ok(lines.join("\n"))
     }
     
-    /// Process markdown to replace local image references with base64 data URIs
-    fn process_markdown_images(&self, markdown: &str) -> Result<String, String> {
+    /// Process markdown to replace local image references with base64 data URIs.
+    /// Only images inside the vault are embedded.
+    fn process_markdown_images(&self, markdown: &str, source: &str) -> Result<String, String> {
         let mut processed = markdown.to_string();
         
+        let extensions = IMAGE_TYPES.iter().map(|(ext, _)| *ext).collect::<Vec<_>>().join("|");
+        
         // Regular expression to find image embeds: ![[filename.png]]
-        let syntax_pattern = regex::Regex::new(r"!\[\[([^\]]+\.(png|jpg|jpeg|gif))\]\]")
+        let syntax_pattern = regex::Regex::new(&format!(r"(?i)!\[\[([^\]]+\.({}))\]\]", extensions))
             .map_err(|e| format!("Failed to create regex: {}", e))?;
         
         // Regular expression to find standard markdown images: ![alt](path)
-        let standard_pattern = regex::Regex::new(r"!\[([^\]]*)\]\(([^)]+\.(png|jpg|jpeg|gif))\)")
+        let standard_pattern = regex::Regex::new(&format!(r"(?i)!\[([^\]]*)\]\(([^)]+\.({}))\)", extensions))
             .map_err(|e| format!("Failed to create regex: {}", e))?;
         
         // Process syntax-style images
         for cap in syntax_pattern.captures_iter(markdown) {
             let filename = &cap[1];
-            let image_path = self.vault_path.join("files").join(filename);
+            // Embeds hold a vault-relative path, or a bare name in the default image folder
+            let image_path = self.find_image(&[filename.to_string(), format!("files/{}", filename)]);
             
-            if let Ok(base64_data) = self.image_to_base64(&image_path) {
+            if let Some(base64_data) = image_path.and_then(|path| self.image_to_base64(&path).ok()) {
                 let replacement = format!("![{}]({})", filename, base64_data);
                 processed = processed.replace(&cap[0], &replacement);
-                println!("📸 Embedded image: {}", filename);
+                eprintln!("📸 Embedded image: {}", filename);
             }
         }
         
@@ -194,18 +278,21 @@ impl PdfExporter {
             let alt_text = &cap[1];
             let image_path = &cap[2];
             
-            // Check if it's a local path (not http/https)
-            if !image_path.starts_with("http://") && !image_path.starts_with("https://") {
-                let full_path = if image_path.starts_with("files/") {
-                    self.vault_path.join(image_path)
-                } else {
-                    PathBuf::from(image_path)
+            // Local paths are relative to the note, falling back to the vault root
+            if let Some(target) = destination_path(image_path) {
+                let folder = crate::index::resolver::parent_dir(source);
+                let rooted = target.strip_prefix('/').map(str::to_string);
+                let candidates = match rooted {
+                    Some(rooted) => vec![target, rooted],
+                    None if folder.is_empty() => vec![target],
+                    None => vec![format!("{}/{}", folder, target), target],
                 };
+                let full_path = self.find_image(&candidates);
                 
-                if let Ok(base64_data) = self.image_to_base64(&full_path) {
+                if let Some(base64_data) = full_path.and_then(|path| self.image_to_base64(&path).ok()) {
                     let replacement = format!("![{}]({})", alt_text, base64_data);
                     processed = processed.replace(&cap[0], &replacement);
-                    println!("📸 Embedded image: {}", image_path);
+                    eprintln!("📸 Embedded image: {}", image_path);
                 }
             }
         }
@@ -213,6 +300,14 @@ impl PdfExporter {
         Ok(processed)
     }
 
+    /// First of the vault-relative candidates that is a file in the vault
+    fn find_image(&self, candidates: &[String]) -> Option<PathBuf> {
+        candidates
+            .iter()
+            .filter_map(|candidate| crate::vault::resolve_in_vault(&self.vault_path, Path::new(candidate)).ok())
+            .find(|path| path.is_file())
+    }
+
     /// Convert image file to base64 data URI
     fn image_to_base64(&self, image_path: &Path) -> Result<String, String> {
         // Read image file
@@ -220,17 +315,8 @@ impl PdfExporter {
             .map_err(|e| format!("Failed to read image file: {}", e))?;
         
         // Determine content type from extension
-        let extension = image_path.extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("png")
-            .to_lowercase();
-        
-        let content_type = match extension.as_str() {
-            "jpg" | "jpeg" => "image/jpeg",
-            "png" => "image/png",
-            "gif" => "image/gif",
-            _ => "image/png"
-        };
+        let content_type = mime_type(&image_path.to_string_lossy())
+            .ok_or_else(|| format!("Not an image: {}", image_path.display()))?;
         
         // Encode to base64
         let base64_string = general_purpose::STANDARD.encode(&image_bytes);
@@ -239,7 +325,7 @@ impl PdfExporter {
     }
 
     /// Create a styled HTML document
-    fn create_styled_html(&self, content: &str, options: &ExportOptions) -> Result<String, String> {
+    pub(crate) fn create_styled_html(&self, content: &str, options: &ExportOptions) -> Result<String, String> {
         let styles = if options.include_styles {
             r#"
             <style>
@@ -426,7 +512,7 @@ impl PdfExporter {
 
     /// Convert HTML to PDF using headless Chrome
     async fn html_to_pdf(&self, html: &str, output_path: &Path) -> Result<(), String> {
-        println!("🌐 Launching headless Chrome...");
+        eprintln!("🌐 Launching headless Chrome...");
         
         // Launch headless Chrome
         let browser = Browser::new(LaunchOptions {
@@ -455,7 +541,7 @@ impl PdfExporter {
             .map_err(|e| format!("Failed to write temp file: {}", e))?;
         
         // Debug: Print first 500 chars of HTML to see if mark tags are present
-        println!("🔍 HTML Preview (first 500 chars): {}", &html.chars().take(500).collect::<String>());
+        eprintln!("🔍 HTML Preview (first 500 chars): {}", &html.chars().take(500).collect::<String>());
         
         temp_file.flush()
             .map_err(|e| format!("Failed to flush temp file: {}", e))?;
@@ -483,7 +569,7 @@ impl PdfExporter {
         // Clean up temp file
         let _ = fs::remove_file(&temp_file_path);
         
-        println!("💾 PDF saved to: {:?}", output_path);
+        eprintln!("💾 PDF saved to: {:?}", output_path);
         
         Ok(())
     }
@@ -496,12 +582,12 @@ pub async fn export_to_html(
     vault_path: &Path,
     options: ExportOptions,
 ) -> Result<(), String> {
-    println!("📄 Starting HTML export to: {:?}", output_path);
+    eprintln!("📄 Starting HTML export to: {:?}", output_path);
     
     let exporter = PdfExporter::new(vault_path.to_path_buf());
     
     // Convert markdown to HTML
-    let html_content = exporter.markdown_to_html(markdown_content)?;
+    let html_content = exporter.markdown_to_html(markdown_content, options.source_path.as_deref().unwrap_or_default())?;
     
     // Create styled HTML document
     let full_html = exporter.create_styled_html(&html_content, &options)?;
@@ -510,7 +596,7 @@ pub async fn export_to_html(
     fs::write(output_path, full_html)
         .map_err(|e| format!("Failed to save HTML: {}", e))?;
     
-    println!("✅ HTML export completed successfully");
+    eprintln!("✅ HTML export completed successfully");
     Ok(())
 }
 
@@ -521,12 +607,12 @@ pub async fn export_to_word(
     vault_path: &Path,
     options: ExportOptions,
 ) -> Result<(), String> {
-    println!("📄 Starting Word export to: {:?}", output_path);
+    eprintln!("📄 Starting Word export to: {:?}", output_path);
     
     let exporter = PdfExporter::new(vault_path.to_path_buf());
     
     // Convert markdown to HTML
-    let html_content = exporter.markdown_to_html(markdown_content)?;
+    let html_content = exporter.markdown_to_html(markdown_content, options.source_path.as_deref().unwrap_or_default())?;
     
     // Convert <mark> tags to Word-compatible spans with background color
     let word_compatible_html = html_content
@@ -656,6 +742,6 @@ pub async fn export_to_word(
     fs::write(output_path, word_html)
         .map_err(|e| format!("Failed to save Word document: {}", e))?;
     
-    println!("✅ Word export completed successfully");
+    eprintln!("✅ Word export completed successfully");
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,373 @@
+use std::path::Path;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::encryption::{write_note, UnlockedVault};
+use crate::templates::{TemplateContext, Templates};
+use crate::vault::Vault;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+const DEFAULT_DAILY_TEMPLATE: &str = "# {{fullDate}}\n\n## Tasks\n- [ ] \n\n## Notes\n\n\n## Highlights\n\n\n---\n*Created: {{time}}*";
+const DEFAULT_WEEKLY_TEMPLATE: &str = "# {{title}}\n\n## Goals\n- [ ] \n\n## Review\n\n";
+const DEFAULT_MONTHLY_TEMPLATE: &str = "# {{monthName}} {{year}}\n\n## Goals\n- [ ] \n\n## Review\n\n";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Period {
+    /// First day of the period containing `date`; periodic notes are keyed by it
+    pub fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Daily => date,
+            Period::Weekly => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Period::Monthly => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    fn default_template(self) -> &'static str {
+        match self {
+            Period::Daily => DEFAULT_DAILY_TEMPLATE,
+            Period::Weekly => DEFAULT_WEEKLY_TEMPLATE,
+            Period::Monthly => DEFAULT_MONTHLY_TEMPLATE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Previous,
+    Next,
+}
+
+/// Where the notes of one period live and how they are named
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PeriodicNoteSettings {
+    /// Vault-relative folder holding the notes
+    pub folder: String,
+    /// Moment-style file name format, e.g. `YYYY-MM-DD` or `YYYY/MM/YYYY-MM-DD`
+    /// (a `/` adds sub folders); text in `[brackets]` is kept literally
+    pub format: String,
+    /// Template name (or vault-relative path) for new notes; empty uses the built-in template
+    #[serde(default)]
+    pub template: String,
+}
+
+/// A periodic note on disk
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PeriodicNote {
+    pub period: Period,
+    /// First day of the period the note covers
+    pub date: NaiveDate,
+    /// Vault-relative path
+    pub path: String,
+    /// Whether the note was just created from the template
+    #[serde(default)]
+    pub created: bool,
+}
+
+impl PeriodicNoteSettings {
+    /// Vault-relative path of the note for the period containing `date`
+    pub fn note_path(&self, period: Period, date: NaiveDate) -> String {
+        let name = format_date(period.start(date), &self.format);
+        let folder = self.folder.trim_matches('/');
+        if folder.is_empty() {
+            format!("{}.md", name)
+        } else {
+            format!("{}/{}.md", folder, name)
+        }
+    }
+
+    /// Reads the period a vault-relative path stands for, if it matches the format
+    pub fn date_of(&self, period: Period, relative_path: &str) -> Option<NaiveDate> {
+        let folder = self.folder.trim_matches('/');
+        let name = if folder.is_empty() {
+            relative_path
+        } else {
+            relative_path.strip_prefix(folder)?.strip_prefix('/')?
+        };
+        let name = name.strip_suffix(".md")?;
+        parse_date(period, name, &self.format)
+    }
+
+    /// Existing notes of this period, oldest first
+    pub fn existing_notes(&self, vault: &Vault, period: Period) -> Vec<PeriodicNote> {
+        let folder = vault.path().join(self.folder.trim_matches('/'));
+        let mut notes: Vec<PeriodicNote> = WalkDir::new(&folder)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| {
+                let relative = crate::index::resolver::relative_path(vault.path(), e.path())?;
+                let date = self.date_of(period, &relative)?;
+                Some(PeriodicNote { period, date, path: relative, created: false })
+            })
+            .collect();
+        notes.sort_by_key(|n| n.date);
+        notes.dedup_by_key(|n| n.date);
+        notes
+    }
+
+    /// Returns the note for the period containing `date`, creating it from the
+    /// template if it does not exist yet
+    pub fn open_or_create(
+        &self,
+        vault: &Vault,
+        templates: &Templates,
+        period: Period,
+        date: NaiveDate,
+        unlocked: Option<&UnlockedVault>,
+    ) -> Result<PeriodicNote, String> {
+        let date = period.start(date);
+        let path = self.note_path(period, date);
+        let full_path = vault.resolve_path(Path::new(&path))?;
+        if full_path.exists() {
+            return Ok(PeriodicNote { period, date, path, created: false });
+        }
+
+        let title = Path::new(&path).file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let context = TemplateContext::new(title).with_date(date);
+        let rendered = if self.template.trim().is_empty() {
+            templates.render_source(period.default_template(), &context)?
+        } else {
+            templates.render(&self.template, &context)?
+        };
+
+        write_note(vault, Path::new(&path), &rendered.content, unlocked)
+            .map_err(|e| format!("Failed to create {}: {}", path, e))?;
+        println!("📅 Created {:?} note {}", period, path);
+        Ok(PeriodicNote { period, date, path, created: true })
+    }
+
+    /// Nearest existing note before or after the period containing `date`
+    pub fn adjacent(&self, vault: &Vault, period: Period, date: NaiveDate, direction: Direction) -> Option<PeriodicNote> {
+        let date = period.start(date);
+        let notes = self.existing_notes(vault, period);
+        match direction {
+            Direction::Previous => notes.into_iter().rev().find(|n| n.date < date),
+            Direction::Next => notes.into_iter().find(|n| n.date > date),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    Year,
+    ShortYear,
+    WeekYear,
+    MonthName,
+    ShortMonthName,
+    PaddedMonth,
+    Month,
+    PaddedDay,
+    Day,
+    DayName,
+    ShortDayName,
+    PaddedWeek,
+    Week,
+    Quarter,
+    Hour,
+    Minute,
+    Second,
+}
+
+/// Longest tokens first so `YYYY` is not read as two `YY`s
+const TOKENS: [(&str, Token); 20] = [
+    ("YYYY", Token::Year),
+    ("gggg", Token::WeekYear),
+    ("GGGG", Token::WeekYear),
+    ("MMMM", Token::MonthName),
+    ("dddd", Token::DayName),
+    ("MMM", Token::ShortMonthName),
+    ("ddd", Token::ShortDayName),
+    ("YY", Token::ShortYear),
+    ("MM", Token::PaddedMonth),
+    ("DD", Token::PaddedDay),
+    ("ww", Token::PaddedWeek),
+    ("WW", Token::PaddedWeek),
+    ("HH", Token::Hour),
+    ("mm", Token::Minute),
+    ("ss", Token::Second),
+    ("M", Token::Month),
+    ("D", Token::Day),
+    ("w", Token::Week),
+    ("W", Token::Week),
+    ("Q", Token::Quarter),
+];
+
+fn tokenize(format: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut rest = format;
+
+    'outer: while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                literal.push_str(&rest[1..end]);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        for (pattern, token) in &TOKENS {
+            if let Some(after) = rest.strip_prefix(pattern) {
+                if !literal.is_empty() {
+                    tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                }
+                tokens.push(token.clone());
+                rest = after;
+                continue 'outer;
+            }
+        }
+        literal.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if !literal.is_empty() {
+        tokens.push(Token::Literal(literal));
+    }
+    tokens
+}
+
+/// Formats a date with a moment-style format string
+pub fn format_date(date: NaiveDate, format: &str) -> String {
+    format_datetime(date.and_time(chrono::NaiveTime::MIN), format)
+}
+
+/// Like `format_date`, also filling in `HH`, `mm` and `ss`
+pub fn format_datetime(datetime: NaiveDateTime, format: &str) -> String {
+    let date = datetime.date();
+    tokenize(format)
+        .into_iter()
+        .map(|token| match token {
+            Token::Literal(text) => text,
+            Token::Year => format!("{:04}", date.year()),
+            Token::ShortYear => format!("{:02}", date.year() % 100),
+            Token::WeekYear => format!("{:04}", date.iso_week().year()),
+            Token::MonthName => MONTH_NAMES[date.month0() as usize].to_string(),
+            Token::ShortMonthName => MONTH_NAMES[date.month0() as usize][..3].to_string(),
+            Token::PaddedMonth => format!("{:02}", date.month()),
+            Token::Month => date.month().to_string(),
+            Token::PaddedDay => format!("{:02}", date.day()),
+            Token::Day => date.day().to_string(),
+            Token::DayName => date.format("%A").to_string(),
+            Token::ShortDayName => date.format("%a").to_string(),
+            Token::PaddedWeek => format!("{:02}", date.iso_week().week()),
+            Token::Week => date.iso_week().week().to_string(),
+            Token::Quarter => (date.month0() / 3 + 1).to_string(),
+            Token::Hour => format!("{:02}", datetime.hour()),
+            Token::Minute => format!("{:02}", datetime.minute()),
+            Token::Second => format!("{:02}", datetime.second()),
+        })
+        .collect()
+}
+
+/// Reads back a name produced by `format_date` for the given period
+fn parse_date(period: Period, name: &str, format: &str) -> Option<NaiveDate> {
+    let tokens = tokenize(format);
+    let mut pattern = String::from("^");
+    for token in &tokens {
+        pattern.push_str(&match token {
+            Token::Literal(text) => regex::escape(text),
+            Token::Year | Token::WeekYear => r"(\d{4})".to_string(),
+            Token::ShortYear | Token::PaddedMonth | Token::PaddedDay | Token::PaddedWeek
+            | Token::Hour | Token::Minute | Token::Second => r"(\d{2})".to_string(),
+            Token::Month | Token::Day | Token::Week => r"(\d{1,2})".to_string(),
+            Token::Quarter => r"([1-4])".to_string(),
+            Token::MonthName | Token::ShortMonthName | Token::DayName | Token::ShortDayName => r"([A-Za-z]+)".to_string(),
+        });
+    }
+    pattern.push('$');
+    let captures = Regex::new(&pattern).ok()?.captures(name)?;
+
+    let (mut year, mut week_year, mut month, mut day, mut week) = (None, None, None, None, None);
+    for (token, value) in tokens.iter().filter(|t| !matches!(t, Token::Literal(_))).zip(captures.iter().skip(1)) {
+        let value = value?.as_str();
+        let number = value.parse::<u32>().ok();
+        match token {
+            Token::Year => year = number.map(|y| y as i32),
+            Token::ShortYear => year = number.map(|y| 2000 + y as i32),
+            Token::WeekYear => week_year = number.map(|y| y as i32),
+            Token::PaddedMonth | Token::Month => month = number,
+            Token::PaddedDay | Token::Day => day = number,
+            Token::PaddedWeek | Token::Week => week = number,
+            Token::MonthName | Token::ShortMonthName => {
+                month = MONTH_NAMES.iter()
+                    .position(|m| m.to_lowercase().starts_with(&value.to_lowercase()))
+                    .map(|i| i as u32 + 1);
+            }
+            _ => {}
+        }
+    }
+
+    let date = match period {
+        Period::Daily => NaiveDate::from_ymd_opt(year?, month?, day?)?,
+        Period::Weekly => match (week_year.or(year), week) {
+            (Some(y), Some(w)) => NaiveDate::from_isoywd_opt(y, w, chrono::Weekday::Mon)?,
+            _ => NaiveDate::from_ymd_opt(year?, month?, day?)?,
+        },
+        Period::Monthly => NaiveDate::from_ymd_opt(year?, month?, 1)?,
+    };
+    // Names that only look like the format (e.g. a day in a weekly folder) are skipped
+    (format_date(date, format) == name).then_some(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_format_and_parse_round_trip() {
+        let daily = PeriodicNoteSettings { folder: "Daily Notes".into(), format: "YYYY/MM/YYYY-MM-DD".into(), template: String::new() };
+        assert_eq!(daily.note_path(Period::Daily, date(2025, 7, 3)), "Daily Notes/2025/07/2025-07-03.md");
+        assert_eq!(daily.date_of(Period::Daily, "Daily Notes/2025/07/2025-07-03.md"), Some(date(2025, 7, 3)));
+        assert_eq!(daily.date_of(Period::Daily, "Daily Notes/2025/07/notes.md"), None);
+
+        let weekly = PeriodicNoteSettings { folder: "Weekly".into(), format: "gggg-[W]ww".into(), template: String::new() };
+        // 2024-12-31 belongs to ISO week 1 of 2025
+        assert_eq!(weekly.note_path(Period::Weekly, date(2024, 12, 31)), "Weekly/2025-W01.md");
+        assert_eq!(weekly.date_of(Period::Weekly, "Weekly/2025-W01.md"), Some(date(2024, 12, 30)));
+
+        let monthly = PeriodicNoteSettings { folder: String::new(), format: "MMMM YYYY".into(), template: String::new() };
+        assert_eq!(monthly.note_path(Period::Monthly, date(2025, 3, 18)), "March 2025.md");
+        assert_eq!(monthly.date_of(Period::Monthly, "March 2025.md"), Some(date(2025, 3, 1)));
+    }
+
+    #[test]
+    fn test_open_or_create_and_navigate() {
+        let dir = std::env::temp_dir().join(format!("gaimplan-periodic-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("Templates")).unwrap();
+        std::fs::write(dir.join("Templates/Daily.md"), "# {{date}} ({{dayName}})").unwrap();
+        let vault = Vault::new(dir.clone()).unwrap();
+        let templates = Templates::new(&dir, "Templates");
+        let daily = PeriodicNoteSettings { folder: "Daily".into(), format: "YYYY-MM-DD".into(), template: "Daily".into() };
+
+        let note = daily.open_or_create(&vault, &templates, Period::Daily, date(2025, 7, 3), None).unwrap();
+        assert!(note.created);
+        assert_eq!(std::fs::read_to_string(dir.join(&note.path)).unwrap(), "# 2025-07-03 (Thursday)");
+        assert!(!daily.open_or_create(&vault, &templates, Period::Daily, date(2025, 7, 3), None).unwrap().created);
+        daily.open_or_create(&vault, &templates, Period::Daily, date(2025, 7, 10), None).unwrap();
+
+        let next = daily.adjacent(&vault, Period::Daily, date(2025, 7, 4), Direction::Next).unwrap();
+        assert_eq!(next.path, "Daily/2025-07-10.md");
+        let previous = daily.adjacent(&vault, Period::Daily, date(2025, 7, 10), Direction::Previous).unwrap();
+        assert_eq!(previous.path, "Daily/2025-07-03.md");
+        assert!(daily.adjacent(&vault, Period::Daily, date(2025, 7, 3), Direction::Previous).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
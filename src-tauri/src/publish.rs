@@ -0,0 +1,543 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::encryption::is_encrypted;
+use crate::index::attachments::{destination_path, is_image, resolve_reference, AttachmentReference};
+use crate::index::links::parse_wiki_links;
+use crate::file_tree::file_info;
+use crate::index::query::{escape_html, QueryNote};
+use crate::index::refactor::relative_between;
+use crate::index::resolver::{is_markdown, parent_dir};
+use crate::index::scanner::{code_ranges, frontmatter_range, in_ranges, markdown_link_destinations};
+use crate::index::tags::parse_tags;
+use crate::index::{NoteProperties, NoteResolver, Transcluder};
+use crate::pdf_export::{ExportOptions, NoteScope, PdfExporter};
+use crate::vault::{is_inside, Vault};
+
+/// Site stylesheet and search files
+const ASSETS_DIR: &str = "_assets";
+/// One page per tag, plus an index of all tags
+const TAGS_DIR: &str = "_tags";
+/// Attachments from outside the published folder
+const ATTACHMENTS_DIR: &str = "_attachments";
+/// Characters of each page's text kept in the search index
+const SEARCH_TEXT_LEN: usize = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishOptions {
+    /// Vault-relative folder to publish; empty publishes the whole vault
+    #[serde(default)]
+    pub folder: String,
+    /// `light` or `dark`
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    /// Shown at the top of every page; defaults to the folder or vault name
+    #[serde(default)]
+    pub site_title: Option<String>,
+}
+
+fn default_theme() -> String {
+    "light".to_string()
+}
+
+impl Default for PublishOptions {
+    fn default() -> Self {
+        Self {
+            folder: String::new(),
+            theme: default_theme(),
+            site_title: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishReport {
+    pub pages: usize,
+    pub tags: usize,
+    pub attachments: usize,
+    /// Notes left out because of `publish: false` or because they are encrypted
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SearchEntry {
+    title: String,
+    /// Relative to the site root
+    url: String,
+    tags: Vec<String>,
+    text: String,
+}
+
+/// A published note, rendered but not yet wrapped in the site layout
+struct Page {
+    title: String,
+    tags: Vec<String>,
+    html: String,
+    /// Published notes this one links to
+    links: BTreeSet<String>,
+}
+
+/// Renders the notes of a vault, or of one folder in it, to a directory of
+/// linked HTML pages using the same markdown rendering as HTML export
+struct Site<'a> {
+    vault_path: &'a Path,
+    folder: String,
+    resolver: NoteResolver,
+    /// Content of the notes being published, by vault-relative path
+    notes: BTreeMap<String, String>,
+    /// The published notes, which are all that query blocks get to see
+    query_notes: Vec<QueryNote>,
+    exporter: PdfExporter,
+}
+
+/// Publishes a vault or folder as a static site in `output_dir`. Existing
+/// files there are overwritten but never deleted; notes the vault ignores are
+/// left out.
+pub fn publish_site(vault: &Vault, output_dir: &Path, options: &PublishOptions) -> Result<PublishReport, String> {
+    let vault_path = vault.path();
+    if is_inside(output_dir, vault_path) {
+        return Err("Choose an output folder outside the vault".to_string());
+    }
+    let folder = options.folder.replace('\\', "/").trim_matches('/').to_string();
+    if !folder.is_empty() && !vault_path.join(&folder).is_dir() {
+        return Err(format!("Folder not found: {}", folder));
+    }
+
+    let files = vault.list_markdown_files().map_err(|e| format!("Failed to list files: {}", e))?;
+    let resolver = NoteResolver::from_paths(vault_path, &files);
+
+    let mut notes = BTreeMap::new();
+    let mut skipped = Vec::new();
+    for rel in resolver.notes().filter(|rel| in_folder(rel, &folder)) {
+        let content = match fs::read_to_string(vault_path.join(rel)) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("⚠️ Skipping {}: {}", rel, e);
+                continue;
+            }
+        };
+        if is_encrypted(&content) || !is_published(&content) {
+            skipped.push(rel.clone());
+        } else {
+            notes.insert(rel.clone(), content);
+        }
+    }
+
+    let query_notes = notes.iter()
+        .filter_map(|(rel, content)| file_info(&vault_path.join(rel), rel).map(|file| QueryNote::from_content(file, content)))
+        .collect();
+    let site = Site {
+        vault_path,
+        folder,
+        resolver,
+        notes,
+        query_notes,
+        exporter: PdfExporter::new(vault_path.to_path_buf()),
+    };
+    let site_title = options.site_title.clone().unwrap_or_else(|| {
+        let root = if site.folder.is_empty() { vault_path.to_path_buf() } else { vault_path.join(&site.folder) };
+        root.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "Notes".to_string())
+    });
+    site.write(output_dir, &site_title, &options.theme, skipped)
+}
+
+fn in_folder(rel_path: &str, folder: &str) -> bool {
+    folder.is_empty() || rel_path.strip_prefix(folder).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Notes are published unless their frontmatter says `publish: false`
+fn is_published(content: &str) -> bool {
+    match NoteProperties::from_content(content).custom.get("publish") {
+        Some(Value::Bool(false)) => false,
+        Some(Value::String(s)) => !s.eq_ignore_ascii_case("false"),
+        _ => true,
+    }
+}
+
+/// `url` relative to the page at `from`, both site-relative file paths
+fn relative_url(from: &str, to: &str) -> String {
+    let path = relative_between(parent_dir(from), to);
+    let encoded: Vec<String> = path
+        .split('/')
+        .map(|segment| if segment == ".." { segment.to_string() } else { urlencoding::encode(segment).into_owned() })
+        .collect();
+    let url = encoded.join("/");
+    // A leading `./` keeps these from being taken for vault paths by the image embedding
+    if url.starts_with("..") { url } else { format!("./{}", url) }
+}
+
+/// Text of a link label that markdown would otherwise interpret
+fn escape_markdown(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            '[' | ']' | '*' | '_' | '`' | '\\' | '<' | '>' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect()
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Site<'_> {
+    /// Where a note or attachment ends up in the site
+    fn output_path(&self, rel_path: &str) -> String {
+        let path = match self.folder.as_str() {
+            "" => Some(rel_path),
+            folder => rel_path.strip_prefix(folder).and_then(|rest| rest.strip_prefix('/')),
+        };
+        match path {
+            Some(path) if is_markdown(path) => format!("{}.html", &path[..path.len() - 3]),
+            Some(path) => path.to_string(),
+            None => format!("{}/{}", ATTACHMENTS_DIR, rel_path),
+        }
+    }
+
+    fn tag_page(tag: &str) -> String {
+        format!("{}/{}.html", TAGS_DIR, tag.to_lowercase())
+    }
+
+    fn title(rel_path: &str, content: &str) -> String {
+        NoteProperties::from_content(content).title.unwrap_or_else(|| {
+            let name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+            name.strip_suffix(".md").unwrap_or(name).to_string()
+        })
+    }
+
+    /// Turns a note into markdown whose links point at pages and copied
+    /// attachments of the site. Attachments used are added to `attachments`.
+    fn prepare(&self, rel_path: &str, content: &str, attachments: &mut BTreeSet<String>, links: &mut BTreeSet<String>) -> String {
+        let body = match frontmatter_range(content) {
+            Some((whole, _)) => &content[whole.end..],
+            None => content,
+        };
+        let read_note = |path: &str| self.notes.get(path).cloned();
+        let mut markdown = Transcluder::new(&self.resolver, &read_note).expand(body, rel_path);
+        let page = self.output_path(rel_path);
+
+        // Markdown links and images with paths in the vault
+        let code = code_ranges(&markdown);
+        let mut edits = Vec::new();
+        for (start, end, destination) in markdown_link_destinations(&markdown) {
+            if in_ranges(&code, start) {
+                continue;
+            }
+            let Some(target) = destination_path(destination) else { continue };
+            let reference = AttachmentReference { target, is_wiki_link: false, line: 0 };
+            let Some(resolved) = resolve_reference(&self.resolver, &reference, rel_path) else { continue };
+            if is_markdown(&resolved) && !self.notes.contains_key(&resolved) {
+                continue;
+            }
+            let fragment = destination.trim_end_matches('>').find('#').map(|i| &destination.trim_end_matches('>')[i..]).unwrap_or("");
+            edits.push((start, end, format!("{}{}", relative_url(&page, &self.output_path(&resolved)), fragment)));
+            if is_markdown(&resolved) {
+                links.insert(resolved);
+            } else {
+                attachments.insert(resolved);
+            }
+        }
+        for (start, end, replacement) in edits.into_iter().rev() {
+            markdown.replace_range(start..end, &replacement);
+        }
+
+        // Wiki-links, and embeds the transclusion left (attachments and missing notes)
+        let mut edits = Vec::new();
+        for link in parse_wiki_links(&markdown) {
+            let label = link.alias.clone().unwrap_or_else(|| match (&link.heading, link.target.is_empty()) {
+                (Some(heading), true) => heading.clone(),
+                (Some(heading), false) => format!("{} > {}", link.target, heading),
+                (None, _) => link.target.clone(),
+            });
+            let label = escape_markdown(&label);
+            let replacement = match self.resolver.resolve(&link.target, rel_path) {
+                Some(resolved) if is_markdown(&resolved) && self.notes.contains_key(&resolved) => {
+                    let url = relative_url(&page, &self.output_path(&resolved));
+                    links.insert(resolved);
+                    format!("[{}]({})", label, url)
+                }
+                Some(resolved) if !is_markdown(&resolved) => {
+                    let url = relative_url(&page, &self.output_path(&resolved));
+                    let embed = link.is_embed && is_image(&resolved);
+                    attachments.insert(resolved);
+                    if embed { format!("![{}]({})", label, url) } else { format!("[{}]({})", label, url) }
+                }
+                // Links to missing or unpublished notes become plain text
+                _ => label,
+            };
+            edits.push((link.start, link.end, replacement));
+        }
+        for (start, end, replacement) in edits.into_iter().rev() {
+            markdown.replace_range(start..end, &replacement);
+        }
+
+        markdown
+    }
+
+    fn write(&self, output_dir: &Path, site_title: &str, theme: &str, skipped: Vec<String>) -> Result<PublishReport, String> {
+        // Pages render from the published notes only, never the rest of the vault
+        let read_note = |path: &str| self.notes.get(path).cloned();
+        let scope = NoteScope { resolver: &self.resolver, read_note: &read_note, query_notes: &self.query_notes };
+        let mut attachments = BTreeSet::new();
+        let mut pages = BTreeMap::new();
+        for (rel_path, content) in &self.notes {
+            let mut links = BTreeSet::new();
+            let markdown = self.prepare(rel_path, content, &mut attachments, &mut links);
+            links.remove(rel_path);
+            pages.insert(rel_path.clone(), Page {
+                title: Self::title(rel_path, content),
+                tags: parse_tags(content),
+                html: self.exporter.markdown_to_html_over(&markdown, rel_path, &scope)?,
+                links,
+            });
+        }
+
+        let mut backlinks: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut tags: BTreeMap<String, (String, Vec<&str>)> = BTreeMap::new();
+        for (rel_path, page) in &pages {
+            for target in &page.links {
+                backlinks.entry(target).or_default().push(rel_path);
+            }
+            for tag in &page.tags {
+                tags.entry(tag.to_lowercase()).or_insert_with(|| (tag.clone(), Vec::new())).1.push(rel_path);
+            }
+        }
+
+        // The vault's own index.md is the home page when there is one
+        let has_home_note = pages.keys().any(|rel| self.output_path(rel) == "index.html");
+        let listing = if has_home_note { "_all-notes.html" } else { "index.html" };
+        let layout = Layout { exporter: &self.exporter, site_title, theme, listing };
+
+        let mut search = Vec::new();
+        for (rel_path, page) in &pages {
+            let out = self.output_path(rel_path);
+            let mut body = format!("<article>\n{}\n</article>\n", page.html);
+            if !page.tags.is_empty() {
+                body.push_str("<nav class=\"page-tags\">");
+                for tag in &page.tags {
+                    body.push_str(&format!("<a href=\"{}\">#{}</a> ", relative_url(&out, &Self::tag_page(tag)), escape_html(tag)));
+                }
+                body.push_str("</nav>\n");
+            }
+            if let Some(sources) = backlinks.get(rel_path.as_str()) {
+                body.push_str("<section class=\"backlinks\">\n<h2>Linked from</h2>\n<ul>\n");
+                for source in sources {
+                    body.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", relative_url(&out, &self.output_path(source)), escape_html(&pages[*source].title)));
+                }
+                body.push_str("</ul>\n</section>\n");
+            }
+            write_file(output_dir, &out, layout.render(&out, &page.title, &body)?.as_bytes())?;
+
+            let text: String = strip_tags(&page.html).chars().take(SEARCH_TEXT_LEN).collect();
+            search.push(SearchEntry { title: page.title.clone(), url: out.clone(), tags: page.tags.clone(), text });
+        }
+
+        // Listing of every page, grouped by folder
+        let mut body = format!("<h1>{}</h1>\n<ul class=\"page-list\">\n", escape_html(site_title));
+        for (rel_path, page) in &pages {
+            let out = self.output_path(rel_path);
+            let folder = parent_dir(&out);
+            let folder = if folder.is_empty() { String::new() } else { format!(" <span class=\"folder\">{}</span>", escape_html(folder)) };
+            body.push_str(&format!("<li><a href=\"{}\">{}</a>{}</li>\n", relative_url(listing, &out), escape_html(&page.title), folder));
+        }
+        body.push_str("</ul>\n");
+        write_file(output_dir, listing, layout.render(listing, site_title, &body)?.as_bytes())?;
+
+        // Tag pages and the tag index
+        let tag_index = format!("{}/index.html", TAGS_DIR);
+        let mut index_body = "<h1>Tags</h1>\n<ul class=\"tag-list\">\n".to_string();
+        for (tag, notes) in tags.values() {
+            let out = Self::tag_page(tag);
+            let mut body = format!("<h1>#{}</h1>\n<ul class=\"page-list\">\n", escape_html(tag));
+            for rel_path in notes {
+                body.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", relative_url(&out, &self.output_path(rel_path)), escape_html(&pages[*rel_path].title)));
+            }
+            body.push_str("</ul>\n");
+            write_file(output_dir, &out, layout.render(&out, &format!("#{}", tag), &body)?.as_bytes())?;
+            index_body.push_str(&format!("<li><a href=\"{}\">#{}</a> ({})</li>\n", relative_url(&tag_index, &out), escape_html(tag), notes.len()));
+        }
+        index_body.push_str("</ul>\n");
+        write_file(output_dir, &tag_index, layout.render(&tag_index, "Tags", &index_body)?.as_bytes())?;
+
+        // Attachments, theme and search
+        for rel_path in &attachments {
+            let target = output_dir.join(self.output_path(rel_path));
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            fs::copy(self.vault_path.join(rel_path), &target).map_err(|e| format!("Failed to copy {}: {}", rel_path, e))?;
+        }
+        let css = if theme == "dark" { format!("{}{}", SITE_CSS, DARK_CSS) } else { SITE_CSS.to_string() };
+        write_file(output_dir, &format!("{}/site.css", ASSETS_DIR), css.as_bytes())?;
+        write_file(output_dir, &format!("{}/search.js", ASSETS_DIR), SEARCH_JS.as_bytes())?;
+        let index = serde_json::to_string(&search).map_err(|e| e.to_string())?;
+        write_file(output_dir, &format!("{}/search-index.json", ASSETS_DIR), index.as_bytes())?;
+
+        eprintln!("🌐 Published {} pages, {} tags and {} attachments to {}", pages.len(), tags.len(), attachments.len(), output_dir.display());
+        Ok(PublishReport { pages: pages.len(), tags: tags.len(), attachments: attachments.len(), skipped })
+    }
+}
+
+/// Wraps page content in the export document with the site's header
+struct Layout<'a> {
+    exporter: &'a PdfExporter,
+    site_title: &'a str,
+    theme: &'a str,
+    listing: &'a str,
+}
+
+impl Layout<'_> {
+    fn render(&self, out: &str, title: &str, content: &str) -> Result<String, String> {
+        let asset = |name: &str| relative_url(out, &format!("{}/{}", ASSETS_DIR, name));
+        let body = format!(
+            r#"<header class="site-header">
+    <a class="site-title" href="{home}">{site_title}</a>
+    <a href="{listing}">All notes</a>
+    <a href="{tags}">Tags</a>
+    <input id="site-search" type="search" placeholder="Search" data-index="{index}" data-root="{root}">
+    <ul id="site-search-results"></ul>
+</header>
+{content}
+<script src="{script}"></script>"#,
+            home = relative_url(out, "index.html"),
+            site_title = escape_html(self.site_title),
+            listing = relative_url(out, self.listing),
+            tags = relative_url(out, &format!("{}/index.html", TAGS_DIR)),
+            index = asset("search-index.json"),
+            root = relative_url(out, "index.html").trim_end_matches("index.html"),
+            content = content,
+            script = asset("search.js"),
+        );
+        let options = ExportOptions { theme: self.theme.to_string(), include_styles: true, paper_size: None, source_path: None };
+        let html = self.exporter.create_styled_html(&body, &options)?;
+        Ok(html
+            .replacen("<title>Exported Document</title>", &format!("<title>{}</title>", escape_html(title)), 1)
+            .replacen("</head>", &format!("<link rel=\"stylesheet\" href=\"{}\">\n</head>", asset("site.css")), 1))
+    }
+}
+
+fn write_file(output_dir: &Path, rel_path: &str, content: &[u8]) -> Result<(), String> {
+    let path = output_dir.join(rel_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+const SITE_CSS: &str = r#"
+.site-header { display: flex; flex-wrap: wrap; align-items: center; gap: 16px; padding-bottom: 12px; margin-bottom: 24px; border-bottom: 1px solid #e5e5e5; position: relative; }
+.site-header .site-title { font-weight: 600; margin-right: auto; }
+#site-search { padding: 4px 8px; border: 1px solid #d0d0d0; border-radius: 4px; font: inherit; }
+#site-search-results { position: absolute; right: 0; top: 100%; z-index: 10; list-style: none; margin: 0; padding: 0; background: #fff; border: 1px solid #e5e5e5; border-radius: 4px; max-width: 400px; }
+#site-search-results:empty { display: none; }
+#site-search-results li { padding: 6px 12px; }
+.page-tags { margin-top: 24px; }
+.page-tags a { margin-right: 8px; }
+.backlinks { margin-top: 32px; padding-top: 16px; border-top: 1px solid #e5e5e5; font-size: 0.9em; }
+.backlinks h2 { font-size: 1.1em; }
+.page-list .folder { color: #888; font-size: 0.85em; margin-left: 8px; }
+"#;
+
+const DARK_CSS: &str = r#"
+body { background-color: #1e1e1e; color: #dcdcdc; }
+a { color: #7aa2f7; }
+h1, .site-header, .backlinks, #site-search-results { border-color: #3a3a3a; }
+#site-search, #site-search-results { background: #2a2a2a; color: #dcdcdc; border-color: #3a3a3a; }
+code, pre { background-color: #2a2a2a; color: #dcdcdc; }
+"#;
+
+const SEARCH_JS: &str = r#"(function () {
+  var input = document.getElementById('site-search');
+  var results = document.getElementById('site-search-results');
+  if (!input || !results) return;
+  var index = null;
+  function load() {
+    if (index) return Promise.resolve(index);
+    return fetch(input.dataset.index).then(function (r) { return r.json(); }).then(function (data) { index = data; return data; });
+  }
+  input.addEventListener('input', function () {
+    var words = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    if (!words.length) { results.innerHTML = ''; return; }
+    load().then(function (entries) {
+      var matches = entries.filter(function (e) {
+        var haystack = (e.title + ' ' + e.tags.join(' ') + ' ' + e.text).toLowerCase();
+        return words.every(function (w) { return haystack.indexOf(w) !== -1; });
+      }).slice(0, 20);
+      results.innerHTML = '';
+      matches.forEach(function (e) {
+        var li = document.createElement('li');
+        var a = document.createElement('a');
+        a.href = input.dataset.root + e.url.split('/').map(encodeURIComponent).join('/');
+        a.textContent = e.title;
+        li.appendChild(a);
+        results.appendChild(li);
+      });
+    });
+  });
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_urls_and_publish_flag() {
+        assert_eq!(relative_url("a/b.html", "c/d e.html"), "../c/d%20e.html");
+        assert_eq!(relative_url("index.html", "files/x.png"), "./files/x.png");
+        assert!(is_published("# Note"));
+        assert!(!is_published("---\npublish: false\n---\n# Note"));
+        assert!(in_folder("Projects/a.md", "Projects"));
+        assert!(!in_folder("Projects2/a.md", "Projects"));
+    }
+
+    #[test]
+    fn test_publish_folder() {
+        let root = std::env::temp_dir().join(format!("gaimplan-publish-{}", uuid::Uuid::new_v4()));
+        let (vault, out) = (root.join("vault"), root.join("site"));
+        fs::create_dir_all(vault.join("Blog/posts")).unwrap();
+        fs::create_dir_all(vault.join("files")).unwrap();
+        fs::write(vault.join("Blog/index.md"), "# Home\nSee [[First post|the first post]] and [[Private]]. #blog\n```query\nTABLE\n```").unwrap();
+        fs::write(vault.join("Blog/posts/First post.md"), "![[pic.png]]\nBack [[index]]\n`x` ![[Private]]").unwrap();
+        fs::write(vault.join("Blog/Private.md"), "---\npublish: false\n---\nsecret").unwrap();
+        fs::write(vault.join("files/pic.png"), [0u8; 4]).unwrap();
+        fs::write(vault.join("Elsewhere.md"), "not in the folder").unwrap();
+
+        let options = PublishOptions { folder: "Blog".to_string(), ..Default::default() };
+        let report = publish_site(&Vault::new(vault.clone()).unwrap(), &out, &options).unwrap();
+        assert_eq!((report.pages, report.tags, report.attachments), (2, 1, 1));
+        assert_eq!(report.skipped, vec!["Blog/Private.md".to_string()]);
+
+        let home = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(home.contains(r#"<a href="./posts/First%20post.html" rel="noopener noreferrer">the first post</a>"#));
+        assert!(!home.contains("Private.html"));
+        // Queries only list published notes
+        assert_eq!(home.matches("<tr><td>").count(), 2, "{}", home);
+        let post = fs::read_to_string(out.join("posts/First post.html")).unwrap();
+        assert!(post.contains(r#"src="../_attachments/files/pic.png""#));
+        assert!(post.contains("Linked from"));
+        assert!(!post.contains("secret"));
+        assert!(out.join("_attachments/files/pic.png").is_file());
+        assert!(out.join("_tags/blog.html").is_file());
+        assert!(fs::read_to_string(out.join("_assets/search-index.json")).unwrap().contains("First post"));
+        assert!(publish_site(&Vault::new(vault.clone()).unwrap(), &vault.join("site"), &options).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -4,12 +4,22 @@ use tauri::AppHandle;
 use crate::window_state::{WindowRegistry, WindowState};
 use crate::docker::DockerManager;
 use crate::graph::GraphManagerImpl;
+use crate::index::VaultIndex;
+use crate::file_tree::FileTreeCache;
+use crate::encryption::Keyring;
+use crate::git::AutoCommit;
+use crate::vault::Vault;
+use crate::vault_ignore::IgnoreRules;
 
 /// Global application state that manages multiple windows
 pub struct RefactoredAppState {
     pub window_registry: Arc<WindowRegistry>,
     pub docker_manager: Arc<DockerManager>,
     pub graph_manager: Arc<Mutex<Option<GraphManagerImpl>>>,
+    /// Keys of vaults whose encrypted notes are unlocked, shared by all windows
+    pub keyring: Arc<Keyring>,
+    /// Save counts and timers behind automatic git commits
+    pub auto_commit: Arc<AutoCommit>,
 }
 
 impl RefactoredAppState {
@@ -21,6 +31,8 @@ impl RefactoredAppState {
             window_registry,
             docker_manager,
             graph_manager: Arc::new(Mutex::new(None)),
+            keyring: Arc::new(Keyring::new()),
+            auto_commit: Arc::new(AutoCommit::new()),
         })
     }
     
@@ -59,6 +71,20 @@ impl RefactoredAppState {
         self.window_registry.get_window_vault_path(window_id).await
     }
 
+    /// Vault path of a window, for commands that need an open vault
+    pub async fn window_vault_path(&self, window_id: &str) -> Result<std::path::PathBuf, String> {
+        self.get_window_vault_path(window_id).await
+            .ok_or_else(|| "No vault opened".to_string())
+    }
+
+    /// The vault open in a window, for commands that need one
+    pub async fn window_vault(&self, window_id: &str) -> Result<Vault, String> {
+        let window = self.get_window_state(window_id).await
+            .ok_or_else(|| "Window not found".to_string())?;
+        let vault = window.vault.lock().await;
+        vault.clone().ok_or_else(|| "No vault opened".to_string())
+    }
+
     /// Lists all currently watched vaults
     pub async fn get_watched_vaults(&self) -> Vec<std::path::PathBuf> {
         self.window_registry.get_watched_vaults().await
@@ -68,6 +94,31 @@ impl RefactoredAppState {
     pub async fn get_vault_watcher_ref_count(&self, vault_path: &std::path::PathBuf) -> usize {
         self.window_registry.get_vault_watcher_ref_count(vault_path).await
     }
+
+    /// Gets the shared link/tag index for the vault open in a window
+    pub async fn get_vault_index(&self, window_id: &str) -> Result<Arc<VaultIndex>, String> {
+        self.window_registry.get_vault_index(window_id).await
+    }
+
+    /// Gets a vault's ignore rules, shared with its index and file tree
+    pub fn ignore_rules(&self, vault_path: &std::path::Path) -> Arc<IgnoreRules> {
+        self.window_registry.ignore_rules(vault_path)
+    }
+
+    /// Applies the excluded folders from a vault's settings
+    pub fn set_excluded_folders(&self, vault_path: &std::path::Path, excluded_folders: &[String]) {
+        self.window_registry.ignore_rules(vault_path).set_excluded_folders(excluded_folders);
+    }
+
+    /// Forces a vault's index and file tree to be rebuilt on next use
+    pub async fn invalidate_vault_index(&self, vault_path: &std::path::Path) {
+        self.window_registry.invalidate_vault_index(vault_path).await
+    }
+
+    /// Gets the cached file tree for the vault open in a window
+    pub async fn get_file_tree(&self, window_id: &str) -> Result<Arc<Mutex<FileTreeCache>>, String> {
+        self.window_registry.get_file_tree(window_id).await
+    }
 }
 
 /// Helper function to extract window ID from Tauri commands
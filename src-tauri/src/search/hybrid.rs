@@ -4,33 +4,33 @@ use tokio::sync::Mutex;
 use serde_json::json;
 
 use crate::graph::GraphManagerTrait;
-use crate::mcp::MCPManager;
 use crate::search::types::{
     HybridSearchResult, SearchQuery, GraphResult, SemanticResult, 
     MatchType, SearchMode, SearchOptions
 };
 use crate::search::fusion::{ResultFusion, FusionConfig};
+use crate::vault_ignore::VaultIgnore;
 
 pub struct HybridSearchManager {
     graph_manager: Arc<Mutex<Option<Arc<dyn GraphManagerTrait>>>>,
-    mcp_manager: Arc<MCPManager>,
     vault_path: PathBuf,
     vault_id: String,
+    ignore: Arc<VaultIgnore>,
     fusion: ResultFusion,
 }
 
 impl HybridSearchManager {
     pub fn new(
         graph_manager: Arc<Mutex<Option<Arc<dyn GraphManagerTrait>>>>,
-        mcp_manager: Arc<MCPManager>,
         vault_path: PathBuf,
         vault_id: String,
+        ignore: Arc<VaultIgnore>,
     ) -> Self {
         Self {
             graph_manager,
-            mcp_manager,
             vault_path,
             vault_id,
+            ignore,
             fusion: ResultFusion::with_default_config(),
         }
     }
@@ -113,9 +113,11 @@ impl HybridSearchManager {
         let mut results = Vec::new();
         let search_query = query.query.to_lowercase();
         
+        let ignore = &self.ignore;
         for entry in WalkDir::new(&self.vault_path)
             .follow_links(true)
             .into_iter()
+            .filter_entry(|e| !ignore.is_ignored(e.path(), e.file_type().is_dir()))
             .filter_map(Result::ok)
             .filter(|e| e.file_type().is_file())
             .filter(|e| e.path().extension().map_or(false, |ext| ext == "md"))
@@ -128,6 +130,10 @@ impl HybridSearchManager {
             
             // Read file content
             if let Ok(content) = std::fs::read_to_string(path) {
+                // Search results feed the AI, so encrypted notes stay out of them
+                if crate::encryption::is_encrypted(&content) {
+                    continue;
+                }
                 let content_lower = content.to_lowercase();
                 if content_lower.contains(&search_query) || relative_path.to_lowercase().contains(&search_query) {
                     let title = path.file_stem()
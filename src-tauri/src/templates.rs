@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use walkdir::WalkDir;
+
+use crate::index::frontmatter::merge_frontmatter;
+use crate::index::scanner::frontmatter_range;
+use crate::periodic::format_datetime;
+use crate::vault::resolve_in_vault;
+
+/// Templates including each other deeper than this are treated as a cycle
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Stands in for `{{cursor}}` until the final content is known
+const CURSOR_MARKER: &str = "\u{0}cursor\u{0}";
+
+/// Used for notes created without choosing a template
+pub const NEW_NOTE_TEMPLATE: &str = "# {{title}}";
+
+/// First note of a new vault; `{{title}}` is the vault name
+pub const WELCOME_TEMPLATE: &str = "# Welcome to {{title}}\n\nThis is your new gaimplan vault! Start taking notes by creating new markdown files.\n\n## Getting Started\n\n- Create new notes by clicking the + button\n- Organize your thoughts in folders\n- All your notes are stored as plain markdown files\n\nHappy note-taking! ✨\n";
+
+lazy_static::lazy_static! {
+    static ref VARIABLE: Regex = Regex::new(r"\{\{\s*([A-Za-z_][\w-]*)\s*(?::([^{}]*?))?\s*\}\}").unwrap();
+}
+
+/// What the variables of a template are filled in with
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    pub title: String,
+    /// Day the note is about; today unless it is a periodic note
+    pub date: NaiveDate,
+    /// When the note is created
+    pub now: NaiveDateTime,
+    /// Extra `{{name}}` variables supplied by the caller
+    pub variables: HashMap<String, String>,
+    /// Answers to `{{prompt:Question}}` placeholders, keyed by question
+    pub answers: HashMap<String, String>,
+    /// Frontmatter merged over the template's own
+    pub properties: Map<String, Value>,
+}
+
+impl TemplateContext {
+    pub fn new(title: &str) -> Self {
+        let now = chrono::Local::now().naive_local();
+        Self {
+            title: title.to_string(),
+            date: now.date(),
+            now,
+            variables: HashMap::new(),
+            answers: HashMap::new(),
+            properties: Map::new(),
+        }
+    }
+
+    pub fn with_date(mut self, date: NaiveDate) -> Self {
+        self.date = date;
+        self
+    }
+
+    pub fn with_variable(mut self, name: &str, value: &str) -> Self {
+        self.variables.insert(name.to_string(), value.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RenderedTemplate {
+    pub content: String,
+    /// Where `{{cursor}}` was, in UTF-16 code units as editor positions count
+    pub cursor: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TemplateInfo {
+    /// Name relative to the templates folder, without `.md`
+    pub name: String,
+    /// Vault-relative path
+    pub path: String,
+}
+
+/// Templates stored as notes in a vault folder. Besides `{{title}}`,
+/// `{{date}}`/`{{date:FORMAT}}`, `{{time}}`/`{{time:FORMAT}}` and `{{cursor}}`,
+/// a template can ask for values with `{{prompt:Question}}` and pull in another
+/// with `{{include:Name}}`; the frontmatter of all of them is merged into one block.
+pub struct Templates {
+    vault_path: PathBuf,
+    folder: String,
+}
+
+impl Templates {
+    pub fn new(vault_path: &Path, folder: &str) -> Self {
+        Self {
+            vault_path: vault_path.to_path_buf(),
+            folder: folder.trim_matches('/').to_string(),
+        }
+    }
+
+    /// Templates in the templates folder, sorted by name
+    pub fn list(&self) -> Vec<TemplateInfo> {
+        let folder = self.vault_path.join(&self.folder);
+        let mut templates: Vec<TemplateInfo> = WalkDir::new(&folder)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.path().extension().and_then(|s| s.to_str()) == Some("md"))
+            .filter_map(|e| {
+                let name = e.path().strip_prefix(&folder).ok()?.with_extension("");
+                Some(TemplateInfo {
+                    name: name.to_string_lossy().replace('\\', "/"),
+                    path: crate::index::resolver::relative_path(&self.vault_path, e.path())?,
+                })
+            })
+            .collect();
+        templates.sort_by_key(|t| t.name.to_lowercase());
+        templates
+    }
+
+    /// Reads a template by its name in the templates folder or by its vault-relative path
+    pub fn load(&self, name: &str) -> Result<String, String> {
+        let name = name.trim();
+        let file_name = if name.ends_with(".md") { name.to_string() } else { format!("{}.md", name) };
+        let candidates = [format!("{}/{}", self.folder, file_name), file_name];
+
+        for candidate in candidates.iter().filter(|c| !c.starts_with('/')) {
+            let path = resolve_in_vault(&self.vault_path, Path::new(candidate))?;
+            if path.is_file() {
+                return std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read template {}: {}", name, e));
+            }
+        }
+        Err(format!("Template not found: {}", name))
+    }
+
+    pub fn render(&self, name: &str, context: &TemplateContext) -> Result<RenderedTemplate, String> {
+        let source = self.load(name)?;
+        let mut stack = vec![name.trim().trim_end_matches(".md").to_string()];
+        self.render_with(&source, context, &mut stack)
+    }
+
+    /// Questions of the `{{prompt:...}}` placeholders in a template and the
+    /// templates it includes, in order and without repeats
+    pub fn prompts(&self, name: &str) -> Result<Vec<String>, String> {
+        let mut prompts = Vec::new();
+        let mut stack = vec![name.trim().trim_end_matches(".md").to_string()];
+        self.collect_prompts(&self.load(name)?, &mut prompts, &mut stack)?;
+        Ok(prompts)
+    }
+
+    fn collect_prompts(&self, source: &str, prompts: &mut Vec<String>, stack: &mut Vec<String>) -> Result<(), String> {
+        for captures in VARIABLE.captures_iter(source) {
+            let argument = match captures.get(2).map(|m| m.as_str().trim()).filter(|a| !a.is_empty()) {
+                Some(argument) => argument,
+                None => continue,
+            };
+            match &captures[1] {
+                "prompt" if !prompts.iter().any(|p| p == argument) => prompts.push(argument.to_string()),
+                "include" => {
+                    let key = argument.trim_end_matches(".md").to_string();
+                    if stack.contains(&key) || stack.len() >= MAX_INCLUDE_DEPTH {
+                        return Err(format!("Template include cycle: {} -> {}", stack.join(" -> "), key));
+                    }
+                    let source = self.load(argument)?;
+                    stack.push(key);
+                    self.collect_prompts(&source, prompts, stack)?;
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders template text that is not stored in the vault (e.g. a built-in default)
+    pub fn render_source(&self, source: &str, context: &TemplateContext) -> Result<RenderedTemplate, String> {
+        self.render_with(source, context, &mut Vec::new())
+    }
+
+    fn render_with(&self, source: &str, context: &TemplateContext, stack: &mut Vec<String>) -> Result<RenderedTemplate, String> {
+        let mut blocks = Vec::new();
+        let body = self.expand(source, context, &mut blocks, stack)?;
+
+        // Properties from the caller win over the template's
+        if !context.properties.is_empty() {
+            blocks.push(serde_yaml::to_string(&context.properties).map_err(|e| e.to_string())?);
+        }
+        let blocks: Vec<&str> = blocks.iter().map(String::as_str).collect();
+        let mut content = match merge_frontmatter(&blocks)? {
+            Some(yaml) => format!("---\n{}---\n{}", yaml, body),
+            None => body,
+        };
+
+        let cursor = content.find(CURSOR_MARKER).map(|pos| content[..pos].encode_utf16().count());
+        content = content.replace(CURSOR_MARKER, "");
+        Ok(RenderedTemplate { content, cursor })
+    }
+
+    /// Fills in one template's variables and returns its body; its frontmatter
+    /// (and that of included templates, first) is collected in `blocks`
+    fn expand(&self, source: &str, context: &TemplateContext, blocks: &mut Vec<String>, stack: &mut Vec<String>) -> Result<String, String> {
+        let (yaml, body) = match frontmatter_range(source) {
+            Some((whole, yaml)) => (Some(&source[yaml]), &source[whole.end..]),
+            None => (None, source),
+        };
+
+        let body = self.substitute(body, context, blocks, stack)?;
+        if let Some(yaml) = yaml {
+            let yaml = self.substitute(yaml, context, blocks, stack)?;
+            blocks.push(yaml.replace(CURSOR_MARKER, ""));
+        }
+        Ok(body)
+    }
+
+    fn substitute(&self, text: &str, context: &TemplateContext, blocks: &mut Vec<String>, stack: &mut Vec<String>) -> Result<String, String> {
+        let mut output = String::with_capacity(text.len());
+        let mut last = 0;
+
+        for captures in VARIABLE.captures_iter(text) {
+            let whole = captures.get(0).unwrap();
+            let name = &captures[1];
+            let argument = captures.get(2).map(|m| m.as_str().trim()).filter(|a| !a.is_empty());
+            output.push_str(&text[last..whole.start()]);
+            last = whole.end();
+
+            if name == "include" {
+                let included = argument.ok_or_else(|| "{{include}} needs a template name".to_string())?;
+                let key = included.trim_end_matches(".md").to_string();
+                if stack.contains(&key) || stack.len() >= MAX_INCLUDE_DEPTH {
+                    return Err(format!("Template include cycle: {} -> {}", stack.join(" -> "), key));
+                }
+                let source = self.load(included)?;
+                stack.push(key);
+                output.push_str(&self.expand(&source, context, blocks, stack)?);
+                stack.pop();
+                continue;
+            }
+
+            match variable(name, argument, context) {
+                Some(value) => output.push_str(&value),
+                // Unknown variables stay as written
+                None => output.push_str(whole.as_str()),
+            }
+        }
+        output.push_str(&text[last..]);
+        Ok(output)
+    }
+}
+
+fn variable(name: &str, argument: Option<&str>, context: &TemplateContext) -> Option<String> {
+    let date = context.date;
+    let month_name = date.format("%B").to_string();
+    let day_name = date.format("%A").to_string();
+
+    Some(match name {
+        "title" => context.title.clone(),
+        "date" => format_datetime(date.and_time(context.now.time()), argument.unwrap_or("YYYY-MM-DD")),
+        "time" => format_datetime(context.now, argument.unwrap_or("HH:mm")),
+        "cursor" => CURSOR_MARKER.to_string(),
+        // Unanswered prompts leave nothing behind
+        "prompt" => context.answers.get(argument?).cloned().unwrap_or_default(),
+        // Variables the calendar widget's daily note templates have always used
+        "dayName" => day_name,
+        "monthName" => month_name,
+        "day" => date.day().to_string(),
+        "year" => date.year().to_string(),
+        "week" => date.iso_week().week().to_string(),
+        "fullDate" => format!("{}, {} {}, {}", day_name, month_name, date.day(), date.year()),
+        _ => return context.variables.get(name).cloned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> TemplateContext {
+        let now = NaiveDate::from_ymd_opt(2025, 7, 3).unwrap().and_hms_opt(9, 5, 0).unwrap();
+        TemplateContext { now, date: now.date(), ..TemplateContext::new("Plan") }
+    }
+
+    #[test]
+    fn test_variables_and_cursor() {
+        let templates = Templates::new(Path::new("/nonexistent"), "Templates");
+        let rendered = templates
+            .render_source("# {{title}}\n{{date:dddd, MMMM D}} at {{time}} — {{unknown}}\n{{cursor}}é", &context())
+            .unwrap();
+        assert_eq!(rendered.content, "# Plan\nThursday, July 3 at 09:05 — {{unknown}}\né");
+        assert_eq!(rendered.cursor, Some(rendered.content.encode_utf16().count() - 1));
+    }
+
+    #[test]
+    fn test_prompts_are_listed_and_answered() {
+        let vault = std::env::temp_dir().join(format!("gaimplan-prompts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(vault.join("Templates")).unwrap();
+        std::fs::write(vault.join("Templates/Owner.md"), "Owner: {{prompt:Owner}}").unwrap();
+        std::fs::write(vault.join("Templates/Project.md"), "# {{prompt:Project name}}\n{{include:Owner}}\n{{prompt:Project name}} ({{prompt:Status}})").unwrap();
+
+        let templates = Templates::new(&vault, "Templates");
+        assert_eq!(templates.prompts("Project").unwrap(), vec!["Project name", "Owner", "Status"]);
+
+        let mut context = context();
+        context.answers.insert("Project name".into(), "Apollo".into());
+        context.answers.insert("Owner".into(), "Sam".into());
+        let rendered = templates.render("Project", &context).unwrap();
+        assert_eq!(rendered.content, "# Apollo\nOwner: Sam\nApollo ()");
+
+        std::fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_includes_merge_frontmatter() {
+        let vault = std::env::temp_dir().join(format!("gaimplan-templates-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(vault.join("Templates/Parts")).unwrap();
+        std::fs::write(vault.join("Templates/Parts/Footer.md"), "---\ntags: [base]\nstatus: draft\n---\n-- {{title}}").unwrap();
+        std::fs::write(vault.join("Templates/Meeting.md"), "---\ntags: [meeting]\ncreated: {{date}}\n---\n# {{title}}\n{{include:Parts/Footer}}").unwrap();
+        std::fs::write(vault.join("Templates/Loop.md"), "{{include:Loop}}").unwrap();
+
+        let templates = Templates::new(&vault, "Templates");
+        assert_eq!(templates.list().iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), vec!["Loop", "Meeting", "Parts/Footer"]);
+
+        let mut context = context();
+        context.properties.insert("status".into(), Value::String("final".into()));
+        let rendered = templates.render("Meeting", &context).unwrap();
+        assert_eq!(
+            rendered.content,
+            "---\ntags:\n- base\n- meeting\nstatus: final\ncreated: 2025-07-03\n---\n# Plan\n-- Plan"
+        );
+
+        assert!(templates.render("Loop", &context).unwrap_err().contains("cycle"));
+        assert!(templates.prompts("Loop").unwrap_err().contains("cycle"));
+        assert!(templates.render("../../etc/passwd", &context).is_err());
+
+        std::fs::remove_dir_all(&vault).unwrap();
+    }
+}
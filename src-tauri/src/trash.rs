@@ -0,0 +1,270 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::vault::{resolve_in_vault, INTERNAL_DIR};
+
+const TRASH_DIR: &str = "trash";
+const META_FILE: &str = "meta.json";
+
+/// A deleted file or folder waiting in the vault trash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    /// Vault-relative path the item was deleted from
+    pub original_path: String,
+    pub trashed_at: DateTime<Utc>,
+    pub is_dir: bool,
+}
+
+/// Vault-local trash in `.gaimplan/trash`. Every entry gets its own folder
+/// holding the deleted item and a `meta.json` describing where it came from.
+pub struct Trash {
+    vault_path: PathBuf,
+}
+
+impl Trash {
+    pub fn new(vault_path: &Path) -> Self {
+        Self {
+            vault_path: vault_path.to_path_buf(),
+        }
+    }
+
+    fn trash_dir(&self) -> PathBuf {
+        self.vault_path.join(INTERNAL_DIR).join(TRASH_DIR)
+    }
+
+    /// Moves a vault-relative file or folder into the trash
+    pub fn move_to_trash(&self, relative_path: &str) -> Result<TrashEntry, String> {
+        let source = resolve_in_vault(&self.vault_path, Path::new(relative_path))?;
+        let original_path = source
+            .strip_prefix(&self.vault_path)
+            .unwrap_or(&source)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let file_name = source
+            .file_name()
+            .ok_or_else(|| format!("Invalid path: {}", relative_path))?
+            .to_os_string();
+        if !source.exists() {
+            return Err(format!("File does not exist: {}", relative_path));
+        }
+
+        let trashed_at = Utc::now();
+        let id = format!(
+            "{}-{}",
+            trashed_at.format("%Y%m%d%H%M%S"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        );
+        let entry = TrashEntry {
+            id: id.clone(),
+            original_path,
+            trashed_at,
+            is_dir: source.is_dir(),
+        };
+
+        let entry_dir = self.trash_dir().join(&id);
+        fs::create_dir_all(&entry_dir)
+            .map_err(|e| format!("Failed to create trash folder: {}", e))?;
+
+        // Metadata goes first so watchers seeing the removal can tell it was trashed
+        let meta = serde_json::to_string_pretty(&entry).map_err(|e| e.to_string())?;
+        fs::write(entry_dir.join(META_FILE), meta)
+            .map_err(|e| format!("Failed to write trash metadata: {}", e))?;
+
+        if let Err(e) = fs::rename(&source, entry_dir.join(&file_name)) {
+            let _ = fs::remove_dir_all(&entry_dir);
+            return Err(format!("Failed to move to trash: {}", e));
+        }
+
+        println!("🗑️ Moved {} to trash ({})", entry.original_path, id);
+        Ok(entry)
+    }
+
+    /// Lists trash entries, most recently deleted first
+    pub fn list(&self) -> Result<Vec<TrashEntry>, String> {
+        let dir = self.trash_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries: Vec<TrashEntry> = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read trash: {}", e))?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| fs::read_to_string(e.path().join(META_FILE)).ok())
+            .filter_map(|meta| serde_json::from_str(&meta).ok())
+            .collect();
+
+        entries.sort_by_key(|e| std::cmp::Reverse(e.trashed_at));
+        Ok(entries)
+    }
+
+    /// Checks whether an item deleted from `relative_path` is in the trash
+    pub fn contains_original(&self, relative_path: &str) -> bool {
+        let relative_path = relative_path.replace('\\', "/");
+        self.list()
+            .map(|entries| {
+                entries.iter().any(|e| {
+                    e.original_path == relative_path
+                        || (e.is_dir && relative_path.starts_with(&format!("{}/", e.original_path)))
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    /// Moves an entry back to its original location and returns the restored
+    /// vault-relative path. If that path is taken, " (restored)" is appended.
+    pub fn restore(&self, id: &str) -> Result<String, String> {
+        let (entry, item_path) = self.load_entry(id)?;
+
+        let restored_path = self.free_restore_path(&entry.original_path);
+        let target = self.vault_path.join(&restored_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+        }
+
+        fs::rename(&item_path, &target)
+            .map_err(|e| format!("Failed to restore from trash: {}", e))?;
+        fs::remove_dir_all(self.trash_dir().join(id))
+            .map_err(|e| format!("Failed to clean up trash entry: {}", e))?;
+
+        println!("♻️ Restored {} from trash", restored_path);
+        Ok(restored_path)
+    }
+
+    /// Original vault-relative paths of the markdown notes inside an entry
+    pub fn note_paths(&self, entry: &TrashEntry) -> Vec<String> {
+        if !entry.is_dir {
+            return if entry.original_path.ends_with(".md") { vec![entry.original_path.clone()] } else { Vec::new() };
+        }
+
+        let item_path = match self.load_entry(&entry.id) {
+            Ok((_, item_path)) => item_path,
+            Err(_) => return Vec::new(),
+        };
+        walkdir::WalkDir::new(&item_path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.path().extension().and_then(|s| s.to_str()) == Some("md"))
+            .filter_map(|e| {
+                e.path().strip_prefix(&item_path).ok().map(|rel| {
+                    format!("{}/{}", entry.original_path, rel.to_string_lossy().replace('\\', "/"))
+                })
+            })
+            .collect()
+    }
+
+    /// Permanently deletes an entry
+    pub fn purge(&self, id: &str) -> Result<TrashEntry, String> {
+        let (entry, _) = self.load_entry(id)?;
+        fs::remove_dir_all(self.trash_dir().join(id))
+            .map_err(|e| format!("Failed to purge trash entry: {}", e))?;
+        Ok(entry)
+    }
+
+    /// Permanently deletes entries older than `retention_days` (0 keeps everything)
+    /// and returns the original paths of the notes they held
+    pub fn purge_expired(&self, retention_days: u32) -> Result<Vec<String>, String> {
+        if retention_days == 0 {
+            return Ok(Vec::new());
+        }
+
+        let cutoff = Utc::now() - Duration::days(retention_days as i64);
+        let mut purged = 0;
+        let mut notes = Vec::new();
+        for entry in self.list()?.into_iter().filter(|e| e.trashed_at < cutoff) {
+            notes.extend(self.note_paths(&entry));
+            self.purge(&entry.id)?;
+            purged += 1;
+        }
+
+        if purged > 0 {
+            println!("🧹 Purged {} expired trash entries", purged);
+        }
+        Ok(notes)
+    }
+
+    fn load_entry(&self, id: &str) -> Result<(TrashEntry, PathBuf), String> {
+        // Ids are generated by us; anything else could point outside the trash
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("Invalid trash entry id: {}", id));
+        }
+
+        let entry_dir = self.trash_dir().join(id);
+        let meta = fs::read_to_string(entry_dir.join(META_FILE))
+            .map_err(|_| format!("Trash entry not found: {}", id))?;
+        let entry: TrashEntry = serde_json::from_str(&meta)
+            .map_err(|e| format!("Invalid trash metadata: {}", e))?;
+
+        let file_name = Path::new(&entry.original_path)
+            .file_name()
+            .ok_or_else(|| "Invalid trash metadata".to_string())?;
+        Ok((entry.clone(), entry_dir.join(file_name)))
+    }
+
+    fn free_restore_path(&self, original_path: &str) -> String {
+        if !self.vault_path.join(original_path).exists() {
+            return original_path.to_string();
+        }
+
+        let path = Path::new(original_path);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("restored");
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|e| format!(".{}", e))
+            .unwrap_or_default();
+        let parent = match original_path.rfind('/') {
+            Some(idx) => format!("{}/", &original_path[..idx]),
+            None => String::new(),
+        };
+
+        let mut counter = 1;
+        loop {
+            let suffix = if counter == 1 { " (restored)".to_string() } else { format!(" (restored {})", counter) };
+            let candidate = format!("{}{}{}{}", parent, stem, suffix, extension);
+            if !self.vault_path.join(&candidate).exists() {
+                return candidate;
+            }
+            counter += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_restore_round_trip() {
+        let vault = std::env::temp_dir().join(format!("gaimplan-trash-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(vault.join("notes")).unwrap();
+        fs::write(vault.join("notes/a.md"), "hello").unwrap();
+
+        let trash = Trash::new(&vault);
+        let entry = trash.move_to_trash("notes/a.md").unwrap();
+        assert!(!vault.join("notes/a.md").exists());
+        assert!(trash.contains_original("notes/a.md"));
+
+        // Restoring next to a new file with the same name keeps both
+        fs::write(vault.join("notes/a.md"), "new").unwrap();
+        assert_eq!(trash.restore(&entry.id).unwrap(), "notes/a (restored).md");
+        assert_eq!(fs::read_to_string(vault.join("notes/a (restored).md")).unwrap(), "hello");
+        assert!(trash.list().unwrap().is_empty());
+        assert!(trash.restore("../../etc").is_err());
+
+        // A trashed folder is a single rename; its notes are listed under their original paths
+        fs::create_dir_all(vault.join("notes/deep")).unwrap();
+        fs::write(vault.join("notes/deep/b.md"), "b").unwrap();
+        let entry = trash.move_to_trash("notes").unwrap();
+        assert!(!vault.join("notes").exists());
+        let mut notes = trash.note_paths(&entry);
+        notes.sort();
+        assert_eq!(notes, vec!["notes/a (restored).md", "notes/a.md", "notes/deep/b.md"]);
+        assert!(trash.contains_original("notes"));
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+}
@@ -1,10 +1,30 @@
-use std::path::{Path, PathBuf};
-use std::io;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, Weak};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 
+use crate::vault_ignore::{IgnoreRules, VaultIgnore};
+
+/// Folder inside each vault for app data (trash, logs, docker config); never listed as content
+pub const INTERNAL_DIR: &str = ".gaimplan";
+
+/// Checks whether a vault-relative path lies inside the internal `.gaimplan` folder
+pub fn is_internal_path(relative_path: &Path) -> bool {
+    relative_path
+        .components()
+        .next()
+        .is_some_and(|c| c.as_os_str() == INTERNAL_DIR)
+}
+
 #[derive(Debug, Clone)]
 pub struct Vault {
     path: PathBuf,
+    /// The vault's shared ignore rules; without them only the built-in
+    /// patterns and `.gaimplanignore` apply
+    ignore: Option<Arc<IgnoreRules>>,
 }
 
 impl Vault {
@@ -23,21 +43,42 @@ impl Vault {
             ));
         }
         
-        Ok(Self { path })
+        Ok(Self { path, ignore: None })
+    }
+    
+    pub fn with_ignore_rules(mut self, ignore: Arc<IgnoreRules>) -> Self {
+        self.ignore = Some(ignore);
+        self
     }
     
     pub fn path(&self) -> &Path {
         &self.path
     }
     
+    /// The vault's ignore rules, which follow later changes to them
+    pub fn ignore_rules(&self) -> Arc<IgnoreRules> {
+        self.ignore.clone()
+            .unwrap_or_else(|| Arc::new(IgnoreRules::new(&self.path, &[])))
+    }
+    
+    /// The ignore rules that currently apply to the vault
+    pub fn ignore(&self) -> Arc<VaultIgnore> {
+        match &self.ignore {
+            Some(ignore) => ignore.current(),
+            None => Arc::new(VaultIgnore::load(&self.path, &[])),
+        }
+    }
+    
     pub fn list_markdown_files(&self) -> io::Result<Vec<PathBuf>> {
         let mut items = Vec::new();
         
         // Scanning vault directory
         
+        let ignore = self.ignore();
         for entry in WalkDir::new(&self.path)
             .follow_links(true)
             .into_iter()
+            .filter_entry(|e| !ignore.is_ignored(e.path(), e.file_type().is_dir()))
             .filter_map(|e| e.ok())
         {
             let path = entry.path();
@@ -58,11 +99,8 @@ impl Vault {
                 if ext == Some("md") {
                     // Adding markdown file
                     items.push(path.to_path_buf());
-                } else if matches!(ext, Some("png") | Some("jpg") | Some("jpeg") | Some("gif")) {
-                    // Adding image file
-                    items.push(path.to_path_buf());
-                } else if ext == Some("pdf") {
-                    // Adding PDF file
+                } else if crate::index::attachments::is_attachment(&path.to_string_lossy()) {
+                    // Adding image or PDF file
                     items.push(path.to_path_buf());
                 }
             }
@@ -73,18 +111,379 @@ impl Vault {
         Ok(items)
     }
     
+    /// Resolves a path from a command (vault-relative, or absolute inside the
+    /// vault) to a full path, refusing anything that escapes the vault
+    pub fn resolve_path(&self, path: &Path) -> Result<PathBuf, PathError> {
+        resolve_in_vault(&self.path, path)
+    }
+    
     pub fn read_file(&self, relative_path: &Path) -> io::Result<String> {
-        let full_path = self.path.join(relative_path);
+        let full_path = self.resolve_path(relative_path)?;
         std::fs::read_to_string(full_path)
     }
     
     pub fn write_file(&self, relative_path: &Path, content: &str) -> io::Result<()> {
-        let full_path = self.path.join(relative_path);
+        let full_path = self.resolve_path(relative_path)?;
+        let lock = write_lock(&full_path);
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        create_and_write(&full_path, content)
+    }
+    
+    /// Reads a file together with the version the client must send back when saving
+    pub fn read_file_versioned(&self, relative_path: &Path) -> io::Result<(String, FileVersion)> {
+        let full_path = self.resolve_path(relative_path)?;
+        let content = std::fs::read_to_string(&full_path)?;
+        let version = FileVersion::of(&full_path, &content)?;
+        Ok((content, version))
+    }
+    
+    /// Writes a file only if it still matches the version the client last read.
+    /// Without an expected hash or mtime the write is unconditional.
+    pub fn write_file_checked(
+        &self,
+        relative_path: &Path,
+        content: &str,
+        expected_hash: Option<&str>,
+        expected_modified: Option<i64>,
+    ) -> Result<FileVersion, WriteError> {
+        let full_path = self.resolve_path(relative_path).map_err(io::Error::from)?;
+        
+        // The comparison and the write happen under one lock, so two saves of
+        // the same file cannot both pass the check
+        let lock = write_lock(&full_path);
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        
+        if full_path.is_file() && (expected_hash.is_some() || expected_modified.is_some()) {
+            let disk_content = std::fs::read_to_string(&full_path)?;
+            let disk_version = FileVersion::of(&full_path, &disk_content)?;
+            
+            // The hash is authoritative; mtime is only used when no hash was sent
+            let changed = match expected_hash {
+                Some(hash) => hash != disk_version.hash,
+                None => expected_modified != Some(disk_version.modified),
+            };
+            
+            if changed && disk_content != content {
+                return Err(WriteError::Conflict {
+                    path: relative_path.to_string_lossy().to_string(),
+                    disk_content,
+                    disk_version,
+                    client_content: content.to_string(),
+                });
+            }
+        }
+        
+        create_and_write(&full_path, content)?;
+        Ok(FileVersion::of(&full_path, content)?)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref WRITE_LOCKS: Mutex<HashMap<PathBuf, Weak<Mutex<()>>>> = Mutex::new(HashMap::new());
+}
+
+/// Lock held while a file is written through the vault; entries go away
+/// once no write holds them
+pub(crate) fn write_lock(full_path: &Path) -> Arc<Mutex<()>> {
+    let mut locks = WRITE_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    locks.retain(|_, lock| lock.strong_count() > 0);
+    if let Some(lock) = locks.get(full_path).and_then(Weak::upgrade) {
+        return lock;
+    }
+    let lock = Arc::new(Mutex::new(()));
+    locks.insert(full_path.to_path_buf(), Arc::downgrade(&lock));
+    lock
+}
+
+fn create_and_write(full_path: &Path, content: &str) -> io::Result<()> {
+    if let Some(parent) = full_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_atomic(full_path, content.as_bytes())
+}
+
+/// Why a path supplied by a command was refused
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PathError {
+    /// `..`, an absolute path or a symlink leads outside the vault
+    OutsideVault { path: String },
+    /// The path contains characters no file system accepts
+    Invalid { path: String },
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::OutsideVault { path } => write!(f, "Path is outside the vault: {}", path),
+            PathError::Invalid { path } => write!(f, "Invalid path: {}", path),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+impl From<PathError> for io::Error {
+    fn from(e: PathError) -> Self {
+        let kind = match e {
+            PathError::OutsideVault { .. } => io::ErrorKind::PermissionDenied,
+            PathError::Invalid { .. } => io::ErrorKind::InvalidInput,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+impl From<PathError> for String {
+    fn from(e: PathError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Resolves `path` against `vault_root` the way every file command must:
+/// absolute paths are only accepted inside the vault, `..` may not climb above
+/// the root, and whatever already exists on disk may not be a symlink leading
+/// out of it. The returned path is under `vault_root` without `.`/`..` parts.
+pub fn resolve_in_vault(vault_root: &Path, path: &Path) -> Result<PathBuf, PathError> {
+    let display = path.to_string_lossy().to_string();
+    if display.contains('\0') {
+        return Err(PathError::Invalid { path: display });
+    }
+    let outside = || PathError::OutsideVault { path: display.clone() };
+    let real_root = vault_root.canonicalize().unwrap_or_else(|_| vault_root.to_path_buf());
+
+    let relative = if path.has_root() {
+        path.strip_prefix(vault_root)
+            .or_else(|_| path.strip_prefix(&real_root))
+            .map_err(|_| outside())?
+    } else {
+        path
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return Err(outside());
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return Err(outside()),
+        }
+    }
+    let full_path = vault_root.join(&normalized);
+
+    // Walk up to the deepest part that exists and check where it really points
+    let mut existing = full_path.as_path();
+    loop {
+        if existing.symlink_metadata().is_ok() {
+            // A dangling symlink cannot be canonicalized and is refused too
+            let real = existing.canonicalize().map_err(|_| outside())?;
+            if !real.starts_with(&real_root) {
+                return Err(outside());
+            }
+            break;
+        }
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => break,
+        }
+    }
+
+    Ok(full_path)
+}
+
+/// Whether `path` is `dir` or lies under it, comparing where they really
+/// point: the deepest part of `path` that exists is canonicalized and the
+/// rest appended, so symlinks and folders not created yet are both handled
+pub fn is_inside(path: &Path, dir: &Path) -> bool {
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let mut existing = path;
+    let mut missing = Vec::new();
+    let real = loop {
+        if let Ok(real) = existing.canonicalize() {
+            break real;
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.starts_with(&dir),
+        }
+    };
+    missing.iter().rev().fold(real, |path, name| path.join(name)).starts_with(dir)
+}
+
+/// What a client knows about the file it is editing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileVersion {
+    /// SHA-256 of the content
+    pub hash: String,
+    /// Modification time in milliseconds since the Unix epoch
+    pub modified: i64,
+}
+
+impl FileVersion {
+    fn of(full_path: &Path, content: &str) -> io::Result<Self> {
+        let modified = std::fs::metadata(full_path)?
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
         
-        if let Some(parent) = full_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        Ok(Self {
+            hash: content_hash(content),
+            modified,
+        })
+    }
+}
+
+/// Error returned by checked writes; serialized so the frontend can offer a merge
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WriteError {
+    /// The file changed on disk since the client read it
+    Conflict {
+        path: String,
+        disk_content: String,
+        disk_version: FileVersion,
+        client_content: String,
+    },
+    Io { message: String },
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::Conflict { path, .. } => write!(f, "File changed on disk: {}", path),
+            WriteError::Io { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<io::Error> for WriteError {
+    fn from(e: io::Error) -> Self {
+        WriteError::Io { message: format!("Failed to write file: {}", e) }
+    }
+}
+
+impl From<String> for WriteError {
+    fn from(message: String) -> Self {
+        WriteError::Io { message }
+    }
+}
+
+/// SHA-256 of a note's content, shared by save conflict checks, the graph
+/// update queue and version history
+pub fn content_hash(content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Writes through a temporary file in the same folder and renames it into
+/// place, so readers never see a half-written file and a crash leaves the old
+/// content intact
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
+    let temp_path = parent.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        uuid::Uuid::new_v4().simple()
+    ));
+    
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        
+        // Keep the permissions of the file being replaced
+        if let Ok(metadata) = std::fs::metadata(path) {
+            std::fs::set_permissions(&temp_path, metadata.permissions())?;
         }
         
-        std::fs::write(full_path, content)
+        std::fs::rename(&temp_path, path)
+    })();
+    
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_write_detects_external_change() {
+        let dir = std::env::temp_dir().join(format!("gaimplan-vault-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let vault = Vault::new(dir.clone()).unwrap();
+        let path = Path::new("note.md");
+
+        vault.write_file(path, "v1").unwrap();
+        let (_, version) = vault.read_file_versioned(path).unwrap();
+
+        // Someone else saves in between
+        vault.write_file(path, "external").unwrap();
+
+        match vault.write_file_checked(path, "mine", Some(&version.hash), None) {
+            Err(WriteError::Conflict { disk_content, .. }) => assert_eq!(disk_content, "external"),
+            other => panic!("expected conflict, got {:?}", other),
+        }
+
+        let (_, current) = vault.read_file_versioned(path).unwrap();
+        let saved = vault.write_file_checked(path, "mine", Some(&current.hash), None).unwrap();
+        assert_eq!(saved.hash, content_hash("mine"));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_path_rejects_escapes() {
+        let dir = std::env::temp_dir().join(format!("gaimplan-sandbox-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("notes")).unwrap();
+        let vault = Vault::new(dir.clone()).unwrap();
+
+        assert_eq!(vault.resolve_path(Path::new("notes/../a.md")).unwrap(), dir.join("a.md"));
+        assert_eq!(vault.resolve_path(&dir.join("notes/b.md")).unwrap(), dir.join("notes/b.md"));
+        assert_eq!(vault.resolve_path(Path::new("./new/c.md")).unwrap(), dir.join("new/c.md"));
+
+        for escape in ["../../.ssh/id_rsa", "notes/../../x.md", "/etc/passwd"] {
+            assert!(matches!(vault.resolve_path(Path::new(escape)), Err(PathError::OutsideVault { .. })), "{}", escape);
+        }
+        assert!(vault.read_file(Path::new("../secret.md")).is_err());
+        assert!(vault.write_file(Path::new("../secret.md"), "x").is_err());
+        assert!(!dir.parent().unwrap().join("secret.md").exists());
+
+        #[cfg(unix)]
+        {
+            let outside = std::env::temp_dir().join(format!("gaimplan-outside-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&outside).unwrap();
+            std::os::unix::fs::symlink(&outside, dir.join("linked")).unwrap();
+            std::os::unix::fs::symlink(outside.join("missing.md"), dir.join("dangling.md")).unwrap();
+            std::os::unix::fs::symlink(dir.join("notes"), dir.join("inside")).unwrap();
+
+            assert!(vault.resolve_path(Path::new("linked/id_rsa")).is_err());
+            assert!(vault.resolve_path(Path::new("dangling.md")).is_err());
+            assert!(vault.resolve_path(Path::new("inside/ok.md")).is_ok());
+
+            std::os::unix::fs::symlink(&dir, outside.join("vault")).unwrap();
+            assert!(is_inside(&outside.join("vault/site/new"), &dir));
+            assert!(!is_inside(&dir.join("linked/site"), &dir));
+            assert!(is_inside(&dir.join("notes"), &dir) && !is_inside(&outside, &dir));
+
+            std::fs::remove_dir_all(&outside).unwrap();
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
-}
\ No newline at end of file
+}
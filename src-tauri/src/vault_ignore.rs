@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::vault::is_internal_path;
+
+/// Per-vault ignore file with gitignore syntax, read from the vault root
+pub const IGNORE_FILE: &str = ".gaimplanignore";
+
+/// Skipped in every vault, on top of the internal `.gaimplan` folder
+const BUILTIN_PATTERNS: &[&str] = &[".git/", "node_modules/", ".trash/"];
+
+/// A vault's current ignore rules, kept with its other per-vault state next
+/// to its `VaultIndex`. Rebuilt whenever `.gaimplanignore` changes.
+#[derive(Debug)]
+pub struct IgnoreRules {
+    vault_path: PathBuf,
+    cached: Mutex<CachedRules>,
+}
+
+#[derive(Debug)]
+struct CachedRules {
+    excluded_folders: Vec<String>,
+    /// Modification time of `.gaimplanignore` when the rules were built
+    ignore_file_modified: Option<SystemTime>,
+    rules: Arc<VaultIgnore>,
+}
+
+impl IgnoreRules {
+    pub fn new(vault_path: &Path, excluded_folders: &[String]) -> Self {
+        Self {
+            vault_path: vault_path.to_path_buf(),
+            cached: Mutex::new(CachedRules::load(vault_path, excluded_folders)),
+        }
+    }
+
+    /// The rules as of now, reloaded if `.gaimplanignore` changed
+    pub fn current(&self) -> Arc<VaultIgnore> {
+        let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        if cached.ignore_file_modified != ignore_file_modified(&self.vault_path) {
+            *cached = CachedRules::load(&self.vault_path, &cached.excluded_folders);
+        }
+        cached.rules.clone()
+    }
+
+    /// Replaces the excluded folders configured in the vault's settings
+    pub fn set_excluded_folders(&self, excluded_folders: &[String]) {
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = CachedRules::load(&self.vault_path, excluded_folders);
+    }
+}
+
+impl CachedRules {
+    fn load(vault_path: &Path, excluded_folders: &[String]) -> Self {
+        Self {
+            excluded_folders: excluded_folders.to_vec(),
+            ignore_file_modified: ignore_file_modified(vault_path),
+            rules: Arc::new(VaultIgnore::load(vault_path, excluded_folders)),
+        }
+    }
+}
+
+/// Decides which vault paths are left out of the file tree, indexes, graph
+/// sync, search and watcher events. Combines built-in patterns, the vault's
+/// `.gaimplanignore` and the excluded folders from its settings.
+#[derive(Debug)]
+pub struct VaultIgnore {
+    vault_path: PathBuf,
+    matcher: Gitignore,
+}
+
+impl VaultIgnore {
+    /// Builds the rules for a vault. Invalid lines in `.gaimplanignore` are
+    /// reported and skipped.
+    pub fn load(vault_path: &Path, excluded_folders: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new(vault_path);
+        for pattern in BUILTIN_PATTERNS {
+            let _ = builder.add_line(None, pattern);
+        }
+        for folder in excluded_folders {
+            let folder = folder.trim().replace('\\', "/");
+            let folder = folder.trim_matches('/');
+            if !folder.is_empty() {
+                let _ = builder.add_line(None, &format!("/{}/", folder));
+            }
+        }
+
+        let ignore_file = vault_path.join(IGNORE_FILE);
+        if ignore_file.is_file() {
+            if let Some(e) = builder.add(&ignore_file) {
+                eprintln!("⚠️ Problem reading {}: {}", ignore_file.display(), e);
+            }
+        }
+
+        let matcher = builder.build().unwrap_or_else(|e| {
+            eprintln!("⚠️ Failed to build ignore rules for {}: {}", vault_path.display(), e);
+            Gitignore::empty()
+        });
+
+        Self {
+            vault_path: vault_path.to_path_buf(),
+            matcher,
+        }
+    }
+
+    /// Checks an absolute or vault-relative path. A path is ignored when it or
+    /// any folder above it matches. Paths outside the vault are never ignored.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = if path.is_absolute() {
+            match path.strip_prefix(&self.vault_path) {
+                Ok(relative) => relative,
+                Err(_) => return false,
+            }
+        } else {
+            path
+        };
+        if relative.as_os_str().is_empty() {
+            return false;
+        }
+
+        is_internal_path(relative)
+            || self.matcher.matched_path_or_any_parents(relative, is_dir).is_ignore()
+    }
+}
+
+fn ignore_file_modified(vault_path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(vault_path.join(IGNORE_FILE))
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+/// Checks whether a watcher event path is the vault's ignore file
+pub fn is_ignore_file(vault_path: &Path, path: &Path) -> bool {
+    path.strip_prefix(vault_path).map(|rel| rel == Path::new(IGNORE_FILE)).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_ignore_file_and_excluded_folders() {
+        let vault = std::env::temp_dir().join(format!("gaimplan-ignore-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&vault).unwrap();
+        std::fs::write(vault.join(IGNORE_FILE), "drafts/\n*.tmp.md\n!keep.tmp.md\n").unwrap();
+
+        let rules = VaultIgnore::load(&vault, &["Archive/Old".to_string()]);
+
+        assert!(rules.is_ignored(Path::new("node_modules/pkg/readme.md"), false));
+        assert!(rules.is_ignored(Path::new(".git"), true));
+        assert!(rules.is_ignored(Path::new(".gaimplan/docker/data.md"), false));
+        assert!(rules.is_ignored(&vault.join("notes/drafts/idea.md"), false));
+        assert!(rules.is_ignored(Path::new("scratch.tmp.md"), false));
+        assert!(!rules.is_ignored(Path::new("keep.tmp.md"), false));
+        assert!(rules.is_ignored(Path::new("Archive/Old/2019.md"), false));
+        assert!(!rules.is_ignored(Path::new("Archive/2019.md"), false));
+        assert!(!rules.is_ignored(Path::new("notes/idea.md"), false));
+        assert!(!rules.is_ignored(Path::new("/elsewhere/node_modules/x.md"), false));
+
+        let vault_rules = IgnoreRules::new(&vault, &[]);
+        assert!(!vault_rules.current().is_ignored(Path::new("Archive/Old/2019.md"), false));
+        vault_rules.set_excluded_folders(&["Archive/Old".to_string()]);
+        assert!(vault_rules.current().is_ignored(Path::new("Archive/Old/2019.md"), false));
+
+        std::fs::remove_dir_all(&vault).unwrap();
+    }
+}
@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 use tauri_plugin_store::StoreExt;
 use sha2::{Sha256, Digest};
 use std::path::Path;
 
+use crate::periodic::{Period, PeriodicNoteSettings};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VaultSettings {
     pub vault_path: String,
@@ -25,15 +27,133 @@ pub struct EditorSettings {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileSettings {
     pub image_location: String,
+    /// Pasted image names; `{timestamp}`, `{note}` and `{n}` are filled in
     pub image_naming_pattern: String,
+    /// Puts pasted images in a subfolder of `image_location` named after the note
+    #[serde(default)]
+    pub image_subfolder_per_note: bool,
     #[serde(default = "default_daily_notes_folder")]
     pub daily_notes_folder: String,
+    /// File name format of daily notes (see `periodic::format_date`)
+    #[serde(default = "default_daily_note_format")]
+    pub daily_note_format: String,
+    /// Vault-relative template for new daily notes; empty uses the built-in one
+    #[serde(default)]
+    pub daily_note_template: String,
+    #[serde(default = "default_weekly_notes")]
+    pub weekly_notes: PeriodicNoteSettings,
+    #[serde(default = "default_monthly_notes")]
+    pub monthly_notes: PeriodicNoteSettings,
+    /// Vault-relative folder holding note templates
+    #[serde(default = "default_templates_folder")]
+    pub templates_folder: String,
+    /// Template for notes created with the + button; empty uses `# {{title}}`
+    #[serde(default)]
+    pub new_note_template: String,
+    /// Template chat exports are written with (`{{content}}` is the chat); empty writes the chat as is
+    #[serde(default)]
+    pub chat_export_template: String,
+    /// Days deleted files stay in the vault trash before being purged (0 keeps them forever)
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// Vault-relative folders left out of the file tree, graph, search and indexes
+    #[serde(default)]
+    pub excluded_folders: Vec<String>,
+    /// Saved versions kept per note in the version history (0 keeps all)
+    #[serde(default = "default_history_max_versions")]
+    pub history_max_versions: u32,
+    /// Days saved versions are kept; the newest is always kept (0 keeps them forever)
+    #[serde(default = "default_history_retention_days")]
+    pub history_retention_days: u32,
+    /// Commits the vault automatically when it is a git repository
+    #[serde(default)]
+    pub git_auto_commit: bool,
+    /// Minutes between automatic commits (0 only commits after save batches)
+    #[serde(default = "default_git_commit_interval_minutes")]
+    pub git_commit_interval_minutes: u32,
+    /// Saves after which changes are committed (0 only commits on the schedule)
+    #[serde(default = "default_git_commit_after_saves")]
+    pub git_commit_after_saves: u32,
+    /// Remote pulled from and pushed to
+    #[serde(default = "default_git_remote")]
+    pub git_remote: String,
+    /// Pushes to the remote after every automatic commit
+    #[serde(default)]
+    pub git_auto_push: bool,
+    /// Folder scheduled backups are written to; empty turns them off
+    #[serde(default)]
+    pub backup_directory: String,
+    /// Hours between scheduled backups
+    #[serde(default = "default_backup_interval_hours")]
+    pub backup_interval_hours: u32,
+    /// Scheduled backups kept before the oldest is deleted (0 keeps all)
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: u32,
+    /// Adds a dump of the vault's graph and embeddings to scheduled backups
+    #[serde(default)]
+    pub backup_include_graph: bool,
 }
 
 fn default_daily_notes_folder() -> String {
     "Daily Notes".to_string()
 }
 
+fn default_daily_note_format() -> String {
+    "YYYY-MM-DD".to_string()
+}
+
+fn default_templates_folder() -> String {
+    "Templates".to_string()
+}
+
+fn default_weekly_notes() -> PeriodicNoteSettings {
+    PeriodicNoteSettings {
+        folder: "Weekly Notes".to_string(),
+        format: "gggg-[W]ww".to_string(),
+        template: String::new(),
+    }
+}
+
+fn default_monthly_notes() -> PeriodicNoteSettings {
+    PeriodicNoteSettings {
+        folder: "Monthly Notes".to_string(),
+        format: "YYYY-MM".to_string(),
+        template: String::new(),
+    }
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+fn default_history_max_versions() -> u32 {
+    100
+}
+
+fn default_history_retention_days() -> u32 {
+    90
+}
+
+fn default_git_commit_interval_minutes() -> u32 {
+    10
+}
+
+fn default_git_commit_after_saves() -> u32 {
+    20
+}
+
+fn default_git_remote() -> String {
+    "origin".to_string()
+}
+
+fn default_backup_interval_hours() -> u32 {
+    24
+}
+
+fn default_backup_keep() -> u32 {
+    7
+}
+
 impl Default for VaultSettings {
     fn default() -> Self {
         VaultSettings {
@@ -63,7 +183,43 @@ impl Default for FileSettings {
         FileSettings {
             image_location: "files/".to_string(),
             image_naming_pattern: "Pasted image {timestamp}".to_string(),
+            image_subfolder_per_note: false,
             daily_notes_folder: "Daily Notes".to_string(),
+            daily_note_format: default_daily_note_format(),
+            daily_note_template: String::new(),
+            weekly_notes: default_weekly_notes(),
+            monthly_notes: default_monthly_notes(),
+            templates_folder: default_templates_folder(),
+            new_note_template: String::new(),
+            chat_export_template: String::new(),
+            trash_retention_days: default_trash_retention_days(),
+            excluded_folders: Vec::new(),
+            history_max_versions: default_history_max_versions(),
+            history_retention_days: default_history_retention_days(),
+            git_auto_commit: false,
+            git_commit_interval_minutes: default_git_commit_interval_minutes(),
+            git_commit_after_saves: default_git_commit_after_saves(),
+            git_remote: default_git_remote(),
+            git_auto_push: false,
+            backup_directory: String::new(),
+            backup_interval_hours: default_backup_interval_hours(),
+            backup_keep: default_backup_keep(),
+            backup_include_graph: false,
+        }
+    }
+}
+
+impl FileSettings {
+    /// Folder, name format and template used for notes of a period
+    pub fn periodic_notes(&self, period: Period) -> PeriodicNoteSettings {
+        match period {
+            Period::Daily => PeriodicNoteSettings {
+                folder: self.daily_notes_folder.clone(),
+                format: self.daily_note_format.clone(),
+                template: self.daily_note_template.clone(),
+            },
+            Period::Weekly => self.weekly_notes.clone(),
+            Period::Monthly => self.monthly_notes.clone(),
         }
     }
 }
@@ -136,6 +292,13 @@ pub async fn save_vault_settings(app: AppHandle, settings: VaultSettingsInput) -
     store.save()
         .map_err(|e| format!("Failed to persist settings: {}", e))?;
     
+    // Apply excluded folders right away; indexes are rebuilt with the new rules
+    let vault_path = Path::new(&vault_settings.vault_path);
+    if let Some(state) = app.try_state::<crate::refactored_app_state::RefactoredAppState>() {
+        state.set_excluded_folders(vault_path, &vault_settings.files.excluded_folders);
+        state.invalidate_vault_index(vault_path).await;
+    }
+    
     println!("Vault settings saved successfully");
     Ok(())
 }
@@ -1,16 +1,21 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
 use tauri::AppHandle;
 use crate::vault::Vault;
+use crate::vault_ignore::{is_ignore_file, IgnoreRules};
 use crate::editor::EditorManager;
 use crate::mcp::MCPManager;
 use crate::docker::DockerManager;
 use crate::graph::GraphManagerImpl;
-use notify::{Event, EventKind, RecursiveMode, Watcher};
+use crate::index::{VaultIndex, VaultIndexRegistry};
+use crate::file_tree::{FileTreeCache, FileTreeRegistry, TreeDelta};
+use notify::event::{EventKind, Flag};
+use notify::{Event, RecursiveMode, Watcher};
 use tauri::Emitter;
 
 /// Represents the state of a single window
@@ -188,7 +193,8 @@ impl WindowRegistry {
 
         // Set the vault in the window state
         let vault = crate::vault::Vault::new(vault_path.clone())
-            .map_err(|e| format!("Failed to create vault: {}", e))?;
+            .map_err(|e| format!("Failed to create vault: {}", e))?
+            .with_ignore_rules(self.file_watcher_registry.indexes().ignore_rules(&vault_path));
         
         {
             let mut vault_lock = window.vault.lock().await;
@@ -254,6 +260,33 @@ impl WindowRegistry {
     pub async fn get_vault_watcher_ref_count(&self, vault_path: &PathBuf) -> usize {
         self.file_watcher_registry.get_watcher_ref_count(vault_path).await
     }
+
+    /// Gets the shared index for the vault open in a window, building it on first use
+    pub async fn get_vault_index(&self, window_id: &str) -> Result<Arc<VaultIndex>, String> {
+        let vault_path = self.get_window_vault_path(window_id).await
+            .ok_or_else(|| "No vault opened".to_string())?;
+        self.file_watcher_registry.indexes().get_or_build(&vault_path).await
+    }
+
+    /// Gets a vault's ignore rules, shared with its index and file tree
+    pub fn ignore_rules(&self, vault_path: &Path) -> Arc<IgnoreRules> {
+        self.file_watcher_registry.indexes().ignore_rules(vault_path)
+    }
+
+    /// Drops a vault's index and file tree so the next query rebuilds them
+    /// (e.g. after ignore rules change)
+    pub async fn invalidate_vault_index(&self, vault_path: &Path) {
+        self.file_watcher_registry.indexes().remove(vault_path).await;
+        self.file_watcher_registry.trees().remove(vault_path).await;
+    }
+
+    /// Gets the cached file tree of the vault open in a window, scanning it on first use
+    pub async fn get_file_tree(&self, window_id: &str) -> Result<Arc<Mutex<FileTreeCache>>, String> {
+        let vault_path = self.get_window_vault_path(window_id).await
+            .ok_or_else(|| "No vault opened".to_string())?;
+        let ignore = self.file_watcher_registry.indexes().ignore_rules(&vault_path);
+        self.file_watcher_registry.trees().get_or_build(&vault_path, ignore).await
+    }
 }
 
 /// Window persistence data
@@ -408,6 +441,8 @@ pub struct FileWatchEvent {
 /// Registry that manages shared file watchers across multiple windows
 pub struct SharedFileWatcherRegistry {
     watchers: Arc<Mutex<HashMap<PathBuf, VaultWatcher>>>,
+    indexes: Arc<VaultIndexRegistry>,
+    trees: Arc<FileTreeRegistry>,
     app_handle: AppHandle,
     global_event_tx: Arc<Mutex<Option<tokio::sync::mpsc::Sender<FileWatchEvent>>>>,
     _broadcast_task: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
@@ -418,6 +453,8 @@ impl SharedFileWatcherRegistry {
     pub fn new(app_handle: AppHandle) -> Result<Self, String> {
         Ok(Self {
             watchers: Arc::new(Mutex::new(HashMap::new())),
+            indexes: Arc::new(VaultIndexRegistry::new()),
+            trees: Arc::new(FileTreeRegistry::new()),
             app_handle,
             global_event_tx: Arc::new(Mutex::new(None)),
             _broadcast_task: Arc::new(Mutex::new(None)),
@@ -430,12 +467,45 @@ impl SharedFileWatcherRegistry {
         if tx_lock.is_none() {
             let (global_event_tx, mut global_event_rx) = tokio::sync::mpsc::channel::<FileWatchEvent>(1000);
             let app_handle_clone = self.app_handle.clone();
+            let indexes = self.indexes.clone();
+            let trees = self.trees.clone();
             
             // Start the global event broadcast task
             let broadcast_task = tokio::spawn(async move {
-                while let Some(file_event) = global_event_rx.recv().await {
-                    // Broadcast to all windows viewing this vault
-                    SharedFileWatcherRegistry::broadcast_event_to_windows(&app_handle_clone, &file_event).await;
+                while let Some(mut file_event) = global_event_rx.recv().await {
+                    let vault_path = file_event.vault_path.clone();
+                    
+                    // Events were missed, so the index and tree can no longer be
+                    // patched; rebuild them from disk on next use
+                    if file_event.event.need_rescan() {
+                        eprintln!("⚠️ Missed file events for {}; rebuilding its index and tree", vault_path.display());
+                        SharedFileWatcherRegistry::reset_vault(&indexes, &trees, &app_handle_clone, &vault_path).await;
+                        continue;
+                    }
+                    
+                    // New ignore rules change what belongs in the index and tree; rebuild them on next use
+                    if file_event.event.paths.iter().any(|p| is_ignore_file(&vault_path, p)) {
+                        SharedFileWatcherRegistry::reset_vault(&indexes, &trees, &app_handle_clone, &vault_path).await;
+                    }
+                    
+                    let ignore = indexes.ignore_rules(&vault_path).current();
+                    file_event.event.paths.retain(|p| !ignore.is_ignored(p, p.is_dir()));
+                    if file_event.event.paths.is_empty() {
+                        continue;
+                    }
+                    
+                    // Keep the vault's in-memory indexes current before notifying windows
+                    if let Some(index) = indexes.get(&file_event.vault_path).await {
+                        index.handle_file_event(&file_event.event).await;
+                    }
+                    
+                    // Broadcast what changed in the tree to all windows viewing this vault
+                    if let Some(tree) = trees.get(&vault_path).await {
+                        let deltas = tree.lock().await.apply_event(&file_event.event);
+                        if !deltas.is_empty() {
+                            SharedFileWatcherRegistry::emit_tree_deltas(&app_handle_clone, &vault_path, &deltas);
+                        }
+                    }
                 }
             });
             
@@ -488,6 +558,9 @@ impl SharedFileWatcherRegistry {
                     println!("Removing watcher for vault {} (last window {} closed)", 
                             vault_path.display(), window_id);
                     watchers.remove(vault_path);
+                    drop(watchers);
+                    self.indexes.remove(vault_path).await;
+                    self.trees.remove(vault_path).await;
                     Ok(true)
                 } else {
                     Ok(false)
@@ -500,6 +573,16 @@ impl SharedFileWatcherRegistry {
         }
     }
 
+    /// Gets the per-vault index registry fed by this watcher
+    pub fn indexes(&self) -> &Arc<VaultIndexRegistry> {
+        &self.indexes
+    }
+
+    /// Gets the per-vault file trees fed by this watcher
+    pub fn trees(&self) -> &Arc<FileTreeRegistry> {
+        &self.trees
+    }
+
     /// Gets the reference count for a vault watcher
     pub async fn get_watcher_ref_count(&self, vault_path: &PathBuf) -> usize {
         let watchers = self.watchers.lock().await;
@@ -521,28 +604,46 @@ impl SharedFileWatcherRegistry {
         let global_tx = self.global_event_tx.clone();
         let vault_path_clone = vault_path.clone();
         let event_tx_clone = event_tx.clone();
+        // Set when an event could not be queued
+        let missed = Arc::new(AtomicBool::new(false));
+        let missed_clone = missed.clone();
 
         // Create the file system watcher
         let watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
-            if let Ok(event) = res {
-                let file_event = FileWatchEvent {
-                    vault_path: vault_path_clone.clone(),
-                    event,
-                };
-                // Send to vault-specific channel (non-blocking)
-                let _ = event_tx_clone.try_send(file_event);
+            // A watcher error may have lost events as well
+            let event = res.unwrap_or_else(|_| Event::new(EventKind::Other).set_flag(Flag::Rescan));
+            let file_event = FileWatchEvent {
+                vault_path: vault_path_clone.clone(),
+                event,
+            };
+            // Send to vault-specific channel (non-blocking); a full channel drops the event
+            if event_tx_clone.try_send(file_event).is_err() {
+                missed_clone.store(true, Ordering::SeqCst);
             }
         }).map_err(|e| format!("Failed to create file watcher: {}", e))?;
 
         // Start the event forwarding task
+        let forward_path = vault_path.clone();
         let watcher_task = tokio::spawn(async move {
             while let Some(file_event) = event_rx.recv().await {
+                // A full queue means the events still queued behind this one
+                // are incomplete; ask for a rescan ahead of them
+                let mut file_events = vec![file_event];
+                if missed.swap(false, Ordering::SeqCst) {
+                    file_events.insert(0, FileWatchEvent {
+                        vault_path: forward_path.clone(),
+                        event: Event::new(EventKind::Other).set_flag(Flag::Rescan),
+                    });
+                }
+
                 // Forward to global broadcast channel
                 let tx_lock = global_tx.lock().await;
                 if let Some(ref tx) = *tx_lock {
-                    if let Err(e) = tx.send(file_event).await {
-                        eprintln!("Failed to forward file event to global channel: {}", e);
-                        break;
+                    for file_event in file_events {
+                        if let Err(e) = tx.send(file_event).await {
+                            eprintln!("Failed to forward file event to global channel: {}", e);
+                            return;
+                        }
                     }
                 }
             }
@@ -573,30 +674,24 @@ impl SharedFileWatcherRegistry {
         }
     }
 
-    /// Broadcasts file events to all windows viewing the same vault
-    async fn broadcast_event_to_windows(app_handle: &AppHandle, file_event: &FileWatchEvent) {
-        // Filter relevant events
-        match file_event.event.kind {
-            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
-                // Only process markdown files
-                for path in &file_event.event.paths {
-                    if path.extension().and_then(|s| s.to_str()) == Some("md") {
-                        // Emit to all windows - Tauri will handle filtering by window
-                        let event_data = serde_json::json!({
-                            "vaultPath": file_event.vault_path.to_string_lossy(),
-                            "eventType": format!("{:?}", file_event.event.kind),
-                            "filePath": path.to_string_lossy(),
-                        });
-                        
-                        if let Err(e) = app_handle.emit("vault-file-changed", &event_data) {
-                            eprintln!("Failed to emit vault-file-changed event: {}", e);
-                        }
-                    }
-                }
-            }
-            _ => {
-                // Ignore other event types
-            }
+    /// Drops a vault's index and tree so both are rebuilt from disk on next
+    /// use, and tells windows showing the tree to reload it
+    async fn reset_vault(indexes: &VaultIndexRegistry, trees: &FileTreeRegistry, app_handle: &AppHandle, vault_path: &Path) {
+        indexes.remove(vault_path).await;
+        if trees.get(vault_path).await.is_some() {
+            trees.remove(vault_path).await;
+            Self::emit_tree_deltas(app_handle, vault_path, &[TreeDelta::Reset]);
+        }
+    }
+
+    /// Sends tree changes to all windows; each window applies those for the vault it shows
+    fn emit_tree_deltas(app_handle: &AppHandle, vault_path: &Path, deltas: &[TreeDelta]) {
+        let event_data = serde_json::json!({
+            "vaultPath": vault_path.to_string_lossy(),
+            "deltas": deltas,
+        });
+        if let Err(e) = app_handle.emit("vault-tree-changed", &event_data) {
+            eprintln!("Failed to emit vault-tree-changed event: {}", e);
         }
     }
 }
@@ -42,7 +42,7 @@ export class ContextManager {
   async loadNoteContent(path) {
     try {
       const { invoke } = await import('@tauri-apps/api/core');
-      const content = await invoke('read_file_content', { filePath: path });
+      const content = await invoke('read_note_for_ai', { filePath: path });
       return content;
     } catch (error) {
       console.error('❌ Error loading note content:', error);
@@ -691,6 +691,16 @@ export class EnhancedChatPanel {
         const title = activeTab.title || 'Current Note';
         const filePath = activeTab.filePath;
         
+        // Encrypted notes never go to the AI, even when the editor shows them decrypted
+        let savedContent = null;
+        if (filePath) {
+            savedContent = await this.getNoteContent(filePath);
+            if (savedContent === null) {
+                console.log('Active note is not shared with AI:', filePath);
+                return null;
+            }
+        }
+        
         // Try to get content from editor first
         let content = '';
         
@@ -706,15 +716,10 @@ export class EnhancedChatPanel {
             }
         }
         
-        // If we couldn't get content from editor, try reading from file
-        if ((!content || content.length === 0) && filePath) {
-            console.log('⚠️ No content from editor, trying to read from file:', filePath);
-            try {
-                content = await this.getNoteContent(filePath);
-                console.log('Got content from file:', content?.length || 0, 'chars');
-            } catch (error) {
-                console.error('Failed to read file:', error);
-            }
+        // If we couldn't get content from editor, use the file's content
+        if ((!content || content.length === 0) && savedContent) {
+            console.log('⚠️ No content from editor, using content read from file:', filePath);
+            content = savedContent;
         }
         
         if (!content || content.length === 0) {
@@ -740,7 +745,7 @@ export class EnhancedChatPanel {
     async getNoteContent(path) {
         try {
             const { invoke } = await import('@tauri-apps/api/core');
-            const content = await invoke('read_file_content', {
+            const content = await invoke('read_note_for_ai', {
                 filePath: path
             });
             
@@ -1,5 +1,6 @@
 import { Decoration, ViewPlugin, WidgetType } from '@codemirror/view'
 import { invoke } from '@tauri-apps/api/core'
+import { IMAGE_EXTENSIONS } from '../utils/image-types.js'
 
 // Widget for rendering images
 class ImageWidget extends WidgetType {
@@ -117,8 +118,9 @@ class LocalImageWidget extends WidgetType {
       </div>
     `
     
-    // Read local image file using the new command
-    invoke('read_image_as_base64', { filePath: `files/${this.filename}` })
+    // Embeds hold a vault-relative path, or a bare name in the default image folder
+    invoke('read_image_as_base64', { filePath: this.filename })
+      .catch(() => invoke('read_image_as_base64', { filePath: `files/${this.filename}` }))
       .then(base64Data => {
         img.src = base64Data
         wrapper.innerHTML = ''
@@ -163,7 +165,7 @@ function createImageDecorations(view) {
   const imageRegex = /!\[([^\]]*)\]\(([^)]+)\)/g
   
   // Regex syntax: ![[filename.png]]
-  const syntaxImageRegex = /!\[\[([^\]]+\.(png|jpg|jpeg|gif))\]\]/gi
+  const syntaxImageRegex = new RegExp(`!\\[\\[([^\\]]+\\.(${IMAGE_EXTENSIONS.join('|')}))\\]\\]`, 'gi')
   
   let match
   // Handle standard markdown images
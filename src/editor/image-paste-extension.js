@@ -1,5 +1,6 @@
 import { EditorView } from '@codemirror/view';
 import { invoke } from '@tauri-apps/api/core';
+import { IMAGE_EXTENSIONS } from '../utils/image-types.js';
 
 /**
  * Extension for handling image paste events in CodeMirror
@@ -34,10 +35,14 @@ async function handleImagePaste(imageItem, view) {
     try {
         // Get file extension
         const mimeType = imageItem.type;
-        const extension = mimeType.split('/')[1].replace('jpeg', 'jpg');
+        const extension = {
+            'jpeg': 'jpg',
+            'svg+xml': 'svg',
+            'x-ms-bmp': 'bmp'
+        }[mimeType.split('/')[1]] || mimeType.split('/')[1];
         
-        // Only support png, jpg, gif
-        if (!['png', 'jpg', 'gif'].includes(extension)) {
+        // Only support formats the backend can save and display
+        if (!IMAGE_EXTENSIONS.includes(extension)) {
             console.error('❌ Unsupported image format:', extension);
             return;
         }
@@ -59,9 +64,12 @@ async function handleImagePaste(imageItem, view) {
         console.log('💾 Saving image via Tauri backend...');
         
         // Save image via Tauri
+        // The note being edited names the image and its subfolder
+        const activeTab = window.paneManager?.getActiveTabManager()?.getActiveTab();
         const filename = await invoke('save_pasted_image', {
             imageData: base64Data,
-            extension: extension
+            extension: extension,
+            notePath: activeTab?.filePath || null
         });
         
         console.log('✅ Image saved as:', filename);
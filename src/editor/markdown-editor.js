@@ -12,6 +12,7 @@ import { markdown, markdownLanguage } from '@codemirror/lang-markdown'
 import { languages } from '@codemirror/language-data'
 import { oneDark } from '@codemirror/theme-one-dark'
 import { invoke } from '@tauri-apps/api/core'
+import { writeFileChecked } from '../utils/file-versions.js'
 import { search, searchKeymap, highlightSelectionMatches, openSearchPanel, closeSearchPanel } from '@codemirror/search'
 import { livePreviewPlugin, livePreviewStyles } from './live-preview.js'
 import { inlineFormattingExtension, inlineFormattingStyles, blockWidgetExtension } from './formatting-extension.js'
@@ -1033,10 +1034,12 @@ export class MarkdownEditor {
     if (this.currentFile) {
       try {
         console.log('💾 Saving file:', this.currentFile)
-        await invoke('write_file_content', {
-          filePath: this.currentFile,
-          content: this.getContent()
-        })
+        const content = this.getContent()
+        const savedContent = await writeFileChecked(this.currentFile, content)
+        if (savedContent !== content) {
+          // The user chose to reload the version changed on disk
+          this.setContent(savedContent)
+        }
         this.hasUnsavedChanges = false
         console.log('✅ File saved successfully')
         
@@ -19,6 +19,8 @@ import { perfTestSuite } from './performance/PerformanceTestSuite.js';
 import { globalSearch } from './search/GlobalSearch.js';
 import windowContext from './contexts/WindowContext.js';
 import { VaultPicker } from './components/VaultPicker.js';
+import { readFileVersioned, writeFileChecked } from './utils/file-versions.js';
+import { fileExtension, isImageFile } from './utils/image-types.js';
 
 console.log('✅ Tauri v2 APIs and editor components imported successfully!');
 console.log('🔍 EnhancedChatPanel class:', EnhancedChatPanel);
@@ -677,7 +679,7 @@ async function initializeEditor() {
             
             try {
               // Read the updated content
-              const content = await invoke('read_file_content', { filePath: updatedFilePath });
+              const content = await readFileVersioned(updatedFilePath);
               
               // Update the editor
               tab.editor.setContent(content);
@@ -716,7 +718,7 @@ function setupTabNavigationListeners(tabManager) {
     
     try {
       // Load the file content
-      const content = await invoke('read_file_content', { filePath });
+      const content = await readFileVersioned(filePath);
       
       // Update editor with new content
       if (tab.editor) {
@@ -1251,8 +1253,7 @@ window.showCreateFolderModal = async function() {
     await invoke('create_new_folder', { folderName: folderName.trim() });
     console.log('✅ Folder created successfully');
     
-    const fileTree = await invoke('get_file_tree');
-    displayFileTree(fileTree);
+    await refreshFileTree();
     
   } catch (error) {
     console.error('❌ Failed to create folder:', error);
@@ -1528,6 +1529,12 @@ function rebuildEditorHeader(fileName = 'Welcome to Gaimplan') {
             <div class="editor-dropdown-item" onclick="exportToWord()">
               <span>Export as Word (.doc)</span>
             </div>
+            <div class="editor-dropdown-item" onclick="publishSite('')">
+              <span>Publish Vault as Website</span>
+            </div>
+            <div class="editor-dropdown-item" onclick="backUpVault()">
+              <span>Back Up Vault</span>
+            </div>
           </div>
         </div>
       </div>
@@ -1569,6 +1576,7 @@ window.closeCurrentVault = async function() {
         <p>No vault open</p>
         <button id="open-vault" class="primary-button" onclick="window.openVault()">Open Vault</button>
         <button id="create-vault" class="secondary-button" onclick="window.createVault()">Create Vault</button>
+        <button id="restore-vault" class="secondary-button" onclick="window.restoreVaultBackup()">Restore Backup</button>
       </div>
     `;
   }
@@ -1853,10 +1861,7 @@ async function updateUIWithVault(vaultInfo) {
   
   try {
     console.log('📁 Loading file tree...');
-    const fileTree = await invoke('get_file_tree');
-    console.log('📊 File tree loaded:', fileTree);
-    
-    displayFileTree(fileTree);
+    await refreshFileTree();
     
     // Start file system watcher for this vault
     console.log('👁️ Starting file system watcher...');
@@ -1892,24 +1897,145 @@ Your vault is now open and ready to use.
   }
 }
 
-function displayFileTree(fileTree) {
-  console.log('🌲 Displaying file tree with', fileTree.files.length, 'items');
-  console.log('📂 Currently expanded folders:', Array.from(window.expandedFolders));
+// Loaded pages of the folders shown in the tree: folder path ('' for the
+// vault root) -> { entries, total }. Other folders load when expanded, and
+// file changes from the backend are applied to what is loaded.
+const loadedFolders = new Map();
+const TREE_PAGE_SIZE = 500;
+
+// Folders first, then the selected sort option
+function compareTreeEntries(a, b) {
+  if (a.is_dir !== b.is_dir) {
+    return a.is_dir ? -1 : 1;
+  }
   
-  // Debug: Log first few items to see structure
-  console.log('📊 Sample file tree items:', fileTree.files.slice(0, 5));
+  switch (currentSortOption) {
+    case 'created':
+      if (a.created !== null && b.created !== null) {
+        return b.created - a.created;
+      }
+      if (a.created !== null) return -1;
+      if (b.created !== null) return 1;
+      return a.name.toLowerCase().localeCompare(b.name.toLowerCase());
+      
+    case 'modified':
+      if (a.modified !== null && b.modified !== null) {
+        return b.modified - a.modified;
+      }
+      if (a.modified !== null) return -1;
+      if (b.modified !== null) return 1;
+      return a.name.toLowerCase().localeCompare(b.name.toLowerCase());
+      
+    default:
+      return a.name.toLowerCase().localeCompare(b.name.toLowerCase());
+  }
+}
+
+// Loads the next page of a folder's children from the backend's cached tree
+async function loadFolderPage(folderPath) {
+  const folder = loadedFolders.get(folderPath) || { entries: [], total: 0 };
+  const page = await invoke('list_directory', { path: folderPath, offset: folder.entries.length, limit: TREE_PAGE_SIZE });
+  const known = new Set(folder.entries.map(entry => entry.path));
+  folder.entries.push(...page.entries.filter(entry => !known.has(entry.path)));
+  folder.total = page.total;
+  loadedFolders.set(folderPath, folder);
+  return folder;
+}
+
+// Loads a folder and, inside it, every folder that is expanded
+async function loadExpandedFolder(folderPath) {
+  const folder = loadedFolders.get(folderPath) || await loadFolderPage(folderPath);
+  for (const entry of folder.entries) {
+    if (entry.is_dir && window.expandedFolders.has(entry.path)) {
+      try {
+        await loadExpandedFolder(entry.path);
+      } catch (error) {
+        window.expandedFolders.delete(entry.path);
+      }
+    }
+  }
+}
+
+function treeAttribute(value) {
+  return value.replace(/&/g, '&amp;').replace(/"/g, '&quot;');
+}
+
+function treeItemHtml(file) {
+  // Skip .obsidian folders and their contents
+  if (file.name === '.obsidian') {
+    return '';
+  }
+  
+  const indent = file.depth * 20;
+  const isExpanded = window.expandedFolders.has(file.path);
+  const escapedPath = file.path.replace(/'/g, "\\'").replace(/"/g, "&quot;");
+  
+  if (file.is_dir) {
+    const expandIcon = isExpanded ? '▼' : '▶';
+    const children = isExpanded
+      ? `<div class="tree-children" data-parent="${treeAttribute(file.path)}">${folderChildrenHtml(file.path)}</div>`
+      : '';
+    
+    return `
+      <div class="tree-item folder" data-path="${treeAttribute(file.path)}" style="padding-left: ${indent + 8}px;">
+        <span class="expand-icon" onclick="toggleFolder('${escapedPath}', event)">${expandIcon}</span>
+        <span class="tree-label" onclick="handleFolderClick('${escapedPath}', event)">${file.name}</span>
+        <span class="folder-actions">
+          <button class="folder-action-btn" onclick="showCreateFileModal('${escapedPath}', event)" title="New File in Folder">📄</button>
+          <button class="folder-action-btn" onclick="publishSite('${escapedPath}', event)" title="Publish Folder as Website">🌐</button>
+        </span>
+      </div>${children}
+    `;
+  }
+  
+  const fileIndent = indent + 24;
   
-  // Debug: Show root level items
-  const rootItems = fileTree.files.filter(f => !f.parent_path);
-  console.log('🌳 Root level items:', rootItems.map(f => f.name));
+  // Remove file extension for display
+  const displayName = file.name.replace(/\.(md|markdown|txt|doc|docx|pdf)$/i, '');
   
+  // Uncommitted changes when the vault is a git repository
+  const gitBadge = file.git_status
+    ? `<span class="git-status git-${file.git_status}" title="${file.git_status}">${GIT_STATUS_LETTERS[file.git_status] || ''}</span>`
+    : '';
+  
+  return `
+    <div class="tree-item file" data-path="${treeAttribute(file.path)}" style="padding-left: ${fileIndent}px;" data-file-path="${escapedPath}">
+      <span class="tree-label" onclick="handleFileClick('${escapedPath}', false)">${displayName}</span>
+      ${gitBadge}
+    </div>
+  `;
+}
+
+// The loaded children of a folder, with a row loading the next page
+function folderChildrenHtml(folderPath) {
+  const folder = loadedFolders.get(folderPath);
+  if (!folder) {
+    return '';
+  }
+  
+  const items = [...folder.entries].sort(compareTreeEntries).map(treeItemHtml).join('');
+  const remaining = folder.total - folder.entries.length;
+  if (remaining <= 0) {
+    return items;
+  }
+  const depth = folderPath ? folderPath.split('/').length : 0;
+  const escapedPath = folderPath.replace(/'/g, "\\'").replace(/"/g, "&quot;");
+  return `${items}
+    <div class="tree-item load-more" style="padding-left: ${depth * 20 + 24}px;">
+      <span class="tree-label" onclick="loadMoreTreeItems('${escapedPath}', event)">Show ${remaining} more…</span>
+    </div>
+  `;
+}
+
+function renderFileTree() {
   const fileTreeElement = document.getElementById('file-tree');
   if (!fileTreeElement) {
     console.error('❌ File tree element not found');
     return;
   }
   
-  if (fileTree.files.length === 0) {
+  const root = loadedFolders.get('');
+  if (!root || root.total === 0) {
     fileTreeElement.innerHTML = `
       <div class="empty-vault">
         <p>📁 Vault is empty</p>
@@ -1919,137 +2045,154 @@ function displayFileTree(fileTree) {
     return;
   }
   
-  // Create a tree structure for proper sorting
-  const buildTree = (files) => {
-    const tree = new Map();
-    
-    // First pass: organize by parent
-    files.forEach(file => {
-      const parent = file.parent_path || '';
-      if (!tree.has(parent)) {
-        tree.set(parent, []);
-      }
-      tree.get(parent).push(file);
-    });
-    
-    // Sort each level
-    tree.forEach((children, parent) => {
-      children.sort((a, b) => {
-        // Always put directories before files
-        if (a.is_dir !== b.is_dir) {
-          return a.is_dir ? -1 : 1;
-        }
-        
-        // Apply the selected sort option
-        switch (currentSortOption) {
-          case 'alphabetical':
-            return a.name.toLowerCase().localeCompare(b.name.toLowerCase());
-            
-          case 'created':
-            if (a.created !== null && b.created !== null) {
-              return b.created - a.created;
-            }
-            if (a.created !== null) return -1;
-            if (b.created !== null) return 1;
-            return a.name.toLowerCase().localeCompare(b.name.toLowerCase());
-            
-          case 'modified':
-            if (a.modified !== null && b.modified !== null) {
-              return b.modified - a.modified;
-            }
-            if (a.modified !== null) return -1;
-            if (b.modified !== null) return 1;
-            return a.name.toLowerCase().localeCompare(b.name.toLowerCase());
-            
-          default:
-            return a.name.toLowerCase().localeCompare(b.name.toLowerCase());
-        }
-      });
-    });
-    
-    // Flatten the tree in depth-first order
-    const result = [];
-    const addToResult = (parentPath) => {
-      const children = tree.get(parentPath) || [];
-      children.forEach(child => {
-        result.push(child);
-        if (child.is_dir) {
-          addToResult(child.path);
-        }
-      });
-    };
-    
-    // Start from root
-    addToResult('');
-    
-    return result;
-  };
+  fileTreeElement.innerHTML = `<div class="file-tree-content">${folderChildrenHtml('')}</div>`;
+}
+
+function treeItemElement(path) {
+  return document.querySelector(`#file-tree .tree-item[data-path="${CSS.escape(path)}"]`);
+}
+
+// Element holding a folder's children, if the folder is shown expanded
+function treeFolderElement(folderPath) {
+  return folderPath
+    ? document.querySelector(`#file-tree .tree-children[data-parent="${CSS.escape(folderPath)}"]`)
+    : document.querySelector('#file-tree .file-tree-content');
+}
+
+function removeTreeElement(path) {
+  treeFolderElement(path)?.remove();
+  treeItemElement(path)?.remove();
+}
+
+// Draws an entry at its sorted place among the shown children of its folder
+function insertTreeElement(entry) {
+  const folderPath = entry.parent_path || '';
+  const container = treeFolderElement(folderPath);
+  const folder = loadedFolders.get(folderPath);
+  if (!container || !folder) {
+    return;
+  }
   
-  const sortedFiles = buildTree(fileTree.files);
+  const siblings = [...folder.entries].sort(compareTreeEntries);
+  const index = siblings.findIndex(sibling => sibling.path === entry.path);
+  const next = siblings.slice(index + 1).map(sibling => treeItemElement(sibling.path)).find(Boolean)
+    || container.querySelector(':scope > .load-more');
   
-  let html = `
-    <div class="file-tree-content">
-  `;
+  const template = document.createElement('template');
+  template.innerHTML = treeItemHtml(entry).trim();
+  container.insertBefore(template.content, next || null);
+}
+
+function redrawTreeEntry(entry) {
+  removeTreeElement(entry.path);
+  insertTreeElement(entry);
+}
+
+function findTreeEntry(path) {
+  const folderPath = path.includes('/') ? path.slice(0, path.lastIndexOf('/')) : '';
+  return loadedFolders.get(folderPath)?.entries.find(entry => entry.path === path);
+}
+
+function addTreeEntry(entry) {
+  const folderPath = entry.parent_path || '';
+  const folder = loadedFolders.get(folderPath);
+  if (!folder) {
+    return;
+  }
   
-  sortedFiles.forEach(file => {
-    // Skip .obsidian folders and their contents
-    if (file.name === '.obsidian' || file.path.includes('/.obsidian/')) {
-      return;
-    }
-    
-    // Debug Chat History folder and parent paths
-    // if (file.name === 'Chat History' || file.path.includes('Chat History')) {
-    //   console.log('🔍 Found Chat History:', file);
-    //   console.log('   Parent path:', file.parent_path);
-    //   console.log('   Is parent expanded?', file.parent_path ? window.expandedFolders.has(file.parent_path) : 'N/A (root)');
-    // }
-    
-    // For files (not folders), check if their parent folder is expanded
-    // For folders, always show if their parent is expanded (or if they're root level)
-    if (file.parent_path) {
-      // This item has a parent
-      if (!window.expandedFolders.has(file.parent_path)) {
-        // Parent is not expanded, skip this item
-        // console.log(`⏭️ Skipping ${file.path} because parent ${file.parent_path} is not expanded`);
-        return;
-      }
+  const existing = folder.entries.findIndex(e => e.path === entry.path);
+  if (existing >= 0) {
+    // Keep the git status until the tree is refreshed
+    entry.git_status = entry.git_status ?? folder.entries[existing].git_status;
+    folder.entries[existing] = entry;
+  } else {
+    folder.entries.push(entry);
+    folder.total += 1;
+  }
+  
+  if (!document.querySelector('#file-tree .file-tree-content')) {
+    renderFileTree();
+    return;
+  }
+  redrawTreeEntry(entry);
+  
+  if (entry.is_dir && window.expandedFolders.has(entry.path)) {
+    loadExpandedFolder(entry.path)
+      .then(() => redrawTreeEntry(entry))
+      .catch(error => console.error('❌ Failed to load folder:', error));
+  }
+}
+
+function removeTreeEntry(path) {
+  const folderPath = path.includes('/') ? path.slice(0, path.lastIndexOf('/')) : '';
+  const folder = loadedFolders.get(folderPath);
+  if (folder) {
+    const before = folder.entries.length;
+    folder.entries = folder.entries.filter(entry => entry.path !== path);
+    if (folder.entries.length < before) {
+      folder.total = Math.max(0, folder.total - 1);
     }
-    
-    const indent = file.depth * 20;
-    const isExpanded = window.expandedFolders.has(file.path);
-    
-    if (file.is_dir) {
-      const expandIcon = isExpanded ? '▼' : '▶';
-      const escapedPath = file.path.replace(/'/g, "\\'").replace(/"/g, "&quot;");
-      
-      html += `
-        <div class="tree-item folder" data-path="${file.path}" style="padding-left: ${indent + 8}px;">
-          <span class="expand-icon" onclick="toggleFolder('${escapedPath}', event)">${expandIcon}</span>
-          <span class="tree-label" onclick="handleFolderClick('${escapedPath}', event)">${file.name}</span>
-          <span class="folder-actions">
-            <button class="folder-action-btn" onclick="showCreateFileModal('${escapedPath}', event)" title="New File in Folder">📄</button>
-          </span>
-        </div>
-      `;
-    } else {
-      const escapedPath = file.path.replace(/'/g, "\\'").replace(/"/g, "&quot;");
-      const fileIndent = indent + 24;
-      
-      // Remove file extension for display
-      const displayName = file.name.replace(/\.(md|markdown|txt|doc|docx|pdf)$/i, '');
-      
-      html += `
-        <div class="tree-item file" data-path="${file.path}" style="padding-left: ${fileIndent}px;" data-file-path="${escapedPath}">
-          <span class="tree-label" onclick="handleFileClick('${escapedPath}', false)">${displayName}</span>
-        </div>
-      `;
+  }
+  
+  for (const loaded of [...loadedFolders.keys()]) {
+    if (loaded === path || loaded.startsWith(`${path}/`)) {
+      loadedFolders.delete(loaded);
     }
-  });
+  }
+  removeTreeElement(path);
   
-  html += '</div>';
-  fileTreeElement.innerHTML = html;
+  if (folderPath === '' && folder?.total === 0) {
+    renderFileTree();
+  }
 }
 
+// Applies one change reported by the backend's file tree
+function applyTreeDelta(delta) {
+  switch (delta.type) {
+    case 'added':
+    case 'modified':
+      addTreeEntry(delta.entry);
+      break;
+    case 'removed':
+      removeTreeEntry(delta.path);
+      break;
+    case 'renamed': {
+      // A renamed folder stays expanded, as do the folders inside it
+      for (const expanded of [...window.expandedFolders]) {
+        if (expanded === delta.from || expanded.startsWith(`${delta.from}/`)) {
+          window.expandedFolders.delete(expanded);
+          window.expandedFolders.add(delta.entry.path + expanded.slice(delta.from.length));
+        }
+      }
+      removeTreeEntry(delta.from);
+      addTreeEntry(delta.entry);
+      break;
+    }
+  }
+}
+
+window.loadMoreTreeItems = async function(folderPath, event) {
+  event.stopPropagation();
+  try {
+    await loadFolderPage(folderPath);
+    const container = treeFolderElement(folderPath);
+    if (container) {
+      container.innerHTML = folderChildrenHtml(folderPath);
+    }
+  } catch (error) {
+    console.error('❌ Failed to load more files:', error);
+  }
+};
+
+const GIT_STATUS_LETTERS = {
+  modified: 'M',
+  added: 'A',
+  deleted: 'D',
+  renamed: 'R',
+  untracked: 'U',
+  conflicted: '!'
+};
+
 function showFileTreeError(error) {
   const fileTreeElement = document.getElementById('file-tree');
   if (fileTreeElement) {
@@ -2063,7 +2206,7 @@ function showFileTreeError(error) {
 }
 
 // Toggle folder expansion
-window.toggleFolder = function(folderPath, event) {
+window.toggleFolder = async function(folderPath, event) {
   event.stopPropagation();
   console.log('🔽 Toggling folder:', folderPath);
   
@@ -2071,9 +2214,18 @@ window.toggleFolder = function(folderPath, event) {
     window.expandedFolders.delete(folderPath);
   } else {
     window.expandedFolders.add(folderPath);
+    try {
+      await loadExpandedFolder(folderPath);
+    } catch (error) {
+      console.error('❌ Failed to load folder:', error);
+      window.expandedFolders.delete(folderPath);
+    }
   }
   
-  refreshFileTree();
+  const entry = findTreeEntry(folderPath);
+  if (entry) {
+    redrawTreeEntry(entry);
+  }
 };
 
 // Handle folder clicks
@@ -2083,13 +2235,15 @@ window.handleFolderClick = function(folderPath, event) {
   window.toggleFolder(folderPath, event);
 };
 
-// Refresh file tree
+// Reload the shown folders of the file tree
 async function refreshFileTree() {
   try {
-    const fileTree = await invoke('get_file_tree');
-    displayFileTree(fileTree);
+    loadedFolders.clear();
+    await loadExpandedFolder('');
+    renderFileTree();
   } catch (error) {
     console.error('❌ Failed to refresh file tree:', error);
+    showFileTreeError(error);
   }
 }
 
@@ -2107,10 +2261,18 @@ async function setupFileSystemWatcher() {
   try {
     const { listen } = await import('@tauri-apps/api/event');
     
-    // Listen for file system changes from backend
-    const unlisten = await listen('vault-files-changed', (event) => {
-      console.log('📁 File system changed (backend event), refreshing file tree...');
-      refreshFileTree();
+    // Listen for tree changes from the backend's cached file tree
+    const unlisten = await listen('vault-tree-changed', (event) => {
+      const { vaultPath, deltas = [] } = event.payload || {};
+      if (window.currentVaultPath && vaultPath && vaultPath !== window.currentVaultPath) {
+        return;
+      }
+      if (deltas.some(delta => delta.type === 'reset')) {
+        refreshFileTree();
+        return;
+      }
+      console.log(`📁 Applying ${deltas.length} file tree changes`);
+      deltas.forEach(applyTreeDelta);
     });
     
     // Store unlisten function for cleanup if needed
@@ -2133,10 +2295,8 @@ window.handleFileClick = async function(filePath, isDir) {
   }
   
   // Check file type
-  const imageExtensions = ['png', 'jpg', 'jpeg', 'gif'];
-  const fileExtension = filePath.split('.').pop().toLowerCase();
-  const isImage = imageExtensions.includes(fileExtension);
-  const isPDF = fileExtension === 'pdf';
+  const isImage = isImageFile(filePath);
+  const isPDF = fileExtension(filePath) === 'pdf';
   
   try {
     // Get the active pane's TabManager
@@ -2193,7 +2353,7 @@ window.handleFileClick = async function(filePath, isDir) {
       const filename = filePath.split('/').pop();
       content = `# ${filename}\n\n![[${filename}]]`;
     } else {
-      content = await invoke('read_file_content', { filePath: filePath });
+      content = await readFileVersioned(filePath);
       console.log('📄 File content loaded, length:', content.length);
     }
     
@@ -2305,9 +2465,7 @@ async function saveCurrentFile() {
   }
   
   // Don't save image or PDF files
-  const imageExtensions = ['png', 'jpg', 'jpeg', 'gif'];
-  const fileExtension = activeTab.filePath.split('.').pop().toLowerCase();
-  if (imageExtensions.includes(fileExtension) || fileExtension === 'pdf') {
+  if (isImageFile(activeTab.filePath) || fileExtension(activeTab.filePath) === 'pdf') {
     console.log('🖼️ Skipping save for image/PDF file');
     return;
   }
@@ -2315,7 +2473,11 @@ async function saveCurrentFile() {
   try {
     console.log('💾 Saving file:', activeTab.filePath);
     const content = activeTab.editor.getContent();
-    await invoke('write_file_content', { filePath: activeTab.filePath, content: content });
+    const savedContent = await writeFileChecked(activeTab.filePath, content);
+    if (savedContent !== content) {
+      // The user chose to reload the version changed on disk
+      activeTab.editor.setContent(savedContent);
+    }
     activeTab.editor.hasUnsavedChanges = false;
     tabManager.setTabDirty(activeTab.id, false);
     
@@ -2375,6 +2537,7 @@ async function initializeApp() {
               <p>No vault open</p>
               <button id="open-vault" class="primary-button" onclick="window.openVault()">Open Vault</button>
               <button id="create-vault" class="secondary-button" onclick="window.createVault()">Create Vault</button>
+              <button id="restore-vault" class="secondary-button" onclick="window.restoreVaultBackup()">Restore Backup</button>
             </div>
           </div>
           <div class="sidebar-ribbon" id="vault-actions" style="display: none;">
@@ -2480,6 +2643,12 @@ async function initializeApp() {
                   <div class="editor-dropdown-item" onclick="exportToWord()">
                     <span>Export as Word (.doc)</span>
                   </div>
+                  <div class="editor-dropdown-item" onclick="publishSite('')">
+                    <span>Publish Vault as Website</span>
+                  </div>
+                  <div class="editor-dropdown-item" onclick="backUpVault()">
+                    <span>Back Up Vault</span>
+                  </div>
                 </div>
               </div>
             </div>
@@ -2636,8 +2805,8 @@ async function initializeApp() {
     
     // Set up context menu handling for file items
     document.addEventListener('contextmenu', function(e) {
-      // Check if the right-clicked element is a file item or inside one
-      const fileItem = e.target.closest('.tree-item.file');
+      // Check if the right-clicked element is a file or folder item or inside one
+      const fileItem = e.target.closest('.tree-item.file, .tree-item.folder');
       if (fileItem) {
         e.preventDefault();
         // Use data-path instead of data-file-path to get the unescaped path
@@ -2771,10 +2940,11 @@ window.deleteFile = async function() {
   
   const targetPath = contextMenuTarget; // Capture the path before async operation
   const fileName = targetPath.split('/').pop();
+  const isFolder = !!document.querySelector(`.tree-item.folder[data-path="${CSS.escape(targetPath)}"]`);
   
   // Use Tauri's dialog API for confirmation
-  const confirmed = await ask(`Are you sure you want to delete "${fileName}"?`, {
-    title: 'Delete File',
+  const confirmed = await ask(`Are you sure you want to delete "${fileName}"${isFolder ? ' and everything in it' : ''}?`, {
+    title: isFolder ? 'Delete Folder' : 'Delete File',
     type: 'warning'
   });
   
@@ -2788,19 +2958,21 @@ window.deleteFile = async function() {
           console.log('Looking for tab with path:', targetPath);
           console.log('Current tabs:', Array.from(tabManager.tabs.values()).map(t => ({ id: t.id, path: t.filePath, title: t.title })));
           
-          const tabToClose = tabManager.findTabByPath(targetPath);
-          if (tabToClose) {
+          // A deleted folder closes the tabs of every file inside it
+          const tabsToClose = Array.from(tabManager.tabs.values())
+            .filter(t => t.filePath === targetPath || (isFolder && t.filePath?.startsWith(targetPath + '/')));
+          for (const tabToClose of tabsToClose) {
             console.log('Found tab to close:', tabToClose.id, tabToClose.filePath);
             tabManager.closeTab(tabToClose.id, true); // Force close without save prompt
-          } else {
+          }
+          if (tabsToClose.length === 0) {
             console.log('No tab found for deleted file path:', targetPath);
           }
         }
         
         // Refresh file tree
         try {
-          const fileTree = await invoke('get_file_tree');
-          displayFileTree(fileTree);
+          await refreshFileTree();
         } catch (error) {
           console.error('Error refreshing file tree:', error);
         }
@@ -2956,8 +3128,7 @@ function confirmMove(destinationPath) {
       console.log('File moved successfully');
       // Refresh file tree
       try {
-        const fileTree = await invoke('get_file_tree');
-        displayFileTree(fileTree);
+        await refreshFileTree();
       } catch (error) {
         console.error('Error refreshing file tree:', error);
       }
@@ -3061,8 +3232,7 @@ window.confirmRename = function() {
         console.log('File renamed successfully');
         // Refresh file tree
         try {
-          const fileTree = await invoke('get_file_tree');
-          displayFileTree(fileTree);
+          await refreshFileTree();
         } catch (error) {
           console.error('Error refreshing file tree:', error);
         }
@@ -3453,7 +3623,8 @@ window.exportToPDF = async function() {
       options: {
         theme: 'light',
         include_styles: true,
-        paper_size: 'A4'
+        paper_size: 'A4',
+        source_path: currentFile
       }
     });
     
@@ -3509,7 +3680,8 @@ window.exportToHTML = async function() {
       options: {
         theme: 'light',
         include_styles: true,
-        paper_size: null  // Not needed for HTML export
+        paper_size: null,  // Not needed for HTML export
+        source_path: currentFile
       }
     });
     
@@ -3522,6 +3694,126 @@ window.exportToHTML = async function() {
   }
 };
 
+// Publish the vault, or a folder of it, as a static website
+window.publishSite = async function(folder, event) {
+  if (event) event.stopPropagation();
+  
+  // Hide dropdown
+  const dropdown = document.getElementById('editor-dropdown');
+  if (dropdown) {
+    dropdown.classList.add('hidden');
+  }
+  
+  try {
+    const outputDir = await open({
+      directory: true,
+      multiple: false,
+      title: 'Choose a folder for the website'
+    });
+    
+    if (!outputDir) {
+      console.log('❌ Publishing cancelled by user');
+      return;
+    }
+    
+    showNotification('Publishing website...', 'info');
+    const report = await invoke('publish_site', {
+      outputDir: outputDir,
+      options: { folder: folder || '', theme: 'light' }
+    });
+    
+    console.log('✅ Website published:', report);
+    const skipped = report.skipped.length ? ` (${report.skipped.length} notes not published)` : '';
+    showSuccess(`Published ${report.pages} pages${skipped}`);
+    
+  } catch (error) {
+    console.error('❌ Failed to publish website:', error);
+    showNotification('Failed to publish website: ' + error, 'error');
+  }
+};
+
+// Write an archive of the vault, with its settings, to a chosen folder
+window.backUpVault = async function() {
+  // Hide dropdown
+  const dropdown = document.getElementById('editor-dropdown');
+  if (dropdown) {
+    dropdown.classList.add('hidden');
+  }
+  
+  try {
+    const outputDir = await open({
+      directory: true,
+      multiple: false,
+      title: 'Choose a folder for the backup'
+    });
+    
+    if (!outputDir) {
+      console.log('❌ Backup cancelled by user');
+      return;
+    }
+    
+    const includeGraph = await ask('Include a dump of the knowledge graph and embeddings? The graph services must be running.', {
+      title: 'Back Up Vault',
+      type: 'info'
+    });
+    
+    showNotification('Backing up vault...', 'info');
+    const backup = await invoke('create_backup', { outputDir: outputDir, includeGraph: includeGraph });
+    
+    console.log('✅ Vault backed up:', backup);
+    showSuccess(`Backed up ${backup.files} files to ${backup.archive_path}`);
+    
+  } catch (error) {
+    console.error('❌ Failed to back up vault:', error);
+    showNotification('Failed to back up vault: ' + error, 'error');
+  }
+};
+
+// Recreate a vault from a backup archive and open it
+window.restoreVaultBackup = async function() {
+  try {
+    const archivePath = await open({
+      directory: false,
+      multiple: false,
+      title: 'Choose a vault backup',
+      filters: [{ name: 'Vault backup', extensions: ['zip'] }]
+    });
+    
+    if (!archivePath) {
+      console.log('❌ Restore cancelled by user');
+      return;
+    }
+    
+    const manifest = await invoke('read_backup_manifest', { archivePath: archivePath });
+    const parentDir = await open({
+      directory: true,
+      multiple: false,
+      title: `Choose where to restore "${manifest.vault_name}"`
+    });
+    
+    if (!parentDir) {
+      console.log('❌ Restore cancelled by user');
+      return;
+    }
+    
+    showNotification(`Restoring ${manifest.files.length} files...`, 'info');
+    const report = await invoke('restore_backup', { archivePath: archivePath, parentDir: parentDir });
+    
+    console.log('✅ Vault restored:', report);
+    if (report.graph_error) {
+      showNotification('Vault restored, but the knowledge graph was not rebuilt: ' + report.graph_error, 'info');
+    } else {
+      showSuccess(`Restored ${report.files} files`);
+    }
+    
+    await windowContext.openVault(report.vault_path);
+    
+  } catch (error) {
+    console.error('❌ Failed to restore backup:', error);
+    showNotification('Failed to restore backup: ' + error, 'error');
+  }
+};
+
 // Sync Vault to Knowledge Graph function
 window.syncVaultToGraph = async function() {
   console.log('🔄 Syncing vault to knowledge graph...');
@@ -3596,7 +3888,8 @@ window.exportToWord = async function() {
       options: {
         theme: 'light',
         include_styles: true,
-        paper_size: null  // Not needed for Word export
+        paper_size: null,  // Not needed for Word export
+        source_path: currentFile
       }
     });
     
@@ -4565,7 +4858,7 @@ window.openFile = async function(filePath) {
   } else {
     // Create new tab
     try {
-      const content = await invoke('read_file_content', { filePath });
+      const content = await readFileVersioned(filePath);
       const tabId = tabManager.createTab(filePath, content);
       tabManager.activateTab(tabId);
     } catch (error) {
@@ -4580,8 +4873,8 @@ window.createAndOpenFile = async function(filePath, content = '') {
   console.log('📝 Creating new file:', filePath);
   
   try {
-    // Write the file (backend automatically creates directories)
-    await invoke('write_file_content', { filePath, content });
+    // Write the file (backend automatically creates directories), never over an existing one
+    await writeFileChecked(filePath, content, { create: true });
     
     // Open it
     await window.openFile(filePath);
@@ -2,6 +2,7 @@
 // Handles highlight creation, storage, and extraction to markdown
 
 import { invoke } from '@tauri-apps/api/core';
+import { readFileVersioned, writeFileChecked } from '../utils/file-versions.js';
 import { dirname, join, basename } from '@tauri-apps/api/path';
 import { 
   getHighlights, 
@@ -221,10 +222,13 @@ export async function saveHighlights(filePath) {
     const pdfName = await basename(filePath);
     const highlightsPath = await join(highlightsDir, `${pdfName}.json`);
     
-    await invoke('write_file_content', { 
-      filePath: highlightsPath, 
-      content: JSON.stringify(highlights, null, 2) 
-    });
+    const content = JSON.stringify(highlights, null, 2);
+    const savedContent = await writeFileChecked(highlightsPath, content);
+    if (savedContent !== content) {
+      // The user chose to reload the highlights changed on disk
+      setHighlights(JSON.parse(savedContent));
+      updateHighlightCountInToolbar();
+    }
     console.log(`Saved highlights to: ${highlightsPath}`);
   } catch (error) {
     console.error('Error saving highlights:', error);
@@ -242,7 +246,7 @@ export async function loadHighlights(filePath) {
     const highlightsPath = await join(pdfDir, '.gaimplan', 'pdf-highlights', `${pdfName}.json`);
     
     try {
-      const content = await invoke('read_file_content', { filePath: highlightsPath });
+      const content = await readFileVersioned(highlightsPath);
       const highlights = JSON.parse(content);
       setHighlights(highlights);
       console.log('Loaded highlights from:', highlightsPath);
@@ -308,10 +312,7 @@ export async function extractHighlightsToMarkdown(filePath) {
     const pdfDir = await dirname(filePath);
     const markdownPath = await join(pdfDir, `${pdfNameWithoutExt}-highlights.md`);
     
-    await invoke('write_file_content', { 
-      filePath: markdownPath, 
-      content: markdown 
-    });
+    await writeFileChecked(markdownPath, markdown);
     console.log(`Extracted highlights to: ${markdownPath}`);
     
     // Emit event that file was updated
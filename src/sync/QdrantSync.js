@@ -158,7 +158,7 @@ export class QdrantSync {
       // Convert file paths to note objects
       const notes = await Promise.all(markdownFiles.map(async (fileInfo) => {
         try {
-          const content = await invoke('read_file_content', { 
+          const content = await invoke('read_note_for_ai', { 
             filePath: fileInfo.path 
           });
           
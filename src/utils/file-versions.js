@@ -0,0 +1,45 @@
+// Tracks the version of every file as last read or saved, so saves are refused
+// by the backend when the file changed on disk in the meantime
+
+import { invoke } from '@tauri-apps/api/core';
+import { ask } from '@tauri-apps/plugin-dialog';
+
+const versions = new Map();
+
+// Reads a file and remembers its version for the next save
+export async function readFileVersioned(filePath) {
+  const { content, version } = await invoke('read_file_versioned', { filePath });
+  versions.set(filePath, version.hash);
+  return content;
+}
+
+// Saves a file only if it is unchanged since it was read. On a conflict the user
+// picks between overwriting the version on disk and reloading it. Returns the
+// content now on disk, which differs from `content` when they reloaded.
+// With `create`, the file must not exist yet (an empty hash never matches).
+export async function writeFileChecked(filePath, content, { create = false } = {}) {
+  const expectedHash = create ? '' : (versions.get(filePath) ?? null);
+  try {
+    const version = await invoke('write_file_content', { filePath, content, expectedHash });
+    versions.set(filePath, version.hash);
+    return content;
+  } catch (error) {
+    if (error?.type !== 'conflict') {
+      throw error?.message ?? error;
+    }
+
+    const fileName = filePath.split('/').pop();
+    const overwrite = await ask(
+      `"${fileName}" changed on disk since it was opened. Overwrite it with your version, or reload the version on disk?`,
+      { title: 'File changed on disk', kind: 'warning', okLabel: 'Overwrite', cancelLabel: 'Reload' }
+    );
+    if (overwrite) {
+      const version = await invoke('write_file_content', { filePath, content, expectedHash: error.disk_version.hash });
+      versions.set(filePath, version.hash);
+      return content;
+    }
+
+    versions.set(filePath, error.disk_version.hash);
+    return error.disk_content;
+  }
+}
@@ -0,0 +1,12 @@
+// Image formats shown inline; must match IMAGE_TYPES in
+// src-tauri/src/index/attachments.rs
+export const IMAGE_EXTENSIONS = ['png', 'jpg', 'jpeg', 'gif', 'webp', 'svg', 'avif', 'bmp', 'tif', 'tiff'];
+
+// Lowercased extension of a path
+export function fileExtension(filePath) {
+  return filePath.split('.').pop().toLowerCase();
+}
+
+export function isImageFile(filePath) {
+  return IMAGE_EXTENSIONS.includes(fileExtension(filePath));
+}
@@ -675,7 +675,6 @@ export class CalendarWidget {
                     // Folder doesn't exist, create it
                     console.log(`[CalendarWidget] Creating daily notes folder: ${folderPath}`);
                     await invoke('create_directory', {
-                        vaultPath: window.currentVaultPath,
                         dirPath: folderPath
                     });
                 }